@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use graph_flow::{GraphBuilder, GraphStorage, Task};
+use graph_flow::{Graph, GraphBuilder, GraphStorage, Task};
 
 use crate::processor::tasks::{
     binance_operations_task::BinanceOperationsTask, binance_reporting_task::BinanceReportingTask,
@@ -13,30 +13,102 @@ use crate::processor::tasks::{
     regimen_selection_task::RegimenSelectionTask, regimen_switching_task::RegimenSwitchingTask,
     reply_generation_task::ReplyGenerationTask,
 };
+use crate::processor::{
+    intent::{IntentRouter, LlmIntentModel},
+    llm::LlmConfig,
+    portfolio::PortfolioConfig,
+    prompts::library::PromptLibrary,
+    regimen::RegimenConfig,
+    streaming::{ChatEvents, StreamedTask},
+};
+use crate::runner::core::{ExecutionMode, LiveRunner};
 
 use tracing::info;
 
+/// Builds the chat workflow with each task's model settings from the
+/// environment and saves it under the empty graph id.
 pub async fn setup_graph(
     graph_storage: Arc<dyn GraphStorage>,
+    runner: Arc<LiveRunner>,
+    events: ChatEvents,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("Setting up greenrock workflow graph");
 
-    let entry_interaction_task: Arc<dyn Task> = Arc::new(EntryInteractionTask::new("".to_string()));
+    let graph = Arc::new(build_graph(runner, events, LlmConfig::for_task));
+
+    graph_storage.save("".to_string(), graph).await?;
+
+    info!("Graph built and saved successfully");
+    Ok(())
+}
+
+/// The chat workflow. The entry task's agent gets trading tools backed by
+/// `runner`, the regimen tasks backtest and switch its strategy, the
+/// portfolio tasks read its broker and the Binance operations task trades
+/// through it once the user confirms.
+///
+/// Every task reports its progress to `events` for streamed sessions, and
+/// `llm` resolves the model settings of the `intent`, `entry`, `operations`
+/// and `reply` tasks.
+pub fn build_graph(
+    runner: Arc<LiveRunner>,
+    events: ChatEvents,
+    llm: impl Fn(&str) -> LlmConfig,
+) -> Graph {
+    let router = IntentRouter::from_env().with_model(Arc::new(
+        LlmIntentModel::new(PromptLibrary::from_env()).with_llm(llm("intent")),
+    ));
+
+    let entry_interaction_task: Arc<dyn Task> = Arc::new(
+        EntryInteractionTask::new(PromptLibrary::from_env())
+            .with_router(router)
+            .with_llm(llm("entry"))
+            .with_tools(runner.clone())
+            .with_events(events.clone()),
+    );
 
-    let regimen_reporting_task: Arc<dyn Task> = Arc::new(RegimenReportingTask);
-    let regimen_evaluation_task: Arc<dyn Task> = Arc::new(RegimenEvaluationTask);
-    let regimen_switching_task: Arc<dyn Task> = Arc::new(RegimenSwitchingTask);
+    let regimen_config = RegimenConfig::from_env();
+
+    let regimen_reporting_task: Arc<dyn Task> = Arc::new(RegimenReportingTask::new(
+        runner.clone(),
+        regimen_config.clone(),
+    ));
+    let regimen_evaluation_task: Arc<dyn Task> = Arc::new(RegimenEvaluationTask::new(
+        runner.clone(),
+        regimen_config.clone(),
+    ));
+    let regimen_switching_task: Arc<dyn Task> = Arc::new(RegimenSwitchingTask::new(
+        runner.clone(),
+        regimen_config.clone(),
+    ));
     let regimen_aggregation_task: Arc<dyn Task> = Arc::new(RegimenAggregationTask);
-    let regimen_selection_task: Arc<dyn Task> = Arc::new(RegimenSelectionTask);
+    let regimen_selection_task: Arc<dyn Task> = Arc::new(RegimenSelectionTask::new(regimen_config));
+
+    let binance_reporting_task: Arc<dyn Task> = Arc::new(BinanceReportingTask::new(runner.clone()));
+    let binance_operations_task: Arc<dyn Task> = Arc::new(
+        BinanceOperationsTask::new(
+            runner.clone(),
+            PromptLibrary::from_env(),
+            ExecutionMode::from_env(),
+        )
+        .with_llm(llm("operations")),
+    );
 
-    let binance_reporting_task: Arc<dyn Task> = Arc::new(BinanceReportingTask);
-    let binance_operations_task: Arc<dyn Task> = Arc::new(BinanceOperationsTask);
+    let portfolio_config = PortfolioConfig::from_env();
 
-    let portfolio_reporting_task: Arc<dyn Task> = Arc::new(PortfolioReportingTask);
+    let portfolio_reporting_task: Arc<dyn Task> = Arc::new(PortfolioReportingTask::new(
+        runner.clone(),
+        portfolio_config.clone(),
+    ));
     let portfolio_aggregation_task: Arc<dyn Task> = Arc::new(PortfolioAggregationTask);
-    let portfolio_selection_task: Arc<dyn Task> = Arc::new(PortfolioSelectionTask);
+    let portfolio_selection_task: Arc<dyn Task> =
+        Arc::new(PortfolioSelectionTask::new(portfolio_config));
 
-    let reply_generation_task: Arc<dyn Task> = Arc::new(ReplyGenerationTask);
+    let reply_generation_task: Arc<dyn Task> = Arc::new(
+        ReplyGenerationTask::new(PromptLibrary::from_env())
+            .with_llm(llm("reply"))
+            .with_events(events.clone()),
+    );
 
     //
 
@@ -56,121 +128,113 @@ pub async fn setup_graph(
     let portfolio_aggregation_task_id = portfolio_aggregation_task.id().to_string();
     let portfolio_selection_task_id = portfolio_selection_task.id().to_string();
 
-    // Build graph
-    let graph = Arc::new(
-        GraphBuilder::new("greenrock_main_flow")
-            .add_task(entry_interaction_task)
-            //
-            .add_task(reply_generation_task)
-            //
-            .add_task(regimen_reporting_task)
-            .add_task(regimen_evaluation_task)
-            .add_task(regimen_switching_task)
-            .add_task(regimen_aggregation_task)
-            .add_task(regimen_selection_task)
-            //
-            .add_task(binance_reporting_task)
-            .add_task(binance_operations_task)
-            //
-            .add_task(portfolio_reporting_task)
-            .add_task(portfolio_aggregation_task)
-            //
-            .add_task(portfolio_selection_task)
-            //
-            .add_conditional_edge(
-                entry_interaction_task_id.clone(),
-                {
-                    let reply_generation_task_id = reply_generation_task_id.clone();
-                    let regimen_reporting_task_id = regimen_reporting_task_id.clone();
-
-                    move |ctx| {
-                        (ctx.get_sync::<String>("next_task")
-                            .unwrap_or(reply_generation_task_id.clone()))
-                            == regimen_reporting_task_id
-                    }
-                },
-                regimen_reporting_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_conditional_edge(
-                entry_interaction_task_id.clone(),
-                {
-                    let reply_generation_task_id = reply_generation_task_id.clone();
-                    let binance_reporting_task_id = binance_reporting_task_id.clone();
-
-                    move |ctx| {
-                        (ctx.get_sync::<String>("next_task")
-                            .unwrap_or(reply_generation_task_id.clone()))
-                            == binance_reporting_task_id
-                    }
-                },
-                binance_reporting_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_conditional_edge(
-                entry_interaction_task_id.clone(),
-                {
-                    let reply_generation_task_id = reply_generation_task_id.clone();
-                    let portfolio_reporting_task_id = portfolio_reporting_task_id.clone();
-
-                    move |ctx| {
-                        (ctx.get_sync::<String>("next_task")
-                            .unwrap_or(reply_generation_task_id.clone()))
-                            == portfolio_reporting_task_id
-                    }
-                },
-                portfolio_reporting_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_edge(
-                regimen_reporting_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_edge(
-                binance_reporting_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_edge(
-                portfolio_reporting_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_edge(
-                regimen_selection_task_id.clone(),
-                reply_generation_task_id.clone(),
-            )
-            .add_edge(
-                binance_operations_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .add_edge(
-                portfolio_selection_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .add_edge(
-                portfolio_aggregation_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .add_edge(
-                regimen_aggregation_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .add_edge(
-                regimen_switching_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .add_edge(
-                regimen_evaluation_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .add_edge(
-                regimen_reporting_task_id.clone(),
-                regimen_selection_task_id.clone(),
-            )
-            .build(),
-    );
-
-    graph_storage.save("".to_string(), graph).await?;
-
-    info!("Graph built and saved successfully");
-    Ok(())
+    let streamed = |task: Arc<dyn Task>| -> Arc<dyn Task> {
+        Arc::new(StreamedTask::new(task, events.clone()))
+    };
+
+    GraphBuilder::new("greenrock_main_flow")
+        .add_task(streamed(entry_interaction_task))
+        //
+        .add_task(streamed(reply_generation_task))
+        //
+        .add_task(streamed(regimen_reporting_task))
+        .add_task(streamed(regimen_evaluation_task))
+        .add_task(streamed(regimen_switching_task))
+        .add_task(streamed(regimen_aggregation_task))
+        .add_task(streamed(regimen_selection_task))
+        //
+        .add_task(streamed(binance_reporting_task))
+        .add_task(streamed(binance_operations_task))
+        //
+        .add_task(streamed(portfolio_reporting_task))
+        .add_task(streamed(portfolio_aggregation_task))
+        //
+        .add_task(streamed(portfolio_selection_task))
+        //
+        .add_conditional_edge(
+            entry_interaction_task_id.clone(),
+            {
+                let reply_generation_task_id = reply_generation_task_id.clone();
+                let regimen_reporting_task_id = regimen_reporting_task_id.clone();
+
+                move |ctx| {
+                    (ctx.get_sync::<String>("next_task")
+                        .unwrap_or(reply_generation_task_id.clone()))
+                        == regimen_reporting_task_id
+                }
+            },
+            regimen_reporting_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .add_conditional_edge(
+            entry_interaction_task_id.clone(),
+            {
+                let reply_generation_task_id = reply_generation_task_id.clone();
+                let binance_reporting_task_id = binance_reporting_task_id.clone();
+
+                move |ctx| {
+                    (ctx.get_sync::<String>("next_task")
+                        .unwrap_or(reply_generation_task_id.clone()))
+                        == binance_reporting_task_id
+                }
+            },
+            binance_reporting_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .add_conditional_edge(
+            entry_interaction_task_id.clone(),
+            {
+                let reply_generation_task_id = reply_generation_task_id.clone();
+                let portfolio_reporting_task_id = portfolio_reporting_task_id.clone();
+
+                move |ctx| {
+                    (ctx.get_sync::<String>("next_task")
+                        .unwrap_or(reply_generation_task_id.clone()))
+                        == portfolio_reporting_task_id
+                }
+            },
+            portfolio_reporting_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .add_edge(
+            regimen_reporting_task_id.clone(),
+            regimen_evaluation_task_id.clone(),
+        )
+        .add_edge(
+            regimen_evaluation_task_id.clone(),
+            regimen_aggregation_task_id.clone(),
+        )
+        .add_edge(
+            regimen_aggregation_task_id.clone(),
+            regimen_selection_task_id.clone(),
+        )
+        .add_edge(
+            regimen_selection_task_id.clone(),
+            regimen_switching_task_id.clone(),
+        )
+        .add_edge(
+            regimen_switching_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .add_edge(
+            binance_reporting_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .add_edge(
+            portfolio_reporting_task_id.clone(),
+            portfolio_aggregation_task_id.clone(),
+        )
+        .add_edge(
+            portfolio_aggregation_task_id.clone(),
+            portfolio_selection_task_id.clone(),
+        )
+        .add_edge(
+            portfolio_selection_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .add_edge(
+            binance_operations_task_id.clone(),
+            reply_generation_task_id.clone(),
+        )
+        .build()
 }
@@ -0,0 +1,336 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{analysis::TechnicalAnalysis, timeseries::Candle};
+
+/// Market condition a stretch of candles is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketRegime {
+    Trending,
+    Ranging,
+    HighVolatility,
+    Crash,
+}
+
+impl MarketRegime {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketRegime::Trending => "trending",
+            MarketRegime::Ranging => "ranging",
+            MarketRegime::HighVolatility => "high-volatility",
+            MarketRegime::Crash => "crash",
+        }
+    }
+}
+
+/// Cut-offs used by `classify_regime`, checked from crash down to ranging.
+#[derive(Debug, Clone)]
+pub struct RegimeThresholds {
+    pub adx_period: usize,
+    pub supertrend_period: usize,
+    pub supertrend_factor: f64,
+    /// Candles of the recent window compared against the whole window for
+    /// the volatility ratio.
+    pub recent_candles: usize,
+    /// Window return at or below this, in percent, is a crash.
+    pub crash_return_percent: f64,
+    /// Drop from the window high at or beyond this, in percent, is a crash
+    /// when volatility is also elevated.
+    pub crash_drawdown_percent: f64,
+    /// Recent over overall return volatility at or above this is high
+    /// volatility.
+    pub high_volatility_ratio: f64,
+    /// ADX at or above this is a trend.
+    pub trending_adx: f64,
+}
+
+impl Default for RegimeThresholds {
+    fn default() -> Self {
+        Self {
+            adx_period: 14,
+            supertrend_period: 10,
+            supertrend_factor: 3.0,
+            recent_candles: 24,
+            crash_return_percent: -10.0,
+            crash_drawdown_percent: 15.0,
+            high_volatility_ratio: 1.5,
+            trending_adx: 25.0,
+        }
+    }
+}
+
+/// Statistics the regime is derived from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeFeatures {
+    /// Standard deviation of close-to-close returns, in percent.
+    pub volatility_percent: f64,
+    /// Volatility of the recent candles over that of the whole window.
+    pub volatility_ratio: f64,
+    pub adx: f64,
+    /// 1 for a bullish SuperTrend, 0 for bearish.
+    pub supertrend_trend: i32,
+    /// Close-to-close change over the window, in percent.
+    pub return_percent: f64,
+    /// Drop of the last close from the window high, in percent.
+    pub drawdown_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeReport {
+    pub regime: MarketRegime,
+    pub features: RegimeFeatures,
+    pub candles: usize,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl RegimeReport {
+    /// One-line description for prompts and replies.
+    pub fn summary(&self) -> String {
+        let direction = if self.features.supertrend_trend == 1 {
+            "bullish"
+        } else {
+            "bearish"
+        };
+
+        format!(
+            "{} regime over {} candles: return {:.2}%, drawdown {:.2}%, volatility {:.2}% ({:.2}x recent), ADX {:.1}, SuperTrend {}",
+            self.regime.as_str(),
+            self.candles,
+            self.features.return_percent,
+            self.features.drawdown_percent,
+            self.features.volatility_percent,
+            self.features.volatility_ratio,
+            self.features.adx,
+            direction,
+        )
+    }
+}
+
+fn returns_percent(candles: &[Candle]) -> Vec<f64> {
+    candles
+        .windows(2)
+        .filter(|window| window[0].close.is_positive())
+        .map(|window| {
+            (window[1].close.to_f64() - window[0].close.to_f64()) / window[0].close.to_f64() * 100.0
+        })
+        .collect()
+}
+
+fn standard_deviation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (values.len() - 1) as f64;
+
+    variance.sqrt()
+}
+
+/// Classifies the regime of `candles`, oldest first, from return volatility,
+/// ADX, SuperTrend and return statistics. `None` with fewer than two candles.
+pub fn classify_regime(candles: &[Candle], thresholds: &RegimeThresholds) -> Option<RegimeReport> {
+    let (first, last) = (candles.first()?, candles.last()?);
+
+    if candles.len() < 2 {
+        return None;
+    }
+
+    let returns = returns_percent(candles);
+    let volatility_percent = standard_deviation(&returns);
+    let recent_volatility =
+        standard_deviation(&returns[returns.len().saturating_sub(thresholds.recent_candles)..]);
+    let volatility_ratio = if volatility_percent > 0.0 {
+        recent_volatility / volatility_percent
+    } else {
+        1.0
+    };
+
+    let series = candles.to_vec();
+    let adx = series.adx(thresholds.adx_period);
+    let supertrend_trend = series
+        .supertrend(thresholds.supertrend_period, thresholds.supertrend_factor)
+        .trend;
+
+    let return_percent = if first.close.is_positive() {
+        (last.close.to_f64() - first.close.to_f64()) / first.close.to_f64() * 100.0
+    } else {
+        0.0
+    };
+
+    let high = candles
+        .iter()
+        .map(|candle| candle.high.to_f64())
+        .fold(f64::MIN, f64::max);
+    let drawdown_percent = if high > 0.0 {
+        (high - last.close.to_f64()) / high * 100.0
+    } else {
+        0.0
+    };
+
+    let elevated_volatility = volatility_ratio >= thresholds.high_volatility_ratio;
+
+    let regime = if return_percent <= thresholds.crash_return_percent
+        || (drawdown_percent >= thresholds.crash_drawdown_percent && elevated_volatility)
+    {
+        MarketRegime::Crash
+    } else if elevated_volatility {
+        MarketRegime::HighVolatility
+    } else if adx >= thresholds.trending_adx {
+        MarketRegime::Trending
+    } else {
+        MarketRegime::Ranging
+    };
+
+    Some(RegimeReport {
+        regime,
+        features: RegimeFeatures {
+            volatility_percent,
+            volatility_ratio,
+            adx,
+            supertrend_trend,
+            return_percent,
+            drawdown_percent,
+        },
+        candles: candles.len(),
+        start: first.ts,
+        end: last.ts,
+    })
+}
+
+/// Splits `candles` into consecutive windows of `window` candles, aligned so
+/// that the last window ends at the latest candle, and classifies each. A
+/// shorter leading remainder is dropped.
+pub fn regime_segments(
+    candles: &[Candle],
+    window: usize,
+    thresholds: &RegimeThresholds,
+) -> Vec<(RegimeReport, std::ops::Range<usize>)> {
+    if window < 2 {
+        return Vec::new();
+    }
+
+    let offset = candles.len() % window;
+
+    (0..candles.len() / window)
+        .filter_map(|segment| {
+            let range = offset + segment * window..offset + (segment + 1) * window;
+            classify_regime(&candles[range.clone()], thresholds).map(|report| (report, range))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::money::{Price, Quantity};
+
+    use super::*;
+
+    /// Hourly candles closing at `closes`, each spanning half a point either
+    /// side of the close.
+    fn candles(closes: impl IntoIterator<Item = f64>) -> Vec<Candle> {
+        let mut previous = None;
+
+        closes
+            .into_iter()
+            .enumerate()
+            .map(|(i, close)| {
+                let timestamp = 1_700_000_000 + i as i64 * 3600;
+                let open = previous.replace(close).unwrap_or(close);
+
+                Candle {
+                    open: Price::from_f64(open),
+                    high: Price::from_f64(close + 0.5),
+                    low: Price::from_f64(close - 0.5),
+                    close: Price::from_f64(close),
+                    volume: Quantity::from_f64(1.0),
+                    timestamp,
+                    ts: DateTime::from_timestamp(timestamp, 0).unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    fn regime(closes: impl IntoIterator<Item = f64>) -> MarketRegime {
+        classify_regime(&candles(closes), &RegimeThresholds::default())
+            .unwrap()
+            .regime
+    }
+
+    #[test]
+    fn classifies_a_steady_climb_as_trending() {
+        assert_eq!(
+            regime((0..100).map(|i| 100.0 + i as f64)),
+            MarketRegime::Trending
+        );
+    }
+
+    #[test]
+    fn classifies_a_flat_chop_as_ranging() {
+        assert_eq!(
+            regime((0..100).map(|i| 100.0 + (i % 2) as f64)),
+            MarketRegime::Ranging
+        );
+    }
+
+    #[test]
+    fn classifies_a_recent_volatility_burst_as_high_volatility() {
+        let closes = (0..100).map(|i| {
+            if i < 76 {
+                100.0 + 0.1 * (i % 2) as f64
+            } else {
+                100.0 + 5.0 * (i % 2) as f64
+            }
+        });
+
+        assert_eq!(regime(closes), MarketRegime::HighVolatility);
+    }
+
+    #[test]
+    fn classifies_a_deep_decline_as_a_crash() {
+        assert_eq!(
+            regime((0..100).map(|i| 100.0 - 0.2 * i as f64)),
+            MarketRegime::Crash
+        );
+    }
+
+    #[test]
+    fn needs_two_candles() {
+        let thresholds = RegimeThresholds::default();
+
+        assert!(classify_regime(&[], &thresholds).is_none());
+        assert!(classify_regime(&candles([100.0]), &thresholds).is_none());
+    }
+
+    #[test]
+    fn aligns_segments_to_the_latest_candle() {
+        let candles = candles((0..250).map(|i| 100.0 + i as f64));
+        let thresholds = RegimeThresholds::default();
+
+        let segments = regime_segments(&candles, 100, &thresholds);
+
+        let ranges: Vec<_> = segments.iter().map(|(_, range)| range.clone()).collect();
+        assert_eq!(ranges, [50..150, 150..250]);
+
+        let (last, _) = segments.last().unwrap();
+        assert_eq!(last.end, candles[249].ts);
+        assert_eq!(last.start, candles[150].ts);
+        assert_eq!(last.candles, 100);
+    }
+
+    #[test]
+    fn drops_short_histories_and_degenerate_windows() {
+        let candles = candles((0..50).map(|i| 100.0 + i as f64));
+        let thresholds = RegimeThresholds::default();
+
+        assert!(regime_segments(&candles, 100, &thresholds).is_empty());
+        assert!(regime_segments(&candles, 1, &thresholds).is_empty());
+        assert_eq!(regime_segments(&candles, 50, &thresholds).len(), 1);
+    }
+}
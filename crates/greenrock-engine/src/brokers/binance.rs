@@ -1,16 +1,171 @@
 use std::collections::HashMap;
 use std::env;
+use std::sync::{Arc, LazyLock, OnceLock, RwLock};
+use std::time::Instant;
 
-use binance::model::{KlineSummaries, Order, OrderBook, TradeHistory};
-use binance::{account::Account, api::Binance, market::Market};
+use binance::model::{KlineSummaries, KlineSummary};
+use binance::{
+    account::Account, api::Binance, config::Config, general::General, market::Market,
+    userstream::UserStream,
+};
 use chrono::{DateTime, Utc};
-use tracing::{error, info};
+use rust_decimal::Decimal;
+use tracing::{error, info, warn};
 
-use crate::brokers::core::Broker;
-use crate::models::timeseries::Candle;
+use crate::brokers::core::{Broker, cancel_per_symbol, parse_number, skip_invalid};
+use crate::brokers::rate_limit::{RateLimitMetrics, RateLimiter, VenueError};
+use crate::models::account::{AccountEvent, Balance, OrderUpdate};
+use crate::models::market::{BookLevel, OrderBook, Ticker};
+use crate::models::money::{Price, Quantity, to_decimal};
+use crate::models::orders::{Fill, Order, OrderAck, OrderRequest, OrderType, Side};
+use crate::models::symbols::SymbolInfo;
+use crate::models::timeseries::{Candle, Trade, TradeFeed};
+
+/// Request weights of the REST endpoints used below, per the Binance spot
+/// API docs.
+const WEIGHT_ACCOUNT: u32 = 20;
+const WEIGHT_PRICE: u32 = 2;
+const WEIGHT_TICKER_24H: u32 = 2;
+const WEIGHT_KLINES: u32 = 2;
+const WEIGHT_OPEN_ORDERS: u32 = 6;
+const WEIGHT_ALL_OPEN_ORDERS: u32 = 80;
+const WEIGHT_MY_TRADES: u32 = 20;
+const WEIGHT_ORDER: u32 = 1;
+const WEIGHT_CANCEL_ALL: u32 = 1;
+const WEIGHT_EXCHANGE_INFO: u32 = 20;
+const WEIGHT_LISTEN_KEY: u32 = 2;
+
+/// Listen keys expire after 60 minutes without a keepalive.
+const LISTEN_KEY_KEEPALIVE: Duration = Duration::from_secs(30 * 60);
+
+/// Binance limits are enforced per IP, so every broker instance in the
+/// process draws from the same buckets.
+static REST_LIMITER: LazyLock<Arc<RateLimiter>> =
+    LazyLock::new(|| Arc::new(RateLimiter::default()));
+
+/// How long `exchangeInfo` is cached before it is fetched again.
+const EXCHANGE_INFO_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct SymbolCache {
+    fetched_at: Instant,
+    symbols: Arc<HashMap<String, SymbolInfo>>,
+}
+
+/// REST and websocket base URLs of a Binance deployment.
+#[derive(Debug, Clone)]
+pub struct BinanceEndpoints {
+    /// REST base, e.g. `https://api.binance.com`.
+    pub rest: String,
+    /// Websocket stream base without the `/ws` suffix.
+    pub ws: String,
+}
+
+impl BinanceEndpoints {
+    pub fn mainnet() -> Self {
+        Self {
+            rest: "https://api.binance.com".to_string(),
+            ws: "wss://stream.binance.com:9443".to_string(),
+        }
+    }
+
+    /// Spot testnet. It needs its own API keys from testnet.binance.vision.
+    pub fn testnet() -> Self {
+        Self {
+            rest: "https://testnet.binance.vision".to_string(),
+            ws: "wss://stream.testnet.binance.vision".to_string(),
+        }
+    }
+
+    /// USD-M futures (`fapi`).
+    pub fn futures_mainnet() -> Self {
+        Self {
+            rest: "https://fapi.binance.com".to_string(),
+            ws: "wss://fstream.binance.com".to_string(),
+        }
+    }
+
+    /// USD-M futures testnet, with its own API keys from
+    /// testnet.binancefuture.com.
+    pub fn futures_testnet() -> Self {
+        Self {
+            rest: "https://testnet.binancefuture.com".to_string(),
+            ws: "wss://stream.binancefuture.com".to_string(),
+        }
+    }
+
+    /// Picks a preset from `BINANCE_NETWORK` (`mainnet` or `testnet`), then
+    /// applies `BINANCE_REST_URL` and `BINANCE_WS_URL` overrides, e.g. for a
+    /// local mock server.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Futures counterpart of `from_env`, overridden by
+    /// `BINANCE_FUTURES_REST_URL` and `BINANCE_FUTURES_WS_URL`.
+    pub fn futures_from_env() -> Self {
+        Self::futures_from_vars(|name| env::var(name).ok())
+    }
+
+    /// `from_env` reading the variables through `var`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        Self::preset(var("BINANCE_NETWORK"), Self::mainnet, Self::testnet)
+            .with_overrides(var("BINANCE_REST_URL"), var("BINANCE_WS_URL"))
+    }
+
+    /// `futures_from_env` reading the variables through `var`.
+    pub fn futures_from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        Self::preset(
+            var("BINANCE_NETWORK"),
+            Self::futures_mainnet,
+            Self::futures_testnet,
+        )
+        .with_overrides(
+            var("BINANCE_FUTURES_REST_URL"),
+            var("BINANCE_FUTURES_WS_URL"),
+        )
+    }
+
+    fn preset(network: Option<String>, mainnet: fn() -> Self, testnet: fn() -> Self) -> Self {
+        match network.as_deref() {
+            Some("testnet") => testnet(),
+            Some("mainnet") | None => mainnet(),
+            Some(other) => {
+                warn!("Unknown BINANCE_NETWORK '{}', using mainnet", other);
+                mainnet()
+            }
+        }
+    }
+
+    fn with_overrides(mut self, rest: Option<String>, ws: Option<String>) -> Self {
+        if let Some(rest) = rest {
+            self.rest = rest;
+        }
+
+        if let Some(ws) = ws {
+            self.ws = ws;
+        }
+
+        self.rest = self.rest.trim_end_matches('/').to_string();
+        self.ws = self.ws.trim_end_matches('/').to_string();
+
+        self
+    }
+}
+
+impl Default for BinanceEndpoints {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
 
 #[derive(Clone)]
-pub struct BinanceBroker {}
+pub struct BinanceBroker {
+    endpoints: BinanceEndpoints,
+    symbols: Arc<RwLock<Option<SymbolCache>>>,
+    limiter: Arc<RateLimiter>,
+    /// Account stream shared by all subscribers, started on first use.
+    account_events: Arc<OnceLock<broadcast::Sender<AccountEvent>>>,
+}
 
 use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
@@ -19,46 +174,31 @@ use tokio::time::{Duration, sleep};
 use tokio_tungstenite::tungstenite::Message;
 
 impl Broker for BinanceBroker {
-    fn balance(&self) -> HashMap<String, f64> {
+    fn balances(&self) -> Vec<Balance> {
         // Note: This method should ideally be async, but the trait requires sync
         // The caller should wrap this in spawn_blocking
-        let api_key = env::var("BINANCE_API_KEY").ok();
-        let secret_key = env::var("BINANCE_SECRET_KEY").ok();
-
-        if api_key.is_none() || secret_key.is_none() {
-            error!("Binance API credentials not found");
-            return HashMap::new();
-        }
-
-        let account: Account = Binance::new(api_key, secret_key);
+        let Some(account) = self.account() else {
+            return Vec::new();
+        };
 
-        match account.get_account() {
-            Ok(answer) => {
-                let res = answer
-                    .balances
-                    .iter()
-                    .filter(|instrument| instrument.free.parse::<f64>().unwrap_or(0.0) > 0.0)
-                    .collect::<Vec<_>>();
-
-                res.iter()
-                    .map(|instrument| {
-                        (
-                            instrument.asset.clone(),
-                            instrument.free.parse::<f64>().unwrap_or(0.0),
-                        )
-                    })
-                    .collect()
-            }
+        match self.limited(WEIGHT_ACCOUNT, 0, || account.get_account()) {
+            Ok(answer) => answer
+                .balances
+                .iter()
+                .map(Balance::try_from)
+                .filter_map(|balance| skip_invalid("balance", balance))
+                .filter(|balance| !balance.total().is_zero())
+                .collect(),
             Err(e) => {
                 error!("Failed to get balance: {}", e);
-                HashMap::new()
+                Vec::new()
             }
         }
     }
 
     fn market_current_price(&self, symbol: &str) -> f64 {
-        let market: Market = Binance::new(None, None);
-        match market.get_price(symbol) {
+        let market = self.market();
+        match self.limited(WEIGHT_PRICE, 0, || market.get_price(symbol)) {
             Ok(price) => price.price,
             Err(e) => {
                 error!("Failed to get market price for {}: {}", symbol, e);
@@ -67,60 +207,24 @@ impl Broker for BinanceBroker {
         }
     }
 
+    fn ticker(&self, symbol: &str) -> Option<Ticker> {
+        let market = self.market();
+        match self.limited(WEIGHT_TICKER_24H, 0, || market.get_24h_price_stats(symbol)) {
+            Ok(stats) => skip_invalid("ticker", Ticker::try_from(&stats)),
+            Err(e) => {
+                error!("Failed to get ticker for {}: {}", symbol, e);
+                None
+            }
+        }
+    }
+
     fn candle_stream(
         &self,
         symbol: &str,
         interval: &str,
     ) -> broadcast::Receiver<crate::models::timeseries::Candle> {
-        let (tx, rx) = broadcast::channel::<Candle>(1024);
         let symbol = symbol.to_lowercase();
-
-        let interval = interval.to_string();
-
-        tokio::spawn(async move {
-            let mut backoff = Duration::from_secs(1);
-            let max_backoff = Duration::from_secs(60);
-
-            loop {
-                let url = format!("wss://stream.binance.com:9443/ws/{symbol}@kline_{interval}");
-
-                match tokio_tungstenite::connect_async(&url).await {
-                    Ok((mut ws, _resp)) => {
-                        // Reset backoff on successful connect
-                        backoff = Duration::from_secs(1);
-
-                        while let Some(msg) = ws.next().await {
-                            match msg {
-                                Ok(Message::Text(text)) => {
-                                    if let Ok(candle) = parse_kline(&text) {
-                                        let _ = tx.send(candle);
-                                    }
-                                }
-                                Ok(Message::Binary(_)) => {}
-                                Ok(Message::Ping(p)) => {
-                                    let _ = ws.send(Message::Pong(p)).await;
-                                }
-                                Ok(Message::Pong(_)) => {}
-                                Ok(Message::Close(_)) => break,
-                                Ok(Message::Frame(_)) => {}
-                                Err(e) => {
-                                    eprintln!("binance ws error: {e}");
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("binance connect error: {e}");
-                    }
-                }
-
-                sleep(backoff).await;
-                backoff = (backoff * 2).min(max_backoff);
-            }
-        });
-
-        rx
+        self.ws_stream(format!("{symbol}@kline_{interval}"), parse_kline)
     }
 
     async fn candles(
@@ -135,6 +239,7 @@ impl Broker for BinanceBroker {
 
         let symbol = symbol.to_string();
         let interval = interval.to_string();
+        let broker = self.clone();
 
         tokio::task::spawn_blocking(move || {
             let start_ms = from.map(|f| f.timestamp_millis() as u64);
@@ -150,20 +255,15 @@ impl Broker for BinanceBroker {
                 info!("fetching latest {limit} candles for {symbol}");
             }
 
-            let market: Market = Binance::new(None, None);
+            let market = broker.market();
 
-            match market.get_klines(&symbol, &interval, limit, start_ms, end_ms) {
+            match broker.limited(WEIGHT_KLINES, 0, || {
+                market.get_klines(symbol.as_str(), interval.as_str(), limit, start_ms, end_ms)
+            }) {
                 Ok(KlineSummaries::AllKlineSummaries(summaries)) => summaries
-                    .into_iter()
-                    .map(|k| Candle {
-                        open: k.open.parse().unwrap_or(0.0),
-                        high: k.high.parse().unwrap_or(0.0),
-                        low: k.low.parse().unwrap_or(0.0),
-                        close: k.close.parse().unwrap_or(0.0),
-                        volume: k.volume.parse().unwrap_or(0.0),
-                        timestamp: k.close_time,
-                        ts: DateTime::from_timestamp_millis(k.close_time).unwrap(),
-                    })
+                    .iter()
+                    .map(candle_from_summary)
+                    .filter_map(|candle| skip_invalid("candle", candle))
                     .collect(),
                 Err(e) => {
                     error!("failed to fetch klines: {e}");
@@ -176,17 +276,15 @@ impl Broker for BinanceBroker {
     }
 
     fn open_orders(&self, symbol: &str) -> Vec<Order> {
-        let api_key = env::var("BINANCE_API_KEY").ok();
-        let secret_key = env::var("BINANCE_SECRET_KEY").ok();
-
-        if api_key.is_none() || secret_key.is_none() {
-            error!("Binance API credentials not found");
+        let Some(account) = self.account() else {
             return Vec::new();
-        }
-
-        let account: Account = Binance::new(api_key, secret_key);
-        match account.get_open_orders(symbol) {
-            Ok(orders) => orders,
+        };
+        match self.limited(WEIGHT_OPEN_ORDERS, 0, || account.get_open_orders(symbol)) {
+            Ok(orders) => orders
+                .iter()
+                .map(Order::try_from)
+                .filter_map(|order| skip_invalid("order", order))
+                .collect(),
             Err(e) => {
                 error!("Failed to get open orders: {}", e);
                 Vec::new()
@@ -194,18 +292,16 @@ impl Broker for BinanceBroker {
         }
     }
 
-    fn trade_history(&self, symbol: &str) -> Vec<TradeHistory> {
-        let api_key = env::var("BINANCE_API_KEY").ok();
-        let secret_key = env::var("BINANCE_SECRET_KEY").ok();
-
-        if api_key.is_none() || secret_key.is_none() {
-            error!("Binance API credentials not found");
+    fn trade_history(&self, symbol: &str) -> Vec<Fill> {
+        let Some(account) = self.account() else {
             return Vec::new();
-        }
-
-        let account: Account = Binance::new(api_key, secret_key);
-        match account.trade_history(symbol) {
-            Ok(history) => history,
+        };
+        match self.limited(WEIGHT_MY_TRADES, 0, || account.trade_history(symbol)) {
+            Ok(history) => history
+                .iter()
+                .map(|trade| fill_from_binance(symbol, trade))
+                .filter_map(|fill| skip_invalid("fill", fill))
+                .collect(),
             Err(e) => {
                 error!("Failed to get trade history: {}", e);
                 Vec::new()
@@ -214,63 +310,76 @@ impl Broker for BinanceBroker {
     }
 
     fn order_book(&self, symbol: &str, depth: u64) -> OrderBook {
-        let market: Market = Binance::new(None, None);
-        match market.get_custom_depth(symbol, depth) {
-            Ok(book) => book,
+        let market = self.market();
+        match self.limited(depth_weight(depth), 0, || {
+            market.get_custom_depth(symbol, depth)
+        }) {
+            Ok(book) => OrderBook {
+                symbol: symbol.to_uppercase(),
+                timestamp: Utc::now().timestamp_millis(),
+                ..OrderBook::from(book)
+            },
             Err(e) => {
                 error!("Failed to get order book: {}", e);
-                OrderBook {
-                    last_update_id: 0,
-                    bids: vec![],
-                    asks: vec![],
-                }
+                OrderBook::empty(symbol)
             }
         }
     }
 
     fn order_book_stream(&self, symbol: &str) -> broadcast::Receiver<OrderBook> {
-        let (tx, rx) = broadcast::channel::<OrderBook>(1024);
         let symbol = symbol.to_lowercase();
+        self.ws_stream(format!("{symbol}@depth"), parse_order_book)
+    }
+
+    fn trade_stream(&self, symbol: &str, feed: TradeFeed) -> broadcast::Receiver<Trade> {
+        let symbol = symbol.to_lowercase();
+        let stream = match feed {
+            TradeFeed::Trades => format!("{symbol}@trade"),
+            TradeFeed::AggTrades => format!("{symbol}@aggTrade"),
+        };
+
+        self.ws_stream(stream, parse_trade)
+    }
+
+    fn account_stream(&self) -> broadcast::Receiver<AccountEvent> {
+        let mut created = false;
+        let tx = self.account_events.get_or_init(|| {
+            created = true;
+            broadcast::channel::<AccountEvent>(1024).0
+        });
+        let rx = tx.subscribe();
 
-        let market: Market = Binance::new(None, None);
-        let _stream = market.get_depth(symbol.clone());
+        if !created {
+            return rx;
+        }
+
+        if self.user_stream().is_none() {
+            // Nothing will ever be sent, subscribers just wait.
+            return rx;
+        }
+
+        let tx = tx.clone();
+        let broker = self.clone();
 
         tokio::spawn(async move {
             let mut backoff = Duration::from_secs(1);
             let max_backoff = Duration::from_secs(60);
 
             loop {
-                let url = format!("wss://stream.binance.com:9443/ws/{symbol}@depth");
-
-                match tokio_tungstenite::connect_async(&url).await {
-                    Ok((mut ws, _resp)) => {
-                        // Reset backoff on successful connect
+                let starter = broker.clone();
+                match tokio::task::spawn_blocking(move || starter.start_user_stream()).await {
+                    Ok(Ok(listen_key)) => {
                         backoff = Duration::from_secs(1);
+                        broker.consume_user_stream(&listen_key, &tx).await;
 
-                        while let Some(msg) = ws.next().await {
-                            match msg {
-                                Ok(Message::Text(text)) => {
-                                    if let Ok(book) = parse_order_book(&text) {
-                                        let _ = tx.send(book);
-                                    }
-                                }
-                                Ok(Message::Binary(_)) => {}
-                                Ok(Message::Ping(p)) => {
-                                    let _ = ws.send(Message::Pong(p)).await;
-                                }
-                                Ok(Message::Pong(_)) => {}
-                                Ok(Message::Close(_)) => break,
-                                Ok(Message::Frame(_)) => {}
-                                Err(e) => {
-                                    eprintln!("binance ws error: {e}");
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("binance connect error: {e}");
+                        let closer = broker.clone();
+                        let _ = tokio::task::spawn_blocking(move || {
+                            closer.close_user_stream(&listen_key)
+                        })
+                        .await;
                     }
+                    Ok(Err(e)) => error!("Failed to start user data stream: {}", e),
+                    Err(e) => error!("User data stream task failed: {}", e),
                 }
 
                 sleep(backoff).await;
@@ -280,11 +389,382 @@ impl Broker for BinanceBroker {
 
         rx
     }
+
+    fn place_order(&self, order: &OrderRequest) -> anyhow::Result<OrderAck> {
+        if order.is_futures_only() {
+            anyhow::bail!("Reduce-only and close-position orders need BINANCE_MARKET=usdm");
+        }
+
+        let account = self
+            .account()
+            .ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+
+        let order = match self.symbol_info(&order.symbol) {
+            Some(info) => {
+                let normalized = info.normalize(order);
+                let reference_price = match normalized.order_type {
+                    OrderType::Limit { price } => price,
+                    OrderType::StopMarket { stop_price } => stop_price,
+                    OrderType::Market => {
+                        Price::from_f64(self.market_current_price(&normalized.symbol))
+                    }
+                };
+
+                info.validate(&normalized, reference_price).map_err(|e| {
+                    anyhow::anyhow!("Order rejected by {} filters: {e}", normalized.symbol)
+                })?;
+
+                normalized
+            }
+            None => {
+                warn!("No symbol info for {}, submitting unchecked", order.symbol);
+                order.clone()
+            }
+        };
+
+        let symbol = order.symbol.to_uppercase();
+        // binance-rs only takes floats. Values are already on the symbol
+        // increments, so the round trip prints the same decimal string.
+        let quantity = order.quantity.to_f64();
+        let limit_price = match order.order_type {
+            OrderType::Market => None,
+            OrderType::Limit { price } => Some(price.to_f64()),
+            OrderType::StopMarket { .. } => {
+                anyhow::bail!("Stop-market orders need BINANCE_MARKET=usdm")
+            }
+        };
+
+        let result = self.limited(WEIGHT_ORDER, 1, || match (order.side, limit_price) {
+            (Side::Buy, None) => account.market_buy(symbol.as_str(), quantity),
+            (Side::Sell, None) => account.market_sell(symbol.as_str(), quantity),
+            (Side::Buy, Some(price)) => account.limit_buy(symbol.as_str(), quantity, price),
+            (Side::Sell, Some(price)) => account.limit_sell(symbol.as_str(), quantity, price),
+        });
+
+        let transaction = result.map_err(|e| anyhow::anyhow!("Failed to place order: {e}"))?;
+
+        info!(
+            "Placed {:?} order {} on {}: {}",
+            order.side, transaction.order_id, transaction.symbol, transaction.status
+        );
+
+        let executed_qty = Quantity::from_f64(transaction.executed_qty);
+        let average_price = if executed_qty.is_positive() {
+            Price(to_decimal(transaction.cummulative_quote_qty) / executed_qty.value())
+        } else {
+            Price::ZERO
+        };
+
+        Ok(OrderAck {
+            order_id: transaction.order_id.to_string(),
+            symbol: transaction.symbol,
+            side: order.side,
+            status: transaction.status,
+            executed_qty,
+            average_price,
+        })
+    }
+
+    fn cancel_all_orders(&self) -> anyhow::Result<usize> {
+        let account = self
+            .account()
+            .ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+
+        let orders = self
+            .limited(WEIGHT_ALL_OPEN_ORDERS, 0, || account.get_all_open_orders())
+            .map_err(|e| anyhow::anyhow!("Failed to list open orders: {e}"))?;
+
+        cancel_per_symbol(orders.iter().map(|order| order.symbol.as_str()), |symbol| {
+            self.limited(WEIGHT_CANCEL_ALL, 0, || {
+                account.cancel_all_open_orders(symbol)
+            })?;
+            info!("Cancelled all open orders on {}", symbol);
+            Ok(())
+        })
+    }
+
+    fn cancel_orders(&self, symbol: &str) -> anyhow::Result<usize> {
+        let account = self
+            .account()
+            .ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+        let symbol = symbol.to_uppercase();
+
+        let orders = self
+            .limited(WEIGHT_OPEN_ORDERS, 0, || account.get_open_orders(&symbol))
+            .map_err(|e| anyhow::anyhow!("Failed to list open orders on {symbol}: {e}"))?;
+
+        // Binance rejects a cancel-all on a symbol without open orders.
+        if orders.is_empty() {
+            return Ok(0);
+        }
+
+        self.limited(WEIGHT_CANCEL_ALL, 0, || {
+            account.cancel_all_open_orders(&symbol)
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to cancel orders on {symbol}: {e}"))?;
+        info!("Cancelled all open orders on {}", symbol);
+
+        Ok(orders.len())
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        let mut symbols: Vec<SymbolInfo> = self.exchange_symbols().values().cloned().collect();
+        symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        symbols
+    }
+
+    fn symbol_info(&self, symbol: &str) -> Option<SymbolInfo> {
+        self.exchange_symbols().get(&symbol.to_uppercase()).cloned()
+    }
 }
 
 impl BinanceBroker {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            endpoints: BinanceEndpoints::default(),
+            symbols: Arc::new(RwLock::new(None)),
+            limiter: REST_LIMITER.clone(),
+            account_events: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Points REST calls and streams at other base URLs, e.g. the testnet.
+    pub fn with_endpoints(mut self, endpoints: BinanceEndpoints) -> Self {
+        self.endpoints = endpoints;
+        // Symbol rules and accounts differ between deployments.
+        self.symbols = Arc::new(RwLock::new(None));
+        self.account_events = Arc::new(OnceLock::new());
+        self
+    }
+
+    pub fn endpoints(&self) -> &BinanceEndpoints {
+        &self.endpoints
+    }
+
+    fn client_config(&self) -> Config {
+        Config::default()
+            .set_rest_api_endpoint(self.endpoints.rest.as_str())
+            .set_ws_endpoint(self.endpoints.ws.as_str())
+    }
+
+    /// Unauthenticated market data client.
+    fn market(&self) -> Market {
+        Binance::new_with_config(None, None, &self.client_config())
+    }
+
+    /// Uses a dedicated limiter instead of the process-wide one, e.g. for a
+    /// second account behind a different IP.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    pub fn rate_limits(&self) -> RateLimitMetrics {
+        self.limiter.metrics()
+    }
+
+    /// Waits for `weight` request weight and `orders` order slots, runs
+    /// `call` and feeds 429/418 failures back into the limiter.
+    fn limited<T>(
+        &self,
+        weight: u32,
+        orders: u32,
+        call: impl FnOnce() -> binance::errors::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.limiter.acquire(weight, orders)?;
+
+        match call() {
+            Ok(value) => {
+                self.limiter.record_success();
+                Ok(value)
+            }
+            Err(e) => {
+                let error = venue_error(&e);
+                self.limiter.record_error(&error);
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Symbol rules from `exchangeInfo`, cached for `EXCHANGE_INFO_TTL`.
+    /// A failed refresh keeps serving the previous copy.
+    fn exchange_symbols(&self) -> Arc<HashMap<String, SymbolInfo>> {
+        if let Some(cache) = self.symbols.read().unwrap().as_ref()
+            && cache.fetched_at.elapsed() < EXCHANGE_INFO_TTL
+        {
+            return cache.symbols.clone();
+        }
+
+        let general: General = Binance::new_with_config(None, None, &self.client_config());
+
+        match self.limited(WEIGHT_EXCHANGE_INFO, 0, || general.exchange_info()) {
+            Ok(exchange_info) => {
+                let symbols: HashMap<String, SymbolInfo> = exchange_info
+                    .symbols
+                    .iter()
+                    .filter_map(|symbol| {
+                        let info = skip_invalid("symbol rules", parse_symbol_info(symbol))?;
+                        Some((symbol.symbol.clone(), info))
+                    })
+                    .collect();
+                let symbols = Arc::new(symbols);
+
+                info!("Loaded exchange info for {} symbols", symbols.len());
+
+                *self.symbols.write().unwrap() = Some(SymbolCache {
+                    fetched_at: Instant::now(),
+                    symbols: symbols.clone(),
+                });
+
+                symbols
+            }
+            Err(e) => {
+                error!("Failed to get exchange info: {}", e);
+                self.symbols
+                    .read()
+                    .unwrap()
+                    .as_ref()
+                    .map(|cache| cache.symbols.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// Authenticated account client, `None` when credentials are missing.
+    fn account(&self) -> Option<Account> {
+        self.authenticated()
+    }
+
+    fn user_stream(&self) -> Option<UserStream> {
+        self.authenticated()
+    }
+
+    fn authenticated<T: Binance>(&self) -> Option<T> {
+        let api_key = env::var("BINANCE_API_KEY").ok();
+        let secret_key = env::var("BINANCE_SECRET_KEY").ok();
+
+        if api_key.is_none() || secret_key.is_none() {
+            error!("Binance API credentials not found");
+            return None;
+        }
+
+        Some(Binance::new_with_config(
+            api_key,
+            secret_key,
+            &self.client_config(),
+        ))
+    }
+
+    /// Relays the market stream `stream` (e.g. `btcusdt@depth`) parsed with
+    /// `parse`, reconnecting with exponential backoff.
+    fn ws_stream<T>(
+        &self,
+        stream: String,
+        parse: fn(&str) -> Result<T, serde_json::Error>,
+    ) -> broadcast::Receiver<T>
+    where
+        T: Clone + Send + 'static,
+    {
+        relay_stream(format!("{}/ws/{stream}", self.endpoints.ws), parse)
+    }
+
+    /// Creates a listen key for the user data stream.
+    fn start_user_stream(&self) -> anyhow::Result<String> {
+        let client = self
+            .user_stream()
+            .ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+
+        let stream = self.limited(WEIGHT_LISTEN_KEY, 0, || client.start())?;
+        info!("Started user data stream");
+
+        Ok(stream.listen_key)
+    }
+
+    fn keep_alive_user_stream(&self, listen_key: &str) -> anyhow::Result<()> {
+        let client = self
+            .user_stream()
+            .ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+
+        self.limited(WEIGHT_LISTEN_KEY, 0, || client.keep_alive(listen_key))?;
+        Ok(())
+    }
+
+    fn close_user_stream(&self, listen_key: &str) {
+        let Some(client) = self.user_stream() else {
+            return;
+        };
+
+        if let Err(e) = self.limited(WEIGHT_LISTEN_KEY, 0, || client.close(listen_key)) {
+            error!("Failed to close user data stream: {}", e);
+        }
+    }
+
+    /// Forwards user data events until the connection drops, the keepalive
+    /// fails or the listen key expires.
+    async fn consume_user_stream(&self, listen_key: &str, tx: &broadcast::Sender<AccountEvent>) {
+        let url = format!("{}/ws/{listen_key}", self.endpoints.ws);
+
+        let mut ws = match tokio_tungstenite::connect_async(&url).await {
+            Ok((ws, _resp)) => ws,
+            Err(e) => {
+                error!("binance user stream connect error: {e}");
+                return;
+            }
+        };
+
+        let mut keepalive = tokio::time::interval_at(
+            tokio::time::Instant::now() + LISTEN_KEY_KEEPALIVE,
+            LISTEN_KEY_KEEPALIVE,
+        );
+
+        loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    let broker = self.clone();
+                    let listen_key = listen_key.to_string();
+
+                    match tokio::task::spawn_blocking(move || {
+                        broker.keep_alive_user_stream(&listen_key)
+                    })
+                    .await
+                    {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => {
+                            error!("Failed to keep user data stream alive: {}", e);
+                            return;
+                        }
+                        Err(e) => {
+                            error!("User data stream keepalive task failed: {}", e);
+                            return;
+                        }
+                    }
+                }
+                msg = ws.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => match parse_user_event(&text) {
+                            Ok(UserStreamMessage::Event(event)) => {
+                                let _ = tx.send(event);
+                            }
+                            Ok(UserStreamMessage::ListenKeyExpired) => {
+                                info!("User data stream listen key expired");
+                                return;
+                            }
+                            Ok(UserStreamMessage::Other) => {}
+                            Err(e) => error!("Failed to parse user data event: {}", e),
+                        },
+                        Some(Ok(Message::Ping(p))) => {
+                            let _ = ws.send(Message::Pong(p)).await;
+                        }
+                        Some(Ok(Message::Close(_))) | None => return,
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            error!("binance user stream error: {e}");
+                            return;
+                        }
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -294,6 +774,63 @@ impl Default for BinanceBroker {
     }
 }
 
+/// Relays the websocket at `url` parsed with `parse`, reconnecting with
+/// exponential backoff. Shared by the spot and futures adapters, whose
+/// market payloads have the same shape.
+pub(crate) fn relay_stream<T>(
+    url: String,
+    parse: fn(&str) -> Result<T, serde_json::Error>,
+) -> broadcast::Receiver<T>
+where
+    T: Clone + Send + 'static,
+{
+    let (tx, rx) = broadcast::channel::<T>(1024);
+
+    tokio::spawn(async move {
+        let mut backoff = Duration::from_secs(1);
+        let max_backoff = Duration::from_secs(60);
+
+        loop {
+            match tokio_tungstenite::connect_async(&url).await {
+                Ok((mut ws, _resp)) => {
+                    // Reset backoff on successful connect
+                    backoff = Duration::from_secs(1);
+
+                    while let Some(msg) = ws.next().await {
+                        match msg {
+                            Ok(Message::Text(text)) => match parse(&text) {
+                                Ok(item) => {
+                                    let _ = tx.send(item);
+                                }
+                                Err(e) => warn!("Dropping binance ws message: {e}"),
+                            },
+                            Ok(Message::Binary(_)) => {}
+                            Ok(Message::Ping(p)) => {
+                                let _ = ws.send(Message::Pong(p)).await;
+                            }
+                            Ok(Message::Pong(_)) => {}
+                            Ok(Message::Close(_)) => break,
+                            Ok(Message::Frame(_)) => {}
+                            Err(e) => {
+                                eprintln!("binance ws error: {e}");
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("binance connect error: {e}");
+                }
+            }
+
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(max_backoff);
+        }
+    });
+
+    rx
+}
+
 #[derive(Deserialize)]
 struct WsEnvelope {
     // #[serde(default)]
@@ -305,12 +842,12 @@ struct WsEnvelope {
     // Depth update fields
     #[serde(rename = "e", default)]
     event_type: Option<String>,
-    // #[serde(rename = "E", default)]
-    // event_time: Option<u64>,
+    #[serde(rename = "E", default)]
+    event_time: Option<i64>,
     // #[serde(rename = "T", default)]
     // transaction_time: Option<u64>,
-    // #[serde(rename = "s", default)]
-    // symbol: Option<String>,
+    #[serde(rename = "s", default)]
+    symbol: Option<String>,
     // #[serde(rename = "U", default)]
     // first_update_id: Option<u64>,
     #[serde(rename = "u", default)]
@@ -351,47 +888,269 @@ struct KlineInner {
     // is_final: bool,
 }
 
-fn parse_order_book(text: &str) -> Result<OrderBook, serde_json::Error> {
-    let env: WsEnvelope = serde_json::from_str(text)?;
+/// Weight of `GET /api/v3/depth` for a given limit.
+fn depth_weight(depth: u64) -> u32 {
+    match depth {
+        0..=100 => 5,
+        101..=500 => 25,
+        501..=1000 => 50,
+        _ => 250,
+    }
+}
 
-    // Check if this is a depth update event
-    if env.event_type.as_deref() == Some("depthUpdate") {
-        let bids = env
-            .bids
-            .unwrap_or_default()
-            .into_iter()
-            .filter_map(|bid| {
-                if bid.len() >= 2 {
-                    Some(binance::model::Bids {
-                        price: bid[0].parse().unwrap_or(0.0),
-                        qty: bid[1].parse().unwrap_or(0.0),
-                    })
+/// Structured form of a binance-rs error. The client reports statuses other
+/// than 400 only as `Received response: <status>`, so the status is read
+/// back from that message.
+fn venue_error(e: &binance::errors::Error) -> VenueError {
+    use binance::errors::ErrorKind;
+
+    let (status, code) = match e.kind() {
+        ErrorKind::BinanceError(content) => (Some(400), Some(i64::from(content.code))),
+        ErrorKind::ReqError(e) => (e.status().map(|status| status.as_u16()), None),
+        ErrorKind::Msg(message) => (
+            message
+                .strip_prefix("Received response: ")
+                .and_then(|status| status.parse().ok()),
+            None,
+        ),
+        _ => (None, None),
+    };
+
+    VenueError {
+        status,
+        code,
+        errors: Vec::new(),
+        message: e.to_string(),
+    }
+}
+
+fn parse_symbol_info(symbol: &binance::model::Symbol) -> Result<SymbolInfo, serde_json::Error> {
+    let mut info = SymbolInfo {
+        symbol: symbol.symbol.clone(),
+        status: symbol.status.clone(),
+        base_asset: symbol.base_asset.clone(),
+        quote_asset: symbol.quote_asset.clone(),
+        tick_size: Price::ZERO,
+        min_price: Price::ZERO,
+        max_price: Price::ZERO,
+        step_size: Quantity::ZERO,
+        min_qty: Quantity::ZERO,
+        max_qty: Quantity::ZERO,
+        min_notional: Decimal::ZERO,
+    };
+
+    for filter in &symbol.filters {
+        // The filter variants differ between binance-rs releases, so read
+        // them through their JSON form instead of matching on the enum.
+        let Ok(value) = serde_json::to_value(filter) else {
+            continue;
+        };
+
+        match value.get("filterType").and_then(|kind| kind.as_str()) {
+            Some("PRICE_FILTER") => {
+                info.tick_size = Price(filter_value(&value, "tickSize", "tick_size")?);
+                info.min_price = Price(filter_value(&value, "minPrice", "min_price")?);
+                info.max_price = Price(filter_value(&value, "maxPrice", "max_price")?);
+            }
+            Some("LOT_SIZE") => {
+                info.step_size = Quantity(filter_value(&value, "stepSize", "step_size")?);
+                info.min_qty = Quantity(filter_value(&value, "minQty", "min_qty")?);
+                info.max_qty = Quantity(filter_value(&value, "maxQty", "max_qty")?);
+            }
+            Some("MIN_NOTIONAL") | Some("NOTIONAL") => {
+                info.min_notional = filter_value(&value, "minNotional", "min_notional")?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(info)
+}
+
+/// Reads a numeric filter field that Binance sends as a string. A missing
+/// field means no limit and reads as zero.
+pub(crate) fn filter_value(
+    filter: &serde_json::Value,
+    camel: &str,
+    snake: &str,
+) -> Result<Decimal, serde_json::Error> {
+    match filter.get(camel).or_else(|| filter.get(snake)) {
+        Some(serde_json::Value::String(value)) => parse_number(camel, value),
+        Some(value) => value
+            .as_f64()
+            .map(to_decimal)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid {camel} {value}"))),
+        None => Ok(Decimal::ZERO),
+    }
+}
+
+#[derive(Deserialize)]
+struct UserEventKind {
+    #[serde(rename = "e")]
+    event_type: String,
+}
+
+#[derive(Deserialize)]
+struct ExecutionReport {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "c")]
+    client_order_id: String,
+    #[serde(rename = "S")]
+    side: String,
+    #[serde(rename = "o")]
+    order_type: String,
+    #[serde(rename = "q")]
+    quantity: String,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "x")]
+    execution_type: String,
+    #[serde(rename = "X")]
+    status: String,
+    #[serde(rename = "r")]
+    reject_reason: String,
+    #[serde(rename = "i")]
+    order_id: u64,
+    #[serde(rename = "l")]
+    last_filled_qty: String,
+    #[serde(rename = "z")]
+    cumulative_filled_qty: String,
+    #[serde(rename = "L")]
+    last_filled_price: String,
+    #[serde(rename = "n")]
+    commission: String,
+    #[serde(rename = "N", default)]
+    commission_asset: Option<String>,
+    #[serde(rename = "T")]
+    transaction_time: i64,
+    /// `-1` when the update is not a trade.
+    #[serde(rename = "t")]
+    trade_id: i64,
+    #[serde(rename = "Z")]
+    cumulative_quote_qty: String,
+}
+
+#[derive(Deserialize)]
+struct AccountPosition {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "B")]
+    balances: Vec<AccountPositionBalance>,
+}
+
+#[derive(Deserialize)]
+struct AccountPositionBalance {
+    #[serde(rename = "a")]
+    asset: String,
+    #[serde(rename = "f")]
+    free: String,
+    #[serde(rename = "l")]
+    locked: String,
+}
+
+#[derive(Deserialize)]
+struct BalanceUpdate {
+    #[serde(rename = "a")]
+    asset: String,
+    #[serde(rename = "d")]
+    delta: String,
+    #[serde(rename = "T")]
+    clear_time: i64,
+}
+
+enum UserStreamMessage {
+    Event(AccountEvent),
+    ListenKeyExpired,
+    Other,
+}
+
+fn parse_user_event(text: &str) -> Result<UserStreamMessage, serde_json::Error> {
+    let kind: UserEventKind = serde_json::from_str(text)?;
+    let at = |millis: i64| DateTime::from_timestamp_millis(millis).unwrap_or_else(Utc::now);
+
+    let message = match kind.event_type.as_str() {
+        "executionReport" => {
+            let report: ExecutionReport = serde_json::from_str(text)?;
+
+            UserStreamMessage::Event(AccountEvent::Order(OrderUpdate {
+                symbol: report.symbol,
+                order_id: report.order_id.to_string(),
+                client_order_id: report.client_order_id,
+                side: if report.side == "BUY" {
+                    Side::Buy
                 } else {
-                    None
-                }
-            })
-            .collect();
-
-        let asks = env
-            .asks
-            .unwrap_or_default()
-            .into_iter()
-            .filter_map(|ask| {
-                if ask.len() >= 2 {
-                    Some(binance::model::Asks {
-                        price: ask[0].parse().unwrap_or(0.0),
-                        qty: ask[1].parse().unwrap_or(0.0),
+                    Side::Sell
+                },
+                order_type: report.order_type,
+                execution_type: report.execution_type,
+                status: report.status,
+                price: parse_number("price", &report.price)?,
+                quantity: parse_number("quantity", &report.quantity)?,
+                trade_id: u64::try_from(report.trade_id).ok(),
+                last_filled_qty: parse_number("last filled qty", &report.last_filled_qty)?,
+                last_filled_price: parse_number("last filled price", &report.last_filled_price)?,
+                cumulative_filled_qty: parse_number(
+                    "cumulative filled qty",
+                    &report.cumulative_filled_qty,
+                )?,
+                cumulative_quote_qty: parse_number(
+                    "cumulative quote qty",
+                    &report.cumulative_quote_qty,
+                )?,
+                commission: parse_number("commission", &report.commission)?,
+                commission_asset: report.commission_asset,
+                reject_reason: Some(report.reject_reason).filter(|reason| reason != "NONE"),
+                at: at(report.transaction_time),
+            }))
+        }
+        "outboundAccountPosition" => {
+            let position: AccountPosition = serde_json::from_str(text)?;
+
+            UserStreamMessage::Event(AccountEvent::Balances {
+                balances: position
+                    .balances
+                    .into_iter()
+                    .map(|balance| -> Result<Balance, serde_json::Error> {
+                        Ok(Balance {
+                            free: parse_number("free", &balance.free)?,
+                            locked: parse_number("locked", &balance.locked)?,
+                            asset: balance.asset,
+                        })
                     })
-                } else {
-                    None
-                }
+                    .collect::<Result<_, _>>()?,
+                at: at(position.event_time),
+            })
+        }
+        "balanceUpdate" => {
+            let update: BalanceUpdate = serde_json::from_str(text)?;
+
+            UserStreamMessage::Event(AccountEvent::BalanceDelta {
+                asset: update.asset,
+                delta: parse_number("delta", &update.delta)?,
+                at: at(update.clear_time),
             })
-            .collect();
+        }
+        "listenKeyExpired" => UserStreamMessage::ListenKeyExpired,
+        _ => UserStreamMessage::Other,
+    };
+
+    Ok(message)
+}
+
+pub(crate) fn parse_order_book(text: &str) -> Result<OrderBook, serde_json::Error> {
+    let env: WsEnvelope = serde_json::from_str(text)?;
 
+    // Check if this is a depth update event
+    if env.event_type.as_deref() == Some("depthUpdate") {
         return Ok(OrderBook {
+            symbol: env.symbol.unwrap_or_default(),
             last_update_id: env.final_update_id.unwrap_or(0),
-            bids,
-            asks,
+            bids: parse_levels(env.bids.unwrap_or_default())?,
+            asks: parse_levels(env.asks.unwrap_or_default())?,
+            timestamp: env
+                .event_time
+                .unwrap_or_else(|| Utc::now().timestamp_millis()),
         });
     }
 
@@ -400,17 +1159,453 @@ fn parse_order_book(text: &str) -> Result<OrderBook, serde_json::Error> {
     Err(serde_json::Error::custom("Invalid depth update format"))
 }
 
-fn parse_kline(text: &str) -> Result<Candle, serde_json::Error> {
+/// Reads `[price, qty]` string pairs of a depth message.
+pub(crate) fn parse_levels(levels: Vec<Vec<String>>) -> Result<Vec<BookLevel>, serde_json::Error> {
+    levels
+        .into_iter()
+        .filter(|level| level.len() >= 2)
+        .map(|level| -> Result<BookLevel, serde_json::Error> {
+            Ok(BookLevel {
+                price: parse_number("price", &level[0])?,
+                quantity: parse_number("quantity", &level[1])?,
+            })
+        })
+        .collect()
+}
+
+impl TryFrom<&binance::model::Order> for Order {
+    type Error = serde_json::Error;
+
+    fn try_from(order: &binance::model::Order) -> Result<Self, Self::Error> {
+        Ok(Order {
+            order_id: order.order_id.to_string(),
+            client_order_id: order.client_order_id.clone(),
+            symbol: order.symbol.clone(),
+            side: if order.side == "BUY" {
+                Side::Buy
+            } else {
+                Side::Sell
+            },
+            order_type: order.type_name.clone(),
+            price: Price::from_f64(order.price),
+            quantity: parse_number("quantity", &order.orig_qty)?,
+            executed_qty: parse_number("executed qty", &order.executed_qty)?,
+            status: order.status.clone(),
+            time: DateTime::from_timestamp_millis(order.time as i64).unwrap_or_default(),
+        })
+    }
+}
+
+/// Leaves `symbol` and `timestamp` empty, the REST depth response has
+/// neither.
+impl From<binance::model::OrderBook> for OrderBook {
+    fn from(book: binance::model::OrderBook) -> Self {
+        OrderBook {
+            symbol: String::new(),
+            last_update_id: book.last_update_id,
+            bids: book
+                .bids
+                .iter()
+                .map(|bid| BookLevel {
+                    price: Price::from_f64(bid.price),
+                    quantity: Quantity::from_f64(bid.qty),
+                })
+                .collect(),
+            asks: book
+                .asks
+                .iter()
+                .map(|ask| BookLevel {
+                    price: Price::from_f64(ask.price),
+                    quantity: Quantity::from_f64(ask.qty),
+                })
+                .collect(),
+            timestamp: 0,
+        }
+    }
+}
+
+impl TryFrom<&binance::model::Balance> for Balance {
+    type Error = serde_json::Error;
+
+    fn try_from(balance: &binance::model::Balance) -> Result<Self, Self::Error> {
+        Ok(Balance {
+            asset: balance.asset.clone(),
+            free: parse_number("free", &balance.free)?,
+            locked: parse_number("locked", &balance.locked)?,
+        })
+    }
+}
+
+impl TryFrom<&binance::model::PriceStats> for Ticker {
+    type Error = serde_json::Error;
+
+    fn try_from(stats: &binance::model::PriceStats) -> Result<Self, Self::Error> {
+        Ok(Ticker {
+            symbol: stats.symbol.clone(),
+            last_price: Price::from_f64(stats.last_price),
+            bid_price: Price::from_f64(stats.bid_price),
+            ask_price: Price::from_f64(stats.ask_price),
+            open_price: Price::from_f64(stats.open_price),
+            high_price: Price::from_f64(stats.high_price),
+            low_price: Price::from_f64(stats.low_price),
+            volume: Quantity::from_f64(stats.volume),
+            price_change_percent: parse_number(
+                "price change percent",
+                &stats.price_change_percent,
+            )?,
+            time: DateTime::from_timestamp_millis(stats.close_time as i64).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// Trade history entries carry no symbol, so it is passed alongside.
+fn fill_from_binance(
+    symbol: &str,
+    trade: &binance::model::TradeHistory,
+) -> Result<Fill, serde_json::Error> {
+    Ok(Fill {
+        trade_id: Some(trade.id),
+        symbol: symbol.to_uppercase(),
+        side: if trade.is_buyer {
+            Side::Buy
+        } else {
+            Side::Sell
+        },
+        price: Price::from_f64(trade.price),
+        qty: Quantity::from_f64(trade.qty),
+        fee: parse_number("commission", &trade.commission)?,
+        fee_asset: trade.commission_asset.clone(),
+        time: DateTime::from_timestamp_millis(trade.time as i64).unwrap_or_default(),
+    })
+}
+
+#[derive(Deserialize)]
+struct TradeEvent {
+    #[serde(rename = "s")]
+    symbol: String,
+    /// Set on `trade` events.
+    #[serde(rename = "t", default)]
+    trade_id: Option<u64>,
+    /// Set on `aggTrade` events.
+    #[serde(rename = "a", default)]
+    agg_trade_id: Option<u64>,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "q")]
+    qty: String,
+    #[serde(rename = "T")]
+    trade_time: i64,
+    #[serde(rename = "m")]
+    buyer_is_maker: bool,
+}
+
+pub(crate) fn parse_trade(text: &str) -> Result<Trade, serde_json::Error> {
+    let event: TradeEvent = serde_json::from_str(text)?;
+
+    Ok(Trade {
+        symbol: event.symbol,
+        trade_id: event.trade_id.or(event.agg_trade_id).unwrap_or(0),
+        price: parse_number("price", &event.price)?,
+        qty: parse_number("qty", &event.qty)?,
+        // A maker buyer means the seller took liquidity.
+        aggressor: if event.buyer_is_maker {
+            Side::Sell
+        } else {
+            Side::Buy
+        },
+        timestamp: event.trade_time,
+        ts: DateTime::from_timestamp_millis(event.trade_time).unwrap_or_else(Utc::now),
+    })
+}
+
+pub(crate) fn parse_kline(text: &str) -> Result<Candle, serde_json::Error> {
     let env: WsEnvelope = serde_json::from_str(text)?;
-    let k = env.data.map(|d| d.k).or(env.k_inline).expect("kline");
+    let k = env
+        .data
+        .map(|d| d.k)
+        .or(env.k_inline)
+        .ok_or_else(|| serde::de::Error::custom("missing kline"))?;
     Ok(Candle {
-        open: k.open.parse().unwrap_or(0.0),
-        high: k.high.parse().unwrap_or(0.0),
-        low: k.low.parse().unwrap_or(0.0),
-        close: k.close.parse().unwrap_or(0.0),
-        volume: k.volume.parse().unwrap_or(0.0),
+        open: parse_number("open", &k.open)?,
+        high: parse_number("high", &k.high)?,
+        low: parse_number("low", &k.low)?,
+        close: parse_number("close", &k.close)?,
+        volume: parse_number("volume", &k.volume)?,
         // Use close time in ms to align with binance semantics
         timestamp: k.close_time as i64,
-        ts: DateTime::from_timestamp_millis(k.close_time as i64).unwrap(),
+        ts: close_time(k.close_time as i64)?,
     })
 }
+
+fn candle_from_summary(k: &KlineSummary) -> Result<Candle, serde_json::Error> {
+    Ok(Candle {
+        open: parse_number("open", &k.open)?,
+        high: parse_number("high", &k.high)?,
+        low: parse_number("low", &k.low)?,
+        close: parse_number("close", &k.close)?,
+        volume: parse_number("volume", &k.volume)?,
+        timestamp: k.close_time,
+        ts: close_time(k.close_time)?,
+    })
+}
+
+fn close_time(millis: i64) -> Result<DateTime<Utc>, serde_json::Error> {
+    DateTime::from_timestamp_millis(millis)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid close time {millis}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, routing::get};
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::brokers::mock_server;
+
+    const TRADE_EVENT: &str = r#"{"e":"trade","E":1700000000100,"s":"BTCUSDT","t":12345,"p":"65000.50","q":"0.25","b":88,"a":50,"T":1700000000000,"m":true,"M":true}"#;
+
+    fn ticker_24h() -> Value {
+        json!({
+            "symbol": "BTCUSDT",
+            "priceChange": "1000.50",
+            "priceChangePercent": "1.563",
+            "weightedAvgPrice": "64500.00",
+            "prevClosePrice": "64000.00",
+            "lastPrice": "65000.50",
+            "lastQty": "0.01",
+            "bidPrice": "65000.00",
+            "bidQty": "1.00",
+            "askPrice": "65001.00",
+            "askQty": "2.00",
+            "openPrice": "64000.00",
+            "highPrice": "66000.00",
+            "lowPrice": "63000.00",
+            "volume": "1234.5",
+            "quoteVolume": "80000000.0",
+            "openTime": 1699913600000u64,
+            "closeTime": 1700000000000u64,
+            "firstId": 1,
+            "lastId": 100,
+            "count": 100
+        })
+    }
+
+    #[test]
+    fn reads_trade_events() {
+        let trade = parse_trade(TRADE_EVENT).unwrap();
+
+        assert_eq!(trade.symbol, "BTCUSDT");
+        assert_eq!(trade.trade_id, 12345);
+        assert_eq!(trade.price, Price::from_f64(65000.5));
+        assert_eq!(trade.qty, Quantity::from_f64(0.25));
+        // The buyer was the maker, so a seller crossed the spread.
+        assert_eq!(trade.aggressor, Side::Sell);
+        assert_eq!(trade.timestamp, 1_700_000_000_000);
+
+        let malformed = TRADE_EVENT.replace(r#""p":"65000.50""#, r#""p":"n/a""#);
+        assert!(parse_trade(&malformed).is_err());
+    }
+
+    #[test]
+    fn reads_kline_events() {
+        let event = r#"{"e":"kline","E":1700000060000,"s":"BTCUSDT","k":{"t":1700000000000,"T":1700000059999,"s":"BTCUSDT","i":"1m","o":"100.0","c":"105.0","h":"110.0","l":"90.0","v":"12.5","x":true}}"#;
+        let candle = parse_kline(event).unwrap();
+
+        assert_eq!(candle.timestamp, 1_700_000_059_999);
+        assert_eq!(candle.open, Price::from_f64(100.0));
+        assert_eq!(candle.high, Price::from_f64(110.0));
+        assert_eq!(candle.low, Price::from_f64(90.0));
+        assert_eq!(candle.close, Price::from_f64(105.0));
+        assert_eq!(candle.volume, Quantity::from_f64(12.5));
+
+        let without_kline = r#"{"e":"kline","E":1700000060000,"s":"BTCUSDT"}"#;
+        assert!(parse_kline(without_kline).is_err());
+
+        let out_of_range = event.replace("1700000059999", &i64::MAX.to_string());
+        assert!(parse_kline(&out_of_range).is_err());
+    }
+
+    #[test]
+    fn reads_depth_updates() {
+        let event = r#"{"e":"depthUpdate","E":1700000000000,"s":"BTCUSDT","U":157,"u":160,"b":[["65000.00","1.5"],["64999.00","0"]],"a":[["65001.00","2.0"]]}"#;
+        let book = parse_order_book(event).unwrap();
+
+        assert_eq!(book.symbol, "BTCUSDT");
+        assert_eq!(book.last_update_id, 160);
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.bids[0].quantity, Quantity::from_f64(1.5));
+        assert_eq!(book.asks[0].price, Price::from_f64(65001.0));
+
+        let malformed = event.replace(r#"["65001.00","2.0"]"#, r#"["65001.00",""]"#);
+        assert!(parse_order_book(&malformed).is_err());
+        assert!(parse_order_book(TRADE_EVENT).is_err());
+    }
+
+    #[test]
+    fn reads_execution_reports() {
+        let event = r#"{"e":"executionReport","E":1700000000000,"s":"BTCUSDT","c":"client-1","S":"BUY","o":"LIMIT","f":"GTC","q":"0.50000000","p":"65000.00","P":"0.00","F":"0.00","g":-1,"C":"","x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":4293153,"l":"0.20000000","z":"0.20000000","L":"64999.00","n":"0.00020000","N":"BNB","T":1700000000000,"t":77,"I":8641984,"w":false,"m":false,"M":true,"O":1699999999000,"Z":"12999.80","Y":"12999.80","Q":"0.00"}"#;
+
+        let Ok(UserStreamMessage::Event(AccountEvent::Order(update))) = parse_user_event(event)
+        else {
+            panic!("expected an order update");
+        };
+
+        assert_eq!(update.order_id, "4293153");
+        assert_eq!(update.side, Side::Buy);
+        assert_eq!(update.execution_type, "TRADE");
+        assert_eq!(update.trade_id, Some(77));
+        assert_eq!(update.last_filled_qty, Quantity::from_f64(0.2));
+        assert_eq!(update.last_filled_price, Price::from_f64(64999.0));
+        assert_eq!(update.commission, Quantity::from_f64(0.0002));
+        assert_eq!(update.reject_reason, None);
+
+        let malformed = event.replace(r#""L":"64999.00""#, r#""L":"""#);
+        assert!(parse_user_event(&malformed).is_err());
+    }
+
+    #[test]
+    fn reads_balance_updates() {
+        let event = r#"{"e":"outboundAccountPosition","E":1700000000000,"u":1700000000000,"B":[{"a":"BTC","f":"0.5","l":"0.1"},{"a":"USDT","f":"1000.00","l":"0.00"}]}"#;
+
+        let Ok(UserStreamMessage::Event(AccountEvent::Balances { balances, .. })) =
+            parse_user_event(event)
+        else {
+            panic!("expected balances");
+        };
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].asset, "BTC");
+        assert_eq!(balances[0].free, "0.5".parse::<Decimal>().unwrap());
+        assert_eq!(balances[0].locked, "0.1".parse::<Decimal>().unwrap());
+
+        let expired = r#"{"e":"listenKeyExpired","E":1700000000000}"#;
+        assert!(matches!(
+            parse_user_event(expired),
+            Ok(UserStreamMessage::ListenKeyExpired)
+        ));
+    }
+
+    #[test]
+    fn reads_filter_values() {
+        let filter = json!({"filterType": "PRICE_FILTER", "tickSize": "0.01", "min_price": 0.5});
+
+        assert_eq!(
+            filter_value(&filter, "tickSize", "tick_size").unwrap(),
+            "0.01".parse::<Decimal>().unwrap()
+        );
+        assert_eq!(
+            filter_value(&filter, "minPrice", "min_price").unwrap(),
+            "0.5".parse::<Decimal>().unwrap()
+        );
+        assert_eq!(
+            filter_value(&filter, "maxPrice", "max_price").unwrap(),
+            Decimal::ZERO
+        );
+        assert!(filter_value(&json!({"tickSize": "tick"}), "tickSize", "tick_size").is_err());
+    }
+
+    fn lookup<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn picks_the_network_preset() {
+        let testnet = BinanceEndpoints::from_vars(lookup(&[("BINANCE_NETWORK", "testnet")]));
+        assert_eq!(testnet.rest, BinanceEndpoints::testnet().rest);
+
+        let unknown = BinanceEndpoints::from_vars(lookup(&[("BINANCE_NETWORK", "devnet")]));
+        assert_eq!(unknown.rest, BinanceEndpoints::mainnet().rest);
+
+        let futures = BinanceEndpoints::futures_from_vars(lookup(&[]));
+        assert_eq!(futures.rest, BinanceEndpoints::futures_mainnet().rest);
+    }
+
+    #[test]
+    fn applies_spot_and_futures_overrides_separately() {
+        let vars = [
+            ("BINANCE_NETWORK", "testnet"),
+            ("BINANCE_REST_URL", "http://127.0.0.1:9000/"),
+            ("BINANCE_WS_URL", "ws://127.0.0.1:9000/"),
+            ("BINANCE_FUTURES_REST_URL", "http://127.0.0.1:9001"),
+        ];
+
+        let spot = BinanceEndpoints::from_vars(lookup(&vars));
+        assert_eq!(spot.rest, "http://127.0.0.1:9000");
+        assert_eq!(spot.ws, "ws://127.0.0.1:9000");
+
+        let futures = BinanceEndpoints::futures_from_vars(lookup(&vars));
+        assert_eq!(futures.rest, "http://127.0.0.1:9001");
+        assert_eq!(futures.ws, BinanceEndpoints::futures_testnet().ws);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn talks_to_a_mock_venue() {
+        let base = mock_server::serve(
+            Router::new()
+                .route(
+                    "/api/v3/ticker/price",
+                    get(|| async { Json(json!({"symbol": "BTCUSDT", "price": "65000.50"})) }),
+                )
+                .route("/api/v3/ticker/24hr", get(|| async { Json(ticker_24h()) }))
+                .route(
+                    "/api/v3/depth",
+                    get(|| async {
+                        Json(json!({
+                            "lastUpdateId": 1027024,
+                            "bids": [["65000.00", "1.5"]],
+                            "asks": [["65001.00", "2.0"]]
+                        }))
+                    }),
+                )
+                .route(
+                    "/ws/{stream}",
+                    mock_server::ws_sending(vec![TRADE_EVENT.to_string()]),
+                ),
+        )
+        .await;
+
+        let endpoints = BinanceEndpoints {
+            ws: mock_server::ws_url(&base, ""),
+            rest: base,
+        };
+
+        // The blocking HTTP client must not run on an async worker.
+        let (price, ticker, book, mut trades) = tokio::task::spawn_blocking(|| {
+            let broker = BinanceBroker::new()
+                .with_endpoints(endpoints)
+                .with_rate_limiter(Arc::new(RateLimiter::default()));
+
+            (
+                broker.market_current_price("BTCUSDT"),
+                broker.ticker("BTCUSDT"),
+                broker.order_book("BTCUSDT", 5),
+                broker.trade_stream("BTCUSDT", TradeFeed::Trades),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(price, 65000.5);
+
+        let ticker = ticker.unwrap();
+        assert_eq!(ticker.last_price, Price::from_f64(65000.5));
+        assert_eq!(
+            ticker.price_change_percent,
+            "1.563".parse::<Decimal>().unwrap()
+        );
+
+        assert_eq!(book.symbol, "BTCUSDT");
+        assert_eq!(book.last_update_id, 1027024);
+        assert_eq!(book.asks[0].quantity, Quantity::from_f64(2.0));
+
+        let trade = tokio::time::timeout(Duration::from_secs(5), trades.recv())
+            .await
+            .expect("no trade streamed")
+            .unwrap();
+        assert_eq!(trade.trade_id, 12345);
+    }
+}
@@ -0,0 +1,1748 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, LazyLock, OnceLock, RwLock};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::de::{DeserializeOwned, Error as _};
+use sha2::Sha256;
+use tokio::sync::broadcast;
+use tokio::time::{Duration, sleep};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+use crate::brokers::binance::{
+    BinanceEndpoints, filter_value, parse_kline, parse_levels, parse_order_book, parse_trade,
+    relay_stream,
+};
+use crate::brokers::core::{Broker, FuturesBroker, cancel_per_symbol, parse_number, skip_invalid};
+use crate::brokers::rate_limit::{RateLimitConfig, RateLimitMetrics, RateLimiter, VenueError};
+use crate::models::account::{AccountEvent, Balance, OrderUpdate};
+use crate::models::futures::{FundingRate, FuturesPosition, MarginType, MarkPrice};
+use crate::models::market::{OrderBook, Ticker};
+use crate::models::money::{Price, Quantity};
+use crate::models::orders::{Fill, Order, OrderAck, OrderRequest, OrderType, Side};
+use crate::models::symbols::SymbolInfo;
+use crate::models::timeseries::{Candle, Trade, TradeFeed};
+
+/// Request weights of the REST endpoints used below, per the Binance USD-M
+/// futures API docs.
+const WEIGHT_BALANCE: u32 = 5;
+const WEIGHT_POSITION_RISK: u32 = 5;
+const WEIGHT_PRICE: u32 = 1;
+const WEIGHT_TICKER_24H: u32 = 1;
+const WEIGHT_BOOK_TICKER: u32 = 2;
+const WEIGHT_OPEN_ORDERS: u32 = 1;
+const WEIGHT_ALL_OPEN_ORDERS: u32 = 40;
+const WEIGHT_USER_TRADES: u32 = 5;
+const WEIGHT_ORDER: u32 = 1;
+const WEIGHT_CANCEL_ALL: u32 = 1;
+const WEIGHT_EXCHANGE_INFO: u32 = 1;
+const WEIGHT_LEVERAGE: u32 = 1;
+const WEIGHT_MARGIN_TYPE: u32 = 1;
+const WEIGHT_PREMIUM_INDEX: u32 = 1;
+const WEIGHT_FUNDING_RATE: u32 = 1;
+const WEIGHT_LISTEN_KEY: u32 = 1;
+
+/// Depth limits accepted by `GET /fapi/v1/depth`.
+const DEPTH_LIMITS: [u64; 7] = [5, 10, 20, 50, 100, 500, 1000];
+
+/// How long signed requests stay valid after their timestamp.
+const RECV_WINDOW_MS: u64 = 5_000;
+
+/// Listen keys expire after 60 minutes without a keepalive.
+const LISTEN_KEY_KEEPALIVE: Duration = Duration::from_secs(30 * 60);
+
+/// How long `exchangeInfo` is cached before it is fetched again.
+const EXCHANGE_INFO_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Futures limits are separate from spot and enforced per IP, so every
+/// futures broker in the process draws from the same buckets. There is no
+/// daily order cap, only 1,200 orders per minute.
+static REST_LIMITER: LazyLock<Arc<RateLimiter>> = LazyLock::new(|| {
+    Arc::new(RateLimiter::new(RateLimitConfig {
+        request_weight_per_minute: 2_400,
+        orders_per_10s: 300,
+        orders_per_day: 1_200 * 60 * 24,
+        ..Default::default()
+    }))
+});
+
+struct SymbolCache {
+    fetched_at: Instant,
+    symbols: Arc<HashMap<String, SymbolInfo>>,
+}
+
+/// Binance USD-M perpetual futures over the `fapi` REST API and `fstream`
+/// websockets.
+///
+/// Positions are read in one-way mode: a single signed position per symbol,
+/// so selling without a position opens a short.
+#[derive(Clone)]
+pub struct BinanceFuturesBroker {
+    endpoints: BinanceEndpoints,
+    /// Built on first use, since a blocking client must not be created on
+    /// an async worker thread.
+    http: Arc<OnceLock<reqwest::blocking::Client>>,
+    symbols: Arc<RwLock<Option<SymbolCache>>>,
+    limiter: Arc<RateLimiter>,
+    /// Account stream shared by all subscribers, started on first use.
+    account_events: Arc<OnceLock<broadcast::Sender<AccountEvent>>>,
+}
+
+impl Broker for BinanceFuturesBroker {
+    fn balances(&self) -> Vec<Balance> {
+        match self.signed::<Vec<FuturesBalance>>(
+            Method::GET,
+            "/fapi/v2/balance",
+            WEIGHT_BALANCE,
+            0,
+            &[],
+        ) {
+            Ok(balances) => balances
+                .into_iter()
+                .map(|balance| Balance {
+                    asset: balance.asset,
+                    free: balance.available_balance,
+                    locked: balance.balance - balance.available_balance,
+                })
+                .filter(|balance| !balance.total().is_zero())
+                .collect(),
+            Err(e) => {
+                error!("Failed to get futures balances: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn market_current_price(&self, symbol: &str) -> f64 {
+        let query = [("symbol", symbol.to_uppercase())];
+        match self.public::<SymbolPrice>("/fapi/v1/ticker/price", WEIGHT_PRICE, &query) {
+            Ok(price) => price.price.to_f64(),
+            Err(e) => {
+                error!("Failed to get futures price for {}: {}", symbol, e);
+                0.0
+            }
+        }
+    }
+
+    fn ticker(&self, symbol: &str) -> Option<Ticker> {
+        let query = [("symbol", symbol.to_uppercase())];
+        let stats = self.public::<PriceStats>("/fapi/v1/ticker/24hr", WEIGHT_TICKER_24H, &query);
+        let book =
+            self.public::<BookTicker>("/fapi/v1/ticker/bookTicker", WEIGHT_BOOK_TICKER, &query);
+
+        match (stats, book) {
+            (Ok(stats), Ok(book)) => Some(Ticker {
+                symbol: stats.symbol,
+                last_price: stats.last_price,
+                bid_price: book.bid_price,
+                ask_price: book.ask_price,
+                open_price: stats.open_price,
+                high_price: stats.high_price,
+                low_price: stats.low_price,
+                volume: stats.volume,
+                price_change_percent: stats.price_change_percent,
+                time: at(stats.close_time),
+            }),
+            (Err(e), _) | (_, Err(e)) => {
+                error!("Failed to get futures ticker for {}: {}", symbol, e);
+                None
+            }
+        }
+    }
+
+    fn candle_stream(&self, symbol: &str, interval: &str) -> broadcast::Receiver<Candle> {
+        let symbol = symbol.to_lowercase();
+        self.ws_stream(format!("{symbol}@kline_{interval}"), parse_kline)
+    }
+
+    async fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        limit: u16,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Vec<Candle> {
+        let mut query = vec![
+            ("symbol", symbol.to_uppercase()),
+            ("interval", interval.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(from) = from {
+            query.push(("startTime", from.timestamp_millis().to_string()));
+        }
+        if let Some(to) = to {
+            query.push(("endTime", to.timestamp_millis().to_string()));
+        }
+
+        let broker = self.clone();
+
+        tokio::task::spawn_blocking(move || {
+            match broker.public::<Vec<Vec<serde_json::Value>>>(
+                "/fapi/v1/klines",
+                klines_weight(limit),
+                &query,
+            ) {
+                Ok(rows) => rows
+                    .iter()
+                    .map(|row| parse_kline_row(row))
+                    .filter_map(|candle| skip_invalid("candle", candle))
+                    .collect(),
+                Err(e) => {
+                    error!("failed to fetch futures klines: {e}");
+                    Vec::new()
+                }
+            }
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    fn open_orders(&self, symbol: &str) -> Vec<Order> {
+        let params = [("symbol", symbol.to_uppercase())];
+        match self.signed::<Vec<FuturesOrder>>(
+            Method::GET,
+            "/fapi/v1/openOrders",
+            WEIGHT_OPEN_ORDERS,
+            0,
+            &params,
+        ) {
+            Ok(orders) => orders.into_iter().map(Order::from).collect(),
+            Err(e) => {
+                error!("Failed to get futures open orders: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn trade_history(&self, symbol: &str) -> Vec<Fill> {
+        let params = [("symbol", symbol.to_uppercase())];
+        match self.signed::<Vec<UserTrade>>(
+            Method::GET,
+            "/fapi/v1/userTrades",
+            WEIGHT_USER_TRADES,
+            0,
+            &params,
+        ) {
+            Ok(trades) => trades
+                .into_iter()
+                .map(|trade| Fill {
+                    trade_id: Some(trade.id),
+                    symbol: trade.symbol,
+                    side: parse_side(&trade.side),
+                    price: trade.price,
+                    qty: trade.qty,
+                    fee: trade.commission,
+                    fee_asset: trade.commission_asset,
+                    time: at(trade.time),
+                })
+                .collect(),
+            Err(e) => {
+                error!("Failed to get futures trade history: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn order_book(&self, symbol: &str, depth: u64) -> OrderBook {
+        let limit = DEPTH_LIMITS
+            .into_iter()
+            .find(|limit| *limit >= depth)
+            .unwrap_or(1000);
+        let query = [
+            ("symbol", symbol.to_uppercase()),
+            ("limit", limit.to_string()),
+        ];
+
+        match self.public::<DepthSnapshot>("/fapi/v1/depth", depth_weight(limit), &query) {
+            Ok(book) => match (parse_levels(book.bids), parse_levels(book.asks)) {
+                (Ok(bids), Ok(asks)) => OrderBook {
+                    symbol: symbol.to_uppercase(),
+                    last_update_id: book.last_update_id,
+                    bids,
+                    asks,
+                    timestamp: book.event_time,
+                },
+                (Err(e), _) | (_, Err(e)) => {
+                    error!("Invalid futures order book for {}: {}", symbol, e);
+                    OrderBook::empty(symbol)
+                }
+            },
+            Err(e) => {
+                error!("Failed to get futures order book: {}", e);
+                OrderBook::empty(symbol)
+            }
+        }
+    }
+
+    fn order_book_stream(&self, symbol: &str) -> broadcast::Receiver<OrderBook> {
+        let symbol = symbol.to_lowercase();
+        self.ws_stream(format!("{symbol}@depth"), parse_order_book)
+    }
+
+    /// USD-M futures only publish aggregate trades, so both feeds use them.
+    fn trade_stream(&self, symbol: &str, feed: TradeFeed) -> broadcast::Receiver<Trade> {
+        if feed == TradeFeed::Trades {
+            warn!("Binance futures has no raw trade stream, using aggTrade");
+        }
+
+        let symbol = symbol.to_lowercase();
+        self.ws_stream(format!("{symbol}@aggTrade"), parse_trade)
+    }
+
+    fn account_stream(&self) -> broadcast::Receiver<AccountEvent> {
+        let mut created = false;
+        let tx = self.account_events.get_or_init(|| {
+            created = true;
+            broadcast::channel::<AccountEvent>(1024).0
+        });
+        let rx = tx.subscribe();
+
+        if !created {
+            return rx;
+        }
+
+        if credentials().is_none() {
+            error!("Binance API credentials not found");
+            // Nothing will ever be sent, subscribers just wait.
+            return rx;
+        }
+
+        let tx = tx.clone();
+        let broker = self.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            let max_backoff = Duration::from_secs(60);
+
+            loop {
+                let starter = broker.clone();
+                match tokio::task::spawn_blocking(move || starter.start_user_stream()).await {
+                    Ok(Ok(listen_key)) => {
+                        backoff = Duration::from_secs(1);
+                        broker.consume_user_stream(&listen_key, &tx).await;
+
+                        let closer = broker.clone();
+                        let _ =
+                            tokio::task::spawn_blocking(move || closer.close_user_stream()).await;
+                    }
+                    Ok(Err(e)) => error!("Failed to start futures user data stream: {}", e),
+                    Err(e) => error!("Futures user data stream task failed: {}", e),
+                }
+
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+            }
+        });
+
+        rx
+    }
+
+    fn place_order(&self, order: &OrderRequest) -> anyhow::Result<OrderAck> {
+        if order.close_position && !matches!(order.order_type, OrderType::StopMarket { .. }) {
+            anyhow::bail!("Close-position orders must be stop-market orders");
+        }
+
+        let order = match self.symbol_info(&order.symbol) {
+            Some(info) => {
+                let normalized = info.normalize(order);
+                let reference_price = match normalized.order_type {
+                    OrderType::Limit { price } => price,
+                    OrderType::StopMarket { stop_price } => stop_price,
+                    OrderType::Market => {
+                        Price::from_f64(self.market_current_price(&normalized.symbol))
+                    }
+                };
+
+                info.validate(&normalized, reference_price).map_err(|e| {
+                    anyhow::anyhow!("Order rejected by {} filters: {e}", normalized.symbol)
+                })?;
+
+                normalized
+            }
+            None => {
+                warn!("No symbol info for {}, submitting unchecked", order.symbol);
+                order.clone()
+            }
+        };
+
+        let mut params = vec![
+            ("symbol", order.symbol.to_uppercase()),
+            ("side", side_param(order.side).to_string()),
+            ("newOrderRespType", "RESULT".to_string()),
+        ];
+
+        match order.order_type {
+            OrderType::Market => params.push(("type", "MARKET".to_string())),
+            OrderType::Limit { price } => {
+                params.push(("type", "LIMIT".to_string()));
+                params.push(("timeInForce", "GTC".to_string()));
+                params.push(("price", price.to_string()));
+            }
+            OrderType::StopMarket { stop_price } => {
+                params.push(("type", "STOP_MARKET".to_string()));
+                params.push(("stopPrice", stop_price.to_string()));
+            }
+        }
+
+        // Binance rejects a quantity or reduceOnly next to closePosition.
+        if order.close_position {
+            params.push(("closePosition", "true".to_string()));
+        } else {
+            params.push(("quantity", order.quantity.to_string()));
+            if order.reduce_only {
+                params.push(("reduceOnly", "true".to_string()));
+            }
+        }
+
+        let placed: FuturesOrder = self
+            .signed(Method::POST, "/fapi/v1/order", WEIGHT_ORDER, 1, &params)
+            .map_err(|e| anyhow::anyhow!("Failed to place futures order: {e}"))?;
+
+        info!(
+            "Placed {:?} futures order {} on {}: {}",
+            order.side, placed.order_id, placed.symbol, placed.status
+        );
+
+        Ok(OrderAck {
+            order_id: placed.order_id.to_string(),
+            symbol: placed.symbol,
+            side: order.side,
+            status: placed.status,
+            executed_qty: placed.executed_qty,
+            average_price: placed.avg_price,
+        })
+    }
+
+    fn cancel_all_orders(&self) -> anyhow::Result<usize> {
+        let orders: Vec<FuturesOrder> = self
+            .signed(
+                Method::GET,
+                "/fapi/v1/openOrders",
+                WEIGHT_ALL_OPEN_ORDERS,
+                0,
+                &[],
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to list futures open orders: {e}"))?;
+
+        cancel_per_symbol(orders.iter().map(|order| order.symbol.as_str()), |symbol| {
+            self.signed::<serde_json::Value>(
+                Method::DELETE,
+                "/fapi/v1/allOpenOrders",
+                WEIGHT_CANCEL_ALL,
+                0,
+                &[("symbol", symbol.to_string())],
+            )?;
+            info!("Cancelled all open futures orders on {}", symbol);
+            Ok(())
+        })
+    }
+
+    fn cancel_orders(&self, symbol: &str) -> anyhow::Result<usize> {
+        let symbol = symbol.to_uppercase();
+        let open = self.open_orders(&symbol).len();
+
+        if open == 0 {
+            return Ok(0);
+        }
+
+        self.signed::<serde_json::Value>(
+            Method::DELETE,
+            "/fapi/v1/allOpenOrders",
+            WEIGHT_CANCEL_ALL,
+            0,
+            &[("symbol", symbol.clone())],
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to cancel futures orders on {symbol}: {e}"))?;
+        info!("Cancelled all open futures orders on {}", symbol);
+
+        Ok(open)
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        let mut symbols: Vec<SymbolInfo> = self.exchange_symbols().values().cloned().collect();
+        symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        symbols
+    }
+
+    fn symbol_info(&self, symbol: &str) -> Option<SymbolInfo> {
+        self.exchange_symbols().get(&symbol.to_uppercase()).cloned()
+    }
+
+    fn supports_short(&self) -> bool {
+        true
+    }
+
+    /// Market-closes every open position with reduce-only orders, whatever
+    /// its margin asset.
+    fn flatten_orders(&self, _quote_currency: &str) -> Vec<OrderRequest> {
+        self.positions()
+            .into_iter()
+            .map(|position| {
+                let side = if position.is_long() {
+                    Side::Sell
+                } else {
+                    Side::Buy
+                };
+
+                OrderRequest::market(&position.symbol, side, position.quantity.abs()).reduce_only()
+            })
+            .collect()
+    }
+}
+
+impl FuturesBroker for BinanceFuturesBroker {
+    fn positions(&self) -> Vec<FuturesPosition> {
+        match self.signed::<Vec<PositionRisk>>(
+            Method::GET,
+            "/fapi/v2/positionRisk",
+            WEIGHT_POSITION_RISK,
+            0,
+            &[],
+        ) {
+            Ok(positions) => positions
+                .into_iter()
+                .filter(|position| !position.position_amt.is_zero())
+                .map(FuturesPosition::try_from)
+                .filter_map(|position| skip_invalid("position", position))
+                .collect(),
+            Err(e) => {
+                error!("Failed to get futures positions: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn set_leverage(&self, symbol: &str, leverage: u32) -> anyhow::Result<u32> {
+        let params = [
+            ("symbol", symbol.to_uppercase()),
+            ("leverage", leverage.to_string()),
+        ];
+
+        let applied: LeverageResponse = self
+            .signed(
+                Method::POST,
+                "/fapi/v1/leverage",
+                WEIGHT_LEVERAGE,
+                0,
+                &params,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to set leverage on {symbol}: {e}"))?;
+
+        info!("Set {} leverage to {}x", applied.symbol, applied.leverage);
+
+        Ok(applied.leverage)
+    }
+
+    fn set_margin_type(&self, symbol: &str, margin_type: MarginType) -> anyhow::Result<()> {
+        let params = [
+            ("symbol", symbol.to_uppercase()),
+            (
+                "marginType",
+                match margin_type {
+                    MarginType::Isolated => "ISOLATED",
+                    MarginType::Cross => "CROSSED",
+                }
+                .to_string(),
+            ),
+        ];
+
+        match self.signed::<serde_json::Value>(
+            Method::POST,
+            "/fapi/v1/marginType",
+            WEIGHT_MARGIN_TYPE,
+            0,
+            &params,
+        ) {
+            Ok(_) => {
+                info!("Set {} margin type to {:?}", symbol, margin_type);
+                Ok(())
+            }
+            // -4046: the symbol already uses this margin type.
+            Err(e) if e.to_string().contains("-4046") => Ok(()),
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to set margin type on {symbol}: {e}"
+            )),
+        }
+    }
+
+    fn mark_price(&self, symbol: &str) -> Option<MarkPrice> {
+        let query = [("symbol", symbol.to_uppercase())];
+        match self.public::<PremiumIndex>("/fapi/v1/premiumIndex", WEIGHT_PREMIUM_INDEX, &query) {
+            Ok(index) => Some(MarkPrice {
+                symbol: index.symbol,
+                mark_price: index.mark_price,
+                index_price: index.index_price,
+                funding_rate: index.last_funding_rate,
+                next_funding_time: at(index.next_funding_time),
+                time: at(index.time),
+            }),
+            Err(e) => {
+                error!("Failed to get mark price for {}: {}", symbol, e);
+                None
+            }
+        }
+    }
+
+    fn funding_rates(&self, symbol: &str, limit: u16) -> Vec<FundingRate> {
+        let query = [
+            ("symbol", symbol.to_uppercase()),
+            ("limit", limit.min(1000).to_string()),
+        ];
+
+        match self.public::<Vec<FundingRateEntry>>(
+            "/fapi/v1/fundingRate",
+            WEIGHT_FUNDING_RATE,
+            &query,
+        ) {
+            Ok(rates) => rates
+                .into_iter()
+                .map(|rate| -> Result<FundingRate, serde_json::Error> {
+                    // Older settlements have no mark price.
+                    let mark_price = if rate.mark_price.is_empty() {
+                        Price::ZERO
+                    } else {
+                        parse_number("mark price", &rate.mark_price)?
+                    };
+
+                    Ok(FundingRate {
+                        symbol: rate.symbol,
+                        funding_rate: rate.funding_rate,
+                        funding_time: at(rate.funding_time),
+                        mark_price,
+                    })
+                })
+                .filter_map(|rate| skip_invalid("funding rate", rate))
+                .collect(),
+            Err(e) => {
+                error!("Failed to get funding rates for {}: {}", symbol, e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn mark_price_stream(&self, symbol: &str) -> broadcast::Receiver<MarkPrice> {
+        let symbol = symbol.to_lowercase();
+        self.ws_stream(format!("{symbol}@markPrice@1s"), parse_mark_price)
+    }
+
+    fn funding_rate_stream(&self, symbol: &str) -> broadcast::Receiver<FundingRate> {
+        let mut marks = self.mark_price_stream(symbol);
+        let (tx, rx) = broadcast::channel::<FundingRate>(64);
+
+        tokio::spawn(async move {
+            let mut last = None;
+
+            loop {
+                match marks.recv().await {
+                    Ok(mark) => {
+                        let current = Some((mark.funding_rate, mark.next_funding_time));
+                        if current == last {
+                            continue;
+                        }
+                        last = current;
+
+                        let rate = FundingRate {
+                            symbol: mark.symbol,
+                            funding_rate: mark.funding_rate,
+                            funding_time: mark.next_funding_time,
+                            mark_price: mark.mark_price,
+                        };
+
+                        // Every subscriber is gone.
+                        if tx.send(rate).is_err() {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+
+        rx
+    }
+}
+
+impl BinanceFuturesBroker {
+    pub fn new() -> Self {
+        Self {
+            endpoints: BinanceEndpoints::futures_mainnet(),
+            http: Arc::new(OnceLock::new()),
+            symbols: Arc::new(RwLock::new(None)),
+            limiter: REST_LIMITER.clone(),
+            account_events: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Points REST calls and streams at other base URLs, e.g. the testnet.
+    pub fn with_endpoints(mut self, endpoints: BinanceEndpoints) -> Self {
+        self.endpoints = endpoints;
+        // Symbol rules and accounts differ between deployments.
+        self.symbols = Arc::new(RwLock::new(None));
+        self.account_events = Arc::new(OnceLock::new());
+        self
+    }
+
+    pub fn endpoints(&self) -> &BinanceEndpoints {
+        &self.endpoints
+    }
+
+    /// Uses a dedicated limiter instead of the process-wide one.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    pub fn rate_limits(&self) -> RateLimitMetrics {
+        self.limiter.metrics()
+    }
+
+    fn http(&self) -> &reqwest::blocking::Client {
+        self.http.get_or_init(reqwest::blocking::Client::new)
+    }
+
+    /// Waits for capacity, runs `call` and feeds 429/418 failures back into
+    /// the limiter.
+    fn limited<T>(
+        &self,
+        weight: u32,
+        orders: u32,
+        call: impl FnOnce() -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.limiter.acquire(weight, orders)?;
+
+        match call() {
+            Ok(value) => {
+                self.limiter.record_success();
+                Ok(value)
+            }
+            Err(e) => {
+                self.limiter.record_failure(&e);
+                Err(e)
+            }
+        }
+    }
+
+    fn public<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        weight: u32,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        self.limited(weight, 0, || {
+            let url = format!("{}{path}", self.endpoints.rest);
+            let response = self.http().get(url).query(query).send()?;
+
+            parse_response(path, response)
+        })
+    }
+
+    /// Signed request with `timestamp`, `recvWindow` and the HMAC-SHA256
+    /// `signature` appended to the query string.
+    fn signed<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        weight: u32,
+        orders: u32,
+        params: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        let (api_key, secret) =
+            credentials().ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+
+        self.limited(weight, orders, || {
+            // Values are symbols, enum names and decimal numbers, none of
+            // which need percent-encoding.
+            let query = params
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .chain([
+                    format!("recvWindow={RECV_WINDOW_MS}"),
+                    format!("timestamp={}", Utc::now().timestamp_millis()),
+                ])
+                .collect::<Vec<_>>()
+                .join("&");
+
+            let signature = sign(&query, &secret)?;
+            let url = format!(
+                "{}{path}?{query}&signature={signature}",
+                self.endpoints.rest
+            );
+
+            let response = self
+                .http()
+                .request(method, url)
+                .header("X-MBX-APIKEY", api_key.as_str())
+                .send()?;
+
+            parse_response(path, response)
+        })
+    }
+
+    /// Listen key calls only need the API key header, not a signature.
+    fn listen_key_request(&self, method: Method) -> anyhow::Result<ListenKey> {
+        let (api_key, _) =
+            credentials().ok_or_else(|| anyhow::anyhow!("Binance API credentials not found"))?;
+
+        self.limited(WEIGHT_LISTEN_KEY, 0, || {
+            let path = "/fapi/v1/listenKey";
+            let response = self
+                .http()
+                .request(method, format!("{}{path}", self.endpoints.rest))
+                .header("X-MBX-APIKEY", api_key.as_str())
+                .send()?;
+
+            parse_response(path, response)
+        })
+    }
+
+    /// Perpetual contract rules from `exchangeInfo`, cached for
+    /// `EXCHANGE_INFO_TTL`. A failed refresh keeps serving the previous copy.
+    fn exchange_symbols(&self) -> Arc<HashMap<String, SymbolInfo>> {
+        if let Some(cache) = self.symbols.read().unwrap().as_ref()
+            && cache.fetched_at.elapsed() < EXCHANGE_INFO_TTL
+        {
+            return cache.symbols.clone();
+        }
+
+        match self.public::<ExchangeInfo>("/fapi/v1/exchangeInfo", WEIGHT_EXCHANGE_INFO, &[]) {
+            Ok(exchange_info) => {
+                let symbols: HashMap<String, SymbolInfo> = exchange_info
+                    .symbols
+                    .iter()
+                    .filter(|symbol| symbol.contract_type == "PERPETUAL")
+                    .filter_map(|symbol| {
+                        let info = skip_invalid("symbol rules", parse_symbol_info(symbol))?;
+                        Some((symbol.symbol.clone(), info))
+                    })
+                    .collect();
+                let symbols = Arc::new(symbols);
+
+                info!("Loaded futures exchange info for {} symbols", symbols.len());
+
+                *self.symbols.write().unwrap() = Some(SymbolCache {
+                    fetched_at: Instant::now(),
+                    symbols: symbols.clone(),
+                });
+
+                symbols
+            }
+            Err(e) => {
+                error!("Failed to get futures exchange info: {}", e);
+                self.symbols
+                    .read()
+                    .unwrap()
+                    .as_ref()
+                    .map(|cache| cache.symbols.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    fn ws_stream<T>(
+        &self,
+        stream: String,
+        parse: fn(&str) -> Result<T, serde_json::Error>,
+    ) -> broadcast::Receiver<T>
+    where
+        T: Clone + Send + 'static,
+    {
+        relay_stream(format!("{}/ws/{stream}", self.endpoints.ws), parse)
+    }
+
+    fn start_user_stream(&self) -> anyhow::Result<String> {
+        let key = self.listen_key_request(Method::POST)?;
+        info!("Started futures user data stream");
+        Ok(key.listen_key)
+    }
+
+    /// A futures account has a single listen key, so keepalive and close
+    /// do not take it.
+    fn keep_alive_user_stream(&self) -> anyhow::Result<()> {
+        self.listen_key_request(Method::PUT)?;
+        Ok(())
+    }
+
+    fn close_user_stream(&self) {
+        if let Err(e) = self.listen_key_request(Method::DELETE) {
+            error!("Failed to close futures user data stream: {}", e);
+        }
+    }
+
+    /// Forwards user data events until the connection drops, the keepalive
+    /// fails or the listen key expires.
+    async fn consume_user_stream(&self, listen_key: &str, tx: &broadcast::Sender<AccountEvent>) {
+        let url = format!("{}/ws/{listen_key}", self.endpoints.ws);
+
+        let mut ws = match tokio_tungstenite::connect_async(&url).await {
+            Ok((ws, _resp)) => ws,
+            Err(e) => {
+                error!("binance futures user stream connect error: {e}");
+                return;
+            }
+        };
+
+        let mut keepalive = tokio::time::interval_at(
+            tokio::time::Instant::now() + LISTEN_KEY_KEEPALIVE,
+            LISTEN_KEY_KEEPALIVE,
+        );
+
+        loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    let broker = self.clone();
+
+                    match tokio::task::spawn_blocking(move || broker.keep_alive_user_stream()).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => {
+                            error!("Failed to keep futures user data stream alive: {}", e);
+                            return;
+                        }
+                        Err(e) => {
+                            error!("Futures user data stream keepalive task failed: {}", e);
+                            return;
+                        }
+                    }
+                }
+                msg = ws.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => match parse_user_event(&text) {
+                            Ok(UserStreamMessage::Events(events)) => {
+                                for event in events {
+                                    let _ = tx.send(event);
+                                }
+                            }
+                            Ok(UserStreamMessage::ListenKeyExpired) => {
+                                info!("Futures user data stream listen key expired");
+                                return;
+                            }
+                            Ok(UserStreamMessage::Other) => {}
+                            Err(e) => error!("Failed to parse futures user data event: {}", e),
+                        },
+                        Some(Ok(Message::Ping(p))) => {
+                            let _ = ws.send(Message::Pong(p)).await;
+                        }
+                        Some(Ok(Message::Close(_))) | None => return,
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            error!("binance futures user stream error: {e}");
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for BinanceFuturesBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Same keys as the spot adapter; the key needs futures permissions.
+fn credentials() -> Option<(String, String)> {
+    let api_key = env::var("BINANCE_API_KEY").ok()?;
+    let secret = env::var("BINANCE_SECRET_KEY").ok()?;
+    Some((api_key, secret))
+}
+
+/// Hex HMAC-SHA256 of the query string, keyed with the secret.
+fn sign(query: &str, secret: &str) -> anyhow::Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid Binance secret: {e}"))?;
+    mac.update(query.as_bytes());
+
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    code: i64,
+    msg: String,
+}
+
+/// Turns non-2xx responses into `VenueError`s carrying the status and
+/// Binance error code, which the limiter reads to detect 429/418.
+fn parse_response<T: DeserializeOwned>(
+    path: &str,
+    response: reqwest::blocking::Response,
+) -> anyhow::Result<T> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        let error = serde_json::from_str::<ApiError>(&body).ok();
+
+        return Err(VenueError {
+            status: Some(status.as_u16()),
+            code: error.as_ref().map(|error| error.code),
+            errors: Vec::new(),
+            message: match error {
+                Some(error) => format!(
+                    "Binance {path} returned {status}: {} {}",
+                    error.code, error.msg
+                ),
+                None => format!("Binance {path} returned {status}"),
+            },
+        }
+        .into());
+    }
+
+    Ok(response.json()?)
+}
+
+fn at(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_else(Utc::now)
+}
+
+fn parse_side(side: &str) -> Side {
+    if side.eq_ignore_ascii_case("BUY") {
+        Side::Buy
+    } else {
+        Side::Sell
+    }
+}
+
+fn side_param(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "BUY",
+        Side::Sell => "SELL",
+    }
+}
+
+fn parse_margin_type(margin_type: &str) -> MarginType {
+    if margin_type.eq_ignore_ascii_case("isolated") {
+        MarginType::Isolated
+    } else {
+        MarginType::Cross
+    }
+}
+
+/// Weight of `GET /fapi/v1/klines` for a given limit.
+fn klines_weight(limit: u16) -> u32 {
+    match limit {
+        0..100 => 1,
+        100..500 => 2,
+        500..=1000 => 5,
+        _ => 10,
+    }
+}
+
+/// Weight of `GET /fapi/v1/depth` for one of `DEPTH_LIMITS`.
+fn depth_weight(limit: u64) -> u32 {
+    match limit {
+        0..=50 => 2,
+        51..=100 => 5,
+        101..=500 => 10,
+        _ => 20,
+    }
+}
+
+/// Reads a REST kline row: `[openTime, open, high, low, close, volume,
+/// closeTime, ...]` with prices as strings.
+fn parse_kline_row(row: &[serde_json::Value]) -> Result<Candle, serde_json::Error> {
+    let decimal = |index: usize, field: &str| -> Result<Decimal, serde_json::Error> {
+        let value = row
+            .get(index)
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| serde_json::Error::custom(format!("missing {field}")))?;
+        parse_number(field, value)
+    };
+    let close_time = row
+        .get(6)
+        .and_then(|value| value.as_i64())
+        .ok_or_else(|| serde_json::Error::custom("missing close time"))?;
+
+    Ok(Candle {
+        open: Price(decimal(1, "open")?),
+        high: Price(decimal(2, "high")?),
+        low: Price(decimal(3, "low")?),
+        close: Price(decimal(4, "close")?),
+        volume: Quantity(decimal(5, "volume")?),
+        timestamp: close_time,
+        ts: DateTime::from_timestamp_millis(close_time)
+            .ok_or_else(|| serde_json::Error::custom("invalid close time"))?,
+    })
+}
+
+fn parse_symbol_info(symbol: &FuturesSymbol) -> Result<SymbolInfo, serde_json::Error> {
+    let mut info = SymbolInfo {
+        symbol: symbol.symbol.clone(),
+        status: symbol.status.clone(),
+        base_asset: symbol.base_asset.clone(),
+        quote_asset: symbol.quote_asset.clone(),
+        tick_size: Price::ZERO,
+        min_price: Price::ZERO,
+        max_price: Price::ZERO,
+        step_size: Quantity::ZERO,
+        min_qty: Quantity::ZERO,
+        max_qty: Quantity::ZERO,
+        min_notional: Decimal::ZERO,
+    };
+
+    for filter in &symbol.filters {
+        match filter.get("filterType").and_then(|kind| kind.as_str()) {
+            Some("PRICE_FILTER") => {
+                info.tick_size = Price(filter_value(filter, "tickSize", "tick_size")?);
+                info.min_price = Price(filter_value(filter, "minPrice", "min_price")?);
+                info.max_price = Price(filter_value(filter, "maxPrice", "max_price")?);
+            }
+            Some("LOT_SIZE") => {
+                info.step_size = Quantity(filter_value(filter, "stepSize", "step_size")?);
+                info.min_qty = Quantity(filter_value(filter, "minQty", "min_qty")?);
+                info.max_qty = Quantity(filter_value(filter, "maxQty", "max_qty")?);
+            }
+            // Futures name the field `notional` rather than `minNotional`.
+            Some("MIN_NOTIONAL") => {
+                info.min_notional = filter_value(filter, "notional", "minNotional")?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(info)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FuturesBalance {
+    asset: String,
+    balance: Decimal,
+    available_balance: Decimal,
+}
+
+#[derive(Deserialize)]
+struct SymbolPrice {
+    price: Price,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PriceStats {
+    symbol: String,
+    price_change_percent: Decimal,
+    last_price: Price,
+    open_price: Price,
+    high_price: Price,
+    low_price: Price,
+    volume: Quantity,
+    close_time: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BookTicker {
+    bid_price: Price,
+    ask_price: Price,
+}
+
+#[derive(Deserialize)]
+struct DepthSnapshot {
+    #[serde(rename = "lastUpdateId")]
+    last_update_id: u64,
+    #[serde(rename = "E", default)]
+    event_time: i64,
+    bids: Vec<Vec<String>>,
+    asks: Vec<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FuturesOrder {
+    order_id: u64,
+    #[serde(default)]
+    client_order_id: String,
+    symbol: String,
+    side: String,
+    status: String,
+    #[serde(rename = "type")]
+    order_type: String,
+    price: Price,
+    orig_qty: Quantity,
+    executed_qty: Quantity,
+    avg_price: Price,
+    #[serde(default)]
+    update_time: i64,
+}
+
+impl From<FuturesOrder> for Order {
+    fn from(order: FuturesOrder) -> Self {
+        Order {
+            order_id: order.order_id.to_string(),
+            client_order_id: order.client_order_id,
+            symbol: order.symbol,
+            side: parse_side(&order.side),
+            order_type: order.order_type,
+            price: order.price,
+            quantity: order.orig_qty,
+            executed_qty: order.executed_qty,
+            status: order.status,
+            time: at(order.update_time),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserTrade {
+    id: u64,
+    symbol: String,
+    side: String,
+    price: Price,
+    qty: Quantity,
+    commission: Quantity,
+    commission_asset: String,
+    time: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExchangeInfo {
+    symbols: Vec<FuturesSymbol>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FuturesSymbol {
+    symbol: String,
+    status: String,
+    #[serde(default)]
+    contract_type: String,
+    base_asset: String,
+    quote_asset: String,
+    #[serde(default)]
+    filters: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PositionRisk {
+    symbol: String,
+    position_amt: Quantity,
+    entry_price: Price,
+    mark_price: Price,
+    un_realized_profit: Decimal,
+    liquidation_price: Price,
+    leverage: String,
+    margin_type: String,
+    #[serde(default)]
+    notional: Decimal,
+    #[serde(default)]
+    update_time: i64,
+}
+
+impl TryFrom<PositionRisk> for FuturesPosition {
+    type Error = serde_json::Error;
+
+    fn try_from(position: PositionRisk) -> Result<Self, Self::Error> {
+        Ok(FuturesPosition {
+            symbol: position.symbol,
+            quantity: position.position_amt,
+            entry_price: position.entry_price,
+            mark_price: position.mark_price,
+            liquidation_price: position.liquidation_price,
+            unrealized_pnl: position.un_realized_profit,
+            leverage: parse_number("leverage", &position.leverage)?,
+            margin_type: parse_margin_type(&position.margin_type),
+            notional: position.notional,
+            updated_at: at(position.update_time),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct LeverageResponse {
+    symbol: String,
+    leverage: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PremiumIndex {
+    symbol: String,
+    mark_price: Price,
+    index_price: Price,
+    last_funding_rate: Decimal,
+    next_funding_time: i64,
+    time: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FundingRateEntry {
+    symbol: String,
+    funding_rate: Decimal,
+    funding_time: i64,
+    /// Empty for older settlements.
+    #[serde(default)]
+    mark_price: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListenKey {
+    #[serde(default)]
+    listen_key: String,
+}
+
+#[derive(Deserialize)]
+struct MarkPriceEvent {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "p")]
+    mark_price: String,
+    #[serde(rename = "i")]
+    index_price: String,
+    #[serde(rename = "r")]
+    funding_rate: String,
+    #[serde(rename = "T")]
+    next_funding_time: i64,
+}
+
+fn parse_mark_price(text: &str) -> Result<MarkPrice, serde_json::Error> {
+    let event: MarkPriceEvent = serde_json::from_str(text)?;
+
+    Ok(MarkPrice {
+        symbol: event.symbol,
+        mark_price: parse_number("mark price", &event.mark_price)?,
+        index_price: parse_number("index price", &event.index_price)?,
+        funding_rate: parse_number("funding rate", &event.funding_rate)?,
+        next_funding_time: at(event.next_funding_time),
+        time: at(event.event_time),
+    })
+}
+
+#[derive(Deserialize)]
+struct UserEventKind {
+    #[serde(rename = "e")]
+    event_type: String,
+}
+
+#[derive(Deserialize)]
+struct OrderTradeUpdate {
+    #[serde(rename = "T")]
+    transaction_time: i64,
+    #[serde(rename = "o")]
+    order: ExecutionReport,
+}
+
+#[derive(Deserialize)]
+struct ExecutionReport {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "c")]
+    client_order_id: String,
+    #[serde(rename = "S")]
+    side: String,
+    #[serde(rename = "o")]
+    order_type: String,
+    #[serde(rename = "q")]
+    quantity: String,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "ap")]
+    average_price: String,
+    #[serde(rename = "x")]
+    execution_type: String,
+    #[serde(rename = "X")]
+    status: String,
+    #[serde(rename = "i")]
+    order_id: u64,
+    #[serde(rename = "l")]
+    last_filled_qty: String,
+    #[serde(rename = "z")]
+    cumulative_filled_qty: String,
+    #[serde(rename = "L")]
+    last_filled_price: String,
+    #[serde(rename = "n", default)]
+    commission: Option<String>,
+    #[serde(rename = "N", default)]
+    commission_asset: Option<String>,
+    /// Zero when the update is not a trade.
+    #[serde(rename = "t", default)]
+    trade_id: u64,
+}
+
+#[derive(Deserialize)]
+struct AccountUpdate {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "a")]
+    data: AccountUpdateData,
+}
+
+#[derive(Deserialize)]
+struct AccountUpdateData {
+    /// Why balances changed: `ORDER`, `FUNDING_FEE`, `DEPOSIT`, ...
+    #[serde(rename = "m")]
+    reason: String,
+    #[serde(rename = "B", default)]
+    balances: Vec<WalletBalance>,
+    #[serde(rename = "P", default)]
+    positions: Vec<PositionUpdate>,
+}
+
+#[derive(Deserialize)]
+struct WalletBalance {
+    #[serde(rename = "a")]
+    asset: String,
+    #[serde(rename = "wb")]
+    wallet_balance: String,
+    #[serde(rename = "cw")]
+    cross_wallet_balance: String,
+    #[serde(rename = "bc")]
+    balance_change: String,
+}
+
+#[derive(Deserialize)]
+struct PositionUpdate {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "pa")]
+    position_amount: String,
+    #[serde(rename = "ep")]
+    entry_price: String,
+    #[serde(rename = "up")]
+    unrealized_pnl: String,
+    #[serde(rename = "mt")]
+    margin_type: String,
+}
+
+enum UserStreamMessage {
+    Events(Vec<AccountEvent>),
+    ListenKeyExpired,
+    Other,
+}
+
+fn parse_user_event(text: &str) -> Result<UserStreamMessage, serde_json::Error> {
+    let kind: UserEventKind = serde_json::from_str(text)?;
+
+    let message = match kind.event_type.as_str() {
+        "ORDER_TRADE_UPDATE" => {
+            let update: OrderTradeUpdate = serde_json::from_str(text)?;
+            let report = update.order;
+
+            let cumulative_filled_qty: Quantity =
+                parse_number("cumulative filled qty", &report.cumulative_filled_qty)?;
+            let average_price: Price = parse_number("average price", &report.average_price)?;
+
+            UserStreamMessage::Events(vec![AccountEvent::Order(OrderUpdate {
+                symbol: report.symbol,
+                order_id: report.order_id.to_string(),
+                client_order_id: report.client_order_id,
+                side: parse_side(&report.side),
+                order_type: report.order_type,
+                execution_type: report.execution_type,
+                status: report.status,
+                price: parse_number("price", &report.price)?,
+                quantity: parse_number("quantity", &report.quantity)?,
+                trade_id: Some(report.trade_id).filter(|id| *id > 0),
+                last_filled_qty: parse_number("last filled qty", &report.last_filled_qty)?,
+                last_filled_price: parse_number("last filled price", &report.last_filled_price)?,
+                cumulative_filled_qty,
+                cumulative_quote_qty: cumulative_filled_qty * average_price,
+                // Only sent once something was charged.
+                commission: report
+                    .commission
+                    .map(|commission| parse_number("commission", &commission))
+                    .transpose()?
+                    .unwrap_or_default(),
+                commission_asset: report.commission_asset,
+                reject_reason: None,
+                at: at(update.transaction_time),
+            })])
+        }
+        "ACCOUNT_UPDATE" => {
+            let update: AccountUpdate = serde_json::from_str(text)?;
+            let time = at(update.event_time);
+            let mut events = Vec::new();
+
+            if !update.data.balances.is_empty() {
+                events.push(AccountEvent::Balances {
+                    balances: update
+                        .data
+                        .balances
+                        .iter()
+                        .map(|balance| -> Result<Balance, serde_json::Error> {
+                            let wallet: Decimal =
+                                parse_number("wallet balance", &balance.wallet_balance)?;
+                            let cross: Decimal = parse_number(
+                                "cross wallet balance",
+                                &balance.cross_wallet_balance,
+                            )?;
+
+                            // Isolated margin is tied to its position.
+                            Ok(Balance {
+                                asset: balance.asset.clone(),
+                                free: cross,
+                                locked: wallet - cross,
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                    at: time,
+                });
+            }
+
+            // Fills already arrive as order updates; everything else, such
+            // as funding fees and transfers, is reported as a delta.
+            if update.data.reason != "ORDER" {
+                for balance in &update.data.balances {
+                    let delta: Decimal = parse_number("balance change", &balance.balance_change)?;
+                    if !delta.is_zero() {
+                        events.push(AccountEvent::BalanceDelta {
+                            asset: balance.asset.clone(),
+                            delta,
+                            at: time,
+                        });
+                    }
+                }
+            }
+
+            if !update.data.positions.is_empty() {
+                events.push(AccountEvent::Positions {
+                    positions: update
+                        .data
+                        .positions
+                        .into_iter()
+                        .map(|position| -> Result<FuturesPosition, serde_json::Error> {
+                            Ok(FuturesPosition {
+                                quantity: parse_number(
+                                    "position amount",
+                                    &position.position_amount,
+                                )?,
+                                entry_price: parse_number("entry price", &position.entry_price)?,
+                                mark_price: Price::ZERO,
+                                liquidation_price: Price::ZERO,
+                                unrealized_pnl: parse_number(
+                                    "unrealized pnl",
+                                    &position.unrealized_pnl,
+                                )?,
+                                leverage: 0,
+                                margin_type: parse_margin_type(&position.margin_type),
+                                notional: Decimal::ZERO,
+                                updated_at: time,
+                                symbol: position.symbol,
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                    at: time,
+                });
+            }
+
+            UserStreamMessage::Events(events)
+        }
+        "listenKeyExpired" => UserStreamMessage::ListenKeyExpired,
+        _ => UserStreamMessage::Other,
+    };
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, routing::get};
+    use serde_json::json;
+
+    use super::*;
+    use crate::brokers::mock_server;
+
+    const MARK_PRICE_EVENT: &str = r#"{"e":"markPriceUpdate","E":1700000000000,"s":"BTCUSDT","p":"65000.10","i":"64990.00","P":"65010.00","r":"0.00010000","T":1700006400000}"#;
+
+    #[test]
+    fn reads_kline_rows() {
+        let row = json!([
+            1700000000000i64,
+            "100.0",
+            "110.0",
+            "90.0",
+            "105.0",
+            "12.5",
+            1700000059999i64,
+            "1300.0",
+            10,
+            "6.0",
+            "650.0",
+            "0"
+        ]);
+        let candle = parse_kline_row(row.as_array().unwrap()).unwrap();
+
+        assert_eq!(candle.timestamp, 1_700_000_059_999);
+        assert_eq!(candle.open, Price::from_f64(100.0));
+        assert_eq!(candle.close, Price::from_f64(105.0));
+        assert_eq!(candle.volume, Quantity::from_f64(12.5));
+
+        let mut malformed = row.clone();
+        malformed[4] = json!("close");
+        assert!(parse_kline_row(malformed.as_array().unwrap()).is_err());
+    }
+
+    #[test]
+    fn reads_mark_price_events() {
+        let mark = parse_mark_price(MARK_PRICE_EVENT).unwrap();
+
+        assert_eq!(mark.symbol, "BTCUSDT");
+        assert_eq!(mark.mark_price, Price::from_f64(65000.1));
+        assert_eq!(mark.index_price, Price::from_f64(64990.0));
+        assert_eq!(mark.funding_rate, "0.0001".parse::<Decimal>().unwrap());
+
+        let malformed = MARK_PRICE_EVENT.replace(r#""p":"65000.10""#, r#""p":"""#);
+        assert!(parse_mark_price(&malformed).is_err());
+    }
+
+    #[test]
+    fn reads_order_trade_updates() {
+        let event = r#"{"e":"ORDER_TRADE_UPDATE","E":1700000000000,"T":1700000000000,"o":{"s":"BTCUSDT","c":"client-1","S":"SELL","o":"MARKET","f":"GTC","q":"0.010","p":"0","ap":"65000.00","sp":"0","x":"TRADE","X":"FILLED","i":8886774,"l":"0.010","z":"0.010","L":"65000.00","T":1700000000000,"t":555,"b":"0","a":"0","m":false,"R":false,"wt":"CONTRACT_PRICE","ot":"MARKET","ps":"BOTH","cp":false,"rp":"0"}}"#;
+
+        let Ok(UserStreamMessage::Events(events)) = parse_user_event(event) else {
+            panic!("expected events");
+        };
+        let [AccountEvent::Order(update)] = events.as_slice() else {
+            panic!("expected one order update, got {events:?}");
+        };
+
+        assert_eq!(update.side, Side::Sell);
+        assert_eq!(update.trade_id, Some(555));
+        assert_eq!(update.last_filled_qty, Quantity::from_f64(0.01));
+        assert_eq!(
+            update.cumulative_quote_qty,
+            "650".parse::<Decimal>().unwrap()
+        );
+        // No commission field until something was charged.
+        assert!(update.commission.is_zero());
+
+        let malformed = event.replace(r#""ap":"65000.00""#, r#""ap":"avg""#);
+        assert!(parse_user_event(&malformed).is_err());
+    }
+
+    #[test]
+    fn reads_account_updates() {
+        let event = r#"{"e":"ACCOUNT_UPDATE","E":1700000000000,"T":1700000000000,"a":{"m":"FUNDING_FEE","B":[{"a":"USDT","wb":"1000.00","cw":"900.00","bc":"-1.25"}],"P":[{"s":"BTCUSDT","pa":"-0.010","ep":"65000.00","cr":"0","up":"-2.50","mt":"isolated","iw":"100.00","ps":"BOTH"}]}}"#;
+
+        let Ok(UserStreamMessage::Events(events)) = parse_user_event(event) else {
+            panic!("expected events");
+        };
+
+        let [
+            AccountEvent::Balances { balances, .. },
+            AccountEvent::BalanceDelta { delta, .. },
+            AccountEvent::Positions { positions, .. },
+        ] = events.as_slice()
+        else {
+            panic!("unexpected events {events:?}");
+        };
+
+        assert_eq!(balances[0].free, "900".parse::<Decimal>().unwrap());
+        assert_eq!(balances[0].locked, "100".parse::<Decimal>().unwrap());
+        assert_eq!(*delta, "-1.25".parse::<Decimal>().unwrap());
+        assert_eq!(positions[0].quantity, Quantity::from_f64(-0.01));
+        assert_eq!(positions[0].margin_type, MarginType::Isolated);
+    }
+
+    #[test]
+    fn rejects_malformed_positions() {
+        let position: PositionRisk = serde_json::from_value(json!({
+            "symbol": "BTCUSDT",
+            "positionAmt": "0.010",
+            "entryPrice": "65000.00",
+            "markPrice": "65100.00",
+            "unRealizedProfit": "1.00",
+            "liquidationPrice": "0",
+            "leverage": "ten",
+            "marginType": "cross"
+        }))
+        .unwrap();
+
+        assert!(FuturesPosition::try_from(position).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn talks_to_a_mock_venue() {
+        let base = mock_server::serve(
+            Router::new()
+                .route(
+                    "/fapi/v1/ticker/price",
+                    get(|| async { Json(json!({"symbol": "BTCUSDT", "price": "65000.50"})) }),
+                )
+                .route(
+                    "/fapi/v1/premiumIndex",
+                    get(|| async {
+                        Json(json!({
+                            "symbol": "BTCUSDT",
+                            "markPrice": "65000.10",
+                            "indexPrice": "64990.00",
+                            "estimatedSettlePrice": "64995.00",
+                            "lastFundingRate": "0.00010000",
+                            "interestRate": "0.00010000",
+                            "nextFundingTime": 1700006400000i64,
+                            "time": 1700000000000i64
+                        }))
+                    }),
+                )
+                .route(
+                    "/fapi/v1/depth",
+                    get(|| async {
+                        Json(json!({
+                            "lastUpdateId": 1027024,
+                            "E": 1700000000000i64,
+                            "T": 1700000000000i64,
+                            "bids": [["65000.00", "1.5"]],
+                            "asks": [["65001.00", "2.0"]]
+                        }))
+                    }),
+                )
+                .route(
+                    "/ws/{stream}",
+                    mock_server::ws_sending(vec![MARK_PRICE_EVENT.to_string()]),
+                ),
+        )
+        .await;
+
+        let endpoints = BinanceEndpoints {
+            ws: mock_server::ws_url(&base, ""),
+            rest: base,
+        };
+
+        // The blocking HTTP client must not run on an async worker.
+        let (price, mark, book, mut marks) = tokio::task::spawn_blocking(|| {
+            let broker = BinanceFuturesBroker::new()
+                .with_endpoints(endpoints)
+                .with_rate_limiter(Arc::new(RateLimiter::default()));
+
+            (
+                broker.market_current_price("BTCUSDT"),
+                broker.mark_price("BTCUSDT"),
+                broker.order_book("BTCUSDT", 5),
+                broker.mark_price_stream("BTCUSDT"),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(price, 65000.5);
+        assert_eq!(mark.unwrap().mark_price, Price::from_f64(65000.1));
+        assert_eq!(book.last_update_id, 1027024);
+        assert_eq!(book.bids[0].price, Price::from_f64(65000.0));
+
+        let streamed = tokio::time::timeout(Duration::from_secs(5), marks.recv())
+            .await
+            .expect("no mark price streamed")
+            .unwrap();
+        assert_eq!(streamed.index_price, Price::from_f64(64990.0));
+    }
+}
@@ -1,11 +1,35 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
 
-use binance::model::{Order, OrderBook, TradeHistory};
 use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::de::Error as _;
+use tracing::error;
 
+use crate::models::{
+    account::{AccountEvent, Balance},
+    futures::{FundingRate, FuturesPosition, MarginType, MarkPrice},
+    market::{OrderBook, Ticker},
+    money::Quantity,
+    orders::{Fill, Order, OrderAck, OrderRequest, Side},
+    symbols::SymbolInfo,
+    timeseries::{Trade, TradeFeed},
+};
+
+/// Unified interface to a trading venue. Implementations convert venue
+/// payloads into the models in `crate::models`.
 pub trait Broker {
-    fn balance(&self) -> HashMap<String, f64>;
+    /// Free amount of every asset with a positive free balance.
+    fn balance(&self) -> HashMap<String, f64> {
+        self.balances()
+            .into_iter()
+            .filter_map(|balance| Some((balance.asset, balance.free.to_f64()?)))
+            .filter(|(_, free)| *free > 0.0)
+            .collect()
+    }
+    /// Free and locked amounts of every asset held on the account.
+    fn balances(&self) -> Vec<Balance>;
     fn market_current_price(&self, symbol: &str) -> f64;
+    fn ticker(&self, symbol: &str) -> Option<Ticker>;
     fn candle_stream(
         &self,
         symbol: &str,
@@ -20,7 +44,183 @@ pub trait Broker {
         to: Option<DateTime<Utc>>,
     ) -> impl std::future::Future<Output = Vec<crate::models::timeseries::Candle>> + Send;
     fn open_orders(&self, symbol: &str) -> Vec<Order>;
-    fn trade_history(&self, symbol: &str) -> Vec<TradeHistory>;
+    fn trade_history(&self, symbol: &str) -> Vec<Fill>;
     fn order_book(&self, symbol: &str, depth: u64) -> OrderBook;
     fn order_book_stream(&self, symbol: &str) -> tokio::sync::broadcast::Receiver<OrderBook>;
+    fn trade_stream(
+        &self,
+        symbol: &str,
+        feed: TradeFeed,
+    ) -> tokio::sync::broadcast::Receiver<Trade>;
+    /// Order and balance updates of the authenticated account, pushed as they
+    /// happen.
+    fn account_stream(&self) -> tokio::sync::broadcast::Receiver<AccountEvent>;
+    fn place_order(&self, order: &OrderRequest) -> anyhow::Result<OrderAck>;
+    /// Cancels every open order across all symbols, returning how many were open.
+    fn cancel_all_orders(&self) -> anyhow::Result<usize>;
+    /// Cancels every open order on `symbol`, returning how many were open.
+    fn cancel_orders(&self, symbol: &str) -> anyhow::Result<usize>;
+    /// Trading rules (tick size, lot size, min notional, status) of every
+    /// symbol listed on the venue.
+    fn symbols(&self) -> Vec<SymbolInfo>;
+    fn symbol_info(&self, symbol: &str) -> Option<SymbolInfo> {
+        let symbol = symbol.to_uppercase();
+        self.symbols()
+            .into_iter()
+            .find(|info| info.symbol == symbol)
+    }
+    /// Whether selling without holding the asset opens a short position.
+    fn supports_short(&self) -> bool {
+        false
+    }
+    /// Called by the runner once it has handled a candle or trade. Replays
+    /// wait for it before sending the next message; live venues ignore it.
+    fn market_data_handled(&self) {}
+    /// Orders that close every holding into `quote_currency`, used by the
+    /// kill switch. Spot venues sell each free balance.
+    fn flatten_orders(&self, quote_currency: &str) -> Vec<OrderRequest> {
+        self.balances()
+            .into_iter()
+            .filter(|balance| balance.asset != quote_currency && balance.free > Decimal::ZERO)
+            .map(|balance| {
+                OrderRequest::market(
+                    &format!("{}{quote_currency}", balance.asset),
+                    Side::Sell,
+                    Quantity::new(balance.free),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Perpetual futures venues, on top of the orders and streams of `Broker`.
+pub trait FuturesBroker: Broker {
+    /// Every position with a non-zero quantity.
+    fn positions(&self) -> Vec<FuturesPosition>;
+    /// Sets the initial leverage of `symbol`, returning what the venue
+    /// applied.
+    fn set_leverage(&self, symbol: &str, leverage: u32) -> anyhow::Result<u32>;
+    fn set_margin_type(&self, symbol: &str, margin_type: MarginType) -> anyhow::Result<()>;
+    fn mark_price(&self, symbol: &str) -> Option<MarkPrice>;
+    /// Settled funding rates, oldest first.
+    fn funding_rates(&self, symbol: &str, limit: u16) -> Vec<FundingRate>;
+    fn mark_price_stream(&self, symbol: &str) -> tokio::sync::broadcast::Receiver<MarkPrice>;
+    /// Predicted funding rate, sent whenever the rate or the next funding
+    /// time changes.
+    fn funding_rate_stream(&self, symbol: &str) -> tokio::sync::broadcast::Receiver<FundingRate>;
+}
+
+/// Parses a number a venue sends as a string. A malformed value is an error
+/// rather than zero, which would pass for a real price, amount or fee.
+pub(crate) fn parse_number<T>(field: &str, value: &str) -> Result<T, serde_json::Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| serde_json::Error::custom(format!("invalid {field} {value:?}: {e}")))
+}
+
+/// The converted entry, or `None` with the error logged, so one malformed
+/// row does not hide the rest of a response.
+pub(crate) fn skip_invalid<T>(what: &str, entry: Result<T, serde_json::Error>) -> Option<T> {
+    match entry {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            error!("Skipping {}: {}", what, e);
+            None
+        }
+    }
+}
+
+/// Returned by `Broker::cancel_all_orders` when some symbols could not be
+/// cancelled. Every symbol is still attempted, and `cancelled` counts the
+/// orders on the ones that succeeded.
+#[derive(Debug)]
+pub struct CancelAllError {
+    pub cancelled: usize,
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for CancelAllError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cancelled {} orders, {} symbols failed: {}",
+            self.cancelled,
+            self.errors.len(),
+            self.errors.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for CancelAllError {}
+
+/// Runs `cancel` once per distinct symbol in `order_symbols`, one entry per
+/// open order, and keeps going past failures so one bad symbol does not
+/// leave the rest live. Returns the number of orders cancelled, or a
+/// `CancelAllError` listing every failed symbol.
+pub(crate) fn cancel_per_symbol<'a>(
+    order_symbols: impl IntoIterator<Item = &'a str>,
+    mut cancel: impl FnMut(&str) -> anyhow::Result<()>,
+) -> anyhow::Result<usize> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for symbol in order_symbols {
+        *counts.entry(symbol).or_default() += 1;
+    }
+
+    let mut symbols: Vec<(&str, usize)> = counts.into_iter().collect();
+    symbols.sort();
+
+    let mut cancelled = 0;
+    let mut errors = Vec::new();
+
+    for (symbol, count) in symbols {
+        match cancel(symbol) {
+            Ok(()) => cancelled += count,
+            Err(e) => {
+                error!("Failed to cancel orders on {}: {}", symbol, e);
+                errors.push(format!("{symbol}: {e}"));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(cancelled)
+    } else {
+        Err(CancelAllError { cancelled, errors }.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancels_every_symbol_past_a_failure() {
+        let mut attempted = Vec::new();
+
+        let result = cancel_per_symbol(["BTCUSDT", "ETHUSDT", "BTCUSDT", "SOLUSDT"], |symbol| {
+            attempted.push(symbol.to_string());
+            if symbol == "ETHUSDT" {
+                anyhow::bail!("timeout");
+            }
+            Ok(())
+        });
+
+        assert_eq!(attempted, ["BTCUSDT", "ETHUSDT", "SOLUSDT"]);
+
+        let error = result.unwrap_err();
+        let error = error.downcast_ref::<CancelAllError>().unwrap();
+        assert_eq!(error.cancelled, 3);
+        assert_eq!(error.errors, ["ETHUSDT: timeout"]);
+    }
+
+    #[test]
+    fn counts_every_order_when_all_symbols_succeed() {
+        let cancelled = cancel_per_symbol(["BTCUSDT", "BTCUSDT", "ETHUSDT"], |_| Ok(())).unwrap();
+
+        assert_eq!(cancelled, 3);
+    }
 }
@@ -0,0 +1,1888 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, OnceLock, RwLock};
+use std::time::Instant;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::de::{DeserializeOwned, Error as _};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256, Sha512};
+use tokio::sync::broadcast;
+use tokio::time::{Duration, sleep};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+use crate::brokers::core::{Broker, parse_number, skip_invalid};
+use crate::brokers::rate_limit::{RateLimitConfig, RateLimitMetrics, RateLimiter, VenueError};
+use crate::models::account::{AccountEvent, Balance, OrderUpdate};
+use crate::models::market::{BookLevel, OrderBook, Ticker};
+use crate::models::money::{Price, Quantity, to_decimal};
+use crate::models::orders::{Fill, Order, OrderAck, OrderRequest, OrderType, Side};
+use crate::models::symbols::SymbolInfo;
+use crate::models::timeseries::{Candle, Trade, TradeFeed, parse_interval};
+use crate::portfolio::ledger::split_symbol;
+
+/// Weights charged against the limiter. Kraken meters public calls at about
+/// one per second per IP and private calls through a decaying counter of
+/// roughly one call every three seconds.
+const WEIGHT_PUBLIC: u32 = 1;
+const WEIGHT_PRIVATE: u32 = 3;
+const WEIGHT_HISTORY: u32 = 6;
+
+/// How long `AssetPairs` is cached before it is fetched again.
+const ASSET_PAIRS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Levels kept per side on the book channel.
+const BOOK_DEPTH: usize = 25;
+
+/// Kraken limits are enforced per IP and API key, so every broker instance
+/// in the process draws from the same buckets.
+static REST_LIMITER: LazyLock<Arc<RateLimiter>> = LazyLock::new(|| {
+    Arc::new(RateLimiter::new(RateLimitConfig {
+        request_weight_per_minute: 60,
+        orders_per_10s: 10,
+        orders_per_day: 100_000,
+        ..Default::default()
+    }))
+});
+
+/// REST and websocket base URLs of a Kraken deployment.
+#[derive(Debug, Clone)]
+pub struct KrakenEndpoints {
+    /// REST base, e.g. `https://api.kraken.com`.
+    pub rest: String,
+    /// Public websocket v2 URL.
+    pub ws: String,
+    /// Authenticated websocket v2 URL, used for executions and balances.
+    pub ws_auth: String,
+}
+
+impl KrakenEndpoints {
+    pub fn production() -> Self {
+        Self {
+            rest: "https://api.kraken.com".to_string(),
+            ws: "wss://ws.kraken.com/v2".to_string(),
+            ws_auth: "wss://ws-auth.kraken.com/v2".to_string(),
+        }
+    }
+
+    /// Production endpoints with `KRAKEN_REST_URL`, `KRAKEN_WS_URL` and
+    /// `KRAKEN_WS_AUTH_URL` overrides, e.g. for a local mock server.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// `from_env` reading the variables through `var`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut endpoints = Self::production();
+
+        if let Some(rest) = var("KRAKEN_REST_URL") {
+            endpoints.rest = rest;
+        }
+
+        if let Some(ws) = var("KRAKEN_WS_URL") {
+            endpoints.ws = ws;
+        }
+
+        if let Some(ws_auth) = var("KRAKEN_WS_AUTH_URL") {
+            endpoints.ws_auth = ws_auth;
+        }
+
+        endpoints.rest = endpoints.rest.trim_end_matches('/').to_string();
+
+        endpoints
+    }
+}
+
+impl Default for KrakenEndpoints {
+    fn default() -> Self {
+        Self::production()
+    }
+}
+
+/// A Kraken pair under its three names, plus its trading rules.
+#[derive(Debug, Clone)]
+struct KrakenPair {
+    /// Key in `AssetPairs` and in private history, e.g. `XXBTZUSD`.
+    name: String,
+    /// REST pair name and order descriptions, e.g. `XBTUSD`.
+    altname: String,
+    /// Websocket v2 symbol, e.g. `BTC/USD`.
+    ws_symbol: String,
+    info: SymbolInfo,
+}
+
+impl KrakenPair {
+    /// Best guess for a pair missing from `AssetPairs`, so calls still go
+    /// out when the pair list could not be loaded.
+    fn guess(symbol: &str) -> Self {
+        let symbol = symbol.to_uppercase();
+        let (base, quote) = split_symbol(&symbol).unwrap_or((symbol.clone(), String::new()));
+
+        Self {
+            name: symbol.clone(),
+            altname: symbol.clone(),
+            ws_symbol: format!("{base}/{quote}"),
+            info: SymbolInfo {
+                symbol,
+                status: "TRADING".to_string(),
+                base_asset: base,
+                quote_asset: quote,
+                tick_size: Price::ZERO,
+                min_price: Price::ZERO,
+                max_price: Price::ZERO,
+                step_size: Quantity::ZERO,
+                min_qty: Quantity::ZERO,
+                max_qty: Quantity::ZERO,
+                min_notional: Decimal::ZERO,
+            },
+        }
+    }
+}
+
+struct PairCache {
+    fetched_at: Instant,
+    /// Keyed by engine symbol, e.g. `BTCUSD`.
+    pairs: Arc<HashMap<String, KrakenPair>>,
+}
+
+/// Spot broker for Kraken over its REST API and websocket v2 feeds.
+///
+/// Symbols use the engine convention (`BTCUSD`, `ETHUSDT`) and are mapped to
+/// Kraken pair names through `AssetPairs`, so `XBT` and the `X`/`Z` asset
+/// prefixes never leave this module.
+#[derive(Clone)]
+pub struct KrakenBroker {
+    endpoints: KrakenEndpoints,
+    /// Built on first use, since a blocking client must not be created on
+    /// an async worker thread.
+    http: Arc<OnceLock<reqwest::blocking::Client>>,
+    pairs: Arc<RwLock<Option<PairCache>>>,
+    limiter: Arc<RateLimiter>,
+    nonce: Arc<AtomicU64>,
+    /// Account stream shared by all subscribers, started on first use.
+    account_events: Arc<OnceLock<broadcast::Sender<AccountEvent>>>,
+}
+
+impl Broker for KrakenBroker {
+    fn balances(&self) -> Vec<Balance> {
+        match self.private::<HashMap<String, ExtendedBalance>>("BalanceEx", WEIGHT_PRIVATE, &[]) {
+            Ok(balances) => balances
+                .into_iter()
+                .map(|(asset, balance)| -> Result<Balance, serde_json::Error> {
+                    let total: Decimal = parse_number("balance", &balance.balance)?;
+                    let locked: Decimal = if balance.hold_trade.is_empty() {
+                        Decimal::ZERO
+                    } else {
+                        parse_number("hold_trade", &balance.hold_trade)?
+                    };
+
+                    Ok(Balance {
+                        asset: normalize_asset(&asset),
+                        free: total - locked,
+                        locked,
+                    })
+                })
+                .filter_map(|balance| skip_invalid("balance", balance))
+                .filter(|balance| !balance.total().is_zero())
+                .collect(),
+            Err(e) => {
+                error!("Failed to get balance: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn market_current_price(&self, symbol: &str) -> f64 {
+        let pair = self.pair(symbol);
+
+        match self.public::<HashMap<String, TickerInfo>>("Ticker", &[("pair", pair.altname)]) {
+            Ok(tickers) => {
+                let last_trade = tickers
+                    .values()
+                    .next()
+                    .and_then(|ticker| ticker.last_trade.first());
+
+                match last_trade.map(|price| parse_number::<f64>("last trade", price)) {
+                    Some(Ok(price)) => price,
+                    Some(Err(e)) => {
+                        error!("Invalid market price for {}: {}", symbol, e);
+                        0.0
+                    }
+                    None => {
+                        error!("No market price for {}", symbol);
+                        0.0
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to get market price for {}: {}", symbol, e);
+                0.0
+            }
+        }
+    }
+
+    fn ticker(&self, symbol: &str) -> Option<Ticker> {
+        let pair = self.pair(symbol);
+        let query = [("pair", pair.altname.clone())];
+
+        match self.public::<HashMap<String, TickerInfo>>("Ticker", &query) {
+            Ok(tickers) => tickers
+                .into_values()
+                .next()
+                .and_then(|ticker| skip_invalid("ticker", ticker.into_ticker(&pair.info.symbol))),
+            Err(e) => {
+                error!("Failed to get ticker for {}: {}", symbol, e);
+                None
+            }
+        }
+    }
+
+    fn candle_stream(&self, symbol: &str, interval: &str) -> broadcast::Receiver<Candle> {
+        let Some(minutes) = interval_minutes(interval) else {
+            error!("Kraken has no {} candles", interval);
+            return broadcast::channel(1).1;
+        };
+
+        let subscription = json!({
+            "method": "subscribe",
+            "params": {
+                "channel": "ohlc",
+                "symbol": [self.cached_pair(symbol).ws_symbol],
+                "interval": minutes,
+            },
+        });
+
+        self.ws_stream(subscription, || parse_ohlc)
+    }
+
+    async fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        limit: u16,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Vec<Candle> {
+        let Some(minutes) = interval_minutes(interval) else {
+            error!("Kraken has no {} candles", interval);
+            return Vec::new();
+        };
+
+        let symbol = symbol.to_string();
+        let broker = self.clone();
+
+        tokio::task::spawn_blocking(move || {
+            info!("fetching latest {limit} candles for {symbol}");
+
+            let mut query = vec![
+                ("pair", broker.pair(&symbol).altname),
+                ("interval", minutes.to_string()),
+            ];
+            if let Some(from) = from {
+                query.push(("since", from.timestamp().to_string()));
+            }
+
+            // The pair key varies, so read the rows next to `last` untyped.
+            let result = match broker.public::<HashMap<String, Value>>("OHLC", &query) {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("failed to fetch ohlc: {e}");
+                    return Vec::new();
+                }
+            };
+
+            let interval_ms = minutes as i64 * 60_000;
+            let mut candles: Vec<Candle> = result
+                .iter()
+                .filter(|(key, _)| key.as_str() != "last")
+                .filter_map(|(_, rows)| rows.as_array())
+                .flatten()
+                .map(|row| parse_ohlc_row(row, interval_ms))
+                .filter_map(|candle| skip_invalid("candle", candle))
+                .filter(|candle| to.is_none_or(|to| candle.ts <= to))
+                .collect();
+
+            let skip = candles.len().saturating_sub(limit as usize);
+            candles.drain(..skip);
+            candles
+        })
+        .await
+        .unwrap()
+    }
+
+    fn open_orders(&self, symbol: &str) -> Vec<Order> {
+        let pair = self.pair(symbol);
+
+        match self.private::<OpenOrders>("OpenOrders", WEIGHT_PRIVATE, &[]) {
+            Ok(open) => open
+                .open
+                .into_iter()
+                .filter(|(_, order)| {
+                    order.descr.pair == pair.altname || order.descr.pair == pair.name
+                })
+                .map(|(txid, order)| -> Result<Order, serde_json::Error> {
+                    Ok(Order {
+                        price: parse_number("price", &order.descr.price)?,
+                        quantity: parse_number("vol", &order.vol)?,
+                        executed_qty: parse_number("vol_exec", &order.vol_exec)?,
+                        order_id: txid,
+                        client_order_id: order.cl_ord_id.unwrap_or_default(),
+                        symbol: pair.info.symbol.clone(),
+                        side: parse_side(&order.descr.side),
+                        order_type: order.descr.ordertype.to_uppercase(),
+                        status: order.status.to_uppercase(),
+                        time: from_unix_seconds(order.opentm),
+                    })
+                })
+                .filter_map(|order| skip_invalid("order", order))
+                .collect(),
+            Err(e) => {
+                error!("Failed to get open orders: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn trade_history(&self, symbol: &str) -> Vec<Fill> {
+        let pair = self.pair(symbol);
+
+        match self.private::<TradesHistory>("TradesHistory", WEIGHT_HISTORY, &[]) {
+            Ok(history) => {
+                let mut fills: Vec<Fill> = history
+                    .trades
+                    .into_values()
+                    .filter(|trade| trade.pair == pair.name || trade.pair == pair.altname)
+                    .map(|trade| -> Result<Fill, serde_json::Error> {
+                        Ok(Fill {
+                            trade_id: trade.trade_id,
+                            symbol: pair.info.symbol.clone(),
+                            side: parse_side(&trade.side),
+                            price: parse_number("price", &trade.price)?,
+                            qty: parse_number("vol", &trade.vol)?,
+                            fee: parse_number("fee", &trade.fee)?,
+                            // Spot fees are charged in the quote currency.
+                            fee_asset: pair.info.quote_asset.clone(),
+                            time: from_unix_seconds(trade.time),
+                        })
+                    })
+                    .filter_map(|fill| skip_invalid("fill", fill))
+                    .collect();
+
+                fills.sort_by_key(|fill| fill.time);
+                fills
+            }
+            Err(e) => {
+                error!("Failed to get trade history: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn order_book(&self, symbol: &str, depth: u64) -> OrderBook {
+        let pair = self.pair(symbol);
+        let query = [("pair", pair.altname.clone()), ("count", depth.to_string())];
+
+        match self.public::<HashMap<String, DepthResult>>("Depth", &query) {
+            Ok(books) => {
+                let book = books.into_values().next().unwrap_or_default();
+
+                match (parse_rest_levels(&book.bids), parse_rest_levels(&book.asks)) {
+                    (Ok(bids), Ok(asks)) => OrderBook {
+                        symbol: pair.info.symbol,
+                        last_update_id: 0,
+                        bids,
+                        asks,
+                        timestamp: Utc::now().timestamp_millis(),
+                    },
+                    (Err(e), _) | (_, Err(e)) => {
+                        error!("Invalid order book for {}: {}", symbol, e);
+                        OrderBook::empty(symbol)
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to get order book: {}", e);
+                OrderBook::empty(symbol)
+            }
+        }
+    }
+
+    fn order_book_stream(&self, symbol: &str) -> broadcast::Receiver<OrderBook> {
+        let subscription = json!({
+            "method": "subscribe",
+            "params": {
+                "channel": "book",
+                "symbol": [self.cached_pair(symbol).ws_symbol],
+                "depth": BOOK_DEPTH,
+            },
+        });
+
+        // The channel sends one snapshot and then level deltas, so each
+        // connection keeps its own copy of the book and emits it whole.
+        self.ws_stream(subscription, || {
+            let mut book = OrderBook::default();
+            move |text: &str| apply_book_message(&mut book, text)
+        })
+    }
+
+    fn trade_stream(&self, symbol: &str, feed: TradeFeed) -> broadcast::Receiver<Trade> {
+        if feed == TradeFeed::AggTrades {
+            info!("Kraken has no aggregated trade feed, streaming every trade");
+        }
+
+        let subscription = json!({
+            "method": "subscribe",
+            "params": {
+                "channel": "trade",
+                "symbol": [self.cached_pair(symbol).ws_symbol],
+                "snapshot": false,
+            },
+        });
+
+        self.ws_stream(subscription, || parse_trades)
+    }
+
+    fn account_stream(&self) -> broadcast::Receiver<AccountEvent> {
+        let mut created = false;
+        let tx = self.account_events.get_or_init(|| {
+            created = true;
+            broadcast::channel::<AccountEvent>(1024).0
+        });
+        let rx = tx.subscribe();
+
+        if !created || credentials().is_none() {
+            return rx;
+        }
+
+        let tx = tx.clone();
+        let broker = self.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            let max_backoff = Duration::from_secs(60);
+
+            loop {
+                let requester = broker.clone();
+                match tokio::task::spawn_blocking(move || requester.websocket_token()).await {
+                    Ok(Ok(token)) => {
+                        backoff = Duration::from_secs(1);
+
+                        let subscriptions = [
+                            json!({
+                                "method": "subscribe",
+                                "params": {
+                                    "channel": "executions",
+                                    "token": token,
+                                    "snap_orders": false,
+                                    "snap_trades": false,
+                                },
+                            }),
+                            json!({
+                                "method": "subscribe",
+                                "params": { "channel": "balances", "token": token },
+                            }),
+                        ];
+
+                        relay(
+                            &broker.endpoints.ws_auth,
+                            &subscriptions,
+                            parse_account_message,
+                            &tx,
+                        )
+                        .await;
+                    }
+                    Ok(Err(e)) => error!("Failed to get Kraken websocket token: {}", e),
+                    Err(e) => error!("Kraken account stream task failed: {}", e),
+                }
+
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+            }
+        });
+
+        rx
+    }
+
+    fn place_order(&self, order: &OrderRequest) -> anyhow::Result<OrderAck> {
+        if order.is_futures_only() {
+            anyhow::bail!("Kraken spot does not support reduce-only or close-position orders");
+        }
+
+        let order = match self.symbol_info(&order.symbol) {
+            Some(info) => {
+                let normalized = info.normalize(order);
+                let reference_price = match normalized.order_type {
+                    OrderType::Limit { price } => price,
+                    OrderType::StopMarket { stop_price } => stop_price,
+                    OrderType::Market => {
+                        Price::from_f64(self.market_current_price(&normalized.symbol))
+                    }
+                };
+
+                info.validate(&normalized, reference_price).map_err(|e| {
+                    anyhow::anyhow!("Order rejected by {} filters: {e}", normalized.symbol)
+                })?;
+
+                normalized
+            }
+            None => {
+                warn!("No symbol info for {}, submitting unchecked", order.symbol);
+                order.clone()
+            }
+        };
+
+        let pair = self.pair(&order.symbol);
+        let mut params = vec![
+            ("pair", pair.altname.clone()),
+            (
+                "type",
+                match order.side {
+                    Side::Buy => "buy",
+                    Side::Sell => "sell",
+                }
+                .to_string(),
+            ),
+            ("volume", order.quantity.to_string()),
+        ];
+
+        match order.order_type {
+            OrderType::Market => params.push(("ordertype", "market".to_string())),
+            OrderType::Limit { price } => {
+                params.push(("ordertype", "limit".to_string()));
+                params.push(("price", price.to_string()));
+            }
+            OrderType::StopMarket { stop_price } => {
+                params.push(("ordertype", "stop-loss".to_string()));
+                params.push(("price", stop_price.to_string()));
+            }
+        }
+
+        let added: AddOrder = self
+            .limited(WEIGHT_PRIVATE, 1, || self.post_private("AddOrder", &params))
+            .map_err(|e| anyhow::anyhow!("Failed to place order: {e}"))?;
+
+        let order_id = added
+            .txid
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Kraken returned no order id"))?;
+
+        info!(
+            "Placed {:?} order {} on {}: {}",
+            order.side, order_id, pair.info.symbol, added.descr.order
+        );
+
+        // Kraken only acknowledges the order, fills arrive on the account
+        // stream.
+        Ok(OrderAck {
+            order_id,
+            symbol: pair.info.symbol,
+            side: order.side,
+            status: "NEW".to_string(),
+            executed_qty: Quantity::ZERO,
+            average_price: Price::ZERO,
+        })
+    }
+
+    fn cancel_all_orders(&self) -> anyhow::Result<usize> {
+        let cancelled: CancelAll = self
+            .private("CancelAll", WEIGHT_PRIVATE, &[])
+            .map_err(|e| anyhow::anyhow!("Failed to cancel orders: {e}"))?;
+
+        info!("Cancelled {} open orders", cancelled.count);
+
+        Ok(cancelled.count)
+    }
+
+    fn cancel_orders(&self, symbol: &str) -> anyhow::Result<usize> {
+        let orders = self.open_orders(symbol);
+
+        for order in &orders {
+            self.private::<CancelAll>(
+                "CancelOrder",
+                WEIGHT_PRIVATE,
+                &[("txid", order.order_id.clone())],
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to cancel order {}: {e}", order.order_id))?;
+        }
+
+        info!("Cancelled {} open orders on {}", orders.len(), symbol);
+
+        Ok(orders.len())
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        let mut symbols: Vec<SymbolInfo> = self
+            .asset_pairs()
+            .values()
+            .map(|pair| pair.info.clone())
+            .collect();
+        symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        symbols
+    }
+
+    fn symbol_info(&self, symbol: &str) -> Option<SymbolInfo> {
+        self.asset_pairs()
+            .get(&symbol.to_uppercase())
+            .map(|pair| pair.info.clone())
+    }
+}
+
+impl KrakenBroker {
+    pub fn new() -> Self {
+        Self {
+            endpoints: KrakenEndpoints::default(),
+            http: Arc::new(OnceLock::new()),
+            pairs: Arc::new(RwLock::new(None)),
+            limiter: REST_LIMITER.clone(),
+            nonce: Arc::new(AtomicU64::new(0)),
+            account_events: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Points REST calls and streams at other base URLs, e.g. a mock server.
+    pub fn with_endpoints(mut self, endpoints: KrakenEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self.pairs = Arc::new(RwLock::new(None));
+        self.account_events = Arc::new(OnceLock::new());
+        self
+    }
+
+    pub fn endpoints(&self) -> &KrakenEndpoints {
+        &self.endpoints
+    }
+
+    /// Uses a dedicated limiter instead of the process-wide one.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    pub fn rate_limits(&self) -> RateLimitMetrics {
+        self.limiter.metrics()
+    }
+
+    fn http(&self) -> &reqwest::blocking::Client {
+        self.http.get_or_init(reqwest::blocking::Client::new)
+    }
+
+    /// Waits for capacity, runs `call` and feeds rate limit errors back into
+    /// the limiter.
+    fn limited<T>(
+        &self,
+        weight: u32,
+        orders: u32,
+        call: impl FnOnce() -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.limiter.acquire(weight, orders)?;
+
+        match call() {
+            Ok(value) => {
+                self.limiter.record_success();
+                Ok(value)
+            }
+            Err(e) => {
+                self.limiter.record_failure(&e);
+                Err(e)
+            }
+        }
+    }
+
+    fn public<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        self.limited(WEIGHT_PUBLIC, 0, || {
+            let url = format!("{}/0/public/{method}", self.endpoints.rest);
+            let response = self.http().get(url).query(query).send()?;
+
+            unwrap_result(method, response)
+        })
+    }
+
+    fn private<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        weight: u32,
+        params: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        self.limited(weight, 0, || self.post_private(method, params))
+    }
+
+    /// Signed `POST /0/private/<method>`, without rate limiting.
+    fn post_private<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        let (api_key, secret) =
+            credentials().ok_or_else(|| anyhow::anyhow!("Kraken API credentials not found"))?;
+
+        let path = format!("/0/private/{method}");
+        let nonce = self.next_nonce();
+
+        // Values are pair names, sides and decimal numbers, none of which
+        // need percent-encoding.
+        let body = std::iter::once(format!("nonce={nonce}"))
+            .chain(params.iter().map(|(key, value)| format!("{key}={value}")))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let signature = sign(&path, nonce, &body, &secret)?;
+
+        let response = self
+            .http()
+            .post(format!("{}{path}", self.endpoints.rest))
+            .header("API-Key", api_key)
+            .header("API-Sign", signature)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()?;
+
+        unwrap_result(method, response)
+    }
+
+    /// Strictly increasing nonce based on the current time in ms.
+    fn next_nonce(&self) -> u64 {
+        let now = Utc::now().timestamp_millis() as u64;
+        let previous = self.nonce.fetch_max(now, Ordering::SeqCst);
+
+        if previous >= now {
+            self.nonce.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            now
+        }
+    }
+
+    /// Token for the authenticated websocket, valid for 15 minutes to
+    /// establish a connection.
+    fn websocket_token(&self) -> anyhow::Result<String> {
+        let token: WebSocketToken = self.private("GetWebSocketsToken", WEIGHT_PRIVATE, &[])?;
+        info!("Obtained Kraken websocket token");
+        Ok(token.token)
+    }
+
+    /// Pair rules from `AssetPairs`, cached for `ASSET_PAIRS_TTL`. A failed
+    /// refresh keeps serving the previous copy.
+    fn asset_pairs(&self) -> Arc<HashMap<String, KrakenPair>> {
+        if let Some(cache) = self.pairs.read().unwrap().as_ref()
+            && cache.fetched_at.elapsed() < ASSET_PAIRS_TTL
+        {
+            return cache.pairs.clone();
+        }
+
+        match self.public::<HashMap<String, AssetPair>>("AssetPairs", &[]) {
+            Ok(asset_pairs) => {
+                let pairs: HashMap<String, KrakenPair> = asset_pairs
+                    .into_iter()
+                    .filter_map(|(name, pair)| {
+                        skip_invalid("asset pair", parse_asset_pair(name, pair)).flatten()
+                    })
+                    .map(|pair| (pair.info.symbol.clone(), pair))
+                    .collect();
+                let pairs = Arc::new(pairs);
+
+                info!("Loaded Kraken asset pairs for {} symbols", pairs.len());
+
+                *self.pairs.write().unwrap() = Some(PairCache {
+                    fetched_at: Instant::now(),
+                    pairs: pairs.clone(),
+                });
+
+                pairs
+            }
+            Err(e) => {
+                error!("Failed to get asset pairs: {}", e);
+                self.pairs
+                    .read()
+                    .unwrap()
+                    .as_ref()
+                    .map(|cache| cache.pairs.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    fn pair(&self, symbol: &str) -> KrakenPair {
+        self.asset_pairs()
+            .get(&symbol.to_uppercase())
+            .cloned()
+            .unwrap_or_else(|| KrakenPair::guess(symbol))
+    }
+
+    /// Like `pair`, but never calls the REST API, so streams can be opened
+    /// from async code.
+    fn cached_pair(&self, symbol: &str) -> KrakenPair {
+        self.pairs
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|cache| cache.pairs.get(&symbol.to_uppercase()).cloned())
+            .unwrap_or_else(|| KrakenPair::guess(symbol))
+    }
+
+    /// Relays a public channel, reconnecting with exponential backoff.
+    /// `handler` builds a fresh parser for every connection.
+    fn ws_stream<T, P>(
+        &self,
+        subscription: Value,
+        handler: impl Fn() -> P + Send + 'static,
+    ) -> broadcast::Receiver<T>
+    where
+        T: Clone + Send + 'static,
+        P: FnMut(&str) -> Result<Vec<T>, serde_json::Error> + Send + 'static,
+    {
+        let (tx, rx) = broadcast::channel::<T>(1024);
+        let url = self.endpoints.ws.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            let max_backoff = Duration::from_secs(60);
+
+            loop {
+                if relay(&url, std::slice::from_ref(&subscription), handler(), &tx).await {
+                    backoff = Duration::from_secs(1);
+                }
+
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+            }
+        });
+
+        rx
+    }
+}
+
+impl Default for KrakenBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Connects to `url`, sends `subscriptions` and forwards what `parse`
+/// extracts until the connection drops. Returns whether it connected.
+async fn relay<T, P>(
+    url: &str,
+    subscriptions: &[Value],
+    mut parse: P,
+    tx: &broadcast::Sender<T>,
+) -> bool
+where
+    P: FnMut(&str) -> Result<Vec<T>, serde_json::Error>,
+{
+    let mut ws = match tokio_tungstenite::connect_async(url).await {
+        Ok((ws, _resp)) => ws,
+        Err(e) => {
+            error!("kraken connect error: {e}");
+            return false;
+        }
+    };
+
+    for subscription in subscriptions {
+        if let Err(e) = ws
+            .send(Message::Text(subscription.to_string().into()))
+            .await
+        {
+            error!("kraken subscribe error: {e}");
+            return true;
+        }
+    }
+
+    while let Some(msg) = ws.next().await {
+        match msg {
+            Ok(Message::Text(text)) => {
+                if let Some(reason) = subscription_error(&text) {
+                    error!("Kraken subscription failed: {}", reason);
+                    continue;
+                }
+
+                match parse(&text) {
+                    Ok(items) => {
+                        for item in items {
+                            let _ = tx.send(item);
+                        }
+                    }
+                    Err(e) => error!("Dropping kraken ws message: {e}"),
+                }
+            }
+            Ok(Message::Ping(p)) => {
+                let _ = ws.send(Message::Pong(p)).await;
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                error!("kraken ws error: {e}");
+                break;
+            }
+        }
+    }
+
+    true
+}
+
+fn credentials() -> Option<(String, String)> {
+    let api_key = env::var("KRAKEN_API_KEY").ok()?;
+    let secret = env::var("KRAKEN_SECRET_KEY").ok()?;
+    Some((api_key, secret))
+}
+
+/// `API-Sign`: HMAC-SHA512 of the URI path and SHA256(nonce + body), keyed
+/// with the base64-decoded secret.
+fn sign(path: &str, nonce: u64, body: &str, secret: &str) -> anyhow::Result<String> {
+    let mut sha = Sha256::new();
+    sha.update(nonce.to_string().as_bytes());
+    sha.update(body.as_bytes());
+    let digest = sha.finalize();
+
+    let key = STANDARD
+        .decode(secret)
+        .map_err(|e| anyhow::anyhow!("Invalid Kraken secret: {e}"))?;
+    let mut mac = Hmac::<Sha512>::new_from_slice(&key)
+        .map_err(|e| anyhow::anyhow!("Invalid Kraken secret: {e}"))?;
+    mac.update(path.as_bytes());
+    mac.update(&digest);
+
+    Ok(STANDARD.encode(mac.finalize().into_bytes()))
+}
+
+#[derive(Deserialize)]
+struct KrakenResponse<T> {
+    #[serde(default)]
+    error: Vec<String>,
+    result: Option<T>,
+}
+
+/// Unwraps Kraken's `{"error": [...], "result": ...}` envelope.
+fn unwrap_result<T: DeserializeOwned>(
+    method: &str,
+    response: reqwest::blocking::Response,
+) -> anyhow::Result<T> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(VenueError {
+            status: Some(status.as_u16()),
+            message: format!("Kraken {method} returned {status}"),
+            ..Default::default()
+        }
+        .into());
+    }
+
+    let response: KrakenResponse<T> = response.json()?;
+    if !response.error.is_empty() {
+        return Err(VenueError {
+            message: format!("Kraken {method} failed: {}", response.error.join(", ")),
+            errors: response.error,
+            ..Default::default()
+        }
+        .into());
+    }
+
+    response
+        .result
+        .ok_or_else(|| anyhow::anyhow!("Kraken {method} returned no result"))
+}
+
+/// Maps Kraken asset codes such as `XXBT`, `ZUSD` or `XBT` to the common
+/// tickers used by the rest of the engine.
+fn normalize_asset(asset: &str) -> String {
+    let asset = match asset {
+        "XXBT" | "XBT" => "BTC",
+        "XXDG" | "XDG" => "DOGE",
+        asset if asset.len() == 4 && (asset.starts_with('X') || asset.starts_with('Z')) => {
+            &asset[1..]
+        }
+        asset => asset,
+    };
+
+    asset.to_string()
+}
+
+fn parse_side(side: &str) -> Side {
+    if side.eq_ignore_ascii_case("buy") {
+        Side::Buy
+    } else {
+        Side::Sell
+    }
+}
+
+fn from_unix_seconds(seconds: f64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis((seconds * 1000.0) as i64).unwrap_or_default()
+}
+
+/// Minutes of an engine interval, if Kraken offers candles of that size.
+fn interval_minutes(interval: &str) -> Option<u64> {
+    let minutes = parse_interval(interval)?.as_secs() / 60;
+    [1, 5, 15, 30, 60, 240, 1440, 10080, 21600]
+        .contains(&minutes)
+        .then_some(minutes)
+}
+
+/// Reads a number that Kraken sends either as a string or as a number,
+/// keeping strings exact.
+fn value_decimal(field: &str, value: &Value) -> Result<Decimal, serde_json::Error> {
+    match value {
+        Value::String(value) => parse_number(field, value),
+        value => value
+            .as_f64()
+            .map(to_decimal)
+            .ok_or_else(|| serde_json::Error::custom(format!("invalid {field} {value}"))),
+    }
+}
+
+#[derive(Deserialize)]
+struct AssetPair {
+    altname: String,
+    #[serde(default)]
+    wsname: Option<String>,
+    base: String,
+    quote: String,
+    #[serde(default)]
+    lot_decimals: i32,
+    #[serde(default)]
+    tick_size: Option<String>,
+    #[serde(default)]
+    ordermin: Option<String>,
+    #[serde(default)]
+    costmin: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+/// Dark pool pairs (`.d`) have no websocket name and are skipped with
+/// `Ok(None)`.
+fn parse_asset_pair(
+    name: String,
+    pair: AssetPair,
+) -> Result<Option<KrakenPair>, serde_json::Error> {
+    let Some(wsname) = pair.wsname.as_deref() else {
+        return Ok(None);
+    };
+
+    let (base, quote) = match wsname.split_once('/') {
+        Some((base, quote)) => (normalize_asset(base), normalize_asset(quote)),
+        None => (normalize_asset(&pair.base), normalize_asset(&pair.quote)),
+    };
+
+    // Unset limits read as zero, meaning no limit.
+    let parse = |field: &str, value: &Option<String>| -> Result<Decimal, serde_json::Error> {
+        value
+            .as_deref()
+            .map(|value| parse_number(field, value))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    };
+
+    let status = match pair.status.as_deref() {
+        Some("online") | None => "TRADING".to_string(),
+        Some(other) => other.to_uppercase(),
+    };
+
+    Ok(Some(KrakenPair {
+        name,
+        altname: pair.altname,
+        ws_symbol: format!("{base}/{quote}"),
+        info: SymbolInfo {
+            symbol: format!("{base}{quote}"),
+            status,
+            base_asset: base,
+            quote_asset: quote,
+            tick_size: Price(parse("tick_size", &pair.tick_size)?),
+            min_price: Price::ZERO,
+            max_price: Price::ZERO,
+            step_size: Quantity(Decimal::new(1, pair.lot_decimals.max(0) as u32)),
+            min_qty: Quantity(parse("ordermin", &pair.ordermin)?),
+            max_qty: Quantity::ZERO,
+            min_notional: parse("costmin", &pair.costmin)?,
+        },
+    }))
+}
+
+#[derive(Deserialize)]
+struct ExtendedBalance {
+    balance: String,
+    /// Amount reserved by open orders.
+    #[serde(default)]
+    hold_trade: String,
+}
+
+#[derive(Deserialize)]
+struct TickerInfo {
+    /// Best ask as `[price, whole lot volume, lot volume]`.
+    #[serde(rename = "a")]
+    ask: Vec<String>,
+    #[serde(rename = "b")]
+    bid: Vec<String>,
+    /// Last trade as `[price, lot volume]`.
+    #[serde(rename = "c")]
+    last_trade: Vec<String>,
+    /// `[today, last 24 hours]`.
+    #[serde(rename = "v")]
+    volume: Vec<String>,
+    #[serde(rename = "h")]
+    high: Vec<String>,
+    #[serde(rename = "l")]
+    low: Vec<String>,
+    /// Opening price of the UTC day.
+    #[serde(rename = "o")]
+    open: String,
+}
+
+impl TickerInfo {
+    fn into_ticker(self, symbol: &str) -> Result<Ticker, serde_json::Error> {
+        let field =
+            |name: &str, values: &[String], index: usize| -> Result<Decimal, serde_json::Error> {
+                let value = values
+                    .get(index)
+                    .ok_or_else(|| serde_json::Error::custom(format!("missing {name}")))?;
+                parse_number(name, value)
+            };
+
+        let last_price = field("last trade", &self.last_trade, 0)?;
+        let open_price: Decimal = parse_number("open", &self.open)?;
+        let price_change_percent = if open_price.is_zero() {
+            Decimal::ZERO
+        } else {
+            (last_price - open_price) / open_price * Decimal::ONE_HUNDRED
+        };
+
+        Ok(Ticker {
+            symbol: symbol.to_string(),
+            last_price: Price(last_price),
+            bid_price: Price(field("bid", &self.bid, 0)?),
+            ask_price: Price(field("ask", &self.ask, 0)?),
+            open_price: Price(open_price),
+            high_price: Price(field("high", &self.high, 1)?),
+            low_price: Price(field("low", &self.low, 1)?),
+            volume: Quantity(field("volume", &self.volume, 1)?),
+            price_change_percent,
+            time: Utc::now(),
+        })
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct DepthResult {
+    /// `[price, volume, timestamp]` rows.
+    #[serde(default)]
+    bids: Vec<Vec<Value>>,
+    #[serde(default)]
+    asks: Vec<Vec<Value>>,
+}
+
+fn parse_rest_levels(levels: &[Vec<Value>]) -> Result<Vec<BookLevel>, serde_json::Error> {
+    levels
+        .iter()
+        .filter(|level| level.len() >= 2)
+        .map(|level| -> Result<BookLevel, serde_json::Error> {
+            Ok(BookLevel {
+                price: Price(value_decimal("price", &level[0])?),
+                quantity: Quantity(value_decimal("volume", &level[1])?),
+            })
+        })
+        .collect()
+}
+
+/// `[time, open, high, low, close, vwap, volume, count]`, where `time` is
+/// the interval start in seconds.
+fn parse_ohlc_row(row: &Value, interval_ms: i64) -> Result<Candle, serde_json::Error> {
+    let invalid = || serde_json::Error::custom(format!("invalid ohlc row {row}"));
+
+    let row = row
+        .as_array()
+        .filter(|row| row.len() >= 7)
+        .ok_or_else(invalid)?;
+
+    // Candles carry the interval close time, like Binance klines.
+    let close_time = row[0].as_i64().ok_or_else(invalid)? * 1000 + interval_ms - 1;
+
+    Ok(Candle {
+        open: Price(value_decimal("open", &row[1])?),
+        high: Price(value_decimal("high", &row[2])?),
+        low: Price(value_decimal("low", &row[3])?),
+        close: Price(value_decimal("close", &row[4])?),
+        volume: Quantity(value_decimal("volume", &row[6])?),
+        timestamp: close_time,
+        ts: DateTime::from_timestamp_millis(close_time).ok_or_else(invalid)?,
+    })
+}
+
+#[derive(Deserialize)]
+struct OpenOrders {
+    #[serde(default)]
+    open: HashMap<String, KrakenOrder>,
+}
+
+#[derive(Deserialize)]
+struct KrakenOrder {
+    status: String,
+    opentm: f64,
+    descr: OrderDescription,
+    vol: String,
+    vol_exec: String,
+    #[serde(default)]
+    cl_ord_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OrderDescription {
+    pair: String,
+    #[serde(rename = "type")]
+    side: String,
+    ordertype: String,
+    price: String,
+}
+
+#[derive(Deserialize)]
+struct TradesHistory {
+    #[serde(default)]
+    trades: HashMap<String, KrakenTrade>,
+}
+
+#[derive(Deserialize)]
+struct KrakenTrade {
+    pair: String,
+    time: f64,
+    #[serde(rename = "type")]
+    side: String,
+    price: String,
+    fee: String,
+    vol: String,
+    #[serde(default)]
+    trade_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct AddOrder {
+    descr: AddOrderDescription,
+    txid: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AddOrderDescription {
+    order: String,
+}
+
+/// Result of `CancelAll` and `CancelOrder`.
+#[derive(Deserialize)]
+struct CancelAll {
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct WebSocketToken {
+    token: String,
+}
+
+/// Channel messages of the websocket v2 API.
+#[derive(Deserialize)]
+struct WsMessage<T> {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+}
+
+/// Reads the `channel` of a message without parsing its data.
+#[derive(Deserialize)]
+struct WsHeader {
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    success: Option<bool>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn channel_of(text: &str) -> Option<String> {
+    serde_json::from_str::<WsHeader>(text).ok()?.channel
+}
+
+fn subscription_error(text: &str) -> Option<String> {
+    let header: WsHeader = serde_json::from_str(text).ok()?;
+
+    (header.method.as_deref() == Some("subscribe") && header.success == Some(false))
+        .then(|| header.error.unwrap_or_default())
+}
+
+/// Engine symbol of a websocket v2 symbol, e.g. `BTC/USD` to `BTCUSD`.
+fn ws_to_symbol(symbol: &str) -> String {
+    symbol.replace('/', "")
+}
+
+#[derive(Deserialize)]
+struct OhlcItem {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    interval_begin: DateTime<Utc>,
+    /// Minutes.
+    interval: i64,
+}
+
+fn parse_ohlc(text: &str) -> Result<Vec<Candle>, serde_json::Error> {
+    if channel_of(text).as_deref() != Some("ohlc") {
+        return Ok(Vec::new());
+    }
+
+    let message: WsMessage<OhlcItem> = serde_json::from_str(text)?;
+
+    Ok(message
+        .data
+        .into_iter()
+        .map(|item| {
+            let close_time = item.interval_begin.timestamp_millis() + item.interval * 60_000 - 1;
+
+            Candle {
+                open: Price::from_f64(item.open),
+                high: Price::from_f64(item.high),
+                low: Price::from_f64(item.low),
+                close: Price::from_f64(item.close),
+                volume: Quantity::from_f64(item.volume),
+                timestamp: close_time,
+                ts: DateTime::from_timestamp_millis(close_time).unwrap_or(item.interval_begin),
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct TradeItem {
+    symbol: String,
+    /// Side of the taker.
+    side: String,
+    price: f64,
+    qty: f64,
+    trade_id: u64,
+    timestamp: DateTime<Utc>,
+}
+
+fn parse_trades(text: &str) -> Result<Vec<Trade>, serde_json::Error> {
+    if channel_of(text).as_deref() != Some("trade") {
+        return Ok(Vec::new());
+    }
+
+    let message: WsMessage<TradeItem> = serde_json::from_str(text)?;
+
+    Ok(message
+        .data
+        .into_iter()
+        .map(|item| Trade {
+            symbol: ws_to_symbol(&item.symbol),
+            trade_id: item.trade_id,
+            price: Price::from_f64(item.price),
+            qty: Quantity::from_f64(item.qty),
+            aggressor: parse_side(&item.side),
+            timestamp: item.timestamp.timestamp_millis(),
+            ts: item.timestamp,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct BookItem {
+    symbol: String,
+    #[serde(default)]
+    bids: Vec<WsLevel>,
+    #[serde(default)]
+    asks: Vec<WsLevel>,
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct WsLevel {
+    price: f64,
+    qty: f64,
+}
+
+/// Applies a book snapshot or delta to `book`, returning the updated book.
+fn apply_book_message(
+    book: &mut OrderBook,
+    text: &str,
+) -> Result<Vec<OrderBook>, serde_json::Error> {
+    if channel_of(text).as_deref() != Some("book") {
+        return Ok(Vec::new());
+    }
+
+    let message: WsMessage<BookItem> = serde_json::from_str(text)?;
+    let Some(item) = message.data.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+
+    if message.kind == "snapshot" {
+        book.bids.clear();
+        book.asks.clear();
+    }
+
+    for level in &item.bids {
+        apply_level(&mut book.bids, level, |a, b| b.cmp(&a));
+    }
+    for level in &item.asks {
+        apply_level(&mut book.asks, level, |a, b| a.cmp(&b));
+    }
+
+    book.symbol = ws_to_symbol(&item.symbol);
+    book.timestamp = item.timestamp.unwrap_or_else(Utc::now).timestamp_millis();
+
+    Ok(vec![book.clone()])
+}
+
+/// Inserts, replaces or (for a zero quantity) removes one level, keeping
+/// the side sorted by `order` and at most `BOOK_DEPTH` deep.
+fn apply_level(
+    levels: &mut Vec<BookLevel>,
+    level: &WsLevel,
+    order: impl Fn(Price, Price) -> std::cmp::Ordering,
+) {
+    let price = Price::from_f64(level.price);
+    let quantity = Quantity::from_f64(level.qty);
+
+    levels.retain(|existing| existing.price != price);
+
+    if quantity.is_positive() {
+        let index = levels
+            .partition_point(|existing| order(existing.price, price) == std::cmp::Ordering::Less);
+        levels.insert(index, BookLevel { price, quantity });
+    }
+
+    levels.truncate(BOOK_DEPTH);
+}
+
+#[derive(Deserialize)]
+struct ExecutionItem {
+    order_id: String,
+    #[serde(default)]
+    cl_ord_id: Option<String>,
+    #[serde(default)]
+    symbol: Option<String>,
+    #[serde(default)]
+    side: Option<String>,
+    #[serde(default)]
+    order_type: Option<String>,
+    #[serde(default)]
+    order_qty: Option<f64>,
+    #[serde(default)]
+    limit_price: Option<f64>,
+    /// `new`, `trade`, `filled`, `canceled`, `expired`, ...
+    exec_type: String,
+    #[serde(default)]
+    order_status: Option<String>,
+    #[serde(default)]
+    trade_id: Option<u64>,
+    #[serde(default)]
+    last_qty: Option<f64>,
+    #[serde(default)]
+    last_price: Option<f64>,
+    #[serde(default)]
+    cum_qty: Option<f64>,
+    #[serde(default)]
+    cum_cost: Option<f64>,
+    #[serde(default)]
+    fees: Vec<ExecutionFee>,
+    #[serde(default)]
+    reason: Option<String>,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct ExecutionFee {
+    asset: String,
+    qty: f64,
+}
+
+#[derive(Deserialize)]
+struct BalanceItem {
+    asset: String,
+    #[serde(default)]
+    balance: f64,
+    /// Change of the balance, only set on updates.
+    #[serde(default)]
+    amount: Option<f64>,
+    /// Ledger entry type of an update: `trade`, `deposit`, `withdrawal`, ...
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+}
+
+fn parse_account_message(text: &str) -> Result<Vec<AccountEvent>, serde_json::Error> {
+    match channel_of(text).as_deref() {
+        Some("executions") => {
+            let message: WsMessage<ExecutionItem> = serde_json::from_str(text)?;
+            Ok(message
+                .data
+                .into_iter()
+                .map(|item| AccountEvent::Order(order_update(item)))
+                .collect())
+        }
+        Some("balances") => {
+            let message: WsMessage<BalanceItem> = serde_json::from_str(text)?;
+            Ok(balance_events(message.data))
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn order_update(item: ExecutionItem) -> OrderUpdate {
+    let last_filled_qty = Quantity::from_f64(item.last_qty.unwrap_or(0.0));
+
+    // The final fill may be reported as `filled` rather than `trade`. Both
+    // carry the trade id, so the ledger books it once either way.
+    let execution_type = match item.exec_type.as_str() {
+        "trade" | "filled" if item.trade_id.is_some() && last_filled_qty.is_positive() => {
+            "TRADE".to_string()
+        }
+        other => other.to_uppercase(),
+    };
+
+    let fee = item.fees.first();
+
+    OrderUpdate {
+        symbol: item.symbol.as_deref().map(ws_to_symbol).unwrap_or_default(),
+        order_id: item.order_id,
+        client_order_id: item.cl_ord_id.unwrap_or_default(),
+        side: parse_side(item.side.as_deref().unwrap_or("buy")),
+        order_type: item.order_type.unwrap_or_default().to_uppercase(),
+        execution_type,
+        status: item
+            .order_status
+            .unwrap_or_else(|| item.exec_type.clone())
+            .to_uppercase(),
+        price: Price::from_f64(item.limit_price.unwrap_or(0.0)),
+        quantity: Quantity::from_f64(item.order_qty.unwrap_or(0.0)),
+        trade_id: item.trade_id,
+        last_filled_qty,
+        last_filled_price: Price::from_f64(item.last_price.unwrap_or(0.0)),
+        cumulative_filled_qty: Quantity::from_f64(item.cum_qty.unwrap_or(0.0)),
+        cumulative_quote_qty: to_decimal(item.cum_cost.unwrap_or(0.0)),
+        commission: Quantity::from_f64(fee.map(|fee| fee.qty).unwrap_or(0.0)),
+        commission_asset: fee.map(|fee| normalize_asset(&fee.asset)),
+        reject_reason: item.reason,
+        at: item.timestamp,
+    }
+}
+
+/// Every item reports the asset's balance after the change. Deposits,
+/// withdrawals and transfers additionally produce a `BalanceDelta`.
+fn balance_events(items: Vec<BalanceItem>) -> Vec<AccountEvent> {
+    if items.is_empty() {
+        return Vec::new();
+    }
+
+    let at = items
+        .iter()
+        .find_map(|item| item.timestamp)
+        .unwrap_or_else(Utc::now);
+
+    let mut events = vec![AccountEvent::Balances {
+        balances: items
+            .iter()
+            .map(|item| Balance {
+                asset: normalize_asset(&item.asset),
+                free: to_decimal(item.balance),
+                locked: Decimal::ZERO,
+            })
+            .collect(),
+        at,
+    }];
+
+    events.extend(
+        items
+            .iter()
+            .filter(|item| {
+                matches!(
+                    item.kind.as_deref(),
+                    Some("deposit" | "withdrawal" | "transfer")
+                )
+            })
+            .filter_map(|item| {
+                Some(AccountEvent::BalanceDelta {
+                    asset: normalize_asset(&item.asset),
+                    delta: to_decimal(item.amount?),
+                    at: item.timestamp.unwrap_or(at),
+                })
+            }),
+    );
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, routing::get};
+    use serde_json::json;
+
+    use super::*;
+    use crate::brokers::mock_server;
+
+    fn asset_pairs() -> Value {
+        json!({
+            "error": [],
+            "result": {
+                "XXBTZUSD": {
+                    "altname": "XBTUSD",
+                    "wsname": "XBT/USD",
+                    "base": "XXBT",
+                    "quote": "ZUSD",
+                    "lot_decimals": 8,
+                    "tick_size": "0.1",
+                    "ordermin": "0.0001",
+                    "costmin": "0.5",
+                    "status": "online"
+                },
+                "XXBTZUSD.d": {
+                    "altname": "XBTUSD.d",
+                    "base": "XXBT",
+                    "quote": "ZUSD"
+                }
+            }
+        })
+    }
+
+    fn ticker() -> Value {
+        json!({
+            "error": [],
+            "result": {
+                "XXBTZUSD": {
+                    "a": ["65001.00000", "1", "1.000"],
+                    "b": ["65000.00000", "2", "2.000"],
+                    "c": ["65000.50000", "0.00100000"],
+                    "v": ["100.5", "250.25"],
+                    "p": ["64000.1", "64100.2"],
+                    "t": [100, 200],
+                    "l": ["63000.00000", "62000.00000"],
+                    "h": ["66000.00000", "67000.00000"],
+                    "o": "64000.00000"
+                }
+            }
+        })
+    }
+
+    fn depth() -> Value {
+        json!({
+            "error": [],
+            "result": {
+                "XXBTZUSD": {
+                    "asks": [["65001.00000", "1.500", 1700000000]],
+                    "bids": [["65000.00000", "2.000", 1700000000], ["64999.00000", "0.500", 1700000000]]
+                }
+            }
+        })
+    }
+
+    const TRADE_MESSAGE: &str = r#"{"channel":"trade","type":"update","data":[{"symbol":"BTC/USD","side":"sell","price":65000.5,"qty":0.25,"ord_type":"market","trade_id":42,"timestamp":"2024-01-01T00:00:00.000000Z"}]}"#;
+
+    #[test]
+    fn reads_asset_pairs() {
+        let result: HashMap<String, AssetPair> =
+            serde_json::from_value(asset_pairs()["result"].clone()).unwrap();
+        let mut pairs = result
+            .into_iter()
+            .filter_map(|(name, pair)| parse_asset_pair(name, pair).unwrap())
+            .collect::<Vec<_>>();
+
+        // The dark pool pair has no websocket name.
+        assert_eq!(pairs.len(), 1);
+        let pair = pairs.pop().unwrap();
+
+        assert_eq!(pair.name, "XXBTZUSD");
+        assert_eq!(pair.altname, "XBTUSD");
+        assert_eq!(pair.ws_symbol, "BTC/USD");
+        assert_eq!(pair.info.symbol, "BTCUSD");
+        assert_eq!(pair.info.tick_size, "0.1".parse().unwrap());
+        assert_eq!(pair.info.step_size, "0.00000001".parse().unwrap());
+        assert_eq!(pair.info.min_qty, "0.0001".parse().unwrap());
+        assert_eq!(pair.info.status, "TRADING");
+    }
+
+    #[test]
+    fn rejects_malformed_asset_pair_limits() {
+        let pair: AssetPair = serde_json::from_value(json!({
+            "altname": "XBTUSD",
+            "wsname": "XBT/USD",
+            "base": "XXBT",
+            "quote": "ZUSD",
+            "tick_size": "0.1x"
+        }))
+        .unwrap();
+
+        assert!(parse_asset_pair("XXBTZUSD".to_string(), pair).is_err());
+    }
+
+    #[test]
+    fn reads_ticker() {
+        let result: HashMap<String, TickerInfo> =
+            serde_json::from_value(ticker()["result"].clone()).unwrap();
+        let ticker = result
+            .into_values()
+            .next()
+            .unwrap()
+            .into_ticker("BTCUSD")
+            .unwrap();
+
+        assert_eq!(ticker.last_price, Price::from_f64(65000.5));
+        assert_eq!(ticker.bid_price, Price::from_f64(65000.0));
+        assert_eq!(ticker.ask_price, Price::from_f64(65001.0));
+        assert_eq!(ticker.high_price, Price::from_f64(67000.0));
+        assert_eq!(ticker.low_price, Price::from_f64(62000.0));
+        assert_eq!(ticker.volume, Quantity::from_f64(250.25));
+    }
+
+    #[test]
+    fn rejects_malformed_ticker_prices() {
+        let mut fixture = ticker();
+        fixture["result"]["XXBTZUSD"]["c"] = json!(["", "0.001"]);
+
+        let result: HashMap<String, TickerInfo> =
+            serde_json::from_value(fixture["result"].clone()).unwrap();
+        let ticker = result.into_values().next().unwrap();
+
+        assert!(ticker.into_ticker("BTCUSD").is_err());
+    }
+
+    #[test]
+    fn reads_ohlc_rows() {
+        let row = json!([
+            1700000000, "100.0", "110.0", "90.0", "105.0", "101.0", "12.5", 42
+        ]);
+        let candle = parse_ohlc_row(&row, 60_000).unwrap();
+
+        assert_eq!(candle.timestamp, 1_700_000_000_000 + 60_000 - 1);
+        assert_eq!(candle.open, Price::from_f64(100.0));
+        assert_eq!(candle.close, Price::from_f64(105.0));
+        assert_eq!(candle.volume, Quantity::from_f64(12.5));
+
+        let malformed = json!([
+            1700000000, "100.0", "abc", "90.0", "105.0", "101.0", "12.5", 42
+        ]);
+        assert!(parse_ohlc_row(&malformed, 60_000).is_err());
+        assert!(parse_ohlc_row(&json!([1700000000]), 60_000).is_err());
+    }
+
+    #[test]
+    fn reads_websocket_trades() {
+        let trades = parse_trades(TRADE_MESSAGE).unwrap();
+
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].symbol, "BTCUSD");
+        assert_eq!(trades[0].trade_id, 42);
+        assert_eq!(trades[0].price, Price::from_f64(65000.5));
+        assert_eq!(trades[0].aggressor, Side::Sell);
+
+        let heartbeat = r#"{"channel":"heartbeat"}"#;
+        assert!(parse_trades(heartbeat).unwrap().is_empty());
+    }
+
+    #[test]
+    fn applies_book_snapshot_and_updates() {
+        let mut book = OrderBook::default();
+
+        let snapshot = r#"{"channel":"book","type":"snapshot","data":[{"symbol":"BTC/USD","bids":[{"price":100.0,"qty":1.0},{"price":99.0,"qty":2.0}],"asks":[{"price":101.0,"qty":1.5}]}]}"#;
+        apply_book_message(&mut book, snapshot).unwrap();
+
+        let update = r#"{"channel":"book","type":"update","data":[{"symbol":"BTC/USD","bids":[{"price":100.0,"qty":0.0},{"price":99.5,"qty":3.0}],"asks":[],"timestamp":"2024-01-01T00:00:00.000000Z"}]}"#;
+        let books = apply_book_message(&mut book, update).unwrap();
+
+        assert_eq!(books[0].symbol, "BTCUSD");
+        let bids: Vec<Price> = book.bids.iter().map(|level| level.price).collect();
+        assert_eq!(bids, vec![Price::from_f64(99.5), Price::from_f64(99.0)]);
+        assert_eq!(book.asks.len(), 1);
+    }
+
+    #[test]
+    fn reads_executions() {
+        let message = r#"{"channel":"executions","type":"update","data":[{"order_id":"OABC-123","symbol":"BTC/USD","side":"buy","order_type":"limit","order_qty":0.5,"limit_price":65000.0,"exec_type":"trade","order_status":"partially_filled","trade_id":7,"last_qty":0.2,"last_price":64999.0,"cum_qty":0.2,"cum_cost":12999.8,"fees":[{"asset":"USD","qty":1.3}],"timestamp":"2024-01-01T00:00:00.000000Z"}]}"#;
+
+        let events = parse_account_message(message).unwrap();
+
+        let [AccountEvent::Order(update)] = events.as_slice() else {
+            panic!("expected one order update, got {events:?}");
+        };
+        assert_eq!(update.symbol, "BTCUSD");
+        assert_eq!(update.execution_type, "TRADE");
+        assert_eq!(update.status, "PARTIALLY_FILLED");
+        assert_eq!(update.last_filled_qty, Quantity::from_f64(0.2));
+        assert_eq!(update.last_filled_price, Price::from_f64(64999.0));
+        assert_eq!(update.commission_asset.as_deref(), Some("USD"));
+    }
+
+    #[test]
+    fn overrides_production_endpoints() {
+        let defaults = KrakenEndpoints::from_vars(|_| None);
+        assert_eq!(defaults.ws_auth, KrakenEndpoints::production().ws_auth);
+
+        let local = KrakenEndpoints::from_vars(|name| match name {
+            "KRAKEN_REST_URL" => Some("http://127.0.0.1:9000/".to_string()),
+            "KRAKEN_WS_URL" => Some("ws://127.0.0.1:9000/v2".to_string()),
+            _ => None,
+        });
+        assert_eq!(local.rest, "http://127.0.0.1:9000");
+        assert_eq!(local.ws, "ws://127.0.0.1:9000/v2");
+        assert_eq!(local.ws_auth, KrakenEndpoints::production().ws_auth);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn talks_to_a_mock_venue() {
+        let base = mock_server::serve(
+            Router::new()
+                .route(
+                    "/0/public/AssetPairs",
+                    get(|| async { Json(asset_pairs()) }),
+                )
+                .route("/0/public/Ticker", get(|| async { Json(ticker()) }))
+                .route("/0/public/Depth", get(|| async { Json(depth()) }))
+                .route(
+                    "/v2",
+                    mock_server::ws_sending(vec![TRADE_MESSAGE.to_string()]),
+                ),
+        )
+        .await;
+
+        let endpoints = KrakenEndpoints {
+            ws: mock_server::ws_url(&base, "/v2"),
+            rest: base,
+            ..KrakenEndpoints::production()
+        };
+
+        // The blocking HTTP client must not run on an async worker.
+        let (price, ticker, book, mut trades) = tokio::task::spawn_blocking(|| {
+            let broker = KrakenBroker::new()
+                .with_endpoints(endpoints)
+                .with_rate_limiter(Arc::new(RateLimiter::default()));
+
+            (
+                broker.market_current_price("BTCUSD"),
+                broker.ticker("BTCUSD"),
+                broker.order_book("BTCUSD", 10),
+                broker.trade_stream("BTCUSD", TradeFeed::Trades),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(price, 65000.5);
+        assert_eq!(ticker.unwrap().symbol, "BTCUSD");
+        assert_eq!(book.symbol, "BTCUSD");
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.asks[0].price, Price::from_f64(65001.0));
+
+        let trade = tokio::time::timeout(Duration::from_secs(5), trades.recv())
+            .await
+            .expect("no trade streamed")
+            .unwrap();
+        assert_eq!(trade.symbol, "BTCUSD");
+        assert_eq!(trade.qty, Quantity::from_f64(0.25));
+    }
+}
@@ -0,0 +1,43 @@
+//! Local HTTP and websocket server standing in for a venue in adapter tests.
+
+use axum::{
+    Router,
+    extract::ws::{Message, WebSocketUpgrade},
+    routing::{MethodRouter, get},
+};
+use tokio::net::TcpListener;
+
+/// Serves `router` on a free local port, returning its `http://` base URL.
+pub async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    format!("http://{addr}")
+}
+
+/// The `ws://` URL of `path` on the server at `base`.
+pub fn ws_url(base: &str, path: &str) -> String {
+    format!("{}{path}", base.replacen("http", "ws", 1))
+}
+
+/// Websocket route that sends `messages` to every client once connected and
+/// then stays open, like a venue between updates.
+pub fn ws_sending(messages: Vec<String>) -> MethodRouter {
+    get(move |ws: WebSocketUpgrade| {
+        let messages = messages.clone();
+
+        async move {
+            ws.on_upgrade(move |mut socket| async move {
+                for message in messages {
+                    if socket.send(Message::Text(message.into())).await.is_err() {
+                        return;
+                    }
+                }
+
+                while let Some(Ok(_)) = socket.recv().await {}
+            })
+        }
+    })
+}
@@ -1,2 +1,10 @@
 pub mod binance;
+pub mod binance_futures;
 pub mod core;
+pub mod kraken;
+#[cfg(test)]
+pub(crate) mod mock_server;
+pub mod rate_limit;
+pub mod recorder;
+pub mod replay;
+pub mod venue;
@@ -0,0 +1,403 @@
+use std::{
+    fmt,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::warn;
+
+/// Client-side REST limits. Defaults follow the Binance spot limits, which
+/// are enforced per IP address.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub request_weight_per_minute: u32,
+    pub orders_per_10s: u32,
+    pub orders_per_day: u32,
+    /// Longest a call waits for capacity before it fails instead.
+    pub max_wait: Duration,
+    /// First pause after a 429, doubled on every further one.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            request_weight_per_minute: 6_000,
+            orders_per_10s: 50,
+            orders_per_day: 160_000,
+            max_wait: Duration::from_secs(10),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RateLimitError {
+    /// The venue asked us to stop (429/418) and the pause has not elapsed.
+    Blocked { until: DateTime<Utc> },
+    /// Local capacity would not free up within `max_wait`.
+    Exhausted { wait: Duration },
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitError::Blocked { until } => {
+                write!(f, "requests blocked by the exchange until {until}")
+            }
+            RateLimitError::Exhausted { wait } => {
+                write!(f, "rate limit exhausted, capacity frees up in {wait:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+/// Binance code for exceeding the request weight limit.
+const BINANCE_TOO_MANY_REQUESTS: i64 = -1003;
+
+/// Kraken error codes that mean the caller is being throttled.
+const KRAKEN_RATE_LIMIT_ERRORS: [&str; 3] = [
+    "EAPI:Rate limit exceeded",
+    "EOrder:Rate limit exceeded",
+    "EGeneral:Too many requests",
+];
+
+/// Failed venue call, with the parts the limiter classifies on.
+#[derive(Debug, Clone, Default)]
+pub struct VenueError {
+    /// HTTP status, when the venue answered.
+    pub status: Option<u16>,
+    /// Binance error code from the response body.
+    pub code: Option<i64>,
+    /// Kraken errors from the response envelope.
+    pub errors: Vec<String>,
+    pub message: String,
+}
+
+impl VenueError {
+    /// 418: the IP is banned until the expiry in the message, if any.
+    pub fn is_banned(&self) -> bool {
+        self.status == Some(418)
+    }
+
+    /// 429, 418 or a rate limit error reported in the body.
+    pub fn is_rate_limited(&self) -> bool {
+        self.is_banned()
+            || self.status == Some(429)
+            || self.code == Some(BINANCE_TOO_MANY_REQUESTS)
+            || self
+                .errors
+                .iter()
+                .any(|error| KRAKEN_RATE_LIMIT_ERRORS.contains(&error.as_str()))
+    }
+}
+
+impl fmt::Display for VenueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for VenueError {}
+
+/// Current limiter usage, served at `/broker/rate_limits`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitMetrics {
+    pub used_weight_1m: u32,
+    pub weight_limit_1m: u32,
+    pub orders_10s: u32,
+    pub orders_limit_10s: u32,
+    pub orders_1d: u32,
+    pub orders_limit_1d: u32,
+    /// Calls that had to wait for capacity.
+    pub throttled_requests: u64,
+    /// Calls that failed without reaching the exchange.
+    pub rejected_requests: u64,
+    /// 429/418 responses received from the exchange.
+    pub rate_limited_responses: u64,
+    pub blocked_until: Option<DateTime<Utc>>,
+    pub backoff_ms: u128,
+}
+
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, window: Duration) -> Self {
+        let capacity = capacity as f64;
+
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / window.as_secs_f64(),
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last = now;
+    }
+
+    /// Time until `amount` tokens are available, zero when they already are.
+    fn wait_for(&mut self, amount: u32, now: Instant) -> Duration {
+        self.refill(now);
+
+        // A single call heavier than the whole bucket waits for a full bucket.
+        let amount = (amount as f64).min(self.capacity);
+        if self.tokens >= amount {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64((amount - self.tokens) / self.refill_per_sec)
+    }
+
+    fn take(&mut self, amount: u32) {
+        self.tokens = (self.tokens - amount as f64).max(0.0);
+    }
+
+    fn used(&self) -> u32 {
+        (self.capacity - self.tokens).round() as u32
+    }
+}
+
+struct LimiterState {
+    weight: TokenBucket,
+    orders_10s: TokenBucket,
+    orders_1d: TokenBucket,
+    blocked_until: Option<DateTime<Utc>>,
+    backoff: Duration,
+    throttled_requests: u64,
+    rejected_requests: u64,
+    rate_limited_responses: u64,
+}
+
+/// Token buckets for request weight and order count, shared by every client
+/// that talks to the same venue.
+///
+/// binance-rs does not expose response headers, so usage is tracked locally
+/// from the documented endpoint weights, and `Retry-After` is approximated
+/// with exponential backoff or the ban expiry sent in the error body.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let state = LimiterState {
+            weight: TokenBucket::new(config.request_weight_per_minute, Duration::from_secs(60)),
+            orders_10s: TokenBucket::new(config.orders_per_10s, Duration::from_secs(10)),
+            orders_1d: TokenBucket::new(config.orders_per_day, Duration::from_secs(24 * 60 * 60)),
+            blocked_until: None,
+            backoff: config.initial_backoff,
+            throttled_requests: 0,
+            rejected_requests: 0,
+            rate_limited_responses: 0,
+        };
+
+        Self {
+            config,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Blocks the calling thread until `weight` request weight and `orders`
+    /// order slots are available, then consumes them.
+    pub fn acquire(&self, weight: u32, orders: u32) -> Result<(), RateLimitError> {
+        let deadline = Instant::now() + self.config.max_wait;
+        let mut throttled = false;
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+
+                let blocked = state
+                    .blocked_until
+                    .and_then(|until| (until - Utc::now()).to_std().ok())
+                    .unwrap_or(Duration::ZERO);
+
+                let mut wait = blocked.max(state.weight.wait_for(weight, now));
+                if orders > 0 {
+                    wait = wait
+                        .max(state.orders_10s.wait_for(orders, now))
+                        .max(state.orders_1d.wait_for(orders, now));
+                }
+
+                if wait.is_zero() {
+                    state.weight.take(weight);
+                    state.orders_10s.take(orders);
+                    state.orders_1d.take(orders);
+                    return Ok(());
+                }
+
+                if now + wait > deadline {
+                    state.rejected_requests += 1;
+
+                    return Err(match state.blocked_until {
+                        Some(until) if !blocked.is_zero() => RateLimitError::Blocked { until },
+                        _ => RateLimitError::Exhausted { wait },
+                    });
+                }
+
+                if !throttled {
+                    state.throttled_requests += 1;
+                    throttled = true;
+                }
+
+                wait
+            };
+
+            thread::sleep(wait);
+        }
+    }
+
+    /// Feeds a failed call back into the limiter when it carries a
+    /// `VenueError`; transport errors are ignored.
+    pub fn record_failure(&self, error: &anyhow::Error) {
+        if let Some(error) = error.downcast_ref::<VenueError>() {
+            self.record_error(error);
+        }
+    }
+
+    /// Resets the backoff after a successful call.
+    pub fn record_success(&self) {
+        self.state.lock().unwrap().backoff = self.config.initial_backoff;
+    }
+
+    /// Pauses all requests when a failed call was rate limited or banned,
+    /// see `VenueError::is_rate_limited`.
+    pub fn record_error(&self, error: &VenueError) {
+        if !error.is_rate_limited() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.rate_limited_responses += 1;
+
+        let backoff = chrono::Duration::from_std(state.backoff).unwrap_or(chrono::Duration::zero());
+        let until = parse_banned_until(&error.message).unwrap_or_else(|| Utc::now() + backoff);
+
+        state.blocked_until = state.blocked_until.max(Some(until));
+        state.backoff = (state.backoff * 2).min(self.config.max_backoff);
+
+        warn!(
+            "Exchange {} requests, pausing REST calls until {}",
+            if error.is_banned() {
+                "banned"
+            } else {
+                "rate limited"
+            },
+            until
+        );
+    }
+
+    pub fn metrics(&self) -> RateLimitMetrics {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        state.weight.refill(now);
+        state.orders_10s.refill(now);
+        state.orders_1d.refill(now);
+
+        RateLimitMetrics {
+            used_weight_1m: state.weight.used(),
+            weight_limit_1m: self.config.request_weight_per_minute,
+            orders_10s: state.orders_10s.used(),
+            orders_limit_10s: self.config.orders_per_10s,
+            orders_1d: state.orders_1d.used(),
+            orders_limit_1d: self.config.orders_per_day,
+            throttled_requests: state.throttled_requests,
+            rejected_requests: state.rejected_requests,
+            rate_limited_responses: state.rate_limited_responses,
+            blocked_until: state.blocked_until.filter(|until| *until > Utc::now()),
+            backoff_ms: state.backoff.as_millis(),
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+/// Extracts the expiry from messages like
+/// `Way too much request weight used; IP banned until 1659146813123.`
+fn parse_banned_until(message: &str) -> Option<DateTime<Utc>> {
+    let (_, rest) = message.split_once("banned until ")?;
+    let millis: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+
+    DateTime::from_timestamp_millis(millis.parse().ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked(error: VenueError) -> bool {
+        let limiter = RateLimiter::default();
+        limiter.record_error(&error);
+        limiter.metrics().blocked_until.is_some()
+    }
+
+    #[test]
+    fn pauses_on_rate_limit_statuses_and_codes() {
+        assert!(blocked(VenueError {
+            status: Some(429),
+            ..Default::default()
+        }));
+        assert!(blocked(VenueError {
+            code: Some(-1003),
+            ..Default::default()
+        }));
+        assert!(blocked(VenueError {
+            errors: vec!["EAPI:Rate limit exceeded".to_string()],
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn waits_out_a_ban() {
+        let limiter = RateLimiter::default();
+
+        limiter.record_error(&VenueError {
+            status: Some(418),
+            message: "Way too much request weight used; IP banned until 4102444800000.".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            limiter.metrics().blocked_until,
+            DateTime::from_timestamp_millis(4_102_444_800_000)
+        );
+    }
+
+    #[test]
+    fn ignores_other_failures() {
+        assert!(!blocked(VenueError {
+            status: Some(400),
+            code: Some(-2011),
+            message: "order 4291 not found, rate 429".to_string(),
+            ..Default::default()
+        }));
+        assert!(!blocked(VenueError {
+            errors: vec!["EOrder:Insufficient funds".to_string()],
+            message: "too many requests".to_string(),
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    brokers::core::Broker,
+    models::{
+        market::OrderBook,
+        timeseries::{Candle, Trade, TradeFeed},
+    },
+};
+
+/// One market data message as it was received from the broker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub received_at: DateTime<Utc>,
+    pub symbol: String,
+    #[serde(flatten)]
+    pub event: RecordedEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "stream", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// Candles returned by `Broker::candles` when the recording started, so a
+    /// replay warms strategies up with the same history.
+    History {
+        interval: String,
+        candles: Vec<Candle>,
+    },
+    Candle {
+        interval: String,
+        candle: Candle,
+    },
+    Depth {
+        book: OrderBook,
+    },
+    Trade {
+        feed: TradeFeed,
+        trade: Trade,
+    },
+}
+
+impl RecordedEvent {
+    /// File name stem of the partition this event is written to.
+    fn stream_name(&self) -> String {
+        match self {
+            RecordedEvent::History { interval, .. } => format!("history_{interval}"),
+            RecordedEvent::Candle { interval, .. } => format!("kline_{interval}"),
+            RecordedEvent::Depth { .. } => "depth".to_string(),
+            RecordedEvent::Trade {
+                feed: TradeFeed::Trades,
+                ..
+            } => "trade".to_string(),
+            RecordedEvent::Trade {
+                feed: TradeFeed::AggTrades,
+                ..
+            } => "agg_trade".to_string(),
+        }
+    }
+}
+
+/// What to record for one symbol.
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    pub dir: PathBuf,
+    pub symbol: String,
+    /// Kline interval to record, `None` to skip candles.
+    pub interval: Option<String>,
+    /// Number of history candles captured at start.
+    pub history: u16,
+    pub depth: bool,
+    pub trades: Option<TradeFeed>,
+}
+
+impl RecorderConfig {
+    pub fn new(dir: impl Into<PathBuf>, symbol: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            symbol: symbol.to_uppercase(),
+            interval: Some("1m".to_string()),
+            history: 1000,
+            depth: true,
+            trades: Some(TradeFeed::AggTrades),
+        }
+    }
+}
+
+/// Captures a broker's market streams to JSONL files partitioned by UTC day,
+/// as `<dir>/<YYYY-MM-DD>/<SYMBOL>-<stream>.jsonl`.
+pub struct Recorder {
+    config: RecorderConfig,
+}
+
+impl Recorder {
+    pub fn new(config: RecorderConfig) -> Self {
+        Self { config }
+    }
+
+    /// Records until `cancel` fires or every stream has closed, returning the
+    /// number of messages written.
+    pub async fn run<B: Broker>(&self, broker: &B, cancel: CancellationToken) -> u64 {
+        let config = &self.config;
+        let mut writer = PartitionWriter::new(&config.dir);
+        let mut written = 0;
+
+        let mut candle_rx = config
+            .interval
+            .as_ref()
+            .map(|interval| broker.candle_stream(&config.symbol, interval));
+        let mut depth_rx = config
+            .depth
+            .then(|| broker.order_book_stream(&config.symbol));
+        let mut trade_rx = config
+            .trades
+            .map(|feed| broker.trade_stream(&config.symbol, feed));
+
+        if let Some(interval) = &config.interval {
+            let candles = broker
+                .candles(&config.symbol, interval, config.history, None, None)
+                .await;
+
+            written += writer.write(&RecordedMessage {
+                received_at: Utc::now(),
+                symbol: config.symbol.clone(),
+                event: RecordedEvent::History {
+                    interval: interval.clone(),
+                    candles,
+                },
+            });
+        }
+
+        info!(
+            "Recording {} market data to {}",
+            config.symbol,
+            config.dir.display()
+        );
+
+        let mut flush = tokio::time::interval(std::time::Duration::from_secs(1));
+
+        while candle_rx.is_some() || depth_rx.is_some() || trade_rx.is_some() {
+            let event = tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = flush.tick() => {
+                    writer.flush();
+                    continue;
+                }
+                candle = next(&mut candle_rx, "candle") => match candle {
+                    Some(candle) => RecordedEvent::Candle {
+                        interval: config.interval.clone().unwrap_or_default(),
+                        candle,
+                    },
+                    None => continue,
+                },
+                book = next(&mut depth_rx, "depth") => match book {
+                    Some(book) => RecordedEvent::Depth { book },
+                    None => continue,
+                },
+                trade = next(&mut trade_rx, "trade") => match trade {
+                    Some(trade) => RecordedEvent::Trade {
+                        feed: config.trades.unwrap_or(TradeFeed::Trades),
+                        trade,
+                    },
+                    None => continue,
+                },
+            };
+
+            written += writer.write(&RecordedMessage {
+                received_at: Utc::now(),
+                symbol: config.symbol.clone(),
+                event,
+            });
+        }
+
+        writer.flush();
+        info!("Recorded {} {} messages", written, config.symbol);
+
+        written
+    }
+}
+
+/// Next value of an optional stream, `None` once it closed. Lagged messages
+/// are skipped with a warning, and a closed stream is set to `None` so it is
+/// no longer polled.
+async fn next<T: Clone>(rx: &mut Option<Receiver<T>>, name: &str) -> Option<T> {
+    loop {
+        let Some(receiver) = rx.as_mut() else {
+            return std::future::pending().await;
+        };
+
+        match receiver.recv().await {
+            Ok(value) => return Some(value),
+            Err(RecvError::Lagged(n)) => {
+                warn!("Recorder {} stream lagged, {} messages lost", name, n);
+            }
+            Err(RecvError::Closed) => {
+                info!("Recorder {} stream closed", name);
+                *rx = None;
+                return None;
+            }
+        }
+    }
+}
+
+/// Appends messages to the current partition of each stream.
+struct PartitionWriter {
+    dir: PathBuf,
+    open: HashMap<String, (PathBuf, BufWriter<File>)>,
+}
+
+impl PartitionWriter {
+    fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            open: HashMap::new(),
+        }
+    }
+
+    /// Writes `message`, returning how many messages were written (0 or 1).
+    fn write(&mut self, message: &RecordedMessage) -> u64 {
+        let stream = format!("{}-{}", message.symbol, message.event.stream_name());
+        let path = self
+            .dir
+            .join(message.received_at.format("%Y-%m-%d").to_string())
+            .join(format!("{stream}.jsonl"));
+
+        if self
+            .open
+            .get(&stream)
+            .is_none_or(|(current, _)| *current != path)
+        {
+            // Day rollover: finish the previous partition first.
+            if let Some((_, mut previous)) = self.open.remove(&stream)
+                && let Err(e) = previous.flush()
+            {
+                error!("Failed to flush recording: {}", e);
+            }
+
+            match open_partition(&path) {
+                Ok(file) => {
+                    self.open
+                        .insert(stream.clone(), (path, BufWriter::new(file)));
+                }
+                Err(e) => {
+                    error!("Failed to open {}: {}", path.display(), e);
+                    return 0;
+                }
+            }
+        }
+
+        let (_, file) = self.open.get_mut(&stream).unwrap();
+        let result = serde_json::to_writer(&mut *file, message)
+            .map_err(std::io::Error::from)
+            .and_then(|_| file.write_all(b"\n"));
+
+        match result {
+            Ok(()) => 1,
+            Err(e) => {
+                error!("Failed to write recording: {}", e);
+                0
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        for (path, file) in self.open.values_mut() {
+            if let Err(e) = file.flush() {
+                error!("Failed to flush {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn open_partition(path: &Path) -> std::io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Loads every `.jsonl` partition under `dir`, ordered by receive time.
+/// Messages received at the same instant keep their file order.
+pub fn load_recording(dir: impl AsRef<Path>) -> anyhow::Result<Vec<RecordedMessage>> {
+    let mut files = Vec::new();
+    collect_partitions(dir.as_ref(), &mut files)?;
+    files.sort();
+
+    let mut messages = Vec::new();
+
+    for path in files {
+        let reader = BufReader::new(File::open(&path)?);
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<RecordedMessage>(&line) {
+                Ok(message) => messages.push(message),
+                // A crash can leave a partial last line behind.
+                Err(e) => warn!("Skipping {}:{}: {}", path.display(), number + 1, e),
+            }
+        }
+    }
+
+    messages.sort_by_key(|message| message.received_at);
+
+    Ok(messages)
+}
+
+fn collect_partitions(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_partitions(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "jsonl")
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
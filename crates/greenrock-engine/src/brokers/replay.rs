@@ -0,0 +1,822 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tokio::sync::{Semaphore, broadcast};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    brokers::{
+        core::Broker,
+        recorder::{RecordedEvent, RecordedMessage, load_recording},
+    },
+    models::{
+        account::{AccountEvent, Balance, OrderUpdate},
+        market::{OrderBook, Ticker},
+        money::{Price, Quantity},
+        orders::{Fill, Order, OrderAck, OrderRequest, OrderType, Side},
+        symbols::SymbolInfo,
+        timeseries::{Candle, Trade, TradeFeed},
+    },
+    portfolio::ledger::split_symbol,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Keep the original gaps between messages.
+    RealTime,
+    /// Divide the original gaps by this factor.
+    Accelerated(f64),
+    /// No gaps at all. Each message is only sent once the runner has
+    /// handled the previous one, so the run stays deterministic.
+    AsFastAsPossible,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    pub speed: ReplaySpeed,
+    /// The replay starts this long after the first stream subscription, so
+    /// every stream a runner opens at startup sees the whole recording.
+    pub start_delay: Duration,
+    /// Balances reported by `balances` before any simulated fill.
+    pub balances: HashMap<String, Decimal>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            speed: ReplaySpeed::AsFastAsPossible,
+            start_delay: Duration::from_millis(250),
+            balances: HashMap::new(),
+        }
+    }
+}
+
+const REPLAY_CAPACITY: usize = 1024;
+
+/// Longest the replay waits for a candle or trade to be handled, so a stream
+/// watched by something other than a runner cannot stall it.
+const HANDLED_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct ReplayChannels {
+    candles: HashMap<(String, String), broadcast::Sender<Candle>>,
+    depth: HashMap<String, broadcast::Sender<OrderBook>>,
+    trades: HashMap<(String, TradeFeed), broadcast::Sender<Trade>>,
+}
+
+struct ReplayState {
+    /// Taken by the replay task when it starts.
+    channels: Option<ReplayChannels>,
+    started: bool,
+    prices: HashMap<String, Price>,
+    books: HashMap<String, OrderBook>,
+    balances: HashMap<String, Decimal>,
+}
+
+/// `Broker` that replays a recording made by `Recorder`, so a live session
+/// can be re-run through `Runner` offline.
+///
+/// Orders fill immediately at the last replayed price and are reported on
+/// the account stream like real executions. Candles and trades are sent in
+/// lockstep with `Broker::market_data_handled`, so one runner per replay
+/// sees the same fills on every run. Every stream closes once the recording
+/// is exhausted, which ends the runner loop.
+#[derive(Clone)]
+pub struct ReplayBroker {
+    recording: Arc<Vec<RecordedMessage>>,
+    config: ReplayConfig,
+    state: Arc<Mutex<ReplayState>>,
+    account_tx: broadcast::Sender<AccountEvent>,
+    next_trade_id: Arc<AtomicU64>,
+    /// One permit per candle or trade the runner has handled.
+    handled: Arc<Semaphore>,
+}
+
+impl ReplayBroker {
+    pub fn new(recording: Vec<RecordedMessage>, config: ReplayConfig) -> Self {
+        let state = ReplayState {
+            channels: Some(ReplayChannels::default()),
+            started: false,
+            prices: HashMap::new(),
+            books: HashMap::new(),
+            balances: config.balances.clone(),
+        };
+
+        Self {
+            recording: Arc::new(recording),
+            config,
+            state: Arc::new(Mutex::new(state)),
+            account_tx: broadcast::channel(REPLAY_CAPACITY).0,
+            next_trade_id: Arc::new(AtomicU64::new(1)),
+            handled: Arc::new(Semaphore::new(0)),
+        }
+    }
+
+    /// Loads the recording stored under `dir`.
+    pub fn load(dir: impl AsRef<Path>, config: ReplayConfig) -> anyhow::Result<Self> {
+        let recording = load_recording(dir)?;
+        info!("Loaded recording with {} messages", recording.len());
+
+        Ok(Self::new(recording, config))
+    }
+
+    /// Subscribes to the channel selected by `select`, starting the replay
+    /// on first use. Streams opened after the replay started are closed.
+    fn subscribe<T: Clone>(
+        &self,
+        select: impl FnOnce(&mut ReplayChannels) -> &mut broadcast::Sender<T>,
+    ) -> broadcast::Receiver<T> {
+        let mut state = self.state.lock().unwrap();
+
+        let rx = match state.channels.as_mut() {
+            Some(channels) => select(channels).subscribe(),
+            None => broadcast::channel(1).1,
+        };
+
+        if !state.started {
+            state.started = true;
+            self.spawn_replay();
+        }
+
+        rx
+    }
+
+    fn spawn_replay(&self) {
+        let recording = self.recording.clone();
+        let state = self.state.clone();
+        let handled = self.handled.clone();
+        let speed = self.config.speed;
+        let start_delay = self.config.start_delay;
+
+        tokio::spawn(async move {
+            tokio::time::sleep(start_delay).await;
+
+            let Some(channels) = state.lock().unwrap().channels.take() else {
+                return;
+            };
+
+            let Some(first_at) = recording.first().map(|message| message.received_at) else {
+                return;
+            };
+
+            let origin = tokio::time::Instant::now();
+
+            for message in recording.iter() {
+                let factor = match speed {
+                    ReplaySpeed::RealTime => Some(1.0),
+                    ReplaySpeed::Accelerated(factor) if factor > 0.0 => Some(factor),
+                    ReplaySpeed::Accelerated(_) | ReplaySpeed::AsFastAsPossible => None,
+                };
+
+                if let Some(factor) = factor {
+                    let offset = (message.received_at - first_at)
+                        .to_std()
+                        .unwrap_or_default()
+                        .div_f64(factor);
+                    tokio::time::sleep_until(origin + offset).await;
+                }
+
+                replay_message(message, &channels, &state, &handled).await;
+            }
+
+            info!("Replay finished after {} messages", recording.len());
+            // Dropping `channels` closes every stream.
+        });
+    }
+
+    fn last_price(&self, symbol: &str) -> Price {
+        self.state
+            .lock()
+            .unwrap()
+            .prices
+            .get(&symbol.to_uppercase())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Updates the replay market state with `message` and sends it to the
+/// matching stream, waiting until a candle or trade has been handled.
+async fn replay_message(
+    message: &RecordedMessage,
+    channels: &ReplayChannels,
+    state: &Mutex<ReplayState>,
+    handled: &Semaphore,
+) {
+    let symbol = message.symbol.clone();
+
+    match &message.event {
+        RecordedEvent::History { .. } => {}
+        RecordedEvent::Candle { interval, candle } => {
+            state
+                .lock()
+                .unwrap()
+                .prices
+                .insert(symbol.clone(), candle.close);
+
+            if let Some(tx) = channels.candles.get(&(symbol, interval.clone())) {
+                send_in_lockstep(tx, candle.clone(), handled).await;
+            }
+        }
+        RecordedEvent::Depth { book } => {
+            state
+                .lock()
+                .unwrap()
+                .books
+                .insert(symbol.clone(), book.clone());
+
+            // Runners do not read depth, so nobody acknowledges it.
+            if let Some(tx) = channels.depth.get(&symbol) {
+                let _ = tx.send(book.clone());
+            }
+        }
+        RecordedEvent::Trade { feed, trade } => {
+            state
+                .lock()
+                .unwrap()
+                .prices
+                .insert(symbol.clone(), trade.price);
+
+            if let Some(tx) = channels.trades.get(&(symbol, *feed)) {
+                send_in_lockstep(tx, trade.clone(), handled).await;
+            }
+        }
+    }
+}
+
+/// Sends `value` and waits until the runner reports it handled, so the
+/// replayed prices never move ahead of the orders it places.
+async fn send_in_lockstep<T: Clone>(tx: &broadcast::Sender<T>, value: T, handled: &Semaphore) {
+    if tx.send(value).is_err() {
+        return;
+    }
+
+    match tokio::time::timeout(HANDLED_TIMEOUT, handled.acquire()).await {
+        Ok(Ok(permit)) => permit.forget(),
+        Ok(Err(_)) => {}
+        Err(_) => warn!(
+            "Replayed message not handled within {:?}, moving on",
+            HANDLED_TIMEOUT
+        ),
+    }
+}
+
+impl Broker for ReplayBroker {
+    fn balances(&self) -> Vec<Balance> {
+        self.state
+            .lock()
+            .unwrap()
+            .balances
+            .iter()
+            .filter(|(_, amount)| **amount > Decimal::ZERO)
+            .map(|(asset, amount)| Balance {
+                asset: asset.clone(),
+                free: *amount,
+                locked: Decimal::ZERO,
+            })
+            .collect()
+    }
+
+    fn market_current_price(&self, symbol: &str) -> f64 {
+        self.last_price(symbol).to_f64()
+    }
+
+    /// Top of book from the last replayed depth message. The recording has
+    /// no 24h statistics, so open, high and low are the last price.
+    fn ticker(&self, symbol: &str) -> Option<Ticker> {
+        let symbol = symbol.to_uppercase();
+        let state = self.state.lock().unwrap();
+        let last_price = *state.prices.get(&symbol)?;
+        let book = state.books.get(&symbol);
+
+        Some(Ticker {
+            last_price,
+            bid_price: book
+                .and_then(|book| book.best_bid())
+                .map_or(last_price, |level| level.price),
+            ask_price: book
+                .and_then(|book| book.best_ask())
+                .map_or(last_price, |level| level.price),
+            open_price: last_price,
+            high_price: last_price,
+            low_price: last_price,
+            volume: Quantity::ZERO,
+            price_change_percent: Decimal::ZERO,
+            time: Utc::now(),
+            symbol,
+        })
+    }
+
+    fn candle_stream(&self, symbol: &str, interval: &str) -> broadcast::Receiver<Candle> {
+        let key = (symbol.to_uppercase(), interval.to_string());
+        self.subscribe(|channels| {
+            channels
+                .candles
+                .entry(key)
+                .or_insert_with(|| broadcast::channel(REPLAY_CAPACITY).0)
+        })
+    }
+
+    /// Returns the history captured when the recording started.
+    async fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        limit: u16,
+        _from: Option<DateTime<Utc>>,
+        _to: Option<DateTime<Utc>>,
+    ) -> Vec<Candle> {
+        let symbol = symbol.to_uppercase();
+
+        let history = self
+            .recording
+            .iter()
+            .find_map(|message| match &message.event {
+                RecordedEvent::History {
+                    interval: recorded,
+                    candles,
+                } if message.symbol == symbol && recorded == interval => Some(candles),
+                _ => None,
+            });
+
+        history
+            .map(|candles| {
+                let skip = candles.len().saturating_sub(limit as usize);
+                candles[skip..].to_vec()
+            })
+            .unwrap_or_default()
+    }
+
+    fn open_orders(&self, _symbol: &str) -> Vec<Order> {
+        Vec::new()
+    }
+
+    fn trade_history(&self, _symbol: &str) -> Vec<Fill> {
+        Vec::new()
+    }
+
+    fn order_book(&self, symbol: &str, _depth: u64) -> OrderBook {
+        self.state
+            .lock()
+            .unwrap()
+            .books
+            .get(&symbol.to_uppercase())
+            .cloned()
+            .unwrap_or_else(|| OrderBook::empty(symbol))
+    }
+
+    fn order_book_stream(&self, symbol: &str) -> broadcast::Receiver<OrderBook> {
+        let key = symbol.to_uppercase();
+        self.subscribe(|channels| {
+            channels
+                .depth
+                .entry(key)
+                .or_insert_with(|| broadcast::channel(REPLAY_CAPACITY).0)
+        })
+    }
+
+    fn trade_stream(&self, symbol: &str, feed: TradeFeed) -> broadcast::Receiver<Trade> {
+        let key = (symbol.to_uppercase(), feed);
+        self.subscribe(|channels| {
+            channels
+                .trades
+                .entry(key)
+                .or_insert_with(|| broadcast::channel(REPLAY_CAPACITY).0)
+        })
+    }
+
+    fn account_stream(&self) -> broadcast::Receiver<AccountEvent> {
+        self.account_tx.subscribe()
+    }
+
+    fn market_data_handled(&self) {
+        self.handled.add_permits(1);
+    }
+
+    /// Fills the whole order at the last replayed price. Limit orders fill
+    /// at their limit price.
+    fn place_order(&self, order: &OrderRequest) -> anyhow::Result<OrderAck> {
+        let symbol = order.symbol.to_uppercase();
+        let (base, quote) = split_symbol(&symbol)
+            .ok_or_else(|| anyhow::anyhow!("Unknown quote asset in {symbol}"))?;
+
+        if order.is_futures_only() {
+            anyhow::bail!("Replay simulates spot balances, not futures positions");
+        }
+
+        let price = match order.order_type {
+            OrderType::Limit { price } => price,
+            OrderType::Market => self.last_price(&symbol),
+            OrderType::StopMarket { .. } => anyhow::bail!("Replay does not simulate stop orders"),
+        };
+
+        if !price.is_positive() {
+            anyhow::bail!("No replayed price for {symbol} yet");
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            let notional = order.quantity * price;
+            let (base_change, quote_change) = match order.side {
+                Side::Buy => (order.quantity.value(), -notional),
+                Side::Sell => (-order.quantity.value(), notional),
+            };
+
+            *state.balances.entry(base).or_default() += base_change;
+            *state.balances.entry(quote).or_default() += quote_change;
+        }
+
+        let order_id = format!("replay-{}", Uuid::new_v4());
+        let trade_id = self.next_trade_id.fetch_add(1, Ordering::Relaxed);
+
+        let _ = self.account_tx.send(AccountEvent::Order(OrderUpdate {
+            symbol: symbol.clone(),
+            order_id: order_id.clone(),
+            client_order_id: order_id.clone(),
+            side: order.side,
+            order_type: match order.order_type {
+                OrderType::Market => "MARKET".to_string(),
+                OrderType::Limit { .. } => "LIMIT".to_string(),
+                OrderType::StopMarket { .. } => "STOP_MARKET".to_string(),
+            },
+            execution_type: "TRADE".to_string(),
+            status: "FILLED".to_string(),
+            price,
+            quantity: order.quantity,
+            trade_id: Some(trade_id),
+            last_filled_qty: order.quantity,
+            last_filled_price: price,
+            cumulative_filled_qty: order.quantity,
+            cumulative_quote_qty: order.quantity * price,
+            commission: Quantity::ZERO,
+            commission_asset: None,
+            reject_reason: None,
+            at: Utc::now(),
+        }));
+
+        Ok(OrderAck {
+            order_id,
+            symbol,
+            side: order.side,
+            status: "FILLED".to_string(),
+            executed_qty: order.quantity,
+            average_price: price,
+        })
+    }
+
+    fn cancel_all_orders(&self) -> anyhow::Result<usize> {
+        Ok(0)
+    }
+
+    fn cancel_orders(&self, _symbol: &str) -> anyhow::Result<usize> {
+        Ok(0)
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::{
+        brokers::recorder::{Recorder, RecorderConfig},
+        portfolio::ledger::{Ledger, PnlReport},
+        runner::{
+            core::{ExecutionMode, RunConfig, Runner},
+            events::RunnerEvent,
+        },
+        strategy::core::{
+            Strategy, StrategyAction, StrategyContext, StrategyTraitKind, TradingAction,
+        },
+    };
+
+    fn recording() -> Vec<RecordedMessage> {
+        let start = Utc::now();
+
+        (0..50)
+            .map(|i| {
+                let close = Price::from_f64(100.0 + (i % 7) as f64 * 3.0 - (i % 3) as f64);
+
+                RecordedMessage {
+                    received_at: start + chrono::Duration::seconds(i),
+                    symbol: "BTCUSDT".to_string(),
+                    event: RecordedEvent::Candle {
+                        interval: "1m".to_string(),
+                        candle: Candle {
+                            open: close,
+                            high: close,
+                            low: close,
+                            close,
+                            volume: Quantity::from_f64(1.0),
+                            timestamp: (start + chrono::Duration::seconds(i)).timestamp(),
+                            ts: start + chrono::Duration::seconds(i),
+                        },
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Trades every candle like a runner would, buying on even and selling
+    /// on odd closes, and returns the fill prices and the final PnL.
+    async fn run(recording: Vec<RecordedMessage>) -> (Vec<Price>, Decimal) {
+        let broker = ReplayBroker::new(
+            recording,
+            ReplayConfig {
+                start_delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+
+        let mut account_rx = broker.account_stream();
+        let mut candle_rx = broker.candle_stream("BTCUSDT", "1m");
+        let mut ledger = Ledger::new("USDT");
+        let mut closes = Vec::new();
+
+        while let Ok(candle) = candle_rx.recv().await {
+            let side = if closes.len() % 2 == 0 {
+                Side::Buy
+            } else {
+                Side::Sell
+            };
+
+            broker
+                .place_order(&OrderRequest::market(
+                    "BTCUSDT",
+                    side,
+                    Quantity::from_f64(1.0),
+                ))
+                .unwrap();
+
+            closes.push(candle.close);
+            broker.market_data_handled();
+        }
+
+        let mut prices = Vec::new();
+
+        while let Ok(AccountEvent::Order(update)) = account_rx.try_recv() {
+            prices.push(update.last_filled_price);
+            ledger.apply_fill(&Fill {
+                trade_id: update.trade_id,
+                symbol: update.symbol,
+                side: update.side,
+                price: update.last_filled_price,
+                qty: update.last_filled_qty,
+                fee: update.commission,
+                fee_asset: update.commission_asset.unwrap_or_default(),
+                time: update.at,
+            });
+        }
+
+        // Every order fills at the close of the candle that triggered it.
+        assert_eq!(prices, closes);
+
+        (prices, ledger.pnl().total_pnl)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replays_deterministically() {
+        let recording = recording();
+
+        let first = run(recording.clone()).await;
+        let second = run(recording).await;
+
+        assert_eq!(first.0.len(), 50);
+        assert_eq!(first, second);
+    }
+
+    /// Buys half a unit on the fifth candle and sells it on the tenth.
+    struct RoundTripStrategy;
+
+    impl Strategy for RoundTripStrategy {
+        type State = usize;
+
+        fn init(&self, ctx: &mut StrategyContext, state: &mut usize) -> (StrategyContext, usize) {
+            (ctx.clone(), *state)
+        }
+
+        fn end(&self, ctx: &mut StrategyContext, state: &mut usize) -> (StrategyContext, usize) {
+            (ctx.clone(), *state)
+        }
+
+        fn tick(
+            &self,
+            _ctx: &mut StrategyContext,
+            at: DateTime<Utc>,
+            state: &mut usize,
+            symbol: String,
+            _data_scope: Vec<Candle>,
+            _tick: Candle,
+        ) -> StrategyAction {
+            *state += 1;
+
+            let side = match *state {
+                5 => Side::Buy,
+                10 => Side::Sell,
+                _ => return StrategyAction::Pass,
+            };
+
+            StrategyAction::Emitted(Box::new(TradingAction {
+                id: format!("round-trip-{state}"),
+                timestamp: at,
+                symbol,
+                side,
+                amount: 0.5,
+                kind: StrategyTraitKind::Long,
+            }))
+        }
+
+        fn initial_state(&self) -> usize {
+            0
+        }
+
+        fn portfolio(&self) -> HashMap<String, f64> {
+            HashMap::new()
+        }
+    }
+
+    fn closes(messages: &[RecordedMessage]) -> Vec<Price> {
+        messages
+            .iter()
+            .filter_map(|message| match &message.event {
+                RecordedEvent::Candle { candle, .. } => Some(candle.close),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Records `recording` into a fresh temp dir with `Recorder` and loads
+    /// it back.
+    async fn record_and_load(recording: Vec<RecordedMessage>) -> Vec<RecordedMessage> {
+        let dir = std::env::temp_dir().join(format!("greenrock-recording-{}", Uuid::new_v4()));
+        let source = ReplayBroker::new(
+            recording,
+            ReplayConfig {
+                start_delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+        // The recorder does not report messages handled, so release them all
+        // up front.
+        source.handled.add_permits(REPLAY_CAPACITY);
+
+        let recorder = Recorder::new(RecorderConfig {
+            history: 10,
+            depth: false,
+            trades: None,
+            ..RecorderConfig::new(&dir, "BTCUSDT")
+        });
+        let written = recorder.run(&source, CancellationToken::new()).await;
+
+        let loaded = load_recording(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(written, loaded.len() as u64);
+
+        loaded
+    }
+
+    /// Runs `recording` through a live `Runner` and returns the fill prices,
+    /// the number of ticks and the final PnL.
+    async fn run_runner(
+        recording: Vec<RecordedMessage>,
+        speed: ReplaySpeed,
+    ) -> (Vec<Price>, usize, PnlReport) {
+        let broker = ReplayBroker::new(
+            recording,
+            ReplayConfig {
+                speed,
+                start_delay: Duration::from_millis(10),
+                balances: HashMap::from([("USDT".to_string(), Decimal::from(10_000))]),
+            },
+        );
+        let runner = Runner::new(broker, RoundTripStrategy);
+        let mut events = runner.events();
+
+        let config = RunConfig {
+            symbol: "BTCUSDT".to_string(),
+            interval: "1m".to_string(),
+            execution: ExecutionMode::Live,
+            stale_after: None,
+            trade_feed: None,
+        };
+        runner
+            .run_with_cancel_signal(0, &config, CancellationToken::new())
+            .await;
+
+        let mut fills = Vec::new();
+        let mut ticks = 0;
+
+        while let Ok(event) = events.try_recv() {
+            match event {
+                RunnerEvent::TickProcessed { .. } => ticks += 1,
+                RunnerEvent::OrderFilled { price, .. } => fills.push(price),
+                _ => {}
+            }
+        }
+
+        (fills, ticks, runner.pnl())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replays_a_recording_through_the_runner() {
+        let original = recording();
+        let history = RecordedMessage {
+            received_at: original[0].received_at,
+            symbol: "BTCUSDT".to_string(),
+            event: RecordedEvent::History {
+                interval: "1m".to_string(),
+                candles: (0..20)
+                    .map(|i| Candle {
+                        open: Price::from_f64(90.0 + i as f64),
+                        high: Price::from_f64(90.0 + i as f64),
+                        low: Price::from_f64(90.0 + i as f64),
+                        close: Price::from_f64(90.0 + i as f64),
+                        volume: Quantity::from_f64(1.0),
+                        timestamp: i,
+                        ts: DateTime::from_timestamp(i, 0).unwrap(),
+                    })
+                    .collect(),
+            },
+        };
+        let source = [vec![history], original].concat();
+
+        let loaded = record_and_load(source.clone()).await;
+
+        // History first, trimmed to the recorder's limit, then every candle.
+        assert!(matches!(
+            &loaded[0].event,
+            RecordedEvent::History { candles, .. } if candles.len() == 10
+        ));
+        assert_eq!(closes(&loaded), closes(&source));
+
+        // The recorder stamps its own receive times, so the timed speeds
+        // replay in about as long as the recording took.
+        for speed in [
+            ReplaySpeed::AsFastAsPossible,
+            ReplaySpeed::Accelerated(10.0),
+            ReplaySpeed::RealTime,
+        ] {
+            let (fills, ticks, pnl) = run_runner(loaded.clone(), speed).await;
+
+            assert_eq!(ticks, 50, "{speed:?}");
+            assert_eq!(
+                fills,
+                vec![Price::from_f64(111.0), Price::from_f64(106.0)],
+                "{speed:?}"
+            );
+            assert_eq!(pnl.realized_pnl, Decimal::new(-25, 1), "{speed:?}");
+            assert_eq!(pnl.cash, Decimal::new(99975, 1), "{speed:?}");
+        }
+    }
+
+    /// The runner needs a multi-threaded runtime for `block_in_place`, which
+    /// paused time does not support, so the pacing is checked on the broker.
+    #[tokio::test(start_paused = true)]
+    async fn paces_timed_speeds_by_the_recorded_gaps() {
+        let start_delay = Duration::from_millis(10);
+
+        for (speed, gap) in [
+            (ReplaySpeed::RealTime, Duration::from_secs(1)),
+            (ReplaySpeed::Accelerated(4.0), Duration::from_millis(250)),
+        ] {
+            let broker = ReplayBroker::new(
+                recording(),
+                ReplayConfig {
+                    speed,
+                    start_delay,
+                    ..Default::default()
+                },
+            );
+
+            let start = tokio::time::Instant::now();
+            let mut candle_rx = broker.candle_stream("BTCUSDT", "1m");
+            let mut offsets = Vec::new();
+
+            while candle_rx.recv().await.is_ok() {
+                offsets.push(start.elapsed().as_millis());
+                broker.market_data_handled();
+            }
+
+            let expected: Vec<_> = (0..50)
+                .map(|i| (start_delay + gap * i).as_millis())
+                .collect();
+
+            assert_eq!(offsets, expected, "{speed:?}");
+        }
+    }
+}
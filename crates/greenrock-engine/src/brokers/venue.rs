@@ -0,0 +1,198 @@
+use std::env;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::brokers::binance::{BinanceBroker, BinanceEndpoints};
+use crate::brokers::binance_futures::BinanceFuturesBroker;
+use crate::brokers::core::Broker;
+use crate::brokers::kraken::{KrakenBroker, KrakenEndpoints};
+use crate::brokers::rate_limit::RateLimitMetrics;
+use crate::models::account::{AccountEvent, Balance};
+use crate::models::market::{OrderBook, Ticker};
+use crate::models::orders::{Fill, Order, OrderAck, OrderRequest};
+use crate::models::symbols::SymbolInfo;
+use crate::models::timeseries::{Candle, Trade, TradeFeed};
+
+/// The venue the engine trades on, picked at startup.
+///
+/// `Broker` is not object safe (`candles` returns `impl Future`), so venues
+/// are dispatched through this enum instead of a `dyn Broker`.
+#[derive(Clone)]
+pub enum VenueBroker {
+    Binance(BinanceBroker),
+    BinanceFutures(BinanceFuturesBroker),
+    Kraken(KrakenBroker),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $broker:ident => $call:expr) => {
+        match $self {
+            VenueBroker::Binance($broker) => $call,
+            VenueBroker::BinanceFutures($broker) => $call,
+            VenueBroker::Kraken($broker) => $call,
+        }
+    };
+}
+
+impl VenueBroker {
+    /// Selects the venue from `BROKER_VENUE` (`binance` or `kraken`), with
+    /// endpoints read from that venue's environment variables. Binance
+    /// trades spot unless `BINANCE_MARKET` is `usdm`.
+    pub fn from_env() -> Self {
+        match env::var("BROKER_VENUE").as_deref() {
+            Ok("kraken") => {
+                let endpoints = KrakenEndpoints::from_env();
+                info!(
+                    "Using Kraken REST {} and streams {}",
+                    endpoints.rest, endpoints.ws
+                );
+
+                VenueBroker::Kraken(KrakenBroker::new().with_endpoints(endpoints))
+            }
+            other => {
+                if let Ok(other) = other
+                    && other != "binance"
+                {
+                    warn!("Unknown BROKER_VENUE '{}', using binance", other);
+                }
+
+                match env::var("BINANCE_MARKET").as_deref() {
+                    Ok("usdm") => {
+                        let endpoints = BinanceEndpoints::futures_from_env();
+                        info!(
+                            "Using Binance USD-M futures REST {} and streams {}",
+                            endpoints.rest, endpoints.ws
+                        );
+
+                        VenueBroker::BinanceFutures(
+                            BinanceFuturesBroker::new().with_endpoints(endpoints),
+                        )
+                    }
+                    market => {
+                        if let Ok(market) = market
+                            && market != "spot"
+                        {
+                            warn!("Unknown BINANCE_MARKET '{}', using spot", market);
+                        }
+
+                        let endpoints = BinanceEndpoints::from_env();
+                        info!(
+                            "Using Binance REST {} and streams {}",
+                            endpoints.rest, endpoints.ws
+                        );
+
+                        VenueBroker::Binance(BinanceBroker::new().with_endpoints(endpoints))
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VenueBroker::Binance(_) => "binance",
+            VenueBroker::BinanceFutures(_) => "binance-usdm",
+            VenueBroker::Kraken(_) => "kraken",
+        }
+    }
+
+    /// The futures broker when trading perpetuals, for positions, leverage
+    /// and funding.
+    pub fn futures(&self) -> Option<&BinanceFuturesBroker> {
+        match self {
+            VenueBroker::BinanceFutures(broker) => Some(broker),
+            _ => None,
+        }
+    }
+
+    pub fn rate_limits(&self) -> RateLimitMetrics {
+        dispatch!(self, broker => broker.rate_limits())
+    }
+}
+
+impl Broker for VenueBroker {
+    fn balances(&self) -> Vec<Balance> {
+        dispatch!(self, broker => broker.balances())
+    }
+
+    fn market_current_price(&self, symbol: &str) -> f64 {
+        dispatch!(self, broker => broker.market_current_price(symbol))
+    }
+
+    fn ticker(&self, symbol: &str) -> Option<Ticker> {
+        dispatch!(self, broker => broker.ticker(symbol))
+    }
+
+    fn candle_stream(&self, symbol: &str, interval: &str) -> broadcast::Receiver<Candle> {
+        dispatch!(self, broker => broker.candle_stream(symbol, interval))
+    }
+
+    async fn candles(
+        &self,
+        symbol: &str,
+        interval: &str,
+        limit: u16,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Vec<Candle> {
+        dispatch!(self, broker => broker.candles(symbol, interval, limit, from, to).await)
+    }
+
+    fn open_orders(&self, symbol: &str) -> Vec<Order> {
+        dispatch!(self, broker => broker.open_orders(symbol))
+    }
+
+    fn trade_history(&self, symbol: &str) -> Vec<Fill> {
+        dispatch!(self, broker => broker.trade_history(symbol))
+    }
+
+    fn order_book(&self, symbol: &str, depth: u64) -> OrderBook {
+        dispatch!(self, broker => broker.order_book(symbol, depth))
+    }
+
+    fn order_book_stream(&self, symbol: &str) -> broadcast::Receiver<OrderBook> {
+        dispatch!(self, broker => broker.order_book_stream(symbol))
+    }
+
+    fn trade_stream(&self, symbol: &str, feed: TradeFeed) -> broadcast::Receiver<Trade> {
+        dispatch!(self, broker => broker.trade_stream(symbol, feed))
+    }
+
+    fn account_stream(&self) -> broadcast::Receiver<AccountEvent> {
+        dispatch!(self, broker => broker.account_stream())
+    }
+
+    fn place_order(&self, order: &OrderRequest) -> anyhow::Result<OrderAck> {
+        dispatch!(self, broker => broker.place_order(order))
+    }
+
+    fn cancel_all_orders(&self) -> anyhow::Result<usize> {
+        dispatch!(self, broker => broker.cancel_all_orders())
+    }
+
+    fn cancel_orders(&self, symbol: &str) -> anyhow::Result<usize> {
+        dispatch!(self, broker => broker.cancel_orders(symbol))
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        dispatch!(self, broker => broker.symbols())
+    }
+
+    fn symbol_info(&self, symbol: &str) -> Option<SymbolInfo> {
+        dispatch!(self, broker => broker.symbol_info(symbol))
+    }
+
+    fn supports_short(&self) -> bool {
+        dispatch!(self, broker => broker.supports_short())
+    }
+
+    fn market_data_handled(&self) {
+        dispatch!(self, broker => broker.market_data_handled())
+    }
+
+    fn flatten_orders(&self, quote_currency: &str) -> Vec<OrderRequest> {
+        dispatch!(self, broker => broker.flatten_orders(quote_currency))
+    }
+}
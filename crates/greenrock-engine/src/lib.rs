@@ -1,6 +1,7 @@
 pub mod analysis;
 pub mod brokers;
 pub mod models;
+pub mod portfolio;
 pub mod processor;
 pub mod runner;
 pub mod strategy;
@@ -2,33 +2,61 @@ use std::{collections::HashMap, env, sync::Arc};
 
 use axum::{
     Json, Router,
-    extract::{Query, State, WebSocketUpgrade, ws::WebSocket},
+    extract::{FromRequestParts, Path, Query, Request, State, WebSocketUpgrade, ws::WebSocket},
     http::{Method, StatusCode},
-    response::{IntoResponse, Response},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{get, get_service, post},
 };
 
 use chrono::DateTime;
 use graph_flow::{
-    Context, ExecutionStatus, FlowRunner, GraphStorage, InMemoryGraphStorage,
-    PostgresSessionStorage, Session, SessionStorage,
+    Context, ExecutionResult, ExecutionStatus, FlowRunner, GraphStorage, InMemoryGraphStorage,
+    Session, SessionStorage,
 };
 
 use greenrock_engine::{
     analysis::graph::setup_graph,
-    brokers::binance::BinanceBroker,
-    models::timeseries::Candle,
-    processor::tasks::entry_interaction_task::EntryInteractionTask,
-    runner::core::{RunConfig, Runner},
-    strategy::core::{MinimalStrategy, Strategy},
+    brokers::{
+        core::FuturesBroker,
+        recorder::{Recorder, RecorderConfig},
+        venue::VenueBroker,
+    },
+    models::{
+        futures::MarginType,
+        money::Price,
+        timeseries::{Candle, TradeFeed, parse_interval},
+        trades::FootprintBuilder,
+    },
+    portfolio::rebalancer::{RebalanceConfig, Rebalancer},
+    processor::{
+        approvals::{DECISION_KEY, Decision, PendingApproval, pending_approvals},
+        prompts::{
+            context::{MarketContextTemplate, PortfolioContextTemplate},
+            library::PromptLibrary,
+        },
+        sessions::SessionStore,
+        streaming::{ChatEvent, ChatEvents},
+        tasks::{
+            entry_interaction_task::EntryInteractionTask, reply_generation_task::COMPLETED_KEY,
+        },
+    },
+    runner::{
+        core::{ExecutionMode, LiveRunner, RunConfig, Runner},
+        kill_switch::KillSwitch,
+        risk::RiskLimits,
+    },
+    strategy::{core::Strategy, regimen::RegimenStrategy},
 };
 
 use serde_json::json;
 
-use polars::frame::DataFrame;
 // use polars::prelude::{IntoLazy, col};
 use serde::{Deserialize, Serialize};
 
+use tokio_util::sync::CancellationToken;
 use tracing::{Level, error, info};
 use uuid::Uuid;
 
@@ -38,10 +66,21 @@ use tower_http::services::ServeDir;
 
 #[derive(Clone)]
 struct AppState {
+    live_loop_runner: Arc<LiveRunner>,
+    greenrock_session: Arc<GreenrockSession>,
+    rebalancer: Arc<Rebalancer>,
+    execution: ExecutionMode,
+}
+
+/// State of the chat routes, which are only served with a session store.
+#[derive(Clone)]
+struct ChatState {
     flow_runner: Arc<FlowRunner>,
     session_storage: Arc<dyn SessionStorage>,
-    live_loop_runner: Arc<Runner<HashMap<String, f64>, BinanceBroker, MinimalStrategy>>,
+    live_loop_runner: Arc<LiveRunner>,
     greenrock_session: Arc<GreenrockSession>,
+    prompts: PromptLibrary,
+    chat_events: ChatEvents,
 }
 
 fn internal_error(message: &str) -> Response {
@@ -73,58 +112,48 @@ struct PauseResponse {
     reason: String,
 }
 
-async fn chat(State(state): State<AppState>, Json(params): Json<ChatRequest>) -> Response {
-    info!("Received recommendation request: {}", params.query);
-
-    let session_id = if params.session_id.is_some()
-        && let Ok(Some(session)) = state.session_storage.get(&params.session_id.unwrap()).await
-    {
-        info!("Session found: {}", session.id);
-        session.id
-    } else {
-        let new_session_id = Uuid::new_v4().to_string();
-
-        info!(
-            "Session not found, creating new session: {}",
-            new_session_id
-        );
-
-        new_session_id
-    };
-
-    let reply_task_id = std::any::type_name::<EntryInteractionTask>();
-
-    // Set up context with chat history limit
-    let context = Context::with_max_chat_messages(50);
+/// Renders the market and portfolio sections of the system prompt into the
+/// chat context. Missing data leaves the section out.
+async fn set_prompt_context(state: &ChatState, context: &Context) {
+    let runner = state.live_loop_runner.clone();
+    let symbol = state.greenrock_session.symbol.clone();
 
-    context.set("user_input", params.query.clone()).await;
-    context.set("session_id", session_id.clone()).await;
-    context.set("retry_count", 0).await;
+    let snapshot = tokio::task::spawn_blocking(move || {
+        (runner.ticker(&symbol), runner.pnl(), runner.positions())
+    })
+    .await;
 
-    let session = Session {
-        id: session_id.clone(),
-        graph_id: "".to_string(),
-        current_task_id: reply_task_id.to_string(),
-        status_message: None,
-        context,
+    let (ticker, pnl, positions) = match snapshot {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            error!("Failed to load prompt context: {}", e);
+            return;
+        }
     };
 
-    if let Err(e) = state.session_storage.save(session).await {
-        error!("Failed to save session: {}", e);
-        return internal_error("Failed to save session");
+    if let Some(ticker) = ticker {
+        match state
+            .prompts
+            .render(&MarketContextTemplate::from(&ticker))
+            .await
+        {
+            Ok(prompt) => context.set("market_context", prompt.text).await,
+            Err(e) => error!("Failed to render market context: {}", e),
+        }
     }
 
-    info!("Session created with ID: {}", session_id);
-
-    let execution = match state.flow_runner.run(&session_id).await {
-        Ok(execution) => execution,
-        Err(e) => {
-            error!("Failed to execute session: {}", e);
-            return internal_error(&format!("Workflow execution failed: {e}"));
-        }
-    };
+    match state
+        .prompts
+        .render(&PortfolioContextTemplate::new(&pnl, &positions))
+        .await
+    {
+        Ok(prompt) => context.set("portfolio_context", prompt.text).await,
+        Err(e) => error!("Failed to render portfolio context: {}", e),
+    }
+}
 
-    // Handle execution result
+/// Turns the outcome of a workflow run into the chat response.
+fn execution_response(session_id: String, execution: ExecutionResult) -> Response {
     match execution.status {
         ExecutionStatus::Completed => {
             info!("Workflow completed successfully");
@@ -151,8 +180,14 @@ async fn chat(State(state): State<AppState>, Json(params): Json<ChatRequest>) ->
         })
         .into_response(),
         ExecutionStatus::WaitingForInput => {
-            info!("Workflow unexpectedly waiting for input");
-            internal_error("Workflow is waiting for input, which is not expected in this flow")
+            info!("Workflow waiting for input");
+
+            Json(ChatResponse {
+                session_id,
+                answer: execution.response.unwrap_or_default(),
+                status: "waiting_for_input".to_string(),
+            })
+            .into_response()
         }
         ExecutionStatus::Error(e) => {
             error!("Workflow error: {}", e);
@@ -161,71 +196,778 @@ async fn chat(State(state): State<AppState>, Json(params): Json<ChatRequest>) ->
     }
 }
 
+/// The outcome of a workflow run as the last event of a streamed chat.
+fn execution_event(session_id: String, execution: ExecutionResult) -> ChatEvent {
+    let (status, answer) = match execution.status {
+        ExecutionStatus::Completed => (
+            "completed",
+            execution
+                .response
+                .unwrap_or_else(|| "No answer generated".to_string()),
+        ),
+        ExecutionStatus::Paused { reason, .. } => ("paused", reason.to_string()),
+        ExecutionStatus::WaitingForInput => {
+            ("waiting_for_input", execution.response.unwrap_or_default())
+        }
+        ExecutionStatus::Error(e) => {
+            error!("Workflow error: {}", e);
+            return ChatEvent::Error {
+                message: format!("Workflow failed: {e}"),
+            };
+        }
+    };
+
+    ChatEvent::Finished {
+        session_id,
+        status: status.to_string(),
+        answer,
+    }
+}
+
+/// Runs the session from its stored task and answers with the outcome.
+async fn run_session(state: &ChatState, session_id: String) -> Response {
+    match state.flow_runner.run(&session_id).await {
+        Ok(execution) => execution_response(session_id, execution),
+        Err(e) => {
+            error!("Failed to execute session: {}", e);
+            internal_error(&format!("Workflow execution failed: {e}"))
+        }
+    }
+}
+
+/// Prepares the session of a chat message: the waiting session it answers,
+/// or a fresh turn at the entry task. Returns the saved session's id.
+async fn start_session(state: &ChatState, params: ChatRequest) -> Result<String, Response> {
+    info!("Received recommendation request: {}", params.query);
+
+    let existing = match &params.session_id {
+        Some(session_id) => state.session_storage.get(session_id).await.ok().flatten(),
+        None => None,
+    };
+
+    let session = match existing {
+        // The message answers the pending confirmation: resume where the
+        // workflow stopped instead of starting over.
+        Some(session) if !pending_approvals(&session.context).is_empty() => {
+            info!("Resuming session {} awaiting confirmation", session.id);
+            session
+                .context
+                .set("user_input", params.query.clone())
+                .await;
+            session
+        }
+        existing => {
+            let session_id = match existing {
+                Some(session) => {
+                    info!("Session found: {}", session.id);
+                    session.id
+                }
+                None => {
+                    let new_session_id = Uuid::new_v4().to_string();
+
+                    info!(
+                        "Session not found, creating new session: {}",
+                        new_session_id
+                    );
+
+                    new_session_id
+                }
+            };
+
+            let reply_task_id = std::any::type_name::<EntryInteractionTask>();
+
+            // Set up context with chat history limit
+            let context = Context::with_max_chat_messages(50);
+
+            context.set("user_input", params.query.clone()).await;
+            context.set("session_id", session_id.clone()).await;
+            context.set("retry_count", 0).await;
+            context
+                .set("symbol", state.greenrock_session.symbol.clone())
+                .await;
+
+            set_prompt_context(state, &context).await;
+
+            Session {
+                id: session_id,
+                graph_id: "".to_string(),
+                current_task_id: reply_task_id.to_string(),
+                status_message: None,
+                context,
+            }
+        }
+    };
+
+    let session_id = session.id.clone();
+
+    if let Err(e) = state.session_storage.save(session).await {
+        error!("Failed to save session: {}", e);
+        return Err(internal_error("Failed to save session"));
+    }
+
+    info!("Session created with ID: {}", session_id);
+
+    Ok(session_id)
+}
+
+async fn chat(State(state): State<ChatState>, Json(params): Json<ChatRequest>) -> Response {
+    match start_session(&state, params).await {
+        Ok(session_id) => run_session(&state, session_id).await,
+        Err(response) => response,
+    }
+}
+
+/// Runs a chat message like `POST /chat`, streaming its progress as
+/// Server-Sent Events: task transitions, tool calls and reply tokens, then
+/// `finished` with the outcome.
+async fn chat_stream(State(state): State<ChatState>, Json(params): Json<ChatRequest>) -> Response {
+    let session_id = match start_session(&state, params).await {
+        Ok(session_id) => session_id,
+        Err(response) => return response,
+    };
+
+    let events = state.chat_events.subscribe(&session_id);
+
+    tokio::spawn(async move {
+        let event = match state.flow_runner.run(&session_id).await {
+            Ok(execution) => execution_event(session_id.clone(), execution),
+            Err(e) => {
+                error!("Failed to execute session: {}", e);
+                ChatEvent::Error {
+                    message: format!("Workflow execution failed: {e}"),
+                }
+            }
+        };
+
+        state.chat_events.send(&session_id, event);
+        state.chat_events.unsubscribe(&session_id);
+    });
+
+    let stream = futures_util::stream::unfold(events, |mut events| async move {
+        let event = events.recv().await?;
+        let sse_event = Event::default().event(event.kind()).json_data(&event);
+        Some((sse_event, events))
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ResumeRequest {
+    /// Answer to the pending approval.
+    decision: Option<Decision>,
+    /// Id of the approval the decision answers. Required with a decision, so
+    /// a stale client cannot approve a plan it has not seen.
+    approval_id: Option<String>,
+    /// Free-text input, read by the waiting task like a chat message.
+    input: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ApprovalsResponse {
+    session_id: String,
+    current_task: String,
+    approvals: Vec<PendingApproval>,
+}
+
+async fn load_session(state: &ChatState, session_id: &str) -> Result<Session, Response> {
+    match state.session_storage.get(session_id).await {
+        Ok(Some(session)) => Ok(session),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Session not found").into_response()),
+        Err(e) => {
+            error!("Failed to load session {}: {}", session_id, e);
+            Err(internal_error("Failed to load session"))
+        }
+    }
+}
+
+async fn get_chat_approvals(
+    State(state): State<ChatState>,
+    Path(session_id): Path<String>,
+) -> Response {
+    match load_session(&state, &session_id).await {
+        Ok(session) => Json(ApprovalsResponse {
+            session_id,
+            approvals: pending_approvals(&session.context),
+            current_task: session.current_task_id,
+        })
+        .into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Continues a paused or waiting session from its stored task, with an
+/// optional approve/reject decision and input for the waiting task.
+async fn resume_chat(
+    State(state): State<ChatState>,
+    Path(session_id): Path<String>,
+    Json(params): Json<ResumeRequest>,
+) -> Response {
+    let session = match load_session(&state, &session_id).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    let approvals = pending_approvals(&session.context);
+
+    if approvals.is_empty() && session.context.get_sync::<bool>(COMPLETED_KEY) == Some(true) {
+        return (
+            StatusCode::CONFLICT,
+            "Session has completed, send a new chat message",
+        )
+            .into_response();
+    }
+
+    if !approvals.is_empty() && params.decision.is_none() && params.input.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Session is waiting for approval, send a decision",
+        )
+            .into_response();
+    }
+
+    if params.decision.is_some() {
+        let current = approvals
+            .iter()
+            .find(|approval| approval.task_id == session.current_task_id)
+            .or(approvals.last());
+
+        let Some(current) = current else {
+            return (StatusCode::CONFLICT, "No approval is pending").into_response();
+        };
+
+        match &params.approval_id {
+            Some(approval_id) if *approval_id == current.id => {}
+            Some(_) => {
+                return (
+                    StatusCode::CONFLICT,
+                    "Approval is no longer pending, fetch the current one",
+                )
+                    .into_response();
+            }
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "A decision needs the approval_id it answers",
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    info!(
+        "Resuming session {} at {} ({:?})",
+        session_id, session.current_task_id, params.decision
+    );
+
+    if let Some(decision) = params.decision {
+        session.context.set(DECISION_KEY, decision).await;
+    }
+
+    if let Some(input) = params.input {
+        session.context.set("user_input", input).await;
+    }
+
+    if let Err(e) = state.session_storage.save(session).await {
+        error!("Failed to save session: {}", e);
+        return internal_error("Failed to save session");
+    }
+
+    run_session(&state, session_id).await
+}
+
 async fn get_balance(State(state): State<AppState>) -> Response {
     match tokio::task::spawn_blocking(move || state.live_loop_runner.balance()).await {
         Ok(balance) => Json(balance).into_response(),
         Err(e) => {
-            error!("Failed to get balance: {}", e);
-            internal_error("Failed to get balance")
+            error!("Failed to get balance: {}", e);
+            internal_error("Failed to get balance")
+        }
+    }
+}
+
+async fn get_balances(State(state): State<AppState>) -> Response {
+    match tokio::task::spawn_blocking(move || state.live_loop_runner.balances()).await {
+        Ok(balances) => Json(balances).into_response(),
+        Err(e) => {
+            error!("Failed to get balances: {}", e);
+            internal_error("Failed to get balances")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TickerQuery {
+    symbol: String,
+}
+
+async fn get_ticker(State(state): State<AppState>, Query(params): Query<TickerQuery>) -> Response {
+    match tokio::task::spawn_blocking(move || state.live_loop_runner.ticker(&params.symbol)).await {
+        Ok(Some(ticker)) => Json(ticker).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Ticker not available").into_response(),
+        Err(e) => {
+            error!("Failed to get ticker: {}", e);
+            internal_error("Failed to get ticker")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenOrdersQuery {
+    symbol: String,
+}
+
+async fn get_open_orders(
+    State(state): State<AppState>,
+    Query(params): Query<OpenOrdersQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || state.live_loop_runner.open_orders(&params.symbol))
+        .await
+    {
+        Ok(orders) => Json(orders).into_response(),
+        Err(e) => {
+            error!("Failed to get open orders: {}", e);
+            internal_error("Failed to get open orders")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TradeHistoryQuery {
+    symbol: String,
+}
+
+async fn get_trade_history(
+    State(state): State<AppState>,
+    Query(params): Query<TradeHistoryQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || state.live_loop_runner.trade_history(&params.symbol))
+        .await
+    {
+        Ok(history) => Json(history).into_response(),
+        Err(e) => {
+            error!("Failed to get trade history: {}", e);
+            internal_error("Failed to get trade history")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SymbolsQuery {
+    /// Only return symbols quoted in this asset, e.g. `USDT`.
+    quote: Option<String>,
+    /// Include symbols that are not currently trading.
+    #[serde(default)]
+    all: bool,
+}
+
+async fn get_symbols(
+    State(state): State<AppState>,
+    Query(params): Query<SymbolsQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || state.live_loop_runner.symbols()).await {
+        Ok(symbols) => {
+            let quote = params.quote.map(|quote| quote.to_uppercase());
+            let symbols: Vec<_> = symbols
+                .into_iter()
+                .filter(|info| params.all || info.is_trading())
+                .filter(|info| {
+                    quote
+                        .as_ref()
+                        .is_none_or(|quote| info.quote_asset == *quote)
+                })
+                .collect();
+
+            Json(symbols).into_response()
+        }
+        Err(e) => {
+            error!("Failed to get symbols: {}", e);
+            internal_error("Failed to get symbols")
+        }
+    }
+}
+
+async fn get_rate_limits(State(state): State<AppState>) -> Response {
+    Json(state.live_loop_runner.broker().rate_limits()).into_response()
+}
+
+fn futures_unavailable() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        "Futures endpoints need BROKER_VENUE=binance and BINANCE_MARKET=usdm",
+    )
+        .into_response()
+}
+
+async fn get_futures_positions(State(state): State<AppState>) -> Response {
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .broker()
+            .futures()
+            .map(|broker| broker.positions())
+    })
+    .await
+    {
+        Ok(Some(positions)) => Json(positions).into_response(),
+        Ok(None) => futures_unavailable(),
+        Err(e) => {
+            error!("Failed to get futures positions: {}", e);
+            internal_error("Failed to get futures positions")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LeverageRequest {
+    symbol: String,
+    leverage: u32,
+}
+
+async fn post_futures_leverage(
+    State(state): State<AppState>,
+    Json(params): Json<LeverageRequest>,
+) -> Response {
+    let symbol = params.symbol.to_uppercase();
+
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .broker()
+            .futures()
+            .map(|broker| broker.set_leverage(&params.symbol, params.leverage))
+    })
+    .await
+    {
+        Ok(Some(Ok(leverage))) => Json(json!({
+            "symbol": symbol,
+            "leverage": leverage,
+        }))
+        .into_response(),
+        Ok(Some(Err(e))) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        Ok(None) => futures_unavailable(),
+        Err(e) => {
+            error!("Failed to set leverage: {}", e);
+            internal_error("Failed to set leverage")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MarginTypeRequest {
+    symbol: String,
+    margin_type: MarginType,
+}
+
+async fn post_futures_margin_type(
+    State(state): State<AppState>,
+    Json(params): Json<MarginTypeRequest>,
+) -> Response {
+    let symbol = params.symbol.to_uppercase();
+    let margin_type = params.margin_type;
+
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .broker()
+            .futures()
+            .map(|broker| broker.set_margin_type(&params.symbol, params.margin_type))
+    })
+    .await
+    {
+        Ok(Some(Ok(()))) => Json(json!({
+            "symbol": symbol,
+            "margin_type": margin_type,
+        }))
+        .into_response(),
+        Ok(Some(Err(e))) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        Ok(None) => futures_unavailable(),
+        Err(e) => {
+            error!("Failed to set margin type: {}", e);
+            internal_error("Failed to set margin type")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MarkPriceQuery {
+    symbol: String,
+}
+
+async fn get_futures_mark_price(
+    State(state): State<AppState>,
+    Query(params): Query<MarkPriceQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .broker()
+            .futures()
+            .map(|broker| broker.mark_price(&params.symbol))
+    })
+    .await
+    {
+        Ok(Some(Some(mark_price))) => Json(mark_price).into_response(),
+        Ok(Some(None)) => (StatusCode::NOT_FOUND, "Mark price not available").into_response(),
+        Ok(None) => futures_unavailable(),
+        Err(e) => {
+            error!("Failed to get mark price: {}", e);
+            internal_error("Failed to get mark price")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FundingRatesQuery {
+    symbol: String,
+    limit: Option<u16>,
+}
+
+async fn get_futures_funding_rates(
+    State(state): State<AppState>,
+    Query(params): Query<FundingRatesQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .broker()
+            .futures()
+            .map(|broker| broker.funding_rates(&params.symbol, params.limit.unwrap_or(100)))
+    })
+    .await
+    {
+        Ok(Some(rates)) => Json(rates).into_response(),
+        Ok(None) => futures_unavailable(),
+        Err(e) => {
+            error!("Failed to get funding rates: {}", e);
+            internal_error("Failed to get funding rates")
+        }
+    }
+}
+
+// #[derive(Clone)]
+struct GreenrockSession {
+    _id: Uuid,
+    symbol: String,
+    interval: String,
+    _candles: Vec<Candle>,
+    _balance: HashMap<String, f64>,
+}
+
+async fn get_portfolio(State(state): State<AppState>) -> Response {
+    match tokio::task::spawn_blocking(move || state.live_loop_runner.portfolio()).await {
+        Ok(portfolio) => Json(portfolio).into_response(),
+        Err(e) => {
+            error!("Failed to get portfolio: {}", e);
+            internal_error("Failed to get portfolio")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LedgerQuery {
+    /// Comma separated symbols to ingest; defaults to the strategy portfolio.
+    symbols: Option<String>,
+}
+
+fn refresh_ledger(state: &AppState, symbols: Option<String>) {
+    let symbols: Vec<String> = match symbols {
+        Some(symbols) => symbols
+            .split(',')
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .collect(),
+        None => state.live_loop_runner.portfolio().into_keys().collect(),
+    };
+
+    for symbol in &symbols {
+        state.live_loop_runner.sync_trade_history(symbol);
+    }
+
+    state.live_loop_runner.mark_to_market();
+}
+
+async fn get_portfolio_positions(
+    State(state): State<AppState>,
+    Query(params): Query<LedgerQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || {
+        refresh_ledger(&state, params.symbols);
+        state.live_loop_runner.positions()
+    })
+    .await
+    {
+        Ok(positions) => Json(positions).into_response(),
+        Err(e) => {
+            error!("Failed to get portfolio positions: {}", e);
+            internal_error("Failed to get portfolio positions")
+        }
+    }
+}
+
+async fn get_portfolio_pnl(
+    State(state): State<AppState>,
+    Query(params): Query<LedgerQuery>,
+) -> Response {
+    match tokio::task::spawn_blocking(move || {
+        refresh_ledger(&state, params.symbols);
+        state.live_loop_runner.pnl()
+    })
+    .await
+    {
+        Ok(pnl) => Json(pnl).into_response(),
+        Err(e) => {
+            error!("Failed to get portfolio pnl: {}", e);
+            internal_error("Failed to get portfolio pnl")
+        }
+    }
+}
+
+async fn get_rebalance_preview(State(state): State<AppState>) -> Response {
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .rebalance_plan(&state.rebalancer, state.execution)
+    })
+    .await
+    {
+        Ok(plan) => Json(plan).into_response(),
+        Err(e) => {
+            error!("Failed to build rebalance plan: {}", e);
+            internal_error("Failed to build rebalance plan")
+        }
+    }
+}
+
+async fn get_kill_switch(State(state): State<AppState>) -> Response {
+    Json(state.live_loop_runner.kill_switch().state()).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct KillSwitchRequest {
+    reason: Option<String>,
+    #[serde(default)]
+    flatten: bool,
+}
+
+async fn post_kill_switch(
+    State(state): State<AppState>,
+    Json(params): Json<KillSwitchRequest>,
+) -> Response {
+    let reason = params
+        .reason
+        .unwrap_or_else(|| "manual kill switch".to_string());
+
+    match tokio::task::spawn_blocking(move || {
+        state
+            .live_loop_runner
+            .trigger_kill_switch(&reason, params.flatten, state.execution)
+    })
+    .await
+    {
+        // Trading is halted either way, but the halt will not survive a
+        // restart when the state could not be written.
+        Ok(report) if report.persist_error.is_some() => {
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(report)).into_response()
+        }
+        Ok(report) => Json(report).into_response(),
+        Err(e) => {
+            error!("Failed to engage kill switch: {}", e);
+            internal_error("Failed to engage kill switch")
         }
     }
 }
 
-#[derive(Deserialize)]
-struct OpenOrdersQuery {
-    symbol: String,
+#[derive(Debug, Deserialize)]
+struct ReleaseKillSwitchRequest {
+    #[serde(default)]
+    confirm: bool,
+    #[serde(default)]
+    reason: String,
 }
 
-async fn get_open_orders(
+/// Resumes trading. Needs `{"confirm": true, "reason": "..."}`, so a stray
+/// request cannot lift a halt.
+async fn delete_kill_switch(
     State(state): State<AppState>,
-    Query(params): Query<OpenOrdersQuery>,
+    Json(params): Json<ReleaseKillSwitchRequest>,
 ) -> Response {
-    match tokio::task::spawn_blocking(move || state.live_loop_runner.open_orders(&params.symbol))
-        .await
-    {
-        Ok(orders) => Json(orders).into_response(),
-        Err(e) => {
-            error!("Failed to get open orders: {}", e);
-            internal_error("Failed to get open orders")
-        }
+    let reason = params.reason.trim();
+
+    if !params.confirm || reason.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Releasing the kill switch needs {\"confirm\": true, \"reason\": \"...\"}",
+        )
+            .into_response();
     }
+
+    if let Err(e) = state.live_loop_runner.kill_switch().release(reason) {
+        return internal_error(&format!(
+            "Kill switch released, but the state was not persisted and the halt returns on restart: {e}"
+        ));
+    }
+
+    Json(state.live_loop_runner.kill_switch().state()).into_response()
 }
 
-#[derive(Deserialize)]
-struct TradeHistoryQuery {
-    symbol: String,
+#[derive(Debug, Deserialize)]
+struct RebalanceRequest {
+    /// Must be `true`; the plan is only built and returned otherwise.
+    #[serde(default)]
+    confirm: bool,
 }
 
-async fn get_trade_history(
+/// Executes the current rebalance plan in the configured `TRADING_MODE`.
+/// Needs `{"confirm": true}`, and only returns the plan while
+/// `REBALANCE_MODE` is not `live`.
+async fn post_rebalance(
     State(state): State<AppState>,
-    Query(params): Query<TradeHistoryQuery>,
+    Json(params): Json<RebalanceRequest>,
 ) -> Response {
-    match tokio::task::spawn_blocking(move || state.live_loop_runner.trade_history(&params.symbol))
-        .await
-    {
-        Ok(history) => Json(history).into_response(),
-        Err(e) => {
-            error!("Failed to get trade history: {}", e);
-            internal_error("Failed to get trade history")
-        }
+    if !params.confirm {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Rebalance needs {\"confirm\": true}, preview it with GET",
+        )
+            .into_response();
     }
-}
 
-// #[derive(Clone)]
-struct GreenrockSession {
-    _id: Uuid,
-    symbol: String,
-    interval: String,
-    _candles: Vec<Candle>,
-    _balance: HashMap<String, f64>,
-}
+    if state.live_loop_runner.kill_switch().is_engaged() {
+        return (StatusCode::CONFLICT, "Kill switch is engaged").into_response();
+    }
 
-async fn get_portfolio(State(state): State<AppState>) -> Response {
-    match tokio::task::spawn_blocking(move || state.live_loop_runner.portfolio()).await {
-        Ok(portfolio) => Json(portfolio).into_response(),
+    let dry_run = state.rebalancer.config.dry_run;
+
+    match tokio::task::spawn_blocking(move || {
+        let plan = state
+            .live_loop_runner
+            .rebalance_plan(&state.rebalancer, state.execution);
+
+        if dry_run {
+            info!("Rebalance requested (dry run): {} legs", plan.legs.len());
+            return (plan, Vec::new());
+        }
+
+        let executions =
+            state
+                .live_loop_runner
+                .execute_rebalance(&state.rebalancer, &plan, state.execution);
+        (plan, executions)
+    })
+    .await
+    {
+        Ok((plan, executions)) => Json(json!({
+            "dry_run": dry_run,
+            "plan": plan,
+            "executions": executions,
+        }))
+        .into_response(),
         Err(e) => {
-            error!("Failed to get portfolio: {}", e);
-            internal_error("Failed to get portfolio")
+            error!("Failed to execute rebalance: {}", e);
+            internal_error("Failed to execute rebalance")
         }
     }
 }
@@ -273,6 +1015,75 @@ async fn get_order_book_stream(State(state): State<AppState>, ws: WebSocketUpgra
     ws.on_upgrade(move |socket| handle_depth_socket_stream(socket, state))
 }
 
+async fn get_account_stream(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_account_socket_stream(socket, state))
+}
+
+#[derive(Debug, Deserialize)]
+struct FootprintQuery {
+    /// Price step the footprint levels are grouped by, e.g. `10`.
+    tick_size: String,
+}
+
+async fn get_footprint_stream(
+    State(state): State<AppState>,
+    Query(params): Query<FootprintQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let tick_size = match params.tick_size.parse::<Price>() {
+        Ok(tick_size) if tick_size.is_positive() => tick_size,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "tick_size must be a positive price",
+            )
+                .into_response();
+        }
+    };
+
+    let Some(interval) = parse_interval(&state.greenrock_session.interval) else {
+        return internal_error("Session interval is not a valid interval");
+    };
+
+    ws.on_upgrade(move |socket| {
+        handle_footprint_socket_stream(socket, state, FootprintBuilder::new(interval, tick_size))
+    })
+}
+
+/// Serves the runner event bus as a WebSocket when the request asks for an
+/// upgrade, and as Server-Sent Events otherwise.
+async fn get_strategy_events(State(state): State<AppState>, request: Request) -> Response {
+    let (mut parts, _body) = request.into_parts();
+
+    if let Ok(ws) = WebSocketUpgrade::from_request_parts(&mut parts, &state).await {
+        return ws.on_upgrade(move |socket| handle_strategy_events_socket_stream(socket, state));
+    }
+
+    let stream =
+        futures_util::stream::unfold(state.live_loop_runner.events(), |mut events| async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let sse_event = Event::default().event(event.kind()).json_data(&event);
+                        return Some((sse_event, events));
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                        info!(
+                            "Strategy events SSE lagged by {} messages, continuing",
+                            count
+                        );
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
 #[derive(Debug, Deserialize)]
 struct OrderBookQuery {
     symbol: String,
@@ -430,6 +1241,190 @@ async fn handle_depth_socket_stream(mut socket: WebSocket, state: AppState) {
     }
 }
 
+/// Groups the session symbol's aggregate trades into one footprint bar per
+/// session interval and sends each bar once its interval closes.
+async fn handle_footprint_socket_stream(
+    mut socket: WebSocket,
+    state: AppState,
+    mut builder: FootprintBuilder,
+) {
+    info!("Footprint WebSocket client connected");
+    let mut stream = state
+        .live_loop_runner
+        .trade_stream(&state.greenrock_session.symbol, TradeFeed::AggTrades);
+
+    loop {
+        tokio::select! {
+            recv_result = stream.recv() => {
+                match recv_result {
+                    Ok(trade) => {
+                        let Some(bar) = builder.push(&trade) else {
+                            continue;
+                        };
+
+                        match serde_json::to_string(&bar) {
+                            Ok(msg) => {
+                                if socket.send(axum::extract::ws::Message::Text(msg.into())).await.is_err() {
+                                    info!("Footprint WebSocket client disconnected");
+                                    return;
+                                }
+                            }
+                            Err(e) => {
+                                error!("Failed to serialize footprint bar: {}", e);
+                                continue;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                        info!("Footprint WebSocket lagged by {} trades, continuing", count);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        info!("Trade stream closed");
+                        return;
+                    }
+                }
+            }
+            msg_result = socket.recv() => {
+                match msg_result {
+                    Some(Ok(axum::extract::ws::Message::Close(_))) | None => {
+                        info!("Footprint WebSocket client disconnected");
+                        return;
+                    }
+                    Some(Ok(axum::extract::ws::Message::Ping(data))) => {
+                        if socket.send(axum::extract::ws::Message::Pong(data)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Err(_)) => {
+                        info!("Footprint WebSocket client connection error");
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+async fn handle_account_socket_stream(mut socket: WebSocket, state: AppState) {
+    info!("Account WebSocket client connected");
+    let mut stream = state.live_loop_runner.account_stream();
+
+    loop {
+        tokio::select! {
+            recv_result = stream.recv() => {
+                match recv_result {
+                    Ok(event) => {
+                        match serde_json::to_string(&event) {
+                            Ok(msg) => {
+                                if socket.send(axum::extract::ws::Message::Text(msg.into())).await.is_err() {
+                                    info!("Account WebSocket client disconnected");
+                                    return;
+                                }
+                            }
+                            Err(e) => {
+                                error!("Failed to serialize account event: {}", e);
+                                continue;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                        info!("Account WebSocket lagged by {} messages, continuing", count);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        info!("Account stream closed");
+                        return;
+                    }
+                }
+            }
+            msg_result = socket.recv() => {
+                match msg_result {
+                    Some(Ok(axum::extract::ws::Message::Close(_))) => {
+                        info!("Account WebSocket client sent close message");
+                        return;
+                    }
+                    Some(Ok(axum::extract::ws::Message::Ping(data))) => {
+                        if socket.send(axum::extract::ws::Message::Pong(data)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Err(_)) => {
+                        info!("Account WebSocket client connection error");
+                        return;
+                    }
+                    None => {
+                        info!("Account WebSocket client disconnected");
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+async fn handle_strategy_events_socket_stream(mut socket: WebSocket, state: AppState) {
+    info!("Strategy events WebSocket client connected");
+    let mut events = state.live_loop_runner.events();
+
+    loop {
+        tokio::select! {
+            recv_result = events.recv() => {
+                match recv_result {
+                    Ok(event) => {
+                        match serde_json::to_string(&event) {
+                            Ok(msg) => {
+                                if socket.send(axum::extract::ws::Message::Text(msg.into())).await.is_err() {
+                                    info!("Strategy events WebSocket client disconnected");
+                                    return;
+                                }
+                            }
+                            Err(e) => {
+                                error!("Failed to serialize strategy event: {}", e);
+                                continue;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                        info!("Strategy events WebSocket lagged by {} messages, continuing", count);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        info!("Strategy event bus closed");
+                        return;
+                    }
+                }
+            }
+            msg_result = socket.recv() => {
+                match msg_result {
+                    Some(Ok(axum::extract::ws::Message::Close(_))) => {
+                        info!("Strategy events WebSocket client sent close message");
+                        return;
+                    }
+                    Some(Ok(axum::extract::ws::Message::Ping(data))) => {
+                        if socket.send(axum::extract::ws::Message::Pong(data)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Err(_)) => {
+                        info!("Strategy events WebSocket client connection error");
+                        return;
+                    }
+                    None => {
+                        info!("Strategy events WebSocket client disconnected");
+                        return;
+                    }
+                    _ => {
+                        // Ignore other message types
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
@@ -441,60 +1436,130 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Starting greenrock chat service");
 
-    let database_url =
-        env::var("DATABASE_URL").map_err(|_| "DATABASE_URL environment variable not set")?;
+    let session_storage = SessionStore::from_env()?.connect().await?;
+
+    let strategy = RegimenStrategy::from_env();
+    let initial_state = strategy.initial_state();
+
+    let broker = VenueBroker::from_env();
 
-    let session_storage: Arc<dyn SessionStorage> =
-        Arc::new(PostgresSessionStorage::connect(&database_url).await?);
+    let kill_switch_path = env::var("KILL_SWITCH_STATE_PATH")
+        .unwrap_or_else(|_| ".greenrock/kill_switch.json".to_string());
+    let flatten_on_trigger = env::var("KILL_SWITCH_FLATTEN").is_ok_and(|value| value == "true");
 
-    let graph_storage: Arc<dyn GraphStorage> = Arc::new(InMemoryGraphStorage::new());
+    let kill_switch =
+        Arc::new(KillSwitch::load(&kill_switch_path).with_flatten_on_trigger(flatten_on_trigger));
 
-    setup_graph(graph_storage.clone()).await?;
+    let runner = Arc::new(
+        Runner::new(broker, strategy)
+            .with_kill_switch(kill_switch)
+            .with_risk_limits(RiskLimits::from_env()),
+    );
 
-    let graph = graph_storage.get("").await?.ok_or(" graph not found")?;
+    let greenrock_session = Arc::new(GreenrockSession {
+        _id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        interval: "1m".to_string(),
+        _candles: vec![],
+        _balance: HashMap::new(),
+    });
 
-    let flow_runner = Arc::new(FlowRunner::new(graph.clone(), session_storage.clone()));
+    let chat_state = match session_storage {
+        Some(session_storage) => {
+            let graph_storage: Arc<dyn GraphStorage> = Arc::new(InMemoryGraphStorage::new());
 
-    let strategy = MinimalStrategy::new(DataFrame::new(vec![]).unwrap());
-    let initial_state = strategy.initial_state();
+            let chat_events = ChatEvents::new();
 
-    let binance_broker = BinanceBroker::default();
+            setup_graph(graph_storage.clone(), runner.clone(), chat_events.clone()).await?;
 
-    let runner = Arc::new(Runner::new(binance_broker, strategy));
+            let graph = graph_storage.get("").await?.ok_or(" graph not found")?;
+
+            Some(ChatState {
+                flow_runner: Arc::new(FlowRunner::new(graph.clone(), session_storage.clone())),
+                session_storage,
+                live_loop_runner: runner.clone(),
+                greenrock_session: greenrock_session.clone(),
+                prompts: PromptLibrary::from_env(),
+                chat_events,
+            })
+        }
+        None => None,
+    };
+
+    let execution = ExecutionMode::from_env();
+
+    // off (default), dry_run or live
+    let rebalance_mode = env::var("REBALANCE_MODE").unwrap_or_else(|_| "off".to_string());
+
+    let rebalancer = Arc::new(Rebalancer::new(RebalanceConfig {
+        dry_run: rebalance_mode != "live",
+        ..Default::default()
+    }));
 
     let state = AppState {
-        flow_runner,
-        session_storage,
         live_loop_runner: runner.clone(),
-        greenrock_session: Arc::new(GreenrockSession {
-            _id: Uuid::new_v4(),
-            symbol: "BTCUSDT".to_string(),
-            interval: "1m".to_string(),
-            _candles: vec![],
-            _balance: HashMap::new(),
-        }),
+        greenrock_session,
+        rebalancer: rebalancer.clone(),
+        execution,
     };
 
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
         .allow_origin(Any);
 
+    let chat_routes: Router = match chat_state {
+        Some(chat_state) => Router::new()
+            .route("/chat", post(chat))
+            .route("/chat/stream", post(chat_stream))
+            .route("/chat/{session_id}/resume", post(resume_chat))
+            .route("/chat/{session_id}/approvals", get(get_chat_approvals))
+            .with_state(chat_state),
+        None => Router::new(),
+    };
+
     let app: Router = Router::new()
         .route("/health", get(health_check))
-        .route("/chat", post(chat))
         //
         .route("/strategy/portfolio", get(get_portfolio))
+        .route("/strategy/events", get(get_strategy_events))
+        //
+        .route("/portfolio/positions", get(get_portfolio_positions))
+        .route("/portfolio/pnl", get(get_portfolio_pnl))
+        .route(
+            "/portfolio/rebalance",
+            get(get_rebalance_preview).post(post_rebalance),
+        )
+        //
+        .route(
+            "/kill_switch",
+            get(get_kill_switch)
+                .post(post_kill_switch)
+                .delete(delete_kill_switch),
+        )
         //
         .route("/broker/balance", get(get_balance))
+        .route("/broker/balances", get(get_balances))
+        .route("/broker/ticker", get(get_ticker))
         .route("/broker/open_orders", get(get_open_orders))
         .route("/broker/trade_history", get(get_trade_history))
+        .route("/broker/symbols", get(get_symbols))
+        .route("/broker/rate_limits", get(get_rate_limits))
         .route("/broker/candles", get(get_candles))
         .route("/broker/candle_stream", get(get_candle_stream))
         .route("/broker/order_book", get(get_order_book))
         .route("/broker/order_book_stream", get(get_order_book_stream))
+        .route("/broker/account_stream", get(get_account_stream))
+        .route("/broker/footprint_stream", get(get_footprint_stream))
+        //
+        .route("/futures/positions", get(get_futures_positions))
+        .route("/futures/leverage", post(post_futures_leverage))
+        .route("/futures/margin_type", post(post_futures_margin_type))
+        .route("/futures/mark_price", get(get_futures_mark_price))
+        .route("/futures/funding_rates", get(get_futures_funding_rates))
+        .with_state(state)
+        .merge(chat_routes)
         .fallback_service(get_service(ServeDir::new("greenrock-web-ui/dist")))
-        .layer(ServiceBuilder::new().layer(cors))
-        .with_state(state);
+        .layer(ServiceBuilder::new().layer(cors));
 
     info!("Starting both web server and trading runner...");
 
@@ -505,18 +1570,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         axum::serve(listener, app).await.unwrap();
     });
 
+    let background_cancel = CancellationToken::new();
+
+    if rebalance_mode != "off" {
+        let runner = runner.clone();
+        let cancel = background_cancel.clone();
+
+        tokio::spawn(async move {
+            info!("Starting scheduled rebalancer ({rebalance_mode})...");
+            runner
+                .run_rebalancer_with_cancel_signal(&rebalancer, execution, cancel)
+                .await;
+        });
+    }
+
+    let run_config = RunConfig {
+        symbol: "BTCUSDT".to_string(),
+        interval: "1m".to_string(),
+        execution,
+        stale_after: Some(std::time::Duration::from_secs(300)),
+        trade_feed: None,
+    };
+
+    if let Ok(record_dir) = env::var("RECORD_DIR") {
+        let runner = runner.clone();
+        let cancel = background_cancel.clone();
+        let recorder = Recorder::new(RecorderConfig {
+            interval: Some(run_config.interval.clone()),
+            ..RecorderConfig::new(record_dir, &run_config.symbol)
+        });
+
+        tokio::spawn(async move {
+            recorder.run(runner.broker(), cancel).await;
+        });
+    }
+
     // Spawn the trading runner task
     let trading_runner_handle = tokio::spawn(async move {
-        info!("Starting trading runner for BTCUSDT...");
-        runner
-            .run_until_ctrl_c(
-                &RunConfig {
-                    symbol: "BTCUSDT".to_string(),
-                    interval: "1m".to_string(),
-                },
-                initial_state,
-            )
-            .await;
+        info!("Starting trading runner for {}...", run_config.symbol);
+        runner.run_until_ctrl_c(&run_config, initial_state).await;
     });
 
     // Wait for Ctrl+C or either task to complete
@@ -536,6 +1628,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    background_cancel.cancel();
+
     info!("shutting down");
 
     Ok(())
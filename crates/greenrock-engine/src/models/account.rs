@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    futures::FuturesPosition,
+    money::{Price, Quantity},
+    orders::Side,
+};
+
+/// Change to one of our orders, pushed by the venue as it happens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderUpdate {
+    pub symbol: String,
+    pub order_id: String,
+    pub client_order_id: String,
+    pub side: Side,
+    pub order_type: String,
+    /// Why this update was sent: `NEW`, `TRADE`, `CANCELED`, `EXPIRED`, ...
+    pub execution_type: String,
+    /// Order status after the update: `NEW`, `PARTIALLY_FILLED`, `FILLED`, ...
+    pub status: String,
+    pub price: Price,
+    pub quantity: Quantity,
+    /// Set on `TRADE` updates.
+    pub trade_id: Option<u64>,
+    pub last_filled_qty: Quantity,
+    pub last_filled_price: Price,
+    pub cumulative_filled_qty: Quantity,
+    /// Cumulative notional in the quote asset.
+    pub cumulative_quote_qty: Decimal,
+    pub commission: Quantity,
+    pub commission_asset: Option<String>,
+    pub reject_reason: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+impl OrderUpdate {
+    pub fn is_trade(&self) -> bool {
+        self.execution_type == "TRADE" && self.last_filled_qty.is_positive()
+    }
+
+    /// No further updates will follow for this order.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "FILLED" | "CANCELED" | "REJECTED" | "EXPIRED" | "EXPIRED_IN_MATCH"
+        )
+    }
+}
+
+/// Holdings of one asset. `locked` is reserved by open orders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Balance {
+    pub asset: String,
+    pub free: Decimal,
+    pub locked: Decimal,
+}
+
+impl Balance {
+    pub fn total(&self) -> Decimal {
+        self.free + self.locked
+    }
+}
+
+/// Typed events from the account (user data) stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccountEvent {
+    Order(OrderUpdate),
+    /// Balances of the assets that changed, after an order or transfer.
+    Balances {
+        balances: Vec<Balance>,
+        at: DateTime<Utc>,
+    },
+    /// Deposit, withdrawal or transfer of a single asset.
+    BalanceDelta {
+        asset: String,
+        delta: Decimal,
+        at: DateTime<Utc>,
+    },
+    /// Futures positions that changed, after a fill, funding or liquidation.
+    /// Leverage, mark and liquidation prices and notional are not part of
+    /// the update and stay zero.
+    Positions {
+        positions: Vec<FuturesPosition>,
+        at: DateTime<Utc>,
+    },
+}
@@ -73,6 +73,10 @@ pub trait TechnicalAnalysis {
     /// Calculate Average True Range
     fn atr(&self, period: usize) -> f64;
 
+    /// Calculate Average Directional Index with Wilder smoothing, 0 when
+    /// there are fewer than `2 * period + 1` candles
+    fn adx(&self, period: usize) -> f64;
+
     /// Calculate HL2 (typical price) for the last candle
     fn hl2(&self) -> f64;
 
@@ -129,22 +133,22 @@ impl TechnicalAnalysis for Vec<Candle> {
         let mut ema = ExponentialMovingAverage::new(period).unwrap();
 
         for candle in self.iter() {
-            ema.next(candle.close);
+            ema.next(candle.close.to_f64());
         }
 
         let last_candle = self.last().unwrap();
 
-        ema.next(last_candle.close)
+        ema.next(last_candle.close.to_f64())
     }
 
     fn rsi(&self, period: usize) -> f64 {
         let mut rsi = RelativeStrengthIndex::new(period).unwrap();
 
         for candle in self.iter() {
-            rsi.next(candle.close);
+            rsi.next(candle.close.to_f64());
         }
 
-        rsi.next(self.last().unwrap().close)
+        rsi.next(self.last().unwrap().close.to_f64())
     }
 
     fn macd(
@@ -158,10 +162,10 @@ impl TechnicalAnalysis for Vec<Candle> {
                 .unwrap();
 
         for candle in self.iter() {
-            macd.next(candle.close);
+            macd.next(candle.close.to_f64());
         }
 
-        macd.next(self.last().unwrap().close)
+        macd.next(self.last().unwrap().close.to_f64())
     }
 
     fn atr(&self, period: usize) -> f64 {
@@ -174,11 +178,11 @@ impl TechnicalAnalysis for Vec<Candle> {
         for candle in self.iter() {
             atr.next(
                 &ta::DataItem::builder()
-                    .open(candle.open)
-                    .high(candle.high)
-                    .low(candle.low)
-                    .close(candle.close)
-                    .volume(candle.volume)
+                    .open(candle.open.to_f64())
+                    .high(candle.high.to_f64())
+                    .low(candle.low.to_f64())
+                    .close(candle.close.to_f64())
+                    .volume(candle.volume.to_f64())
                     .build()
                     .unwrap(),
             );
@@ -186,19 +190,92 @@ impl TechnicalAnalysis for Vec<Candle> {
 
         atr.next(
             &ta::DataItem::builder()
-                .open(self.last().unwrap().open)
-                .high(self.last().unwrap().high)
-                .low(self.last().unwrap().low)
-                .close(self.last().unwrap().close)
-                .volume(self.last().unwrap().volume)
+                .open(self.last().unwrap().open.to_f64())
+                .high(self.last().unwrap().high.to_f64())
+                .low(self.last().unwrap().low.to_f64())
+                .close(self.last().unwrap().close.to_f64())
+                .volume(self.last().unwrap().volume.to_f64())
                 .build()
                 .unwrap(),
         )
     }
 
+    fn adx(&self, period: usize) -> f64 {
+        if period == 0 || self.len() < period * 2 + 1 {
+            return 0.0;
+        }
+
+        let period_f = period as f64;
+        let mut smoothed_tr = 0.0;
+        let mut smoothed_plus_dm = 0.0;
+        let mut smoothed_minus_dm = 0.0;
+        let mut adx = 0.0;
+        let mut dx_count = 0;
+
+        for (i, window) in self.windows(2).enumerate() {
+            let (prev, candle) = (&window[0], &window[1]);
+            let (high, low) = (candle.high.to_f64(), candle.low.to_f64());
+            let prev_close = prev.close.to_f64();
+
+            let up_move = high - prev.high.to_f64();
+            let down_move = prev.low.to_f64() - low;
+
+            let plus_dm = if up_move > down_move && up_move > 0.0 {
+                up_move
+            } else {
+                0.0
+            };
+            let minus_dm = if down_move > up_move && down_move > 0.0 {
+                down_move
+            } else {
+                0.0
+            };
+            let true_range = (high - low)
+                .max((high - prev_close).abs())
+                .max((low - prev_close).abs());
+
+            if i < period {
+                smoothed_tr += true_range;
+                smoothed_plus_dm += plus_dm;
+                smoothed_minus_dm += minus_dm;
+
+                if i < period - 1 {
+                    continue;
+                }
+            } else {
+                smoothed_tr += true_range - smoothed_tr / period_f;
+                smoothed_plus_dm += plus_dm - smoothed_plus_dm / period_f;
+                smoothed_minus_dm += minus_dm - smoothed_minus_dm / period_f;
+            }
+
+            if smoothed_tr == 0.0 {
+                continue;
+            }
+
+            let plus_di = 100.0 * smoothed_plus_dm / smoothed_tr;
+            let minus_di = 100.0 * smoothed_minus_dm / smoothed_tr;
+            let di_sum = plus_di + minus_di;
+            let dx = if di_sum > 0.0 {
+                100.0 * (plus_di - minus_di).abs() / di_sum
+            } else {
+                0.0
+            };
+
+            dx_count += 1;
+
+            if dx_count <= period {
+                adx += dx / period_f;
+            } else {
+                adx = (adx * (period_f - 1.0) + dx) / period_f;
+            }
+        }
+
+        adx
+    }
+
     fn hl2(&self) -> f64 {
         if let Some(last_candle) = self.last() {
-            (last_candle.high + last_candle.low) / 2.0
+            (last_candle.high.to_f64() + last_candle.low.to_f64()) / 2.0
         } else {
             0.0
         }
@@ -217,7 +294,7 @@ impl TechnicalAnalysis for Vec<Candle> {
 
         let atr_value = self.atr(atr_period);
         let hl2_value = self.hl2();
-        let close = self.last().unwrap().close;
+        let close = self.last().unwrap().close.to_f64();
 
         let basic_upper = hl2_value + atr_value * factor;
         let basic_lower = hl2_value - atr_value * factor;
@@ -276,7 +353,7 @@ impl TechnicalAnalysis for Vec<Candle> {
 
         let price_changes: Vec<f64> = self
             .windows(2)
-            .map(|window| window[1].close - window[0].close)
+            .map(|window| window[1].close.to_f64() - window[0].close.to_f64())
             .collect();
 
         if price_changes.is_empty() {
@@ -308,7 +385,7 @@ impl TechnicalAnalysis for Vec<Candle> {
 
         let last_two: Vec<&Candle> = self.iter().rev().take(2).collect();
         if last_two.len() == 2 {
-            (last_two[0].close - last_two[1].close).signum()
+            (last_two[0].close.to_f64() - last_two[1].close.to_f64()).signum()
         } else {
             0.0
         }
@@ -322,12 +399,12 @@ impl TechnicalAnalysis for Vec<Candle> {
         let mut ema = ExponentialMovingAverage::new(period).unwrap();
 
         for window in self.windows(2) {
-            let change = (window[1].close - window[0].close).abs();
+            let change = (window[1].close.to_f64() - window[0].close.to_f64()).abs();
             ema.next(change);
         }
 
         if let Some(last_window) = self.windows(2).last() {
-            let change = (last_window[1].close - last_window[0].close).abs();
+            let change = (last_window[1].close.to_f64() - last_window[0].close.to_f64()).abs();
             ema.next(change)
         } else {
             0.0
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::money::{Price, Quantity};
+
+/// Whether a position draws on its own margin or on the whole wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarginType {
+    Isolated,
+    Cross,
+}
+
+/// Open position on a perpetual contract.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuturesPosition {
+    pub symbol: String,
+    /// Signed contract quantity, negative for short positions.
+    pub quantity: Quantity,
+    pub entry_price: Price,
+    pub mark_price: Price,
+    /// Zero when the position cannot be liquidated, e.g. fully collateralised.
+    pub liquidation_price: Price,
+    pub unrealized_pnl: Decimal,
+    pub leverage: u32,
+    pub margin_type: MarginType,
+    /// Signed position value at the mark price, in the quote asset.
+    pub notional: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FuturesPosition {
+    pub fn is_long(&self) -> bool {
+        self.quantity.is_positive()
+    }
+
+    pub fn is_short(&self) -> bool {
+        self.quantity < Quantity::ZERO
+    }
+}
+
+/// Mark and index price of a perpetual with the funding rate that applies
+/// at `next_funding_time`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkPrice {
+    pub symbol: String,
+    pub mark_price: Price,
+    pub index_price: Price,
+    /// Predicted rate for the next settlement, e.g. `0.0001` for 0.01%.
+    pub funding_rate: Decimal,
+    pub next_funding_time: DateTime<Utc>,
+    pub time: DateTime<Utc>,
+}
+
+/// A funding rate, either settled or predicted for `funding_time`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub symbol: String,
+    /// Longs pay shorts when positive.
+    pub funding_rate: Decimal,
+    pub funding_time: DateTime<Utc>,
+    /// Mark price at settlement, zero when the venue does not report it.
+    pub mark_price: Price,
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::money::{Price, Quantity};
+
+/// One price level of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookLevel {
+    pub price: Price,
+    pub quantity: Quantity,
+}
+
+/// Order book snapshot or update. Bids are sorted best (highest) first and
+/// asks best (lowest) first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub symbol: String,
+    /// Venue sequence number, zero when the venue does not send one.
+    pub last_update_id: u64,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+    /// Time of the book in ms.
+    pub timestamp: i64,
+}
+
+impl OrderBook {
+    pub fn empty(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            ..Default::default()
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<&BookLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&BookLevel> {
+        self.asks.first()
+    }
+
+    /// Midpoint of the best bid and ask.
+    pub fn mid_price(&self) -> Option<Price> {
+        let sum = self.best_bid()?.price + self.best_ask()?.price;
+        Some(Price(sum.value() / Decimal::TWO))
+    }
+}
+
+/// Rolling 24h statistics and top of book of one symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ticker {
+    pub symbol: String,
+    pub last_price: Price,
+    pub bid_price: Price,
+    pub ask_price: Price,
+    pub open_price: Price,
+    pub high_price: Price,
+    pub low_price: Price,
+    /// Base asset volume.
+    pub volume: Quantity,
+    /// Change since `open_price`, in percent.
+    pub price_change_percent: Decimal,
+    pub time: DateTime<Utc>,
+}
+
+impl Ticker {
+    pub fn spread(&self) -> Price {
+        self.ask_price - self.bid_price
+    }
+}
@@ -1,3 +1,10 @@
+pub mod account;
 pub mod analysis;
+pub mod futures;
+pub mod market;
+pub mod money;
+pub mod orders;
 pub mod sources;
+pub mod symbols;
 pub mod timeseries;
+pub mod trades;
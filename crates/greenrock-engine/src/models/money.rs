@@ -0,0 +1,196 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use rust_decimal::{
+    Decimal,
+    prelude::{FromPrimitive, ToPrimitive},
+};
+use serde::{Deserialize, Serialize};
+
+/// Converts a float, e.g. from a websocket payload or an indicator, to a
+/// decimal. Non-finite values become zero.
+pub fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default()
+}
+
+/// Floors `value` to a multiple of `step`. Steps of zero or less leave the
+/// value unchanged.
+pub fn floor_to_step(value: Decimal, step: Decimal) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
+    }
+
+    ((value / step).floor() * step).normalize()
+}
+
+/// Rounds `value` to the nearest multiple of `step`.
+pub fn round_to_step(value: Decimal, step: Decimal) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
+    }
+
+    ((value / step).round() * step).normalize()
+}
+
+macro_rules! decimal_newtype {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub Decimal);
+
+        impl $name {
+            pub const ZERO: Self = Self(Decimal::ZERO);
+
+            pub fn new(value: Decimal) -> Self {
+                Self(value)
+            }
+
+            /// Explicit conversion from float math, e.g. indicators or
+            /// strategy sizing. Non-finite values become zero.
+            pub fn from_f64(value: f64) -> Self {
+                Self(to_decimal(value))
+            }
+
+            /// Explicit conversion for float math such as indicators.
+            pub fn to_f64(self) -> f64 {
+                self.0.to_f64().unwrap_or(0.0)
+            }
+
+            pub fn value(self) -> Decimal {
+                self.0
+            }
+
+            pub fn is_zero(self) -> bool {
+                self.0.is_zero()
+            }
+
+            pub fn is_positive(self) -> bool {
+                self.0 > Decimal::ZERO
+            }
+
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(value: Decimal) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for Decimal {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        /// Parses exchange strings such as `"0.00100000"` without going
+        /// through a float.
+        impl FromStr for $name {
+            type Err = rust_decimal::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Decimal::from_str(s.trim()).map(Self)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0.normalize(), f)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|value| value.0).sum())
+            }
+        }
+    };
+}
+
+decimal_newtype!(
+    /// A price in the quote asset of a symbol.
+    Price
+);
+
+decimal_newtype!(
+    /// An amount of an asset: order and fill sizes, volumes and fees.
+    Quantity
+);
+
+impl Price {
+    /// Rounds to the nearest multiple of the symbol tick size.
+    pub fn round_to_tick(self, tick_size: Price) -> Price {
+        Price(round_to_step(self.0, tick_size.0))
+    }
+}
+
+impl Quantity {
+    /// Floors to the lot step, so the result never exceeds what was asked
+    /// for.
+    pub fn floor_to_step(self, step_size: Quantity) -> Quantity {
+        Quantity(floor_to_step(self.0, step_size.0))
+    }
+}
+
+/// Notional value in the quote asset.
+impl Mul<Quantity> for Price {
+    type Output = Decimal;
+
+    fn mul(self, rhs: Quantity) -> Decimal {
+        self.0 * rhs.0
+    }
+}
+
+/// Notional value in the quote asset.
+impl Mul<Price> for Quantity {
+    type Output = Decimal;
+
+    fn mul(self, rhs: Price) -> Decimal {
+        self.0 * rhs.0
+    }
+}
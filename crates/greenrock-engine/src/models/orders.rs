@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::money::{Price, Quantity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OrderType {
+    Market,
+    Limit {
+        price: Price,
+    },
+    /// Market order sent once the mark or last price crosses `stop_price`.
+    #[serde(rename = "stop_market")]
+    StopMarket {
+        stop_price: Price,
+    },
+}
+
+/// Venue-agnostic order submitted through `Broker::place_order`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    /// Zero for close-position orders, which take the size of the position.
+    pub quantity: Quantity,
+    /// Only shrink an existing futures position, never open or flip one.
+    #[serde(default)]
+    pub reduce_only: bool,
+    /// Close the whole futures position when a stop triggers.
+    #[serde(default)]
+    pub close_position: bool,
+}
+
+impl OrderRequest {
+    pub fn market(symbol: &str, side: Side, quantity: Quantity) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            side,
+            order_type: OrderType::Market,
+            quantity,
+            reduce_only: false,
+            close_position: false,
+        }
+    }
+
+    pub fn limit(symbol: &str, side: Side, quantity: Quantity, price: Price) -> Self {
+        Self {
+            order_type: OrderType::Limit { price },
+            ..Self::market(symbol, side, quantity)
+        }
+    }
+
+    pub fn stop_market(symbol: &str, side: Side, quantity: Quantity, stop_price: Price) -> Self {
+        Self {
+            order_type: OrderType::StopMarket { stop_price },
+            ..Self::market(symbol, side, quantity)
+        }
+    }
+
+    /// Stop that closes the whole position on `symbol`, e.g. a stop loss on
+    /// a short is a buy above the market.
+    pub fn close_position(symbol: &str, side: Side, stop_price: Price) -> Self {
+        Self {
+            close_position: true,
+            ..Self::stop_market(symbol, side, Quantity::ZERO, stop_price)
+        }
+    }
+
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+
+    /// Whether the order needs a venue that can hold futures positions.
+    pub fn is_futures_only(&self) -> bool {
+        self.reduce_only || self.close_position
+    }
+}
+
+/// Exchange acknowledgement of a submitted order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAck {
+    pub order_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub status: String,
+    pub executed_qty: Quantity,
+    /// Volume weighted fill price, zero when nothing has executed yet.
+    pub average_price: Price,
+}
+
+/// An order resting on the venue, as returned by `Broker::open_orders`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub order_id: String,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: Side,
+    /// Venue order type, e.g. `LIMIT` or `STOP_LOSS_LIMIT`.
+    pub order_type: String,
+    /// Limit price, zero for market orders.
+    pub price: Price,
+    pub quantity: Quantity,
+    pub executed_qty: Quantity,
+    pub status: String,
+    pub time: DateTime<Utc>,
+}
+
+/// A single execution, either from the exchange trade history or from a
+/// paper/backtest simulation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub trade_id: Option<u64>,
+    pub symbol: String,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Quantity,
+    /// Amount of `fee_asset` charged.
+    pub fee: Quantity,
+    pub fee_asset: String,
+    pub time: DateTime<Utc>,
+}
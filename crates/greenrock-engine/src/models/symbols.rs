@@ -0,0 +1,330 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    money::{Price, Quantity},
+    orders::{OrderRequest, OrderType},
+};
+
+/// Trading rules of a single exchange symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub status: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub tick_size: Price,
+    pub min_price: Price,
+    /// Zero when the venue sets no maximum.
+    pub max_price: Price,
+    pub step_size: Quantity,
+    pub min_qty: Quantity,
+    /// Zero when the venue sets no maximum.
+    pub max_qty: Quantity,
+    /// Minimum order value in the quote asset.
+    pub min_notional: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "filter", rename_all = "snake_case")]
+pub enum FilterViolation {
+    NotTrading {
+        status: String,
+    },
+    PriceOutOfRange {
+        price: Price,
+        min: Price,
+        max: Price,
+    },
+    QuantityBelowMin {
+        quantity: Quantity,
+        min: Quantity,
+    },
+    QuantityAboveMax {
+        quantity: Quantity,
+        max: Quantity,
+    },
+    NotionalBelowMin {
+        notional: Decimal,
+        min: Decimal,
+    },
+}
+
+impl fmt::Display for FilterViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterViolation::NotTrading { status } => write!(f, "symbol status is {status}"),
+            FilterViolation::PriceOutOfRange { price, min, max } => {
+                write!(f, "price {price} outside [{min}, {max}]")
+            }
+            FilterViolation::QuantityBelowMin { quantity, min } => {
+                write!(f, "quantity {quantity} below minimum {min}")
+            }
+            FilterViolation::QuantityAboveMax { quantity, max } => {
+                write!(f, "quantity {quantity} above maximum {max}")
+            }
+            FilterViolation::NotionalBelowMin { notional, min } => {
+                write!(f, "notional {notional} below minimum {min}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FilterViolation {}
+
+impl SymbolInfo {
+    pub fn is_trading(&self) -> bool {
+        self.status == "TRADING"
+    }
+
+    /// Rounds a price to the nearest valid tick.
+    pub fn round_price(&self, price: Price) -> Price {
+        price.round_to_tick(self.tick_size)
+    }
+
+    /// Rounds a quantity down to the lot step, so it never exceeds what was
+    /// asked for.
+    pub fn round_quantity(&self, quantity: Quantity) -> Quantity {
+        quantity.floor_to_step(self.step_size)
+    }
+
+    /// Returns a copy of `order` with price and quantity on valid increments.
+    pub fn normalize(&self, order: &OrderRequest) -> OrderRequest {
+        let mut normalized = order.clone();
+        normalized.quantity = self.round_quantity(order.quantity);
+
+        match order.order_type {
+            OrderType::Limit { price } => {
+                normalized.order_type = OrderType::Limit {
+                    price: self.round_price(price),
+                };
+            }
+            OrderType::StopMarket { stop_price } => {
+                normalized.order_type = OrderType::StopMarket {
+                    stop_price: self.round_price(stop_price),
+                };
+            }
+            OrderType::Market => {}
+        }
+
+        normalized
+    }
+
+    /// Checks `order` against the symbol filters. `reference_price` values
+    /// market orders for the minimum notional check.
+    pub fn validate(
+        &self,
+        order: &OrderRequest,
+        reference_price: Price,
+    ) -> Result<(), FilterViolation> {
+        if !self.is_trading() {
+            return Err(FilterViolation::NotTrading {
+                status: self.status.clone(),
+            });
+        }
+
+        let price = match order.order_type {
+            OrderType::Limit { price } | OrderType::StopMarket { stop_price: price } => {
+                if price < self.min_price
+                    || (self.max_price.is_positive() && price > self.max_price)
+                {
+                    return Err(FilterViolation::PriceOutOfRange {
+                        price,
+                        min: self.min_price,
+                        max: self.max_price,
+                    });
+                }
+                price
+            }
+            OrderType::Market => reference_price,
+        };
+
+        // The venue sizes close-position orders when they trigger.
+        if order.close_position {
+            return Ok(());
+        }
+
+        if order.quantity < self.min_qty || !order.quantity.is_positive() {
+            return Err(FilterViolation::QuantityBelowMin {
+                quantity: order.quantity,
+                min: self.min_qty,
+            });
+        }
+
+        if self.max_qty.is_positive() && order.quantity > self.max_qty {
+            return Err(FilterViolation::QuantityAboveMax {
+                quantity: order.quantity,
+                max: self.max_qty,
+            });
+        }
+
+        let notional = order.quantity * price;
+        if price.is_positive() && notional < self.min_notional {
+            return Err(FilterViolation::NotionalBelowMin {
+                notional,
+                min: self.min_notional,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::orders::Side;
+
+    use super::*;
+
+    fn price(value: &str) -> Price {
+        value.parse().unwrap()
+    }
+
+    fn qty(value: &str) -> Quantity {
+        value.parse().unwrap()
+    }
+
+    fn btcusdt() -> SymbolInfo {
+        SymbolInfo {
+            symbol: "BTCUSDT".to_string(),
+            status: "TRADING".to_string(),
+            base_asset: "BTC".to_string(),
+            quote_asset: "USDT".to_string(),
+            tick_size: price("0.01"),
+            min_price: price("0.01"),
+            max_price: price("1000000"),
+            step_size: qty("0.00001"),
+            min_qty: qty("0.0001"),
+            max_qty: qty("9000"),
+            min_notional: Decimal::from(5),
+        }
+    }
+
+    #[test]
+    fn rounds_price_to_tick_and_quantity_down_to_step() {
+        let info = btcusdt();
+
+        let order = info.normalize(&OrderRequest::limit(
+            "BTCUSDT",
+            Side::Buy,
+            qty("0.123459"),
+            price("65000.126"),
+        ));
+
+        assert_eq!(order.quantity, qty("0.12345"));
+        assert!(matches!(
+            order.order_type,
+            OrderType::Limit { price: p } if p == price("65000.13")
+        ));
+
+        let stop = info.normalize(&OrderRequest::stop_market(
+            "BTCUSDT",
+            Side::Sell,
+            qty("1"),
+            price("64999.994"),
+        ));
+        assert!(matches!(
+            stop.order_type,
+            OrderType::StopMarket { stop_price } if stop_price == price("64999.99")
+        ));
+    }
+
+    #[test]
+    fn enforces_min_and_max_quantity() {
+        let info = btcusdt();
+        let at = price("65000");
+
+        assert!(matches!(
+            info.validate(
+                &OrderRequest::market("BTCUSDT", Side::Buy, qty("0.00009")),
+                at
+            ),
+            Err(FilterViolation::QuantityBelowMin { .. })
+        ));
+        assert!(matches!(
+            info.validate(
+                &OrderRequest::market("BTCUSDT", Side::Buy, Quantity::ZERO),
+                at
+            ),
+            Err(FilterViolation::QuantityBelowMin { .. })
+        ));
+        assert!(matches!(
+            info.validate(&OrderRequest::market("BTCUSDT", Side::Buy, qty("9001")), at),
+            Err(FilterViolation::QuantityAboveMax { .. })
+        ));
+        assert!(
+            info.validate(
+                &OrderRequest::market("BTCUSDT", Side::Buy, qty("0.001")),
+                at
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn treats_a_zero_maximum_as_no_maximum() {
+        let info = SymbolInfo {
+            max_price: Price::ZERO,
+            max_qty: Quantity::ZERO,
+            ..btcusdt()
+        };
+
+        let order = OrderRequest::limit("BTCUSDT", Side::Sell, qty("100000"), price("5000000"));
+
+        assert!(info.validate(&order, price("65000")).is_ok());
+        assert!(matches!(
+            btcusdt().validate(&order, price("65000")),
+            Err(FilterViolation::PriceOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn values_market_orders_at_the_reference_price() {
+        let info = btcusdt();
+        let order = OrderRequest::market("BTCUSDT", Side::Buy, qty("0.0001"));
+
+        // 0.0001 * 65000 = 6.5 clears the 5 USDT minimum; at 40000 it is 4.
+        assert!(info.validate(&order, price("65000")).is_ok());
+        assert!(matches!(
+            info.validate(&order, price("40000")),
+            Err(FilterViolation::NotionalBelowMin { .. })
+        ));
+
+        // Limit orders are valued at their own price.
+        let limit = OrderRequest::limit("BTCUSDT", Side::Buy, qty("0.0001"), price("40000"));
+        assert!(matches!(
+            info.validate(&limit, price("65000")),
+            Err(FilterViolation::NotionalBelowMin { .. })
+        ));
+    }
+
+    #[test]
+    fn lets_close_position_orders_skip_the_size_checks() {
+        let info = btcusdt();
+
+        let close = OrderRequest::close_position("BTCUSDT", Side::Sell, price("60000"));
+        assert!(info.validate(&close, price("65000")).is_ok());
+
+        // The stop price is still checked.
+        let out_of_range = OrderRequest::close_position("BTCUSDT", Side::Sell, price("0.001"));
+        assert!(matches!(
+            info.validate(&out_of_range, price("65000")),
+            Err(FilterViolation::PriceOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_symbols_that_are_not_trading() {
+        let info = SymbolInfo {
+            status: "BREAK".to_string(),
+            ..btcusdt()
+        };
+
+        assert!(matches!(
+            info.validate(&OrderRequest::market("BTCUSDT", Side::Buy, qty("1")), price("65000")),
+            Err(FilterViolation::NotTrading { status }) if status == "BREAK"
+        ));
+    }
+}
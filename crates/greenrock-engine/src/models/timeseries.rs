@@ -3,17 +3,65 @@ use std::{collections::HashMap, time::Duration};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::models::{
+    money::{Price, Quantity},
+    orders::Side,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Candle {
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
-    pub volume: f64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Quantity,
+    pub timestamp: i64,
+    pub ts: DateTime<Utc>,
+}
+
+/// A single exchange trade, or with `TradeFeed::AggTrades` the trades of one
+/// taker order at one price.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub symbol: String,
+    /// Exchange trade id, or the aggregate trade id for aggregated feeds.
+    pub trade_id: u64,
+    pub price: Price,
+    pub qty: Quantity,
+    /// Side of the taker that crossed the spread.
+    pub aggressor: Side,
+    /// Trade time in ms.
     pub timestamp: i64,
     pub ts: DateTime<Utc>,
 }
 
+/// Which trade stream to subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeFeed {
+    /// Every individual trade.
+    Trades,
+    /// Trades grouped by taker order and price, much lighter on busy symbols.
+    AggTrades,
+}
+
+/// Parses exchange interval strings like `1s`, `15m`, `4h`, `1d` or `1w`.
+pub fn parse_interval(interval: &str) -> Option<Duration> {
+    let unit = interval.chars().last()?;
+    let count: u64 = interval[..interval.len() - unit.len_utf8()].parse().ok()?;
+
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(count * seconds))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TimeSeries {
     pub candles: Vec<Candle>,
@@ -0,0 +1,300 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::DateTime;
+use serde::Serialize;
+
+use crate::models::{
+    money::{Price, Quantity},
+    orders::Side,
+    timeseries::{Candle, Trade},
+};
+
+/// Builds candles from a live trade stream.
+///
+/// Trades must arrive in time order. Candles follow the kline convention of
+/// the rest of the engine: `timestamp` is the interval close time in ms.
+pub struct CandleBuilder {
+    interval_ms: i64,
+    bucket: Option<i64>,
+    current: Option<Candle>,
+}
+
+impl CandleBuilder {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval_ms: (interval.as_millis() as i64).max(1),
+            bucket: None,
+            current: None,
+        }
+    }
+
+    /// Adds `trade`, returning the finished candle when the trade opens a
+    /// new interval.
+    pub fn push(&mut self, trade: &Trade) -> Option<Candle> {
+        let bucket = trade.timestamp.div_euclid(self.interval_ms);
+
+        if self.bucket == Some(bucket)
+            && let Some(candle) = self.current.as_mut()
+        {
+            candle.high = candle.high.max(trade.price);
+            candle.low = candle.low.min(trade.price);
+            candle.close = trade.price;
+            candle.volume += trade.qty;
+            return None;
+        }
+
+        // Late trades from a closed interval are dropped.
+        if self.is_late(trade) {
+            return None;
+        }
+
+        let close_time = (bucket + 1) * self.interval_ms - 1;
+        let finished = self.current.replace(Candle {
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.qty,
+            timestamp: close_time,
+            ts: DateTime::from_timestamp_millis(close_time).unwrap_or(trade.ts),
+        });
+        self.bucket = Some(bucket);
+
+        finished
+    }
+
+    /// Candle of the interval still in progress.
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    /// Whether `trade` belongs to an interval that has already closed, in
+    /// which case `push` drops it.
+    pub fn is_late(&self, trade: &Trade) -> bool {
+        self.bucket
+            .is_some_and(|current| trade.timestamp.div_euclid(self.interval_ms) < current)
+    }
+}
+
+/// Taker buy and sell volume over a set of trades.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VolumeDelta {
+    pub buy_volume: Quantity,
+    pub sell_volume: Quantity,
+    /// `buy_volume - sell_volume`, negative when sellers dominate.
+    pub delta: Quantity,
+    pub trades: usize,
+}
+
+impl VolumeDelta {
+    pub fn add(&mut self, trade: &Trade) {
+        match trade.aggressor {
+            Side::Buy => self.buy_volume += trade.qty,
+            Side::Sell => self.sell_volume += trade.qty,
+        }
+
+        self.delta = self.buy_volume - self.sell_volume;
+        self.trades += 1;
+    }
+}
+
+/// Volume traded at one price level of a footprint.
+#[derive(Debug, Clone, Serialize)]
+pub struct FootprintLevel {
+    pub price: Price,
+    pub buy_volume: Quantity,
+    pub sell_volume: Quantity,
+    pub delta: Quantity,
+}
+
+pub trait TradeAnalysis {
+    /// Groups trades into candles of `interval`, skipping empty intervals.
+    fn to_candles(&self, interval: Duration) -> Vec<Candle>;
+
+    fn volume_delta(&self) -> VolumeDelta;
+
+    /// Buy and sell volume per price level, with prices rounded to
+    /// `tick_size`. Levels are sorted by ascending price.
+    fn footprint(&self, tick_size: Price) -> Vec<FootprintLevel>;
+}
+
+impl TradeAnalysis for [Trade] {
+    fn to_candles(&self, interval: Duration) -> Vec<Candle> {
+        let mut builder = CandleBuilder::new(interval);
+        let mut candles: Vec<Candle> = self
+            .iter()
+            .filter_map(|trade| builder.push(trade))
+            .collect();
+
+        if let Some(candle) = builder.current() {
+            candles.push(candle.clone());
+        }
+
+        candles
+    }
+
+    fn volume_delta(&self) -> VolumeDelta {
+        let mut delta = VolumeDelta::default();
+        for trade in self {
+            delta.add(trade);
+        }
+        delta
+    }
+
+    fn footprint(&self, tick_size: Price) -> Vec<FootprintLevel> {
+        let mut levels: BTreeMap<Price, VolumeDelta> = BTreeMap::new();
+
+        for trade in self {
+            let level = trade.price.round_to_tick(tick_size);
+            levels.entry(level).or_default().add(trade);
+        }
+
+        levels
+            .into_iter()
+            .map(|(price, volume)| FootprintLevel {
+                price,
+                buy_volume: volume.buy_volume,
+                sell_volume: volume.sell_volume,
+                delta: volume.delta,
+            })
+            .collect()
+    }
+}
+
+/// Candle of one closed interval with the volume delta and footprint of its
+/// trades.
+#[derive(Debug, Clone, Serialize)]
+pub struct FootprintBar {
+    pub candle: Candle,
+    pub volume_delta: VolumeDelta,
+    pub levels: Vec<FootprintLevel>,
+}
+
+/// Groups a live trade stream into footprint bars, one per interval.
+pub struct FootprintBuilder {
+    candles: CandleBuilder,
+    tick_size: Price,
+    trades: Vec<Trade>,
+}
+
+impl FootprintBuilder {
+    pub fn new(interval: Duration, tick_size: Price) -> Self {
+        Self {
+            candles: CandleBuilder::new(interval),
+            tick_size,
+            trades: Vec::new(),
+        }
+    }
+
+    /// Adds `trade`, returning the finished bar when the trade opens a new
+    /// interval.
+    pub fn push(&mut self, trade: &Trade) -> Option<FootprintBar> {
+        if self.candles.is_late(trade) {
+            return None;
+        }
+
+        let finished = self.candles.push(trade).map(|candle| FootprintBar {
+            candle,
+            volume_delta: self.trades.volume_delta(),
+            levels: self.trades.footprint(self.tick_size),
+        });
+
+        if finished.is_some() {
+            self.trades.clear();
+        }
+
+        self.trades.push(trade.clone());
+
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(timestamp: i64, price: f64, qty: f64, aggressor: Side) -> Trade {
+        Trade {
+            symbol: "BTCUSDT".to_string(),
+            trade_id: timestamp as u64,
+            price: Price::from_f64(price),
+            qty: Quantity::from_f64(qty),
+            aggressor,
+            timestamp,
+            ts: DateTime::from_timestamp_millis(timestamp).unwrap(),
+        }
+    }
+
+    fn trades() -> Vec<Trade> {
+        vec![
+            trade(1_000, 100.0, 1.0, Side::Buy),
+            trade(20_000, 104.0, 2.0, Side::Buy),
+            trade(30_000, 99.0, 1.5, Side::Sell),
+            trade(59_999, 101.0, 0.5, Side::Sell),
+            trade(61_000, 102.0, 1.0, Side::Buy),
+            // Late, from the first minute.
+            trade(59_000, 90.0, 5.0, Side::Sell),
+            trade(185_000, 103.0, 1.0, Side::Sell),
+        ]
+    }
+
+    #[test]
+    fn builds_candles_from_trades() {
+        let candles = trades().to_candles(Duration::from_secs(60));
+
+        assert_eq!(candles.len(), 3);
+
+        let first = &candles[0];
+        assert_eq!(first.open, Price::from_f64(100.0));
+        assert_eq!(first.high, Price::from_f64(104.0));
+        assert_eq!(first.low, Price::from_f64(99.0));
+        assert_eq!(first.close, Price::from_f64(101.0));
+        assert_eq!(first.volume, Quantity::from_f64(5.0));
+        assert_eq!(first.timestamp, 59_999);
+
+        // The empty third minute is skipped.
+        assert_eq!(candles[2].timestamp, 239_999);
+    }
+
+    #[test]
+    fn computes_volume_delta() {
+        let delta = trades()[..4].volume_delta();
+
+        assert_eq!(delta.buy_volume, Quantity::from_f64(3.0));
+        assert_eq!(delta.sell_volume, Quantity::from_f64(2.0));
+        assert_eq!(delta.delta, Quantity::from_f64(1.0));
+        assert_eq!(delta.trades, 4);
+    }
+
+    #[test]
+    fn groups_the_footprint_by_tick() {
+        let levels = trades()[..4].footprint(Price::from_f64(5.0));
+
+        let prices: Vec<Price> = levels.iter().map(|level| level.price).collect();
+        assert_eq!(prices, [Price::from_f64(100.0), Price::from_f64(105.0)]);
+
+        assert_eq!(levels[0].buy_volume, Quantity::from_f64(1.0));
+        assert_eq!(levels[0].sell_volume, Quantity::from_f64(2.0));
+        assert_eq!(levels[1].delta, Quantity::from_f64(2.0));
+    }
+
+    #[test]
+    fn emits_a_footprint_bar_per_closed_interval() {
+        let mut builder = FootprintBuilder::new(Duration::from_secs(60), Price::from_f64(5.0));
+
+        let bars: Vec<FootprintBar> = trades()
+            .iter()
+            .filter_map(|trade| builder.push(trade))
+            .collect();
+
+        assert_eq!(bars.len(), 2);
+
+        assert_eq!(bars[0].candle.close, Price::from_f64(101.0));
+        assert_eq!(bars[0].volume_delta.trades, 4);
+        assert_eq!(bars[0].levels.len(), 2);
+
+        // The late trade is left out of the second minute.
+        assert_eq!(bars[1].volume_delta.trades, 1);
+        assert_eq!(bars[1].volume_delta.delta, Quantity::from_f64(1.0));
+    }
+}
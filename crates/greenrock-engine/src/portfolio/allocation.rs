@@ -0,0 +1,689 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// How `select_allocation` spreads the invested equity over the assets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocationMethod {
+    EqualWeight,
+    InverseVolatility,
+    RiskParity,
+    MeanVariance,
+}
+
+impl AllocationMethod {
+    pub const ALL: [AllocationMethod; 4] = [
+        AllocationMethod::EqualWeight,
+        AllocationMethod::InverseVolatility,
+        AllocationMethod::RiskParity,
+        AllocationMethod::MeanVariance,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AllocationMethod::EqualWeight => "equal_weight",
+            AllocationMethod::InverseVolatility => "inverse_volatility",
+            AllocationMethod::RiskParity => "risk_parity",
+            AllocationMethod::MeanVariance => "mean_variance",
+        }
+    }
+}
+
+impl fmt::Display for AllocationMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AllocationMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|method| method.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown allocation method {s}, expected one of {}",
+                    Self::ALL.map(|method| method.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+/// One asset of the portfolio, priced in the quote currency, with its recent
+/// closes. Assets the strategy targets but does not hold have zero quantity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetSnapshot {
+    pub asset: String,
+    pub symbol: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    /// Closes of `symbol`, oldest first.
+    pub closes: Vec<f64>,
+    pub unrealized_pnl: Decimal,
+}
+
+/// Broker balances and prices, with ledger PnL, at one point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingsSnapshot {
+    pub quote_currency: String,
+    pub cash: Decimal,
+    pub assets: Vec<AssetSnapshot>,
+    /// Held assets without a price in the quote currency.
+    pub unpriced: Vec<String>,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub fees: Decimal,
+    pub taken_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingReport {
+    pub asset: String,
+    pub symbol: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub value: Decimal,
+    /// Share of equity, cash included.
+    pub weight: f64,
+    /// Standard deviation of close-to-close returns, in percent.
+    pub volatility_percent: f64,
+    pub unrealized_pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskMetrics {
+    /// Per-candle volatility of the current holdings, cash included, in
+    /// percent.
+    pub volatility_percent: f64,
+    /// Largest drop in value the current holdings would have seen over the
+    /// history, in percent.
+    pub max_drawdown_percent: f64,
+    /// Herfindahl index of the weights, cash included: 1 is everything in a
+    /// single asset.
+    pub concentration: f64,
+    pub largest_asset: Option<String>,
+    pub largest_weight: f64,
+}
+
+/// Holdings aggregated into weights, PnL and risk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationReport {
+    pub quote_currency: String,
+    pub equity: Decimal,
+    pub cash: Decimal,
+    pub cash_weight: f64,
+    /// Held assets, largest first.
+    pub holdings: Vec<HoldingReport>,
+    pub unpriced: Vec<String>,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub fees: Decimal,
+    pub total_pnl: Decimal,
+    pub risk: RiskMetrics,
+}
+
+impl AllocationReport {
+    /// Multi-line description for prompts and replies.
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Equity {} {} ({:.1}% cash), realized PnL {}, unrealized PnL {}, fees {}.",
+            self.equity.round_dp(2),
+            self.quote_currency,
+            self.cash_weight * 100.0,
+            self.realized_pnl.round_dp(2),
+            self.unrealized_pnl.round_dp(2),
+            self.fees.round_dp(2),
+        )];
+
+        lines.extend(self.holdings.iter().map(|holding| {
+            format!(
+                "- {}: {} @ {} = {} ({:.1}%, volatility {:.2}%, unrealized {})",
+                holding.asset,
+                holding.quantity.normalize(),
+                holding.price.normalize(),
+                holding.value.round_dp(2),
+                holding.weight * 100.0,
+                holding.volatility_percent,
+                holding.unrealized_pnl.round_dp(2),
+            )
+        }));
+
+        lines.push(format!(
+            "Risk: volatility {:.2}% per candle, historical drawdown {:.2}%, concentration {:.2}{}.",
+            self.risk.volatility_percent,
+            self.risk.max_drawdown_percent,
+            self.risk.concentration,
+            self.risk
+                .largest_asset
+                .as_ref()
+                .map(|asset| format!(
+                    ", largest holding {} at {:.1}%",
+                    asset,
+                    self.risk.largest_weight * 100.0
+                ))
+                .unwrap_or_default(),
+        ));
+
+        if !self.unpriced.is_empty() {
+            lines.push(format!(
+                "Unpriced, left out of the totals: {}.",
+                self.unpriced.join(", ")
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetWeight {
+    pub symbol: String,
+    /// Share of the invested equity.
+    pub weight: f64,
+    pub current_weight: f64,
+    pub volatility_percent: f64,
+    pub mean_return_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetAllocation {
+    pub method: AllocationMethod,
+    pub weights: Vec<TargetWeight>,
+    /// Per-candle volatility of the target weights, in percent.
+    pub volatility_percent: f64,
+    /// Mean per-candle return of the target weights, in percent.
+    pub expected_return_percent: f64,
+}
+
+impl TargetAllocation {
+    /// Symbol to weight, as taken by `Rebalancer::plan`.
+    pub fn targets(&self) -> HashMap<String, f64> {
+        self.weights
+            .iter()
+            .map(|target| (target.symbol.clone(), target.weight))
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let weights = self
+            .weights
+            .iter()
+            .map(|target| {
+                format!(
+                    "{} {:.1}% (now {:.1}%)",
+                    target.symbol,
+                    target.weight * 100.0,
+                    target.current_weight * 100.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "{}: {}; volatility {:.2}%, mean return {:.3}% per candle",
+            self.method, weights, self.volatility_percent, self.expected_return_percent,
+        )
+    }
+}
+
+/// Close-to-close returns as fractions, oldest first.
+pub fn close_returns(closes: &[f64]) -> Vec<f64> {
+    closes
+        .windows(2)
+        .filter(|window| window[0] > 0.0)
+        .map(|window| window[1] / window[0] - 1.0)
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample covariance matrix of `series`. Every pair is taken over its own
+/// common most recent tail, so a short series only shortens its own entries
+/// and a variance uses the whole series.
+fn covariance(series: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    series
+        .iter()
+        .map(|a| series.iter().map(|b| pair_covariance(a, b)).collect())
+        .collect()
+}
+
+/// Zero with fewer than two common values.
+fn pair_covariance(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len().min(b.len());
+    if len < 2 {
+        return 0.0;
+    }
+
+    let (a, b) = (&a[a.len() - len..], &b[b.len() - len..]);
+    let (mean_a, mean_b) = (mean(a), mean(b));
+
+    (0..len)
+        .map(|t| (a[t] - mean_a) * (b[t] - mean_b))
+        .sum::<f64>()
+        / (len - 1) as f64
+}
+
+fn portfolio_variance(weights: &[f64], covariance: &[Vec<f64>]) -> f64 {
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, wi)| {
+            weights
+                .iter()
+                .enumerate()
+                .map(|(j, wj)| wi * wj * covariance[i][j])
+                .sum::<f64>()
+        })
+        .sum()
+}
+
+/// Scales `weights` to sum to one, or equal weights when they sum to zero.
+fn normalize(weights: Vec<f64>) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+
+    if total > 0.0 {
+        weights.into_iter().map(|weight| weight / total).collect()
+    } else {
+        vec![1.0 / weights.len().max(1) as f64; weights.len()]
+    }
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+/// Aggregates a snapshot into per-asset weights, PnL and risk metrics.
+pub fn aggregate(snapshot: &HoldingsSnapshot) -> AllocationReport {
+    let held: Vec<&AssetSnapshot> = snapshot
+        .assets
+        .iter()
+        .filter(|asset| asset.quantity > Decimal::ZERO)
+        .collect();
+
+    let equity = snapshot.cash
+        + held
+            .iter()
+            .map(|asset| asset.quantity * asset.price)
+            .sum::<Decimal>();
+    let weight_of = |value: Decimal| {
+        if equity > Decimal::ZERO {
+            to_f64(value / equity)
+        } else {
+            0.0
+        }
+    };
+
+    let mut holdings: Vec<HoldingReport> = held
+        .iter()
+        .map(|asset| {
+            let value = asset.quantity * asset.price;
+            let returns = close_returns(&asset.closes);
+            let variance = covariance(std::slice::from_ref(&returns))[0][0];
+
+            HoldingReport {
+                asset: asset.asset.clone(),
+                symbol: asset.symbol.clone(),
+                quantity: asset.quantity,
+                price: asset.price,
+                value,
+                weight: weight_of(value),
+                volatility_percent: variance.sqrt() * 100.0,
+                unrealized_pnl: asset.unrealized_pnl,
+            }
+        })
+        .collect();
+
+    holdings.sort_by(|a, b| b.value.cmp(&a.value));
+
+    // Value of today's holdings replayed over their common history.
+    let len = held
+        .iter()
+        .map(|asset| asset.closes.len())
+        .min()
+        .unwrap_or(0);
+    let values: Vec<f64> = (0..len)
+        .map(|t| {
+            to_f64(snapshot.cash)
+                + held
+                    .iter()
+                    .map(|asset| {
+                        to_f64(asset.quantity) * asset.closes[asset.closes.len() - len + t]
+                    })
+                    .sum::<f64>()
+        })
+        .collect();
+
+    let volatility_percent = covariance(&[close_returns(&values)])[0][0].sqrt() * 100.0;
+
+    let mut peak = f64::MIN;
+    let mut max_drawdown_percent: f64 = 0.0;
+    for value in &values {
+        peak = peak.max(*value);
+        if peak > 0.0 {
+            max_drawdown_percent = max_drawdown_percent.max((peak - value) / peak * 100.0);
+        }
+    }
+
+    let cash_weight = weight_of(snapshot.cash);
+    let concentration = cash_weight.powi(2)
+        + holdings
+            .iter()
+            .map(|holding| holding.weight.powi(2))
+            .sum::<f64>();
+
+    let largest = holdings.first();
+
+    AllocationReport {
+        quote_currency: snapshot.quote_currency.clone(),
+        equity,
+        cash: snapshot.cash,
+        cash_weight,
+        unpriced: snapshot.unpriced.clone(),
+        realized_pnl: snapshot.realized_pnl,
+        unrealized_pnl: snapshot.unrealized_pnl,
+        fees: snapshot.fees,
+        total_pnl: snapshot.realized_pnl + snapshot.unrealized_pnl - snapshot.fees,
+        risk: RiskMetrics {
+            volatility_percent,
+            max_drawdown_percent,
+            concentration,
+            largest_asset: largest.map(|holding| holding.asset.clone()),
+            largest_weight: largest.map_or(0.0, |holding| holding.weight),
+        },
+        holdings,
+    }
+}
+
+/// Target weights over every asset of `snapshot` with `method`:
+///
+/// - equal weight: `1 / n` each;
+/// - inverse volatility: proportional to `1 / σ`;
+/// - risk parity: equal contributions to portfolio variance, iterated from
+///   inverse volatility;
+/// - mean-variance: long-only, proportional to `μ / σ²` (diagonal
+///   covariance), falling back to inverse volatility when no asset has a
+///   positive mean return.
+///
+/// The volatility-based methods only weigh assets with at least two returns
+/// of history; the others get a weight of zero. When no asset has history
+/// they fall back to equal weights.
+pub fn select_allocation(
+    method: AllocationMethod,
+    snapshot: &HoldingsSnapshot,
+    report: &AllocationReport,
+) -> TargetAllocation {
+    let returns: Vec<Vec<f64>> = snapshot
+        .assets
+        .iter()
+        .map(|asset| close_returns(&asset.closes))
+        .collect();
+    let covariance = covariance(&returns);
+    let n = returns.len();
+
+    let volatilities: Vec<f64> = (0..n).map(|i| covariance[i][i].sqrt()).collect();
+    let means: Vec<f64> = returns.iter().map(|series| mean(series)).collect();
+
+    let with_history: Vec<usize> = (0..n).filter(|i| returns[*i].len() >= 2).collect();
+
+    let weights = if method == AllocationMethod::EqualWeight || with_history.is_empty() {
+        normalize(vec![1.0; n])
+    } else {
+        let covariance: Vec<Vec<f64>> = with_history
+            .iter()
+            .map(|i| with_history.iter().map(|j| covariance[*i][*j]).collect())
+            .collect();
+        let means: Vec<f64> = with_history.iter().map(|i| means[*i]).collect();
+
+        let mut weights = vec![0.0; n];
+        for (i, weight) in with_history
+            .iter()
+            .zip(volatility_weights(method, &covariance, &means))
+        {
+            weights[*i] = weight;
+        }
+
+        weights
+    };
+
+    let current: HashMap<&str, f64> = report
+        .holdings
+        .iter()
+        .map(|holding| (holding.symbol.as_str(), holding.weight))
+        .collect();
+
+    TargetAllocation {
+        method,
+        volatility_percent: portfolio_variance(&weights, &covariance).sqrt() * 100.0,
+        expected_return_percent: (0..n).map(|i| weights[i] * means[i]).sum::<f64>() * 100.0,
+        weights: snapshot
+            .assets
+            .iter()
+            .enumerate()
+            .map(|(i, asset)| TargetWeight {
+                symbol: asset.symbol.clone(),
+                weight: weights[i],
+                current_weight: current.get(asset.symbol.as_str()).copied().unwrap_or(0.0),
+                volatility_percent: volatilities[i] * 100.0,
+                mean_return_percent: means[i] * 100.0,
+            })
+            .collect(),
+    }
+}
+
+/// Weights of `method` for assets that all have history, given their
+/// covariance matrix and mean returns.
+fn volatility_weights(
+    method: AllocationMethod,
+    covariance: &[Vec<f64>],
+    means: &[f64],
+) -> Vec<f64> {
+    let n = means.len();
+    let volatilities: Vec<f64> = (0..n).map(|i| covariance[i][i].sqrt()).collect();
+
+    let inverse_volatility = || {
+        normalize(
+            volatilities
+                .iter()
+                .map(|sigma| if *sigma > 0.0 { 1.0 / sigma } else { 0.0 })
+                .collect(),
+        )
+    };
+
+    match method {
+        AllocationMethod::EqualWeight => normalize(vec![1.0; n]),
+        AllocationMethod::InverseVolatility => inverse_volatility(),
+        AllocationMethod::RiskParity => {
+            let mut weights = inverse_volatility();
+
+            for _ in 0..100 {
+                let marginal: Vec<f64> = (0..n)
+                    .map(|i| (0..n).map(|j| covariance[i][j] * weights[j]).sum())
+                    .collect();
+                let total: f64 = (0..n).map(|i| weights[i] * marginal[i]).sum();
+
+                if total <= 0.0 {
+                    break;
+                }
+
+                let target = total / n as f64;
+                weights = normalize(
+                    (0..n)
+                        .map(|i| {
+                            let contribution = weights[i] * marginal[i];
+                            if contribution > 0.0 {
+                                weights[i] * (target / contribution).sqrt()
+                            } else {
+                                weights[i]
+                            }
+                        })
+                        .collect(),
+                );
+            }
+
+            weights
+        }
+        AllocationMethod::MeanVariance => {
+            let weights: Vec<f64> = (0..n)
+                .map(|i| {
+                    let variance = covariance[i][i];
+                    if variance > 0.0 && means[i] > 0.0 {
+                        means[i] / variance
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+
+            if weights.iter().any(|weight| *weight > 0.0) {
+                normalize(weights)
+            } else {
+                inverse_volatility()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Closes starting at 100 that move by `returns`.
+    fn closes(returns: &[f64]) -> Vec<f64> {
+        let mut closes = vec![100.0];
+        for r in returns {
+            closes.push(closes.last().unwrap() * (1.0 + r));
+        }
+        closes
+    }
+
+    fn snapshot(assets: Vec<(&str, Vec<f64>)>) -> HoldingsSnapshot {
+        HoldingsSnapshot {
+            quote_currency: "USDT".to_string(),
+            cash: Decimal::from(1_000),
+            assets: assets
+                .into_iter()
+                .map(|(asset, closes)| AssetSnapshot {
+                    asset: asset.to_string(),
+                    symbol: format!("{asset}USDT"),
+                    quantity: Decimal::ZERO,
+                    price: Decimal::from(100),
+                    closes,
+                    unrealized_pnl: Decimal::ZERO,
+                })
+                .collect(),
+            unpriced: Vec::new(),
+            realized_pnl: Decimal::ZERO,
+            unrealized_pnl: Decimal::ZERO,
+            fees: Decimal::ZERO,
+            taken_at: Utc::now(),
+        }
+    }
+
+    fn weights(method: AllocationMethod, snapshot: &HoldingsSnapshot) -> HashMap<String, f64> {
+        select_allocation(method, snapshot, &aggregate(snapshot)).targets()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    /// BTC moves ±1% every candle, ETH ±2% every two candles, uncorrelated
+    /// with BTC, and SOL has a single close.
+    fn synthetic() -> HoldingsSnapshot {
+        snapshot(vec![
+            ("BTC", closes(&[0.01, -0.01].repeat(4))),
+            ("ETH", closes(&[0.02, 0.02, -0.02, -0.02].repeat(2))),
+            ("SOL", vec![100.0]),
+        ])
+    }
+
+    #[test]
+    fn variances_use_each_whole_series() {
+        let long = close_returns(&closes(&[0.05, -0.05, 0.01, -0.01].repeat(4)));
+        let short = close_returns(&closes(&[0.01, -0.01]));
+
+        let alone = covariance(std::slice::from_ref(&long))[0][0];
+        let together = covariance(&[long, short, Vec::new()]);
+
+        assert_close(together[0][0], alone);
+        assert_close(together[2][2], 0.0);
+        assert_close(together[0][2], 0.0);
+    }
+
+    #[test]
+    fn equal_weight_spreads_over_every_asset() {
+        let weights = weights(AllocationMethod::EqualWeight, &synthetic());
+
+        for asset in ["BTCUSDT", "ETHUSDT", "SOLUSDT"] {
+            assert_close(weights[asset], 1.0 / 3.0);
+        }
+    }
+
+    #[test]
+    fn inverse_volatility_leaves_out_assets_without_history() {
+        let weights = weights(AllocationMethod::InverseVolatility, &synthetic());
+
+        assert_close(weights["BTCUSDT"], 2.0 / 3.0);
+        assert_close(weights["ETHUSDT"], 1.0 / 3.0);
+        assert_close(weights["SOLUSDT"], 0.0);
+    }
+
+    #[test]
+    fn risk_parity_matches_inverse_volatility_when_uncorrelated() {
+        let weights = weights(AllocationMethod::RiskParity, &synthetic());
+
+        assert_close(weights["BTCUSDT"], 2.0 / 3.0);
+        assert_close(weights["ETHUSDT"], 1.0 / 3.0);
+        assert_close(weights["SOLUSDT"], 0.0);
+    }
+
+    #[test]
+    fn mean_variance_only_weighs_positive_means() {
+        let snapshot = snapshot(vec![
+            ("BTC", closes(&[0.02, 0.0].repeat(4))),
+            ("ETH", closes(&[-0.02, 0.01].repeat(4))),
+        ]);
+
+        let weights = weights(AllocationMethod::MeanVariance, &snapshot);
+
+        assert_close(weights["BTCUSDT"], 1.0);
+        assert_close(weights["ETHUSDT"], 0.0);
+    }
+
+    #[test]
+    fn mean_variance_falls_back_without_positive_means() {
+        let snapshot = snapshot(vec![
+            ("BTC", closes(&[-0.02, 0.01].repeat(4))),
+            ("ETH", closes(&[-0.04, 0.02].repeat(4))),
+        ]);
+
+        let weights = weights(AllocationMethod::MeanVariance, &snapshot);
+
+        assert_close(weights["BTCUSDT"], 2.0 / 3.0);
+        assert_close(weights["ETHUSDT"], 1.0 / 3.0);
+    }
+
+    #[test]
+    fn falls_back_to_equal_weights_without_any_history() {
+        let snapshot = snapshot(vec![("BTC", vec![100.0]), ("ETH", Vec::new())]);
+
+        let weights = weights(AllocationMethod::RiskParity, &snapshot);
+
+        assert_close(weights["BTCUSDT"], 0.5);
+        assert_close(weights["ETHUSDT"], 0.5);
+    }
+}
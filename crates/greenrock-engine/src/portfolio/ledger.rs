@@ -0,0 +1,525 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::models::{
+    money::{Price, Quantity},
+    orders::{Fill, Side},
+};
+
+/// Quote assets recognised when splitting a pair symbol, checked in order.
+const QUOTE_ASSETS: [&str; 8] = ["USDT", "USDC", "FDUSD", "TUSD", "BUSD", "BTC", "ETH", "BNB"];
+
+/// Splits a pair symbol such as `BTCUSDT` into `("BTC", "USDT")`.
+pub fn split_symbol(symbol: &str) -> Option<(String, String)> {
+    let symbol = symbol.to_uppercase();
+
+    QUOTE_ASSETS.iter().find_map(|quote| {
+        symbol
+            .strip_suffix(quote)
+            .filter(|base| !base.is_empty())
+            .map(|base| (base.to_string(), quote.to_string()))
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Position {
+    pub asset: String,
+    /// Signed quantity, negative for short positions.
+    pub quantity: Quantity,
+    pub average_price: Price,
+    pub realized_pnl: Decimal,
+    pub last_price: Option<Price>,
+}
+
+impl Position {
+    fn mark_price(&self) -> Price {
+        self.last_price.unwrap_or(self.average_price)
+    }
+
+    pub fn market_value(&self) -> Decimal {
+        self.quantity * self.mark_price()
+    }
+
+    pub fn unrealized_pnl(&self) -> Decimal {
+        (self.mark_price() - self.average_price) * self.quantity
+    }
+
+    fn apply(&mut self, side: Side, price: Price, qty: Quantity) {
+        if qty.is_zero() {
+            return;
+        }
+
+        let signed_qty = match side {
+            Side::Buy => qty,
+            Side::Sell => -qty,
+        };
+
+        if self.quantity.is_zero() || self.quantity.is_positive() == signed_qty.is_positive() {
+            // Opening or increasing: blend the average entry price.
+            let new_quantity = self.quantity + signed_qty;
+            self.average_price = Price(
+                (self.average_price * self.quantity.abs() + price * qty) / new_quantity.abs().0,
+            );
+            self.quantity = new_quantity;
+            return;
+        }
+
+        // Reducing, closing or flipping the position.
+        let closing_qty = qty.min(self.quantity.abs());
+        let closed_pnl = (price - self.average_price) * closing_qty;
+        self.realized_pnl += if self.quantity.is_positive() {
+            closed_pnl
+        } else {
+            -closed_pnl
+        };
+
+        let new_quantity = self.quantity + signed_qty;
+
+        if new_quantity.is_zero() {
+            self.quantity = Quantity::ZERO;
+            self.average_price = Price::ZERO;
+        } else {
+            if new_quantity.is_positive() != self.quantity.is_positive() {
+                self.average_price = price;
+            }
+            self.quantity = new_quantity;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionReport {
+    pub asset: String,
+    pub quantity: Quantity,
+    pub average_price: Price,
+    pub last_price: Option<Price>,
+    pub market_value: Decimal,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+}
+
+/// Ledger totals in the quote currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlReport {
+    pub quote_currency: String,
+    pub cash: Decimal,
+    pub market_value: Decimal,
+    pub equity: Decimal,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    /// Fees converted to the quote currency at the latest marks.
+    pub fees: Decimal,
+    /// Fees paid in assets that have no mark price yet.
+    pub unpriced_fees: HashMap<String, Quantity>,
+    pub total_pnl: Decimal,
+}
+
+/// Tracks positions, average cost, fees and PnL in a single quote currency.
+///
+/// Fills are deduplicated by `(symbol, trade_id)`, so trade history can be
+/// ingested repeatedly alongside paper or backtest fills.
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    quote_currency: String,
+    cash: Decimal,
+    positions: HashMap<String, Position>,
+    fees: HashMap<String, Quantity>,
+    seen_trades: HashSet<(String, u64)>,
+    /// When the ledger was seeded from account balances. Earlier fills are
+    /// already part of those balances.
+    seeded_at: Option<DateTime<Utc>>,
+}
+
+impl Ledger {
+    pub fn new(quote_currency: &str) -> Self {
+        Self {
+            quote_currency: quote_currency.to_uppercase(),
+            cash: Decimal::ZERO,
+            positions: HashMap::new(),
+            fees: HashMap::new(),
+            seen_trades: HashSet::new(),
+            seeded_at: None,
+        }
+    }
+
+    pub fn with_cash(mut self, cash: Decimal) -> Self {
+        self.cash = cash;
+        self
+    }
+
+    /// Resets the ledger to the account holdings at `at`: `cash` in the
+    /// quote currency and each `(asset, quantity, price)`, entered at that
+    /// price. Fills older than `at` are ignored from then on.
+    pub fn seed(
+        &mut self,
+        cash: Decimal,
+        holdings: impl IntoIterator<Item = (String, Quantity, Price)>,
+        at: DateTime<Utc>,
+    ) {
+        *self = Self::new(&self.quote_currency).with_cash(cash);
+        self.seeded_at = Some(at);
+
+        for (asset, quantity, price) in holdings {
+            let asset = asset.to_uppercase();
+            self.positions.insert(
+                asset.clone(),
+                Position {
+                    asset,
+                    quantity,
+                    average_price: price,
+                    last_price: Some(price),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    pub fn is_seeded(&self) -> bool {
+        self.seeded_at.is_some()
+    }
+
+    pub fn quote_currency(&self) -> &str {
+        &self.quote_currency
+    }
+
+    pub fn cash(&self) -> Decimal {
+        self.cash
+    }
+
+    pub fn position(&self, asset: &str) -> Option<&Position> {
+        self.positions.get(&asset.to_uppercase())
+    }
+
+    /// Quantity per asset, with the cash under the quote currency.
+    pub fn holdings(&self) -> HashMap<String, Quantity> {
+        self.positions
+            .iter()
+            .map(|(asset, position)| (asset.clone(), position.quantity))
+            .chain([(self.quote_currency.clone(), Quantity(self.cash))])
+            .collect()
+    }
+
+    /// Sum of absolute position values at the latest marks.
+    pub fn gross_exposure(&self) -> Decimal {
+        self.positions
+            .values()
+            .map(|p| p.market_value().abs())
+            .sum()
+    }
+
+    /// Assets with an open or previously traded position.
+    pub fn assets(&self) -> Vec<String> {
+        self.positions.keys().cloned().collect()
+    }
+
+    /// Applies a fill. Returns `false` when it was already ingested, is
+    /// quoted in a different currency than the ledger or predates the seed.
+    pub fn apply_fill(&mut self, fill: &Fill) -> bool {
+        if self.seeded_at.is_some_and(|at| fill.time < at) {
+            return false;
+        }
+
+        if let Some(trade_id) = fill.trade_id
+            && !self.seen_trades.insert((fill.symbol.clone(), trade_id))
+        {
+            return false;
+        }
+
+        let Some((base, quote)) = split_symbol(&fill.symbol) else {
+            warn!("Ledger cannot split symbol {}", fill.symbol);
+            return false;
+        };
+
+        if quote != self.quote_currency {
+            warn!(
+                "Ledger quoted in {} skipping fill on {}",
+                self.quote_currency, fill.symbol
+            );
+            return false;
+        }
+
+        let position = self
+            .positions
+            .entry(base.clone())
+            .or_insert_with(|| Position {
+                asset: base.clone(),
+                ..Default::default()
+            });
+
+        position.apply(fill.side, fill.price, fill.qty);
+
+        let notional = fill.price * fill.qty;
+        match fill.side {
+            Side::Buy => self.cash -= notional,
+            Side::Sell => self.cash += notional,
+        }
+
+        if fill.fee.is_positive() {
+            let fee_asset = fill.fee_asset.to_uppercase();
+
+            if fee_asset == self.quote_currency {
+                self.cash -= fill.fee.value();
+            } else if let Some(fee_position) = self.positions.get_mut(&fee_asset) {
+                fee_position.quantity -= fill.fee;
+            }
+
+            *self.fees.entry(fee_asset).or_default() += fill.fee;
+        }
+
+        true
+    }
+
+    /// Sets the mark price used for unrealized PnL and equity.
+    pub fn mark(&mut self, asset: &str, price: Price) {
+        if let Some(position) = self.positions.get_mut(&asset.to_uppercase()) {
+            position.last_price = Some(price);
+        }
+    }
+
+    pub fn positions(&self) -> Vec<PositionReport> {
+        let mut reports: Vec<PositionReport> = self
+            .positions
+            .values()
+            .map(|position| PositionReport {
+                asset: position.asset.clone(),
+                quantity: position.quantity,
+                average_price: position.average_price,
+                last_price: position.last_price,
+                market_value: position.market_value(),
+                realized_pnl: position.realized_pnl,
+                unrealized_pnl: position.unrealized_pnl(),
+            })
+            .collect();
+
+        reports.sort_by(|a, b| a.asset.cmp(&b.asset));
+        reports
+    }
+
+    pub fn pnl(&self) -> PnlReport {
+        let market_value: Decimal = self.positions.values().map(Position::market_value).sum();
+        let realized_pnl: Decimal = self.positions.values().map(|p| p.realized_pnl).sum();
+        let unrealized_pnl: Decimal = self.positions.values().map(Position::unrealized_pnl).sum();
+
+        let mut fees = Decimal::ZERO;
+        let mut unpriced_fees = HashMap::new();
+
+        for (asset, amount) in &self.fees {
+            if *asset == self.quote_currency {
+                fees += amount.value();
+            } else if let Some(price) = self.positions.get(asset).and_then(|p| p.last_price) {
+                fees += *amount * price;
+            } else {
+                unpriced_fees.insert(asset.clone(), *amount);
+            }
+        }
+
+        PnlReport {
+            quote_currency: self.quote_currency.clone(),
+            cash: self.cash,
+            market_value,
+            equity: self.cash + market_value,
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            unpriced_fees,
+            total_pnl: realized_pnl + unrealized_pnl - fees,
+        }
+    }
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self::new("USDT")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn price(value: f64) -> Price {
+        Price::from_f64(value)
+    }
+
+    fn qty(value: f64) -> Quantity {
+        Quantity::from_f64(value)
+    }
+
+    fn fill(side: Side, price: f64, quantity: f64) -> Fill {
+        Fill {
+            trade_id: None,
+            symbol: "BTCUSDT".to_string(),
+            side,
+            price: self::price(price),
+            qty: qty(quantity),
+            fee: Quantity::ZERO,
+            fee_asset: String::new(),
+            time: Utc::now(),
+        }
+    }
+
+    fn position(fills: &[(Side, f64, f64)]) -> Position {
+        let mut position = Position::default();
+        for (side, price, quantity) in fills {
+            position.apply(*side, self::price(*price), qty(*quantity));
+        }
+        position
+    }
+
+    #[test]
+    fn opens_and_adds_to_a_long() {
+        let long = position(&[(Side::Buy, 100.0, 1.0), (Side::Buy, 110.0, 1.0)]);
+
+        assert_eq!(long.quantity, qty(2.0));
+        assert_eq!(long.average_price, price(105.0));
+        assert_eq!(long.realized_pnl, Decimal::ZERO);
+    }
+
+    #[test]
+    fn reduces_a_long() {
+        let long = position(&[
+            (Side::Buy, 100.0, 1.0),
+            (Side::Buy, 110.0, 1.0),
+            (Side::Sell, 120.0, 0.5),
+        ]);
+
+        assert_eq!(long.quantity, qty(1.5));
+        assert_eq!(long.average_price, price(105.0));
+        assert_eq!(long.realized_pnl, Decimal::new(75, 1));
+    }
+
+    #[test]
+    fn opens_adds_and_reduces_a_short() {
+        let short = position(&[
+            (Side::Sell, 100.0, 1.0),
+            (Side::Sell, 90.0, 1.0),
+            (Side::Buy, 85.0, 1.0),
+        ]);
+
+        assert_eq!(short.quantity, qty(-1.0));
+        assert_eq!(short.average_price, price(95.0));
+        assert_eq!(short.realized_pnl, Decimal::TEN);
+    }
+
+    #[test]
+    fn flips_a_long_into_a_short() {
+        let flipped = position(&[(Side::Buy, 100.0, 1.0), (Side::Sell, 90.0, 3.0)]);
+
+        assert_eq!(flipped.quantity, qty(-2.0));
+        assert_eq!(flipped.average_price, price(90.0));
+        assert_eq!(flipped.realized_pnl, Decimal::from(-10));
+    }
+
+    #[test]
+    fn flips_a_short_into_a_long() {
+        let flipped = position(&[(Side::Sell, 100.0, 1.0), (Side::Buy, 110.0, 2.0)]);
+
+        assert_eq!(flipped.quantity, qty(1.0));
+        assert_eq!(flipped.average_price, price(110.0));
+        assert_eq!(flipped.realized_pnl, Decimal::from(-10));
+    }
+
+    #[test]
+    fn closing_resets_the_entry_price() {
+        let closed = position(&[(Side::Buy, 100.0, 1.0), (Side::Sell, 130.0, 1.0)]);
+
+        assert!(closed.quantity.is_zero());
+        assert_eq!(closed.average_price, Price::ZERO);
+        assert_eq!(closed.realized_pnl, Decimal::from(30));
+    }
+
+    #[test]
+    fn books_quote_fees_against_cash() {
+        let mut ledger = Ledger::new("USDT").with_cash(Decimal::from(1_000));
+
+        assert!(ledger.apply_fill(&Fill {
+            fee: qty(0.1),
+            fee_asset: "usdt".to_string(),
+            ..fill(Side::Buy, 100.0, 2.0)
+        }));
+
+        let pnl = ledger.pnl();
+        assert_eq!(pnl.cash, Decimal::new(7999, 1));
+        assert_eq!(pnl.fees, Decimal::new(1, 1));
+        assert_eq!(pnl.total_pnl, Decimal::new(-1, 1));
+    }
+
+    #[test]
+    fn books_base_fees_against_the_position() {
+        let mut ledger = Ledger::new("USDT").with_cash(Decimal::from(1_000));
+
+        ledger.apply_fill(&Fill {
+            fee: qty(0.001),
+            fee_asset: "BTC".to_string(),
+            ..fill(Side::Buy, 100.0, 1.0)
+        });
+
+        assert_eq!(ledger.position("btc").unwrap().quantity, qty(0.999));
+        assert_eq!(ledger.cash(), Decimal::from(900));
+
+        // Unpriced until the asset is marked.
+        assert_eq!(ledger.pnl().unpriced_fees["BTC"], qty(0.001));
+
+        ledger.mark("BTC", price(100.0));
+        let pnl = ledger.pnl();
+        assert!(pnl.unpriced_fees.is_empty());
+        assert_eq!(pnl.fees, Decimal::new(1, 1));
+    }
+
+    #[test]
+    fn skips_duplicate_and_foreign_fills() {
+        let mut ledger = Ledger::new("USDT");
+
+        let first = Fill {
+            trade_id: Some(7),
+            ..fill(Side::Buy, 100.0, 1.0)
+        };
+
+        assert!(ledger.apply_fill(&first));
+        assert!(!ledger.apply_fill(&first));
+        assert!(!ledger.apply_fill(&Fill {
+            symbol: "ETHBTC".to_string(),
+            ..fill(Side::Buy, 0.05, 1.0)
+        }));
+
+        assert_eq!(ledger.position("BTC").unwrap().quantity, qty(1.0));
+        assert!(ledger.position("ETH").is_none());
+    }
+
+    #[test]
+    fn seeds_holdings_and_skips_older_fills() {
+        let mut ledger = Ledger::new("USDT");
+        ledger.apply_fill(&fill(Side::Buy, 90.0, 3.0));
+
+        let at = Utc::now();
+        ledger.seed(
+            Decimal::from(1000),
+            [("btc".to_string(), qty(2.0), price(100.0))],
+            at,
+        );
+
+        assert!(ledger.is_seeded());
+        assert_eq!(ledger.position("BTC").unwrap().quantity, qty(2.0));
+
+        assert!(!ledger.apply_fill(&Fill {
+            time: at - Duration::seconds(1),
+            ..fill(Side::Buy, 100.0, 1.0)
+        }));
+        assert!(ledger.apply_fill(&fill(Side::Sell, 110.0, 1.0)));
+
+        let pnl = ledger.pnl();
+        assert_eq!(pnl.cash, Decimal::from(1110));
+        assert_eq!(pnl.realized_pnl, Decimal::from(10));
+        assert_eq!(pnl.equity, Decimal::from(1210));
+
+        let holdings = ledger.holdings();
+        assert_eq!(holdings["BTC"], qty(1.0));
+        assert_eq!(holdings["USDT"], Quantity(Decimal::from(1110)));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod allocation;
+pub mod ledger;
+pub mod rebalancer;
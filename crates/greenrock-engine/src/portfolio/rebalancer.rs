@@ -0,0 +1,638 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{
+    brokers::core::Broker,
+    models::{
+        money::{Price, Quantity, to_decimal},
+        orders::{OrderRequest, Side},
+        symbols::SymbolInfo,
+    },
+    portfolio::ledger::split_symbol,
+};
+
+/// When a scheduled rebalance should fire.
+#[derive(Debug, Clone, Copy)]
+pub enum RebalanceSchedule {
+    /// Rebalance at a fixed cadence regardless of drift.
+    Calendar { every: Duration },
+    /// Rebalance as soon as any leg drifts past the threshold, at most once
+    /// per `RebalanceConfig::min_interval`.
+    Drift,
+    /// Whichever of the two comes first.
+    CalendarOrDrift { every: Duration },
+}
+
+#[derive(Debug, Clone)]
+pub struct RebalanceConfig {
+    pub quote_currency: String,
+    /// Absolute weight difference below which a leg is left untouched.
+    pub drift_threshold: f64,
+    /// Smallest order value, in the quote currency, worth sending.
+    pub min_notional: Decimal,
+    /// Quantity step per symbol, used when the broker has no symbol info.
+    /// Symbols without either use `default_step_size`.
+    pub step_sizes: HashMap<String, Quantity>,
+    pub default_step_size: Quantity,
+    /// Fraction of equity always kept in the quote currency.
+    pub cash_buffer: f64,
+    pub schedule: RebalanceSchedule,
+    /// Shortest time between two drift-triggered runs, so a drift that
+    /// does not close right away is not traded again on every check.
+    pub min_interval: Duration,
+    /// How often the scheduled loop re-evaluates the plan.
+    pub check_every: std::time::Duration,
+    /// Scheduled runs only log the plan when set.
+    pub dry_run: bool,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            quote_currency: "USDT".to_string(),
+            drift_threshold: 0.02,
+            min_notional: Decimal::TEN,
+            step_sizes: HashMap::new(),
+            default_step_size: Quantity(Decimal::new(1, 5)),
+            cash_buffer: 0.02,
+            schedule: RebalanceSchedule::Drift,
+            min_interval: Duration::hours(1),
+            check_every: std::time::Duration::from_secs(60),
+            dry_run: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RebalanceLeg {
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: Quantity,
+    pub price: Price,
+    pub notional: Decimal,
+    pub current_weight: f64,
+    pub target_weight: f64,
+    pub drift: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedLeg {
+    pub symbol: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RebalancePlan {
+    pub quote_currency: String,
+    pub equity: Decimal,
+    pub investable: Decimal,
+    pub max_drift: f64,
+    /// Sells first, so their proceeds fund the buys.
+    pub legs: Vec<RebalanceLeg>,
+    pub skipped: Vec<SkippedLeg>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LegExecution {
+    pub leg: RebalanceLeg,
+    /// Paper or venue order id.
+    pub order_id: Option<String>,
+    pub error: Option<String>,
+}
+
+struct Holding {
+    symbol: String,
+    weight: f64,
+    price: Price,
+    value: Decimal,
+}
+
+/// Turns target weights into the orders needed to reach them.
+pub struct Rebalancer {
+    pub config: RebalanceConfig,
+    last_run: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl Rebalancer {
+    pub fn new(config: RebalanceConfig) -> Self {
+        Self {
+            config,
+            last_run: Mutex::new(None),
+        }
+    }
+
+    /// Builds a plan from live broker balances, prices and symbol filters.
+    pub fn plan<B: Broker>(&self, broker: &B, targets: &HashMap<String, f64>) -> RebalancePlan {
+        let balances = broker
+            .balances()
+            .into_iter()
+            .map(|balance| (balance.asset, Quantity(balance.free)))
+            .collect();
+
+        self.plan_holdings(broker, targets, &balances)
+    }
+
+    /// Builds a plan from `balances` (asset -> quantity), such as the paper
+    /// ledger, with live broker prices and symbol filters.
+    pub fn plan_holdings<B: Broker>(
+        &self,
+        broker: &B,
+        targets: &HashMap<String, f64>,
+        balances: &HashMap<String, Quantity>,
+    ) -> RebalancePlan {
+        let mut prices = HashMap::new();
+        let mut symbols = HashMap::new();
+
+        for symbol in targets.keys() {
+            let symbol = symbol.to_uppercase();
+            let price = Price::from_f64(broker.market_current_price(&symbol));
+            prices.insert(symbol.clone(), price);
+
+            if let Some(info) = broker.symbol_info(&symbol) {
+                symbols.insert(symbol, info);
+            }
+        }
+
+        self.plan_with(targets, balances, &prices, &symbols)
+    }
+
+    /// Builds a plan from explicit holdings (asset -> quantity), prices
+    /// (symbol -> price) and symbol filters. Weights and drift are `f64`;
+    /// values and order sizes are decimal.
+    pub fn plan_with(
+        &self,
+        targets: &HashMap<String, f64>,
+        balances: &HashMap<String, Quantity>,
+        prices: &HashMap<String, Price>,
+        symbols: &HashMap<String, SymbolInfo>,
+    ) -> RebalancePlan {
+        let quote = self.config.quote_currency.to_uppercase();
+        let mut skipped = Vec::new();
+
+        let mut holdings: Vec<Holding> = Vec::new();
+
+        for (symbol, weight) in targets {
+            let symbol = symbol.to_uppercase();
+
+            let base = match split_symbol(&symbol) {
+                Some((base, symbol_quote)) if symbol_quote == quote => base,
+                _ => {
+                    skipped.push(SkippedLeg {
+                        symbol,
+                        reason: format!("not quoted in {quote}"),
+                    });
+                    continue;
+                }
+            };
+
+            let price = prices.get(&symbol).copied().unwrap_or_default();
+            if !price.is_positive() {
+                skipped.push(SkippedLeg {
+                    symbol,
+                    reason: "no price available".to_string(),
+                });
+                continue;
+            }
+
+            let quantity = balances.get(&base).copied().unwrap_or_default();
+            holdings.push(Holding {
+                symbol,
+                weight: weight.max(0.0),
+                price,
+                value: quantity * price,
+            });
+        }
+
+        let cash = balances.get(&quote).copied().unwrap_or_default().value();
+        let equity = cash + holdings.iter().map(|h| h.value).sum::<Decimal>();
+        let investable = equity * to_decimal(1.0 - self.config.cash_buffer.clamp(0.0, 1.0));
+
+        // Scale weights down when they over-allocate.
+        let total_weight: f64 = holdings.iter().map(|h| h.weight).sum();
+        let weight_scale = if total_weight > 1.0 {
+            1.0 / total_weight
+        } else {
+            1.0
+        };
+
+        let mut sells = Vec::new();
+        let mut buys = Vec::new();
+        let mut max_drift: f64 = 0.0;
+
+        for Holding {
+            symbol,
+            weight,
+            price,
+            value,
+        } in holdings
+        {
+            let target_weight = weight * weight_scale;
+            let current_weight = if investable > Decimal::ZERO {
+                (value / investable).to_f64().unwrap_or(0.0)
+            } else {
+                0.0
+            };
+            let drift = current_weight - target_weight;
+            max_drift = max_drift.max(drift.abs());
+
+            if drift.abs() < self.config.drift_threshold {
+                skipped.push(SkippedLeg {
+                    symbol,
+                    reason: format!("drift {drift:.4} within threshold"),
+                });
+                continue;
+            }
+
+            if let Some(info) = symbols.get(&symbol)
+                && !info.is_trading()
+            {
+                skipped.push(SkippedLeg {
+                    symbol,
+                    reason: format!("symbol status is {}", info.status),
+                });
+                continue;
+            }
+
+            let delta_value = to_decimal(target_weight) * investable - value;
+            let quantity = Quantity(delta_value.abs() / price.value())
+                .floor_to_step(self.step_size(&symbol, symbols));
+            let notional = quantity * price;
+            let min_notional = self.min_notional(&symbol, symbols);
+
+            if notional < min_notional {
+                skipped.push(SkippedLeg {
+                    symbol,
+                    reason: format!("notional {notional:.2} below minimum {min_notional:.2}"),
+                });
+                continue;
+            }
+
+            let leg = RebalanceLeg {
+                symbol,
+                side: if delta_value > Decimal::ZERO {
+                    Side::Buy
+                } else {
+                    Side::Sell
+                },
+                quantity,
+                price,
+                notional,
+                current_weight,
+                target_weight,
+                drift,
+            };
+
+            match leg.side {
+                Side::Sell => sells.push(leg),
+                Side::Buy => buys.push(leg),
+            }
+        }
+
+        // Never spend into the cash buffer: scale buys to what is available
+        // once the sells have settled.
+        let available =
+            cash + sells.iter().map(|leg| leg.notional).sum::<Decimal>() - (equity - investable);
+        let wanted: Decimal = buys.iter().map(|leg| leg.notional).sum();
+
+        if wanted > available && wanted > Decimal::ZERO {
+            let scale = (available / wanted).max(Decimal::ZERO);
+            let mut scaled = Vec::new();
+
+            for mut leg in buys {
+                leg.quantity = Quantity(leg.quantity.value() * scale)
+                    .floor_to_step(self.step_size(&leg.symbol, symbols));
+                leg.notional = leg.quantity * leg.price;
+
+                if leg.notional < self.min_notional(&leg.symbol, symbols) {
+                    skipped.push(SkippedLeg {
+                        symbol: leg.symbol,
+                        reason: "insufficient cash after buffer".to_string(),
+                    });
+                } else {
+                    scaled.push(leg);
+                }
+            }
+
+            buys = scaled;
+        }
+
+        sells.extend(buys);
+
+        RebalancePlan {
+            quote_currency: quote,
+            equity,
+            investable,
+            max_drift,
+            legs: sells,
+            skipped,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Hands every leg of the plan to `submit` as a market order, in plan
+    /// order. `submit` returns the order id or the rejection reason.
+    pub fn execute<F>(&self, plan: &RebalancePlan, mut submit: F) -> Vec<LegExecution>
+    where
+        F: FnMut(&RebalanceLeg, OrderRequest) -> Result<String, String>,
+    {
+        let executions = plan
+            .legs
+            .iter()
+            .map(|leg| {
+                let order = OrderRequest::market(&leg.symbol, leg.side, leg.quantity);
+
+                match submit(leg, order) {
+                    Ok(order_id) => LegExecution {
+                        leg: leg.clone(),
+                        order_id: Some(order_id),
+                        error: None,
+                    },
+                    Err(e) => {
+                        error!("Rebalance leg on {} failed: {}", leg.symbol, e);
+                        LegExecution {
+                            leg: leg.clone(),
+                            order_id: None,
+                            error: Some(e),
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        self.mark_run(plan.created_at);
+
+        executions
+    }
+
+    /// Whether the schedule calls for acting on `plan` at `now`.
+    pub fn is_due(&self, plan: &RebalancePlan, now: DateTime<Utc>) -> bool {
+        let elapsed = |every: Duration| match *self.last_run.lock().unwrap() {
+            Some(last_run) => now - last_run >= every,
+            None => true,
+        };
+        let drifted = !plan.legs.is_empty()
+            && plan.max_drift >= self.config.drift_threshold
+            && elapsed(self.config.min_interval);
+        let calendar_due = |every: Duration| !plan.legs.is_empty() && elapsed(every);
+
+        match self.config.schedule {
+            RebalanceSchedule::Calendar { every } => calendar_due(every),
+            RebalanceSchedule::Drift => drifted,
+            RebalanceSchedule::CalendarOrDrift { every } => drifted || calendar_due(every),
+        }
+    }
+
+    fn step_size(&self, symbol: &str, symbols: &HashMap<String, SymbolInfo>) -> Quantity {
+        symbols
+            .get(symbol)
+            .map(|info| info.step_size)
+            .or_else(|| self.config.step_sizes.get(symbol).copied())
+            .unwrap_or(self.config.default_step_size)
+    }
+
+    fn min_notional(&self, symbol: &str, symbols: &HashMap<String, SymbolInfo>) -> Decimal {
+        symbols
+            .get(symbol)
+            .map(|info| info.min_notional)
+            .unwrap_or_default()
+            .max(self.config.min_notional)
+    }
+
+    pub fn mark_run(&self, at: DateTime<Utc>) {
+        info!("Rebalance recorded at {}", at);
+        *self.last_run.lock().unwrap() = Some(at);
+    }
+}
+
+impl Default for Rebalancer {
+    fn default() -> Self {
+        Self::new(RebalanceConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebalancer() -> Rebalancer {
+        Rebalancer::new(RebalanceConfig {
+            cash_buffer: 0.0,
+            ..Default::default()
+        })
+    }
+
+    fn targets(weights: &[(&str, f64)]) -> HashMap<String, f64> {
+        weights
+            .iter()
+            .map(|(symbol, weight)| (symbol.to_string(), *weight))
+            .collect()
+    }
+
+    fn balances(amounts: &[(&str, f64)]) -> HashMap<String, Quantity> {
+        amounts
+            .iter()
+            .map(|(asset, amount)| (asset.to_string(), Quantity::from_f64(*amount)))
+            .collect()
+    }
+
+    fn prices() -> HashMap<String, Price> {
+        HashMap::from([
+            ("BTCUSDT".to_string(), Price::from_f64(100.0)),
+            ("ETHUSDT".to_string(), Price::from_f64(10.0)),
+        ])
+    }
+
+    fn skip_reason<'a>(plan: &'a RebalancePlan, symbol: &str) -> &'a str {
+        plan.skipped
+            .iter()
+            .find(|skipped| skipped.symbol == symbol)
+            .map(|skipped| skipped.reason.as_str())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn sells_before_buying() {
+        let plan = rebalancer().plan_with(
+            &targets(&[("BTCUSDT", 0.5), ("ETHUSDT", 0.5)]),
+            &balances(&[("BTC", 1.0)]),
+            &prices(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(plan.equity, Decimal::from(100));
+        assert_eq!(plan.legs.len(), 2);
+
+        let (sell, buy) = (&plan.legs[0], &plan.legs[1]);
+
+        assert_eq!((sell.symbol.as_str(), sell.side), ("BTCUSDT", Side::Sell));
+        assert_eq!(sell.quantity, Quantity::from_f64(0.5));
+        assert_eq!(sell.notional, Decimal::from(50));
+
+        assert_eq!((buy.symbol.as_str(), buy.side), ("ETHUSDT", Side::Buy));
+        assert_eq!(buy.quantity, Quantity::from_f64(5.0));
+        assert_eq!(buy.target_weight, 0.5);
+    }
+
+    #[test]
+    fn leaves_small_drift_and_small_orders_alone() {
+        let plan = rebalancer().plan_with(
+            &targets(&[("BTCUSDT", 0.5), ("ETHUSDT", 0.45)]),
+            &balances(&[("USDT", 350.0), ("BTC", 5.1), ("ETH", 14.0)]),
+            &prices(),
+            &HashMap::new(),
+        );
+
+        // BTC is at 0.51 of the equity, ETH at 0.14.
+        assert!(skip_reason(&plan, "BTCUSDT").contains("within threshold"));
+        assert_eq!(plan.legs.len(), 1);
+        assert_eq!(plan.legs[0].symbol, "ETHUSDT");
+        assert_eq!(plan.legs[0].quantity, Quantity::from_f64(31.0));
+
+        let plan = Rebalancer::new(RebalanceConfig {
+            cash_buffer: 0.0,
+            drift_threshold: 0.001,
+            min_notional: Decimal::from(50),
+            ..Default::default()
+        })
+        .plan_with(
+            &targets(&[("BTCUSDT", 0.5)]),
+            &balances(&[("USDT", 52.0), ("BTC", 0.48)]),
+            &prices(),
+            &HashMap::new(),
+        );
+
+        assert!(plan.legs.is_empty());
+        assert!(skip_reason(&plan, "BTCUSDT").contains("below minimum"));
+    }
+
+    fn halted(symbol: &str) -> HashMap<String, SymbolInfo> {
+        HashMap::from([(
+            symbol.to_string(),
+            SymbolInfo {
+                symbol: symbol.to_string(),
+                status: "BREAK".to_string(),
+                base_asset: "BTC".to_string(),
+                quote_asset: "USDT".to_string(),
+                tick_size: Price::from_f64(0.01),
+                min_price: Price::ZERO,
+                max_price: Price::ZERO,
+                step_size: Quantity::from_f64(0.001),
+                min_qty: Quantity::ZERO,
+                max_qty: Quantity::ZERO,
+                min_notional: Decimal::ZERO,
+            },
+        )])
+    }
+
+    #[test]
+    fn skips_symbols_it_cannot_trade() {
+        let mut prices = prices();
+        prices.remove("ETHUSDT");
+
+        let plan = rebalancer().plan_with(
+            &targets(&[("BTCEUR", 0.2), ("ETHUSDT", 0.2), ("BTCUSDT", 0.2)]),
+            &balances(&[("USDT", 1_000.0)]),
+            &prices,
+            &halted("BTCUSDT"),
+        );
+
+        assert!(plan.legs.is_empty());
+        assert!(skip_reason(&plan, "BTCEUR").contains("not quoted in USDT"));
+        assert_eq!(skip_reason(&plan, "ETHUSDT"), "no price available");
+        assert_eq!(skip_reason(&plan, "BTCUSDT"), "symbol status is BREAK");
+    }
+
+    #[test]
+    fn scales_buys_to_the_cash_available() {
+        // The BTC sell that would fund the ETH buy cannot be placed, so
+        // only the cash is spent.
+        let targets = targets(&[("BTCUSDT", 0.0), ("ETHUSDT", 1.0)]);
+        let balances = balances(&[("USDT", 10.0), ("BTC", 0.9)]);
+
+        let plan = rebalancer().plan_with(&targets, &balances, &prices(), &halted("BTCUSDT"));
+
+        assert_eq!(plan.equity, Decimal::from(100));
+        assert_eq!(plan.legs.len(), 1);
+        assert_eq!(plan.legs[0].quantity, Quantity::from_f64(1.0));
+        assert_eq!(plan.legs[0].notional, Decimal::from(10));
+
+        // With a buffer the same cash is all reserved.
+        let plan = Rebalancer::new(RebalanceConfig {
+            cash_buffer: 0.1,
+            ..Default::default()
+        })
+        .plan_with(&targets, &balances, &prices(), &halted("BTCUSDT"));
+
+        assert_eq!(plan.investable, Decimal::from(90));
+        assert!(plan.legs.is_empty());
+        assert_eq!(
+            skip_reason(&plan, "ETHUSDT"),
+            "insufficient cash after buffer"
+        );
+    }
+
+    #[test]
+    fn executes_legs_in_plan_order() {
+        let rebalancer = rebalancer();
+        let plan = rebalancer.plan_with(
+            &targets(&[("BTCUSDT", 0.5), ("ETHUSDT", 0.5)]),
+            &balances(&[("BTC", 1.0)]),
+            &prices(),
+            &HashMap::new(),
+        );
+
+        let mut submitted = Vec::new();
+        let executions = rebalancer.execute(&plan, |leg, order| {
+            submitted.push((order.symbol.clone(), order.side, order.quantity));
+
+            match leg.side {
+                Side::Sell => Ok("sell-1".to_string()),
+                Side::Buy => Err("rejected".to_string()),
+            }
+        });
+
+        assert_eq!(
+            submitted,
+            [
+                ("BTCUSDT".to_string(), Side::Sell, Quantity::from_f64(0.5)),
+                ("ETHUSDT".to_string(), Side::Buy, Quantity::from_f64(5.0)),
+            ]
+        );
+        assert_eq!(executions[0].order_id.as_deref(), Some("sell-1"));
+        assert_eq!(executions[1].error.as_deref(), Some("rejected"));
+    }
+
+    #[test]
+    fn follows_the_schedule() {
+        let plan = rebalancer().plan_with(
+            &targets(&[("BTCUSDT", 0.5), ("ETHUSDT", 0.5)]),
+            &balances(&[("BTC", 1.0)]),
+            &prices(),
+            &HashMap::new(),
+        );
+        let now = Utc::now();
+
+        let drift = rebalancer();
+        assert!(drift.is_due(&plan, now));
+        drift.mark_run(now);
+        assert!(!drift.is_due(&plan, now + Duration::minutes(30)));
+        assert!(drift.is_due(&plan, now + Duration::hours(1)));
+
+        let calendar = Rebalancer::new(RebalanceConfig {
+            schedule: RebalanceSchedule::Calendar {
+                every: Duration::hours(1),
+            },
+            ..Default::default()
+        });
+
+        assert!(calendar.is_due(&plan, now));
+        calendar.mark_run(now);
+        assert!(!calendar.is_due(&plan, now + Duration::minutes(30)));
+        assert!(calendar.is_due(&plan, now + Duration::hours(1)));
+    }
+}
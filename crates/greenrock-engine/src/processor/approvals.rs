@@ -0,0 +1,74 @@
+//! Human-in-the-loop approvals. A task that needs the user's go-ahead
+//! records a `PendingApproval` and returns `NextAction::WaitForInput`; the
+//! session then resumes at that task with the user's `Decision`.
+
+use chrono::{DateTime, Utc};
+use graph_flow::Context;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Context key of the `Vec<PendingApproval>` of the session.
+pub const APPROVALS_KEY: &str = "pending_approvals";
+/// Context key of the `Decision` sent with a resume request.
+pub const DECISION_KEY: &str = "approval_decision";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub id: String,
+    /// Task the session resumes at.
+    pub task_id: String,
+    pub summary: String,
+    /// What gets executed on approval, one entry per step.
+    pub items: Vec<String>,
+    pub requested_at: DateTime<Utc>,
+}
+
+impl PendingApproval {
+    pub fn new(task_id: &str, summary: String, items: Vec<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            task_id: task_id.to_string(),
+            summary,
+            items,
+            requested_at: Utc::now(),
+        }
+    }
+}
+
+pub fn pending_approvals(context: &Context) -> Vec<PendingApproval> {
+    context.get_sync(APPROVALS_KEY).unwrap_or_default()
+}
+
+/// Records `approval`, replacing any earlier one of the same task.
+pub async fn request_approval(context: &Context, approval: PendingApproval) {
+    let mut approvals = pending_approvals(context);
+    approvals.retain(|pending| pending.task_id != approval.task_id);
+    approvals.push(approval);
+    context.set(APPROVALS_KEY, approvals).await;
+}
+
+/// Drops the approvals of `task_id` once it has acted on the decision.
+pub async fn resolve_approvals(context: &Context, task_id: &str) {
+    let mut approvals = pending_approvals(context);
+    approvals.retain(|pending| pending.task_id != task_id);
+    context.set(APPROVALS_KEY, approvals).await;
+}
+
+/// The decision sent with the resume request, consumed so it only applies
+/// once.
+pub async fn take_decision(context: &Context) -> Option<Decision> {
+    let decision = context.get_sync::<Option<Decision>>(DECISION_KEY).flatten();
+
+    if decision.is_some() {
+        context.set(DECISION_KEY, None::<Decision>).await;
+    }
+
+    decision
+}
@@ -0,0 +1,396 @@
+use std::{env, sync::Arc};
+
+use async_trait::async_trait;
+use graph_flow::SerializableMessage;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::processor::{
+    llm::{LlmConfig, parse_json_answer},
+    prompts::{
+        library::{PromptLibrary, format_history},
+        tasks::IntentClassificationTemplate,
+    },
+    tasks::{
+        binance_reporting_task::BinanceReportingTask,
+        portfolio_reporting_task::PortfolioReportingTask,
+        regimen_reporting_task::RegimenReportingTask, reply_generation_task::ReplyGenerationTask,
+    },
+};
+
+/// Minimum model confidence before the keyword fallback takes over.
+pub const DEFAULT_CONFIDENCE_THRESHOLD: f64 = 0.6;
+
+/// Messages of the conversation passed to the classifier.
+const HISTORY_WINDOW: usize = 6;
+
+const REGIMEN_KEYWORDS: [&str; 9] = [
+    "regimen",
+    "regime",
+    "strategy",
+    "strategies",
+    "backtest",
+    "signal",
+    "trend",
+    "switch",
+    "evaluate",
+];
+
+const BINANCE_KEYWORDS: [&str; 12] = [
+    "binance", "exchange", "balance", "order", "orders", "trade", "trades", "ticker", "price",
+    "buy", "sell", "cancel",
+];
+
+const PORTFOLIO_KEYWORDS: [&str; 9] = [
+    "portfolio",
+    "holdings",
+    "allocation",
+    "exposure",
+    "pnl",
+    "profit",
+    "rebalance",
+    "weights",
+    "diversify",
+];
+
+/// Branch of the workflow a chat message is routed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Intent {
+    Regimen,
+    Binance,
+    Portfolio,
+    Reply,
+}
+
+impl Intent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Intent::Regimen => "regimen",
+            Intent::Binance => "binance",
+            Intent::Portfolio => "portfolio",
+            Intent::Reply => "reply",
+        }
+    }
+
+    /// Id of the task that starts the intent's branch, as stored under
+    /// `next_task` in the graph context.
+    pub fn task_id(&self) -> &'static str {
+        match self {
+            Intent::Regimen => std::any::type_name::<RegimenReportingTask>(),
+            Intent::Binance => std::any::type_name::<BinanceReportingTask>(),
+            Intent::Portfolio => std::any::type_name::<PortfolioReportingTask>(),
+            Intent::Reply => std::any::type_name::<ReplyGenerationTask>(),
+        }
+    }
+}
+
+/// Structured answer of an intent model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentClassification {
+    pub intent: Intent,
+    /// Between 0 and 1.
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntentSource {
+    Model,
+    Keywords,
+    Default,
+}
+
+/// Outcome of routing a message, stored under `intent` in the graph context.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutedIntent {
+    pub intent: Intent,
+    pub confidence: f64,
+    pub source: IntentSource,
+}
+
+/// Classifies a user message. Implemented by the LLM classifier and the
+/// keyword fallback, and by stubs when exercising the router.
+#[async_trait]
+pub trait IntentModel: Send + Sync {
+    async fn classify(
+        &self,
+        user_input: &str,
+        history: &[SerializableMessage],
+    ) -> anyhow::Result<IntentClassification>;
+}
+
+/// Asks the chat model for a JSON `{intent, confidence}` object.
+pub struct LlmIntentModel {
+    prompts: PromptLibrary,
+    llm: LlmConfig,
+}
+
+impl LlmIntentModel {
+    pub fn new(prompts: PromptLibrary) -> Self {
+        Self {
+            prompts,
+            llm: LlmConfig::for_task("intent"),
+        }
+    }
+
+    pub fn with_llm(mut self, llm: LlmConfig) -> Self {
+        self.llm = llm;
+        self
+    }
+}
+
+#[async_trait]
+impl IntentModel for LlmIntentModel {
+    async fn classify(
+        &self,
+        user_input: &str,
+        history: &[SerializableMessage],
+    ) -> anyhow::Result<IntentClassification> {
+        let prompt = self
+            .prompts
+            .render(&IntentClassificationTemplate {
+                history: format_history(history, HISTORY_WINDOW),
+                user_input: user_input.to_string(),
+            })
+            .await?;
+
+        let agent = self
+            .llm
+            .agent("You route messages of a trading assistant. Reply with JSON only.")?;
+
+        let answer = agent.prompt(prompt.text).await?;
+
+        parse_classification(&answer)
+    }
+}
+
+/// Deterministic classifier counting keyword hits per intent.
+pub struct KeywordIntentModel;
+
+impl KeywordIntentModel {
+    pub fn classify_sync(&self, user_input: &str) -> IntentClassification {
+        let words: Vec<String> = user_input
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let hits = |keywords: &[&str]| {
+            words
+                .iter()
+                .filter(|word| keywords.contains(&word.as_str()))
+                .count()
+        };
+
+        let scores = [
+            (Intent::Regimen, hits(&REGIMEN_KEYWORDS)),
+            (Intent::Binance, hits(&BINANCE_KEYWORDS)),
+            (Intent::Portfolio, hits(&PORTFOLIO_KEYWORDS)),
+        ];
+
+        let total: usize = scores.iter().map(|(_, score)| score).sum();
+        let best = scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+        let leaders: Vec<Intent> = scores
+            .iter()
+            .filter(|(_, score)| *score == best)
+            .map(|(intent, _)| *intent)
+            .collect();
+
+        // No hits or a tie between branches is left to the plain reply.
+        if best == 0 || leaders.len() > 1 {
+            return IntentClassification {
+                intent: Intent::Reply,
+                confidence: 0.0,
+            };
+        }
+
+        IntentClassification {
+            intent: leaders[0],
+            confidence: best as f64 / total as f64,
+        }
+    }
+}
+
+#[async_trait]
+impl IntentModel for KeywordIntentModel {
+    async fn classify(
+        &self,
+        user_input: &str,
+        _history: &[SerializableMessage],
+    ) -> anyhow::Result<IntentClassification> {
+        Ok(self.classify_sync(user_input))
+    }
+}
+
+/// Picks the workflow branch for a message: the model's answer when it is
+/// confident enough, otherwise the keyword fallback, otherwise a plain reply.
+#[derive(Clone)]
+pub struct IntentRouter {
+    model: Arc<dyn IntentModel>,
+    threshold: f64,
+}
+
+impl IntentRouter {
+    pub fn new(model: Arc<dyn IntentModel>) -> Self {
+        Self {
+            model,
+            threshold: DEFAULT_CONFIDENCE_THRESHOLD,
+        }
+    }
+
+    /// LLM router with the threshold from `INTENT_CONFIDENCE_THRESHOLD` and
+    /// prompt overrides from `PROMPTS_DIR`.
+    pub fn from_env() -> Self {
+        let threshold = env::var("INTENT_CONFIDENCE_THRESHOLD")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CONFIDENCE_THRESHOLD);
+
+        Self::new(Arc::new(LlmIntentModel::new(PromptLibrary::from_env())))
+            .with_threshold(threshold)
+    }
+
+    pub fn with_model(mut self, model: Arc<dyn IntentModel>) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    pub async fn route(&self, user_input: &str, history: &[SerializableMessage]) -> RoutedIntent {
+        match self.model.classify(user_input, history).await {
+            Ok(classification) if classification.confidence >= self.threshold => {
+                return RoutedIntent {
+                    intent: classification.intent,
+                    confidence: classification.confidence,
+                    source: IntentSource::Model,
+                };
+            }
+            Ok(classification) => info!(
+                "Intent {:?} below threshold ({:.2} < {:.2}), using keywords",
+                classification.intent, classification.confidence, self.threshold
+            ),
+            Err(e) => warn!("Intent classification failed, using keywords: {}", e),
+        }
+
+        let fallback = KeywordIntentModel.classify_sync(user_input);
+
+        RoutedIntent {
+            intent: fallback.intent,
+            confidence: fallback.confidence,
+            source: if fallback.intent == Intent::Reply {
+                IntentSource::Default
+            } else {
+                IntentSource::Keywords
+            },
+        }
+    }
+}
+
+impl Default for IntentRouter {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+/// Reads the classification out of a model answer, with the confidence
+/// clamped to `0..=1`.
+fn parse_classification(answer: &str) -> anyhow::Result<IntentClassification> {
+    let mut classification: IntentClassification = parse_json_answer(answer)?;
+    classification.confidence = classification.confidence.clamp(0.0, 1.0);
+
+    Ok(classification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every message with the same classification, or fails.
+    struct StubModel(Option<IntentClassification>);
+
+    #[async_trait]
+    impl IntentModel for StubModel {
+        async fn classify(
+            &self,
+            _user_input: &str,
+            _history: &[SerializableMessage],
+        ) -> anyhow::Result<IntentClassification> {
+            self.0
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("model unavailable"))
+        }
+    }
+
+    fn router(intent: Intent, confidence: f64) -> IntentRouter {
+        IntentRouter::new(Arc::new(StubModel(Some(IntentClassification {
+            intent,
+            confidence,
+        }))))
+        .with_threshold(0.6)
+    }
+
+    #[tokio::test]
+    async fn uses_the_model_above_the_threshold() {
+        let routed = router(Intent::Portfolio, 0.9)
+            .route("what should I buy on binance", &[])
+            .await;
+
+        assert_eq!(routed.intent, Intent::Portfolio);
+        assert_eq!(routed.source, IntentSource::Model);
+        assert_eq!(routed.confidence, 0.9);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_keywords_below_the_threshold() {
+        let routed = router(Intent::Portfolio, 0.3)
+            .route("show my binance balance", &[])
+            .await;
+
+        assert_eq!(routed.intent, Intent::Binance);
+        assert_eq!(routed.source, IntentSource::Keywords);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_keywords_when_the_model_fails() {
+        let routed = IntentRouter::new(Arc::new(StubModel(None)))
+            .route("backtest the trend strategy", &[])
+            .await;
+
+        assert_eq!(routed.intent, Intent::Regimen);
+        assert_eq!(routed.source, IntentSource::Keywords);
+    }
+
+    #[tokio::test]
+    async fn replies_on_a_keyword_tie() {
+        let routed = IntentRouter::new(Arc::new(StubModel(None)))
+            .route("rebalance after the backtest", &[])
+            .await;
+
+        assert_eq!(routed.intent, Intent::Reply);
+        assert_eq!(routed.source, IntentSource::Default);
+        assert_eq!(routed.confidence, 0.0);
+    }
+
+    #[test]
+    fn parses_fenced_classifications() {
+        let answer =
+            "Sure {here it is}:\n```json\n{\"intent\": \"binance\", \"confidence\": 1.4}\n```";
+
+        let classification = parse_classification(answer).unwrap();
+
+        assert_eq!(classification.intent, Intent::Binance);
+        assert_eq!(classification.confidence, 1.0);
+    }
+
+    #[test]
+    fn rejects_answers_without_a_classification() {
+        assert!(parse_classification("I cannot tell.").is_err());
+        assert!(parse_classification("{\"intent\": \"weather\", \"confidence\": 1}").is_err());
+    }
+}
@@ -0,0 +1,619 @@
+//! Chat model configuration. Every task resolves its own `LlmConfig`, so the
+//! provider, model and sampling can differ between the router, the entry
+//! agent, the reply writer and the operation parser.
+
+use std::{env, fmt, str::FromStr};
+
+use anyhow::anyhow;
+use futures_util::StreamExt;
+use rig::{
+    OneOrMany,
+    agent::{Agent, AgentBuilder},
+    client::CompletionClient,
+    completion::{CompletionModel, Prompt},
+    message::{AssistantContent, Message, ToolResult, ToolResultContent, UserContent},
+    providers::{openai, openrouter},
+    streaming::{StreamedAssistantContent, StreamingCompletion},
+};
+use serde::de::DeserializeOwned;
+use tracing::warn;
+
+use crate::processor::tools::TradingTools;
+
+pub const DEFAULT_MODEL: &str = "google/gemini-2.0-flash-001";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmProvider {
+    OpenRouter,
+    /// Any server speaking the OpenAI chat completions API, e.g. OpenAI or a
+    /// local llama.cpp or Ollama instance.
+    OpenAiCompatible,
+    /// Deterministic answers without network access, for CI and air-gapped
+    /// runs.
+    Mock,
+}
+
+impl LlmProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmProvider::OpenRouter => "openrouter",
+            LlmProvider::OpenAiCompatible => "openai",
+            LlmProvider::Mock => "mock",
+        }
+    }
+}
+
+impl fmt::Display for LlmProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LlmProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "openrouter" => Ok(LlmProvider::OpenRouter),
+            "openai" | "openai_compatible" | "ollama" | "llamacpp" => {
+                Ok(LlmProvider::OpenAiCompatible)
+            }
+            "mock" => Ok(LlmProvider::Mock),
+            other => anyhow::bail!("unknown LLM provider {other}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    pub provider: LlmProvider,
+    pub model: String,
+    /// Provider default when unset.
+    pub temperature: Option<f64>,
+    /// Provider default when unset.
+    pub max_tokens: Option<u64>,
+    /// Server of an OpenAI-compatible provider, e.g.
+    /// `http://localhost:11434/v1` for Ollama. OpenAI itself when unset.
+    pub base_url: Option<String>,
+    /// Falls back to `OPENROUTER_API_KEY` or `OPENAI_API_KEY`.
+    pub api_key: Option<String>,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: LlmProvider::OpenRouter,
+            model: DEFAULT_MODEL.to_string(),
+            temperature: None,
+            max_tokens: None,
+            base_url: None,
+            api_key: None,
+        }
+    }
+}
+
+impl LlmConfig {
+    /// Defaults with the shared `LLM_PROVIDER`, `LLM_MODEL`,
+    /// `LLM_TEMPERATURE`, `LLM_MAX_TOKENS`, `LLM_BASE_URL` and `LLM_API_KEY`
+    /// applied.
+    pub fn from_env() -> Self {
+        Self::default().with_env("LLM")
+    }
+
+    /// Settings of `task`: `LLM_<TASK>_*` variables over the shared ones,
+    /// e.g. `LLM_REPLY_MODEL` over `LLM_MODEL`.
+    pub fn for_task(task: &str) -> Self {
+        Self::from_env().with_env(&format!("LLM_{}", task.to_uppercase()))
+    }
+
+    fn with_env(mut self, prefix: &str) -> Self {
+        let var = |name: &str| {
+            env::var(format!("{prefix}_{name}"))
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+
+        if let Some(provider) = var("PROVIDER") {
+            match provider.parse() {
+                Ok(provider) => self.provider = provider,
+                Err(e) => warn!("{}, keeping {}", e, self.provider),
+            }
+        }
+
+        if let Some(model) = var("MODEL") {
+            self.model = model;
+        }
+
+        if let Some(temperature) = var("TEMPERATURE") {
+            match temperature.parse() {
+                Ok(temperature) => self.temperature = Some(temperature),
+                Err(_) => warn!("Invalid {prefix}_TEMPERATURE {temperature}, ignoring"),
+            }
+        }
+
+        if let Some(max_tokens) = var("MAX_TOKENS") {
+            match max_tokens.parse() {
+                Ok(max_tokens) => self.max_tokens = Some(max_tokens),
+                Err(_) => warn!("Invalid {prefix}_MAX_TOKENS {max_tokens}, ignoring"),
+            }
+        }
+
+        if let Some(base_url) = var("BASE_URL") {
+            self.base_url = Some(base_url);
+        }
+
+        if let Some(api_key) = var("API_KEY") {
+            self.api_key = Some(api_key);
+        }
+
+        self
+    }
+
+    fn api_key(&self, fallback: &str) -> Option<String> {
+        self.api_key.clone().or_else(|| env::var(fallback).ok())
+    }
+
+    /// Agent builder with the model, sampling and preamble set, for adding
+    /// tools.
+    pub fn builder(&self, system_prompt: &str) -> anyhow::Result<LlmAgentBuilder> {
+        match self.provider {
+            LlmProvider::OpenRouter => {
+                let api_key = self
+                    .api_key("OPENROUTER_API_KEY")
+                    .ok_or_else(|| anyhow!("OPENROUTER_API_KEY not set"))?;
+
+                let client = openrouter::Client::new(&api_key);
+
+                Ok(LlmAgentBuilder::OpenRouter(
+                    self.configure(client.agent(&self.model), system_prompt),
+                ))
+            }
+            LlmProvider::OpenAiCompatible => {
+                // Local servers accept any key.
+                let api_key = self.api_key("OPENAI_API_KEY").unwrap_or_default();
+
+                let mut client = openai::Client::builder(&api_key);
+
+                if let Some(base_url) = &self.base_url {
+                    client = client.base_url(base_url);
+                }
+
+                let client = client
+                    .build()
+                    .map_err(|e| anyhow!("Failed to build OpenAI-compatible client: {e:?}"))?;
+
+                // Local servers implement chat completions, not the
+                // responses API.
+                let model = client.completion_model(&self.model).completions_api();
+
+                Ok(LlmAgentBuilder::OpenAiCompatible(
+                    self.configure(AgentBuilder::new(model), system_prompt),
+                ))
+            }
+            LlmProvider::Mock => Ok(LlmAgentBuilder::Mock(MockModel {
+                model: self.model.clone(),
+            })),
+        }
+    }
+
+    pub fn agent(&self, system_prompt: &str) -> anyhow::Result<LlmAgent> {
+        Ok(self.builder(system_prompt)?.build())
+    }
+
+    fn configure<M: CompletionModel>(
+        &self,
+        builder: AgentBuilder<M>,
+        system_prompt: &str,
+    ) -> AgentBuilder<M> {
+        let mut builder = builder.preamble(system_prompt);
+
+        if let Some(temperature) = self.temperature {
+            builder = builder.temperature(temperature);
+        }
+
+        if let Some(max_tokens) = self.max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+
+        builder
+    }
+}
+
+pub enum LlmAgentBuilder {
+    OpenRouter(AgentBuilder<openrouter::CompletionModel>),
+    OpenAiCompatible(AgentBuilder<openai::CompletionModel>),
+    Mock(MockModel),
+}
+
+impl LlmAgentBuilder {
+    /// Registers the trading tools. The mock model never calls them.
+    pub fn with_tools(self, tools: &TradingTools) -> Self {
+        match self {
+            LlmAgentBuilder::OpenRouter(builder) => {
+                LlmAgentBuilder::OpenRouter(tools.attach(builder))
+            }
+            LlmAgentBuilder::OpenAiCompatible(builder) => {
+                LlmAgentBuilder::OpenAiCompatible(tools.attach(builder))
+            }
+            LlmAgentBuilder::Mock(model) => LlmAgentBuilder::Mock(model),
+        }
+    }
+
+    pub fn build(self) -> LlmAgent {
+        match self {
+            LlmAgentBuilder::OpenRouter(builder) => LlmAgent::OpenRouter(builder.build()),
+            LlmAgentBuilder::OpenAiCompatible(builder) => {
+                LlmAgent::OpenAiCompatible(builder.build())
+            }
+            LlmAgentBuilder::Mock(model) => LlmAgent::Mock(model),
+        }
+    }
+}
+
+pub enum LlmAgent {
+    OpenRouter(Agent<openrouter::CompletionModel>),
+    OpenAiCompatible(Agent<openai::CompletionModel>),
+    Mock(MockModel),
+}
+
+impl LlmAgent {
+    pub async fn prompt(&self, prompt: String) -> anyhow::Result<String> {
+        self.chat(prompt, &mut Vec::new(), 0).await
+    }
+
+    /// Answers `prompt` following `history`, with up to `max_turns` rounds of
+    /// tool calls. The exchange is appended to `history`.
+    pub async fn chat(
+        &self,
+        prompt: String,
+        history: &mut Vec<Message>,
+        max_turns: usize,
+    ) -> anyhow::Result<String> {
+        match self {
+            LlmAgent::OpenRouter(agent) => Ok(agent
+                .prompt(prompt)
+                .with_history(history)
+                .multi_turn(max_turns)
+                .await?),
+            LlmAgent::OpenAiCompatible(agent) => Ok(agent
+                .prompt(prompt)
+                .with_history(history)
+                .multi_turn(max_turns)
+                .await?),
+            LlmAgent::Mock(model) => {
+                let answer = model.answer(&prompt);
+
+                history.push(Message::user(prompt));
+                history.push(Message::assistant(answer.clone()));
+
+                Ok(answer)
+            }
+        }
+    }
+
+    /// `chat` with the answer streamed: `on_token` is called for every text
+    /// chunk as it arrives, and the whole streamed text is returned. Tool
+    /// calls run between rounds like in `chat`.
+    pub async fn stream(
+        &self,
+        prompt: String,
+        history: &mut Vec<Message>,
+        max_turns: usize,
+        mut on_token: impl FnMut(&str) + Send,
+    ) -> anyhow::Result<String> {
+        match self {
+            LlmAgent::OpenRouter(agent) => {
+                stream_agent(agent, prompt, history, max_turns, &mut on_token).await
+            }
+            LlmAgent::OpenAiCompatible(agent) => {
+                stream_agent(agent, prompt, history, max_turns, &mut on_token).await
+            }
+            LlmAgent::Mock(model) => {
+                let answer = model.answer(&prompt);
+
+                for word in answer.split_inclusive(' ') {
+                    on_token(word);
+                }
+
+                history.push(Message::user(prompt));
+                history.push(Message::assistant(answer.clone()));
+
+                Ok(answer)
+            }
+        }
+    }
+}
+
+/// Reads the first JSON object of type `T` out of a model answer, tolerating
+/// code fences and prose around it, including prose with braces of its own.
+pub fn parse_json_answer<T: DeserializeOwned>(answer: &str) -> anyhow::Result<T> {
+    let mut last_error = None;
+
+    for (start, _) in answer.match_indices('{') {
+        let mut values = serde_json::Deserializer::from_str(&answer[start..]).into_iter::<T>();
+
+        match values.next() {
+            Some(Ok(value)) => return Ok(value),
+            Some(Err(e)) => last_error = Some(e),
+            None => {}
+        }
+    }
+
+    match last_error {
+        Some(e) => Err(anyhow!("invalid JSON object in answer ({e}): {answer}")),
+        None => Err(anyhow!("no JSON object in answer: {answer}")),
+    }
+}
+
+/// Streams completions of `agent` over `history`, running the tool calls of
+/// each round through the agent's tools and sending their results back,
+/// until a round answers without tool calls or `max_turns` rounds of calls
+/// have run.
+async fn stream_agent<M: CompletionModel>(
+    agent: &Agent<M>,
+    prompt: String,
+    history: &mut Vec<Message>,
+    max_turns: usize,
+    on_token: &mut (impl FnMut(&str) + Send),
+) -> anyhow::Result<String> {
+    let mut prompt = Message::user(prompt);
+    let mut answer = String::new();
+
+    for turn in 0..=max_turns {
+        let mut stream = agent
+            .stream_completion(prompt.clone(), history.clone())
+            .await?
+            .stream()
+            .await?;
+
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+
+        while let Some(chunk) = stream.next().await {
+            match chunk? {
+                StreamedAssistantContent::Text(chunk) => {
+                    on_token(&chunk.text);
+                    text.push_str(&chunk.text);
+                }
+                StreamedAssistantContent::ToolCall(tool_call) => tool_calls.push(tool_call),
+                _ => {}
+            }
+        }
+
+        answer.push_str(&text);
+        history.push(prompt);
+
+        if tool_calls.is_empty() {
+            history.push(Message::assistant(text));
+            return Ok(answer);
+        }
+
+        if turn == max_turns {
+            break;
+        }
+
+        let mut results = Vec::new();
+
+        for tool_call in &tool_calls {
+            let result = agent
+                .tools
+                .call(
+                    &tool_call.function.name,
+                    tool_call.function.arguments.to_string(),
+                )
+                .await
+                .unwrap_or_else(|e| format!("Tool call failed: {e}"));
+
+            results.push(UserContent::ToolResult(ToolResult {
+                id: tool_call.id.clone(),
+                call_id: tool_call.call_id.clone(),
+                content: OneOrMany::one(ToolResultContent::text(result)),
+            }));
+        }
+
+        history.push(Message::Assistant {
+            id: None,
+            content: OneOrMany::many(tool_calls.into_iter().map(AssistantContent::ToolCall))?,
+        });
+        prompt = Message::User {
+            content: OneOrMany::many(results)?,
+        };
+    }
+
+    Err(anyhow!("still calling tools after {max_turns} rounds"))
+}
+
+/// Stand-in for a chat model that answers with its name and the last line of
+/// the prompt. The answers are not the JSON the router and the operation
+/// parser ask for, so both take their rule-based paths.
+#[derive(Debug, Clone)]
+pub struct MockModel {
+    model: String,
+}
+
+impl MockModel {
+    pub fn answer(&self, prompt: &str) -> String {
+        let last_line = prompt
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default();
+
+        format!("[{}] {last_line}", self.model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{Json, Router, routing::get};
+    use graph_flow::{
+        Context, ExecutionStatus, FlowRunner, InMemorySessionStorage, Session, SessionStorage,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        analysis::graph::build_graph,
+        brokers::{
+            binance::{BinanceBroker, BinanceEndpoints},
+            mock_server,
+            rate_limit::RateLimiter,
+            venue::VenueBroker,
+        },
+        models::money::Quantity,
+        processor::{
+            operations::{ACCOUNT_KEY, Operation, PENDING_KEY},
+            streaming::ChatEvents,
+            tasks::{
+                binance_reporting_task::BinanceReportingTask,
+                entry_interaction_task::EntryInteractionTask,
+            },
+        },
+        runner::core::{LiveRunner, Runner},
+        strategy::regimen::RegimenStrategy,
+    };
+
+    /// The chat workflow on the mock model, with a runner on a mock venue
+    /// quoting BTCUSDT.
+    struct Chat {
+        flow: FlowRunner,
+        storage: Arc<InMemorySessionStorage>,
+        runner: Arc<LiveRunner>,
+    }
+
+    impl Chat {
+        async fn new() -> Self {
+            let base = mock_server::serve(Router::new().route(
+                "/api/v3/ticker/price",
+                get(|| async { Json(json!({"symbol": "BTCUSDT", "price": "65000.50"})) }),
+            ))
+            .await;
+
+            let broker = BinanceBroker::new()
+                .with_endpoints(BinanceEndpoints {
+                    ws: mock_server::ws_url(&base, ""),
+                    rest: base,
+                })
+                .with_rate_limiter(Arc::new(RateLimiter::default()));
+            let runner = Arc::new(Runner::new(
+                VenueBroker::Binance(broker),
+                RegimenStrategy::from_env(),
+            ));
+
+            let mock = LlmConfig {
+                provider: LlmProvider::Mock,
+                ..Default::default()
+            };
+            let graph = build_graph(runner.clone(), ChatEvents::new(), |_| mock.clone());
+            let storage = Arc::new(InMemorySessionStorage::new());
+
+            Self {
+                flow: FlowRunner::new(Arc::new(graph), storage.clone()),
+                storage,
+                runner,
+            }
+        }
+
+        /// Starts a turn at the entry task, like a chat message without a
+        /// waiting session.
+        async fn ask(&self, session_id: &str, query: &str) -> ExecutionStatus {
+            let context = Context::new();
+            context.set("user_input", query.to_string()).await;
+            context.set("session_id", session_id.to_string()).await;
+            context.set("retry_count", 0).await;
+            context.set("symbol", "BTCUSDT".to_string()).await;
+
+            self.storage
+                .save(Session {
+                    id: session_id.to_string(),
+                    graph_id: "".to_string(),
+                    current_task_id: std::any::type_name::<EntryInteractionTask>().to_string(),
+                    status_message: None,
+                    context,
+                })
+                .await
+                .unwrap();
+
+            self.run(session_id).await
+        }
+
+        /// Answers the session waiting for confirmation.
+        async fn reply(&self, session_id: &str, query: &str) -> ExecutionStatus {
+            let session = self.storage.get(session_id).await.unwrap().unwrap();
+            session.context.set("user_input", query.to_string()).await;
+            self.storage.save(session).await.unwrap();
+
+            self.run(session_id).await
+        }
+
+        /// Runs until the turn completes or waits for input, resuming the
+        /// jumps between branches like the chat client does.
+        async fn run(&self, session_id: &str) -> ExecutionStatus {
+            for _ in 0..10 {
+                let execution = self.flow.run(session_id).await.unwrap();
+
+                if !matches!(execution.status, ExecutionStatus::Paused { .. }) {
+                    return execution.status;
+                }
+            }
+
+            panic!("session {session_id} kept pausing");
+        }
+
+        async fn context(&self, session_id: &str) -> Context {
+            self.storage.get(session_id).await.unwrap().unwrap().context
+        }
+
+        fn btc_position(&self) -> Quantity {
+            self.runner
+                .positions()
+                .into_iter()
+                .find(|position| position.asset == "BTC")
+                .map_or(Quantity::ZERO, |position| position.quantity)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_the_chat_workflow_on_the_mock_model() {
+        let chat = Chat::new().await;
+
+        // The mock does not answer the router with JSON, so the keywords
+        // pick the Binance branch.
+        let status = chat.ask("report", "show my binance balance").await;
+        assert!(matches!(status, ExecutionStatus::Completed));
+
+        let context = chat.context("report").await;
+        assert_eq!(
+            context.get_sync::<String>("next_task").unwrap(),
+            std::any::type_name::<BinanceReportingTask>()
+        );
+        assert!(context.get_sync::<String>(ACCOUNT_KEY).is_some());
+
+        // An operation waits for confirmation and then fills on paper.
+        let status = chat.ask("buy", "buy 0.01 BTC at market").await;
+        assert!(matches!(status, ExecutionStatus::WaitingForInput));
+        assert_eq!(chat.btc_position(), Quantity::ZERO);
+
+        let status = chat.reply("buy", "confirm").await;
+        assert!(matches!(status, ExecutionStatus::Completed));
+        assert_eq!(chat.btc_position(), Quantity::from_f64(0.01));
+
+        // A declined operation is dropped without trading.
+        let status = chat.ask("sell", "sell 0.01 BTC at market").await;
+        assert!(matches!(status, ExecutionStatus::WaitingForInput));
+
+        let status = chat.reply("sell", "cancel").await;
+        assert!(matches!(status, ExecutionStatus::Completed));
+        assert_eq!(chat.btc_position(), Quantity::from_f64(0.01));
+
+        let pending: Vec<Operation> = chat
+            .context("sell")
+            .await
+            .get_sync(PENDING_KEY)
+            .unwrap_or_default();
+        assert!(pending.is_empty());
+    }
+}
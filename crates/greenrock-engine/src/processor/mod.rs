@@ -1,3 +1,12 @@
+pub mod approvals;
+pub mod intent;
+pub mod llm;
 pub mod loaders;
+pub mod operations;
+pub mod portfolio;
 pub mod prompts;
+pub mod regimen;
+pub mod sessions;
+pub mod streaming;
 pub mod tasks;
+pub mod tools;
@@ -0,0 +1,368 @@
+//! Exchange operations requested in chat, parsed into a plan that is only
+//! executed once the user confirms it.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    models::orders::Side,
+    processor::{
+        llm::{LlmConfig, parse_json_answer},
+        prompts::{library::PromptLibrary, tasks::BinanceOperationsTemplate},
+    },
+};
+
+/// Context key of the account summary written by the reporting task.
+pub const ACCOUNT_KEY: &str = "binance_account";
+/// Context key of the `Vec<Operation>` awaiting confirmation. Empty once
+/// confirmed or declined.
+pub const PENDING_KEY: &str = "pending_operations";
+
+const OPERATION_KEYWORDS: [&str; 5] = ["buy", "sell", "cancel", "place", "close"];
+const CONFIRM_REPLY: &str = "confirm";
+const DECLINE_REPLIES: [&str; 4] = ["cancel", "no", "abort", "decline"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Operation {
+    /// Cancel every open order on `symbol`.
+    Cancel { symbol: String },
+    /// Market order, or limit order when `price` is set.
+    Order {
+        symbol: String,
+        side: Side,
+        quantity: Decimal,
+        #[serde(default)]
+        price: Option<Decimal>,
+    },
+}
+
+impl Operation {
+    pub fn symbol(&self) -> &str {
+        match self {
+            Operation::Cancel { symbol } | Operation::Order { symbol, .. } => symbol,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Cancel { symbol } => format!("cancel all open orders on {symbol}"),
+            Operation::Order {
+                symbol,
+                side,
+                quantity,
+                price,
+            } => {
+                let side = match side {
+                    Side::Buy => "buy",
+                    Side::Sell => "sell",
+                };
+
+                match price {
+                    Some(price) => format!("{side} {quantity} {symbol} at limit {price}"),
+                    None => format!("{side} {quantity} {symbol} at market"),
+                }
+            }
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Operation::Cancel { symbol } => !symbol.is_empty(),
+            Operation::Order {
+                symbol,
+                quantity,
+                price,
+                ..
+            } => {
+                !symbol.is_empty()
+                    && *quantity > Decimal::ZERO
+                    && price.is_none_or(|price| price > Decimal::ZERO)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OperationsAnswer {
+    operations: Vec<Operation>,
+}
+
+fn words(input: &str) -> Vec<String> {
+    input
+        .to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '.'))
+        .map(|word| word.trim_matches('.').to_string())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Whether a Binance request asks for an operation rather than a report.
+pub fn is_operation_request(user_input: &str) -> bool {
+    words(user_input)
+        .iter()
+        .any(|word| OPERATION_KEYWORDS.contains(&word.as_str()))
+}
+
+/// `Some(true)` when the whole message is "confirm", `Some(false)` when it is
+/// a refusal such as "cancel", and `None` for anything else, including
+/// "yes, but only half" or "ok".
+pub fn confirmation(user_input: &str) -> Option<bool> {
+    let reply = user_input
+        .trim()
+        .trim_end_matches(['.', '!'])
+        .to_lowercase();
+
+    if reply == CONFIRM_REPLY {
+        Some(true)
+    } else if DECLINE_REPLIES.contains(&reply.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Turns a request into operations: the chat model first, then a rule-based
+/// parser for the simple forms "cancel my BTC orders" and "buy 0.01 BTC at
+/// market" / "sell 0.5 ETH at 3500".
+pub struct OperationParser {
+    prompts: PromptLibrary,
+    quote_currency: String,
+    llm: LlmConfig,
+}
+
+impl OperationParser {
+    pub fn new(prompts: PromptLibrary, quote_currency: &str) -> Self {
+        Self {
+            prompts,
+            quote_currency: quote_currency.to_uppercase(),
+            llm: LlmConfig::for_task("operations"),
+        }
+    }
+
+    pub fn with_llm(mut self, llm: LlmConfig) -> Self {
+        self.llm = llm;
+        self
+    }
+
+    pub async fn parse(&self, user_input: &str, account: &str) -> Vec<Operation> {
+        let operations = match self.parse_with_model(user_input, account).await {
+            Ok(operations) if !operations.is_empty() => operations,
+            Ok(_) => self.parse_rules(user_input),
+            Err(e) => {
+                warn!("Operation parsing failed, using rules: {}", e);
+                self.parse_rules(user_input)
+            }
+        };
+
+        operations
+            .into_iter()
+            .map(|operation| self.normalize(operation))
+            .filter(Operation::is_valid)
+            .collect()
+    }
+
+    async fn parse_with_model(
+        &self,
+        user_input: &str,
+        account: &str,
+    ) -> anyhow::Result<Vec<Operation>> {
+        let prompt = self
+            .prompts
+            .render(&BinanceOperationsTemplate {
+                user_input: user_input.to_string(),
+                account: account.to_string(),
+                quote_currency: self.quote_currency.clone(),
+            })
+            .await?;
+
+        let agent = self
+            .llm
+            .agent("You turn requests into exchange operations. Reply with JSON only.")?;
+
+        let answer = agent.prompt(prompt.text).await?;
+
+        Ok(parse_json_answer::<OperationsAnswer>(&answer)?.operations)
+    }
+
+    /// Rule-based parser for one operation per message.
+    pub fn parse_rules(&self, user_input: &str) -> Vec<Operation> {
+        let words = words(user_input);
+
+        let quote = self.quote_currency.to_lowercase();
+
+        // The quote currency never names the asset, and "100 USDT of BTC"
+        // has no base quantity to order.
+        let symbol = words
+            .iter()
+            .filter(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+            .find(|word| word.len() >= 2 && !is_filler(word) && **word != quote)
+            .map(|word| self.pair(word));
+
+        let Some(symbol) = symbol else {
+            return Vec::new();
+        };
+
+        if words.iter().any(|word| word == "cancel") {
+            return vec![Operation::Cancel { symbol }];
+        }
+
+        let side = if words.iter().any(|word| word == "buy") {
+            Side::Buy
+        } else if words.iter().any(|word| word == "sell") {
+            Side::Sell
+        } else {
+            return Vec::new();
+        };
+
+        let quote_amount = words
+            .windows(2)
+            .any(|pair| pair[0].parse::<Decimal>().is_ok() && pair[1] == quote);
+        if quote_amount {
+            return Vec::new();
+        }
+
+        let numbers: Vec<Decimal> = words.iter().filter_map(|word| word.parse().ok()).collect();
+
+        let Some(quantity) = numbers.first().copied() else {
+            return Vec::new();
+        };
+
+        // "at 3500" or "limit 3500" makes it a limit order.
+        let price = words
+            .iter()
+            .position(|word| word == "at" || word == "limit")
+            .and_then(|at| words[at + 1..].iter().find_map(|word| word.parse().ok()));
+
+        vec![Operation::Order {
+            symbol,
+            side,
+            quantity,
+            price,
+        }]
+    }
+
+    fn pair(&self, asset: &str) -> String {
+        let asset = asset.to_uppercase();
+
+        if asset.ends_with(&self.quote_currency) && asset != self.quote_currency {
+            asset
+        } else {
+            format!("{asset}{}", self.quote_currency)
+        }
+    }
+
+    fn normalize(&self, operation: Operation) -> Operation {
+        match operation {
+            Operation::Cancel { symbol } => Operation::Cancel {
+                symbol: self.pair(&symbol),
+            },
+            Operation::Order {
+                symbol,
+                side,
+                quantity,
+                price,
+            } => Operation::Order {
+                symbol: self.pair(&symbol),
+                side,
+                quantity,
+                price,
+            },
+        }
+    }
+}
+
+/// Words of an operation request that never name an asset.
+fn is_filler(word: &str) -> bool {
+    const FILLER: [&str; 24] = [
+        "buy", "sell", "cancel", "place", "close", "my", "all", "the", "order", "orders", "at",
+        "market", "limit", "price", "of", "for", "on", "please", "some", "a", "an", "me", "and",
+        "open",
+    ];
+
+    FILLER.contains(&word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirms_only_on_the_whole_reply() {
+        assert_eq!(confirmation("confirm"), Some(true));
+        assert_eq!(confirmation("  Confirm. "), Some(true));
+
+        assert_eq!(confirmation("yes"), None);
+        assert_eq!(confirmation("ok"), None);
+        assert_eq!(confirmation("confirm but only half"), None);
+        assert_eq!(confirmation("go ahead and sell everything"), None);
+    }
+
+    fn parser() -> OperationParser {
+        OperationParser::new(PromptLibrary::new(), "USDT")
+    }
+
+    #[test]
+    fn parses_a_cancel_request() {
+        assert_eq!(
+            parser().parse_rules("cancel my BTC orders"),
+            vec![Operation::Cancel {
+                symbol: "BTCUSDT".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_a_market_order() {
+        assert_eq!(
+            parser().parse_rules("buy 0.01 BTC at market"),
+            vec![Operation::Order {
+                symbol: "BTCUSDT".to_string(),
+                side: Side::Buy,
+                quantity: Decimal::new(1, 2),
+                price: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_a_limit_order() {
+        assert_eq!(
+            parser().parse_rules("sell 0.5 ETH at 3500"),
+            vec![Operation::Order {
+                symbol: "ETHUSDT".to_string(),
+                side: Side::Sell,
+                quantity: Decimal::new(5, 1),
+                price: Some(Decimal::from(3500)),
+            }]
+        );
+    }
+
+    #[test]
+    fn keeps_a_pair_that_already_names_the_quote() {
+        assert_eq!(
+            parser().parse_rules("sell 2 SOLUSDT")[0].symbol(),
+            "SOLUSDT"
+        );
+    }
+
+    #[test]
+    fn never_orders_the_quote_currency_or_a_quote_amount() {
+        assert!(parser().parse_rules("buy 100 USDT of BTC").is_empty());
+        assert!(parser().parse_rules("buy some USDT").is_empty());
+    }
+
+    #[test]
+    fn needs_a_side_and_a_quantity() {
+        assert!(parser().parse_rules("what is BTC doing").is_empty());
+        assert!(parser().parse_rules("buy BTC").is_empty());
+    }
+
+    #[test]
+    fn declines_on_a_refusal() {
+        assert_eq!(confirmation("cancel"), Some(false));
+        assert_eq!(confirmation("No!"), Some(false));
+        assert_eq!(confirmation("cancel my BTC orders instead"), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! Shared configuration and context records of the portfolio pipeline:
+//! reporting, aggregation and selection.
+
+use std::{env, sync::Arc};
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use tracing::warn;
+
+use crate::{
+    models::money::to_decimal,
+    portfolio::{
+        allocation::{AllocationMethod, AssetSnapshot, HoldingsSnapshot},
+        ledger::split_symbol,
+    },
+    runner::core::LiveRunner,
+};
+
+/// Context key of the `HoldingsSnapshot`.
+pub const HOLDINGS_KEY: &str = "portfolio_holdings";
+/// Context key of the `AllocationReport`.
+pub const ALLOCATION_KEY: &str = "portfolio_allocation";
+/// Context key of the `Vec<TargetAllocation>`, one per method.
+pub const CANDIDATES_KEY: &str = "portfolio_candidates";
+/// Context key of the selected `TargetAllocation`.
+pub const TARGET_KEY: &str = "portfolio_target";
+
+#[derive(Debug, Clone)]
+pub struct PortfolioConfig {
+    /// Candle interval volatility and returns are measured on.
+    pub interval: String,
+    /// Candles of history per asset.
+    pub history: u16,
+    /// Method of the selected allocation; the others are kept as candidates.
+    pub method: AllocationMethod,
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        Self {
+            interval: "1d".to_string(),
+            history: 90,
+            method: AllocationMethod::RiskParity,
+        }
+    }
+}
+
+impl PortfolioConfig {
+    /// Defaults with `PORTFOLIO_INTERVAL` and `PORTFOLIO_METHOD` applied.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+
+        if let Ok(interval) = env::var("PORTFOLIO_INTERVAL") {
+            config.interval = interval;
+        }
+
+        if let Ok(method) = env::var("PORTFOLIO_METHOD") {
+            match method.parse() {
+                Ok(method) => config.method = method,
+                Err(e) => warn!("{}, keeping {}", e, config.method),
+            }
+        }
+
+        config
+    }
+
+    /// Broker balances and prices of every held asset and every symbol the
+    /// strategy targets, with ledger PnL and the configured history.
+    pub async fn snapshot(&self, runner: &Arc<LiveRunner>) -> anyhow::Result<HoldingsSnapshot> {
+        let blocking = runner.clone();
+
+        let (quote_currency, cash, pnl, mut assets, unpriced) =
+            tokio::task::spawn_blocking(move || {
+                blocking.mark_to_market();
+
+                let balances = blocking.balances();
+                let pnl = blocking.pnl();
+                let positions = blocking.positions();
+                let quote = pnl.quote_currency.clone();
+
+                let cash = balances
+                    .iter()
+                    .find(|balance| balance.asset == quote)
+                    .map(|balance| balance.total())
+                    .unwrap_or_default();
+
+                let mut held: Vec<(String, Decimal)> = balances
+                    .iter()
+                    .filter(|balance| balance.asset != quote && balance.total() > Decimal::ZERO)
+                    .map(|balance| (balance.asset.clone(), balance.total()))
+                    .collect();
+
+                for symbol in blocking.portfolio().keys() {
+                    if let Some((base, symbol_quote)) = split_symbol(symbol)
+                        && symbol_quote == quote
+                        && !held.iter().any(|(asset, _)| *asset == base)
+                    {
+                        held.push((base, Decimal::ZERO));
+                    }
+                }
+
+                let mut assets = Vec::new();
+                let mut unpriced = Vec::new();
+
+                for (asset, quantity) in held {
+                    let symbol = format!("{asset}{quote}");
+                    let price = blocking.market_current_price(&symbol);
+
+                    if price <= 0.0 {
+                        unpriced.push(asset);
+                        continue;
+                    }
+
+                    let unrealized_pnl = positions
+                        .iter()
+                        .find(|position| position.asset == asset)
+                        .map(|position| position.unrealized_pnl)
+                        .unwrap_or_default();
+
+                    assets.push(AssetSnapshot {
+                        asset,
+                        symbol,
+                        quantity,
+                        price: to_decimal(price),
+                        closes: Vec::new(),
+                        unrealized_pnl,
+                    });
+                }
+
+                (quote, cash, pnl, assets, unpriced)
+            })
+            .await?;
+
+        for asset in &mut assets {
+            asset.closes = runner
+                .candles(&asset.symbol, &self.interval, self.history, None, None)
+                .await
+                .iter()
+                .map(|candle| candle.close.to_f64())
+                .collect();
+        }
+
+        Ok(HoldingsSnapshot {
+            quote_currency,
+            cash,
+            assets,
+            unpriced,
+            realized_pnl: pnl.realized_pnl,
+            unrealized_pnl: pnl.unrealized_pnl,
+            fees: pnl.fees,
+            taken_at: Utc::now(),
+        })
+    }
+}
@@ -0,0 +1,103 @@
+use askama::Template;
+use serde::Serialize;
+
+use crate::{
+    models::market::Ticker,
+    portfolio::ledger::{PnlReport, PositionReport},
+    processor::prompts::library::prompt_version,
+};
+
+/// Preamble of every chat agent. The context sections are rendered from
+/// their own templates and skipped when empty.
+#[derive(Template, Serialize, Default)]
+#[template(path = "prompts/system_persona.v1.md")]
+pub struct SystemPersonaTemplate {
+    pub market_context: String,
+    pub portfolio_context: String,
+    pub risk_disclaimer: String,
+}
+
+prompt_version!(SystemPersonaTemplate, "system_persona", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/risk_disclaimer.v1.md")]
+pub struct RiskDisclaimerTemplate;
+
+prompt_version!(RiskDisclaimerTemplate, "risk_disclaimer", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/market_context.v1.md")]
+pub struct MarketContextTemplate {
+    pub symbol: String,
+    pub last_price: String,
+    pub bid_price: String,
+    pub ask_price: String,
+    pub high_price: String,
+    pub low_price: String,
+    pub volume: String,
+    pub price_change_percent: String,
+    pub as_of: String,
+}
+
+prompt_version!(MarketContextTemplate, "market_context", 1);
+
+impl From<&Ticker> for MarketContextTemplate {
+    fn from(ticker: &Ticker) -> Self {
+        Self {
+            symbol: ticker.symbol.clone(),
+            last_price: ticker.last_price.to_string(),
+            bid_price: ticker.bid_price.to_string(),
+            ask_price: ticker.ask_price.to_string(),
+            high_price: ticker.high_price.to_string(),
+            low_price: ticker.low_price.to_string(),
+            volume: ticker.volume.to_string(),
+            price_change_percent: ticker.price_change_percent.round_dp(2).to_string(),
+            as_of: ticker.time.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/portfolio_context.v1.md")]
+pub struct PortfolioContextTemplate {
+    pub quote_currency: String,
+    pub cash: String,
+    pub equity: String,
+    pub realized_pnl: String,
+    pub unrealized_pnl: String,
+    pub fees: String,
+    /// One `- asset: quantity @ average price` line per open position.
+    pub positions: String,
+}
+
+prompt_version!(PortfolioContextTemplate, "portfolio_context", 1);
+
+impl PortfolioContextTemplate {
+    pub fn new(pnl: &PnlReport, positions: &[PositionReport]) -> Self {
+        let positions = positions
+            .iter()
+            .filter(|position| !position.quantity.is_zero())
+            .map(|position| {
+                format!(
+                    "- {}: {} @ {} (value {}, unrealized {})",
+                    position.asset,
+                    position.quantity,
+                    position.average_price,
+                    position.market_value.round_dp(2),
+                    position.unrealized_pnl.round_dp(2),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Self {
+            quote_currency: pnl.quote_currency.clone(),
+            cash: pnl.cash.round_dp(2).to_string(),
+            equity: pnl.equity.round_dp(2).to_string(),
+            realized_pnl: pnl.realized_pnl.round_dp(2).to_string(),
+            unrealized_pnl: pnl.unrealized_pnl.round_dp(2).to_string(),
+            fees: pnl.fees.round_dp(2).to_string(),
+            positions,
+        }
+    }
+}
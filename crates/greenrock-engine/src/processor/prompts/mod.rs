@@ -1 +1,3 @@
-pub mod main_system;
+pub mod context;
+pub mod library;
+pub mod tasks;
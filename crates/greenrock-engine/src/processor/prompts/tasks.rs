@@ -0,0 +1,126 @@
+//! One prompt per graph task. Data sections are passed pre-formatted so that
+//! overrides can place them with plain `{{ field }}` placeholders.
+
+use askama::Template;
+use serde::Serialize;
+
+use crate::processor::prompts::library::prompt_version;
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/intent_classification.v1.md")]
+pub struct IntentClassificationTemplate {
+    /// Recent messages, one `- role: content` line each.
+    pub history: String,
+    pub user_input: String,
+}
+
+prompt_version!(IntentClassificationTemplate, "intent_classification", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/entry_interaction.v1.md")]
+pub struct EntryInteractionTemplate {
+    pub user_input: String,
+    /// Branch picked by the intent router, e.g. `portfolio`.
+    pub intent: String,
+}
+
+prompt_version!(EntryInteractionTemplate, "entry_interaction", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/reply_generation.v1.md")]
+pub struct ReplyGenerationTemplate {
+    pub user_input: String,
+    /// Reports gathered by the branch tasks, empty for a plain reply.
+    pub findings: String,
+}
+
+prompt_version!(ReplyGenerationTemplate, "reply_generation", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/regimen_reporting.v1.md")]
+pub struct RegimenReportingTemplate {
+    pub user_input: String,
+    pub regimens: String,
+}
+
+prompt_version!(RegimenReportingTemplate, "regimen_reporting", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/regimen_evaluation.v1.md")]
+pub struct RegimenEvaluationTemplate {
+    pub regimen: String,
+    pub metrics: String,
+}
+
+prompt_version!(RegimenEvaluationTemplate, "regimen_evaluation", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/regimen_switching.v1.md")]
+pub struct RegimenSwitchingTemplate {
+    pub current_regimen: String,
+    pub candidates: String,
+}
+
+prompt_version!(RegimenSwitchingTemplate, "regimen_switching", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/regimen_aggregation.v1.md")]
+pub struct RegimenAggregationTemplate {
+    pub evaluations: String,
+}
+
+prompt_version!(RegimenAggregationTemplate, "regimen_aggregation", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/regimen_selection.v1.md")]
+pub struct RegimenSelectionTemplate {
+    pub user_input: String,
+    pub candidates: String,
+}
+
+prompt_version!(RegimenSelectionTemplate, "regimen_selection", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/binance_reporting.v1.md")]
+pub struct BinanceReportingTemplate {
+    pub user_input: String,
+    pub account: String,
+}
+
+prompt_version!(BinanceReportingTemplate, "binance_reporting", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/binance_operations.v2.md")]
+pub struct BinanceOperationsTemplate {
+    pub user_input: String,
+    pub account: String,
+    pub quote_currency: String,
+}
+
+prompt_version!(BinanceOperationsTemplate, "binance_operations", 2);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/portfolio_reporting.v1.md")]
+pub struct PortfolioReportingTemplate {
+    pub user_input: String,
+    pub positions: String,
+}
+
+prompt_version!(PortfolioReportingTemplate, "portfolio_reporting", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/portfolio_aggregation.v1.md")]
+pub struct PortfolioAggregationTemplate {
+    pub portfolio: String,
+}
+
+prompt_version!(PortfolioAggregationTemplate, "portfolio_aggregation", 1);
+
+#[derive(Template, Serialize)]
+#[template(path = "prompts/portfolio_selection.v1.md")]
+pub struct PortfolioSelectionTemplate {
+    pub user_input: String,
+    pub candidates: String,
+}
+
+prompt_version!(PortfolioSelectionTemplate, "portfolio_selection", 1);
@@ -0,0 +1,285 @@
+//! Shared configuration and context records of the regimen pipeline:
+//! reporting, evaluation, aggregation, selection and switching.
+
+use std::env;
+
+use graph_flow::Context;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::regime::{MarketRegime, RegimeReport, RegimeThresholds},
+    models::timeseries::Candle,
+    runner::core::LiveRunner,
+};
+
+/// Context key of the `RegimeReport` for the latest window.
+pub const REGIME_KEY: &str = "regime";
+/// Context key of the `Vec<RegimenEvaluation>`, in registration order.
+pub const EVALUATIONS_KEY: &str = "regimen_evaluations";
+/// Context key of the evaluations ranked best first.
+pub const RANKING_KEY: &str = "regimen_ranking";
+/// Context key of the `RegimenProposal`.
+pub const PROPOSAL_KEY: &str = "regimen_proposal";
+
+#[derive(Debug, Clone)]
+pub struct RegimenConfig {
+    /// Pair analysed when the context has no `symbol`.
+    pub symbol: String,
+    pub interval: String,
+    /// Candles of history fetched for classification and backtests.
+    pub history: u16,
+    /// Candles per regime window; the latest window is the current regime.
+    pub window: usize,
+    /// Starting cash of each backtest, in the quote asset.
+    pub initial_cash: Decimal,
+    /// Score lead, in percentage points, the best regimen needs over the
+    /// active one before a switch is proposed.
+    pub min_improvement: f64,
+    /// Execute proposed switches instead of only reporting them.
+    pub auto_switch: bool,
+    pub thresholds: RegimeThresholds,
+}
+
+impl Default for RegimenConfig {
+    fn default() -> Self {
+        Self {
+            symbol: "BTCUSDT".to_string(),
+            interval: "1h".to_string(),
+            history: 1000,
+            window: 100,
+            initial_cash: Decimal::from(10_000),
+            min_improvement: 0.5,
+            auto_switch: false,
+            thresholds: RegimeThresholds::default(),
+        }
+    }
+}
+
+impl RegimenConfig {
+    /// Defaults with `REGIMEN_INTERVAL` and `REGIMEN_AUTO_SWITCH` applied.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+
+        if let Ok(interval) = env::var("REGIMEN_INTERVAL") {
+            config.interval = interval;
+        }
+
+        config.auto_switch = env::var("REGIMEN_AUTO_SWITCH").is_ok_and(|value| value == "true");
+
+        config
+    }
+
+    pub fn symbol(&self, context: &Context) -> String {
+        context
+            .get_sync::<String>("symbol")
+            .unwrap_or_else(|| self.symbol.clone())
+            .to_uppercase()
+    }
+
+    /// The configured history of `symbol`, oldest first.
+    pub async fn candles(&self, runner: &LiveRunner, symbol: &str) -> Vec<Candle> {
+        runner
+            .candles(symbol, &self.interval, self.history, None, None)
+            .await
+    }
+}
+
+/// Backtest results of one regimen over the past windows that were in the
+/// current regime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimenEvaluation {
+    pub name: String,
+    pub regime: MarketRegime,
+    /// Windows backtested.
+    pub windows: usize,
+    pub mean_return_percent: f64,
+    pub mean_drawdown_percent: f64,
+    pub fills: usize,
+    /// Mean return less half the mean drawdown.
+    pub score: f64,
+    pub active: bool,
+}
+
+impl RegimenEvaluation {
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: score {:.2} (return {:.2}%, drawdown {:.2}%, {} fills over {} windows)",
+            self.name,
+            self.score,
+            self.mean_return_percent,
+            self.mean_drawdown_percent,
+            self.fills,
+            self.windows,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimenProposal {
+    pub regime: RegimeReport,
+    pub current: String,
+    pub candidate: String,
+    /// Whether the candidate should replace the active regimen.
+    pub switch: bool,
+    /// Set once the switch has been applied to the runner.
+    pub executed: bool,
+    pub explanation: String,
+}
+
+impl RegimenProposal {
+    /// Proposes the best of `ranking`, ordered best first and not empty, when
+    /// it is not active yet and leads the active regimen by at least
+    /// `min_improvement` points over past windows in the same regime.
+    pub fn select(
+        regime: RegimeReport,
+        ranking: &[RegimenEvaluation],
+        min_improvement: f64,
+    ) -> RegimenProposal {
+        let best = &ranking[0];
+        let current = ranking.iter().find(|evaluation| evaluation.active);
+        let current_name = current.map_or_else(|| best.name.clone(), |c| c.name.clone());
+        let lead = current.map_or(0.0, |current| best.score - current.score);
+        let market = regime.regime.as_str();
+
+        let (switch, explanation) = if best.active {
+            (
+                false,
+                format!(
+                    "{} already scores best over past {} windows ({}), so it stays active.",
+                    best.name,
+                    market,
+                    best.summary()
+                ),
+            )
+        } else if best.windows == 0 {
+            (
+                false,
+                format!(
+                    "No past {} windows in the history to backtest against, so {} stays active.",
+                    market, current_name
+                ),
+            )
+        } else if lead >= min_improvement {
+            (
+                true,
+                format!(
+                    "Over past {} windows {} leads {} by {:.2} points ({}).",
+                    market,
+                    best.name,
+                    current_name,
+                    lead,
+                    best.summary()
+                ),
+            )
+        } else {
+            (
+                false,
+                format!(
+                    "{} leads {} by only {:.2} points over past {} windows, under the {:.2} needed to switch, so {} stays active.",
+                    best.name, current_name, lead, market, min_improvement, current_name
+                ),
+            )
+        };
+
+        RegimenProposal {
+            regime,
+            current: current_name,
+            candidate: best.name.clone(),
+            switch,
+            executed: false,
+            explanation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::analysis::regime::RegimeFeatures;
+
+    use super::*;
+
+    fn regime() -> RegimeReport {
+        RegimeReport {
+            regime: MarketRegime::Trending,
+            features: RegimeFeatures {
+                volatility_percent: 1.0,
+                volatility_ratio: 1.0,
+                adx: 30.0,
+                supertrend_trend: 1,
+                return_percent: 5.0,
+                drawdown_percent: 1.0,
+            },
+            candles: 100,
+            start: Utc::now(),
+            end: Utc::now(),
+        }
+    }
+
+    fn evaluation(name: &str, score: f64, windows: usize, active: bool) -> RegimenEvaluation {
+        RegimenEvaluation {
+            name: name.to_string(),
+            regime: MarketRegime::Trending,
+            windows,
+            mean_return_percent: score,
+            mean_drawdown_percent: 0.0,
+            fills: 4,
+            score,
+            active,
+        }
+    }
+
+    #[test]
+    fn switches_when_the_lead_clears_the_minimum() {
+        let ranking = [
+            evaluation("trend", 2.0, 3, false),
+            evaluation("mean_reversion", 1.0, 3, true),
+        ];
+
+        let proposal = RegimenProposal::select(regime(), &ranking, 0.5);
+
+        assert!(proposal.switch);
+        assert!(!proposal.executed);
+        assert_eq!(proposal.current, "mean_reversion");
+        assert_eq!(proposal.candidate, "trend");
+    }
+
+    #[test]
+    fn keeps_the_active_regimen_under_the_minimum_lead() {
+        let ranking = [
+            evaluation("trend", 1.3, 3, false),
+            evaluation("mean_reversion", 1.0, 3, true),
+        ];
+
+        let proposal = RegimenProposal::select(regime(), &ranking, 0.5);
+
+        assert!(!proposal.switch);
+        assert_eq!(proposal.candidate, "trend");
+    }
+
+    #[test]
+    fn keeps_the_active_regimen_when_it_ranks_best() {
+        let ranking = [
+            evaluation("mean_reversion", 2.0, 3, true),
+            evaluation("trend", 1.0, 3, false),
+        ];
+
+        let proposal = RegimenProposal::select(regime(), &ranking, 0.5);
+
+        assert!(!proposal.switch);
+        assert_eq!(proposal.current, "mean_reversion");
+        assert_eq!(proposal.candidate, "mean_reversion");
+    }
+
+    #[test]
+    fn never_switches_without_past_windows() {
+        let ranking = [
+            evaluation("trend", 0.0, 0, false),
+            evaluation("mean_reversion", -1.0, 0, true),
+        ];
+
+        assert!(!RegimenProposal::select(regime(), &ranking, 0.5).switch);
+    }
+}
@@ -0,0 +1,226 @@
+//! Where chat sessions are kept between requests: in memory for development,
+//! JSON files for a single node, Postgres for production, or nowhere when
+//! the engine runs without chat.
+
+use std::{env, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use graph_flow::{
+    GraphError, InMemorySessionStorage, PostgresSessionStorage, Session, SessionStorage,
+};
+use tracing::info;
+
+pub const DEFAULT_SESSION_DIR: &str = ".greenrock/sessions";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionStore {
+    /// Lost on restart.
+    Memory,
+    /// One JSON file per session under the directory.
+    File(PathBuf),
+    Postgres {
+        database_url: String,
+    },
+    /// Chat is disabled.
+    Disabled,
+}
+
+impl SessionStore {
+    /// `SESSION_STORE`: `memory`, `file` (under `SESSION_DIR`), `postgres`
+    /// (at `DATABASE_URL`) or `none`. Unset means Postgres when
+    /// `DATABASE_URL` is set and memory otherwise.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = env::var("DATABASE_URL").ok();
+
+        let store = match env::var("SESSION_STORE") {
+            Ok(store) => store.to_lowercase(),
+            Err(_) if database_url.is_some() => "postgres".to_string(),
+            Err(_) => "memory".to_string(),
+        };
+
+        match store.as_str() {
+            "memory" => Ok(SessionStore::Memory),
+            "file" => Ok(SessionStore::File(
+                env::var("SESSION_DIR")
+                    .unwrap_or_else(|_| DEFAULT_SESSION_DIR.to_string())
+                    .into(),
+            )),
+            "postgres" => Ok(SessionStore::Postgres {
+                database_url: database_url
+                    .ok_or_else(|| anyhow::anyhow!("SESSION_STORE=postgres needs DATABASE_URL"))?,
+            }),
+            "none" | "off" => Ok(SessionStore::Disabled),
+            other => anyhow::bail!("unknown SESSION_STORE {other}"),
+        }
+    }
+
+    /// The storage of the store, `None` when chat is disabled.
+    pub async fn connect(&self) -> anyhow::Result<Option<Arc<dyn SessionStorage>>> {
+        let storage: Arc<dyn SessionStorage> = match self {
+            SessionStore::Memory => {
+                info!("Keeping chat sessions in memory");
+                Arc::new(InMemorySessionStorage::new())
+            }
+            SessionStore::File(dir) => {
+                info!("Keeping chat sessions in {}", dir.display());
+                Arc::new(FileSessionStorage::open(dir.clone()).await?)
+            }
+            SessionStore::Postgres { database_url } => {
+                info!("Keeping chat sessions in Postgres");
+                Arc::new(
+                    PostgresSessionStorage::connect(database_url)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e}"))?,
+                )
+            }
+            SessionStore::Disabled => {
+                info!("Chat sessions disabled, serving without chat");
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(storage))
+    }
+}
+
+/// Sessions as `<id>.json` files in one directory, for single-node runs.
+pub struct FileSessionStorage {
+    dir: PathBuf,
+}
+
+impl FileSessionStorage {
+    pub async fn open(dir: PathBuf) -> anyhow::Result<Self> {
+        tokio::fs::create_dir_all(&dir).await?;
+        Ok(Self { dir })
+    }
+
+    /// Path of session `id`. Ids come from clients, so anything that could
+    /// leave the directory is refused.
+    fn path(&self, id: &str) -> graph_flow::Result<PathBuf> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if !valid {
+            return Err(GraphError::StorageError(format!("invalid session id {id}")));
+        }
+
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> GraphError {
+    GraphError::StorageError(e.to_string())
+}
+
+#[async_trait]
+impl SessionStorage for FileSessionStorage {
+    async fn save(&self, session: Session) -> graph_flow::Result<()> {
+        let path = self.path(&session.id)?;
+        let json = serde_json::to_vec_pretty(&session).map_err(storage_error)?;
+
+        // Written aside and renamed, so readers never see a partial file.
+        let partial = path.with_extension("json.tmp");
+
+        tokio::fs::write(&partial, json)
+            .await
+            .map_err(storage_error)?;
+        tokio::fs::rename(&partial, &path)
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn get(&self, id: &str) -> graph_flow::Result<Option<Session>> {
+        let path = self.path(id)?;
+
+        match tokio::fs::read(&path).await {
+            Ok(json) => serde_json::from_slice(&json)
+                .map(Some)
+                .map_err(storage_error),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> graph_flow::Result<()> {
+        match tokio::fs::remove_file(self.path(id)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graph_flow::Context;
+
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        env::temp_dir().join(format!("greenrock-sessions-{}", uuid::Uuid::new_v4()))
+    }
+
+    async fn session(id: &str) -> Session {
+        let context = Context::new();
+        context.set("user_input", "hello".to_string()).await;
+
+        Session {
+            id: id.to_string(),
+            graph_id: "".to_string(),
+            current_task_id: "entry".to_string(),
+            status_message: Some("waiting".to_string()),
+            context,
+        }
+    }
+
+    #[tokio::test]
+    async fn saves_reads_and_deletes_sessions() {
+        let dir = temp_dir();
+        let storage = FileSessionStorage::open(dir.clone()).await.unwrap();
+
+        assert!(storage.get("abc-123").await.unwrap().is_none());
+
+        storage.save(session("abc-123").await).await.unwrap();
+
+        let loaded = storage.get("abc-123").await.unwrap().unwrap();
+        assert_eq!(loaded.id, "abc-123");
+        assert_eq!(loaded.current_task_id, "entry");
+        assert_eq!(loaded.status_message.as_deref(), Some("waiting"));
+        assert_eq!(
+            loaded.context.get_sync::<String>("user_input").as_deref(),
+            Some("hello")
+        );
+
+        // Only the renamed file is left behind.
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, ["abc-123.json"]);
+
+        storage.delete("abc-123").await.unwrap();
+        assert!(storage.get("abc-123").await.unwrap().is_none());
+        // Deleting a missing session is not an error.
+        storage.delete("abc-123").await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_ids_that_could_leave_the_directory() {
+        let dir = temp_dir();
+        let storage = FileSessionStorage::open(dir.clone()).await.unwrap();
+
+        for id in ["../x", "", "a/b", "x.json"] {
+            assert!(storage.save(session(id).await).await.is_err(), "{id:?}");
+            assert!(storage.get(id).await.is_err(), "{id:?}");
+            assert!(storage.delete(id).await.is_err(), "{id:?}");
+        }
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+//! Progress events of a chat workflow run, delivered to the client streaming
+//! that session. Sessions nobody streams publish nothing.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use graph_flow::{Context, Task, TaskResult};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// Events of one streamed chat turn, in the order the tasks produce them.
+/// `finished` or `error` always comes last.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatEvent {
+    TaskStarted {
+        task_id: String,
+    },
+    TaskCompleted {
+        task_id: String,
+        elapsed_ms: u128,
+    },
+    TaskFailed {
+        task_id: String,
+        error: String,
+    },
+    /// Chunk of the reply as the model generates it.
+    Token {
+        task_id: String,
+        text: String,
+    },
+    ToolCall {
+        tool: String,
+        args: Value,
+        error: Option<String>,
+    },
+    /// Outcome of the run, the same fields `POST /chat` answers with.
+    Finished {
+        session_id: String,
+        status: String,
+        answer: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl ChatEvent {
+    /// Same as the serialized `type` tag. `POST /chat/stream` sends it as
+    /// the SSE event name too, for clients listening per event.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChatEvent::TaskStarted { .. } => "task_started",
+            ChatEvent::TaskCompleted { .. } => "task_completed",
+            ChatEvent::TaskFailed { .. } => "task_failed",
+            ChatEvent::Token { .. } => "token",
+            ChatEvent::ToolCall { .. } => "tool_call",
+            ChatEvent::Finished { .. } => "finished",
+            ChatEvent::Error { .. } => "error",
+        }
+    }
+}
+
+/// Sender of the events of one streamed session.
+#[derive(Debug, Clone)]
+pub struct ChatSink(mpsc::UnboundedSender<ChatEvent>);
+
+impl ChatSink {
+    /// Sends `event`, dropping it when the client has gone away.
+    pub fn send(&self, event: ChatEvent) {
+        let _ = self.0.send(event);
+    }
+}
+
+/// Streamed sessions by id, shared by the chat handlers and the tasks.
+#[derive(Debug, Clone, Default)]
+pub struct ChatEvents {
+    sinks: Arc<Mutex<HashMap<String, ChatSink>>>,
+}
+
+impl ChatEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts streaming `session_id`, replacing an earlier subscriber.
+    pub fn subscribe(&self, session_id: &str) -> mpsc::UnboundedReceiver<ChatEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        self.sinks
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), ChatSink(sender));
+
+        receiver
+    }
+
+    /// Stops streaming `session_id`; the receiver ends after the events
+    /// already sent.
+    pub fn unsubscribe(&self, session_id: &str) {
+        self.sinks.lock().unwrap().remove(session_id);
+    }
+
+    /// Sink of the session `context` belongs to, if it is streamed.
+    pub fn sink(&self, context: &Context) -> Option<ChatSink> {
+        let session_id: String = context.get_sync("session_id")?;
+        self.sinks.lock().unwrap().get(&session_id).cloned()
+    }
+
+    pub fn send(&self, session_id: &str, event: ChatEvent) {
+        if let Some(sink) = self.sinks.lock().unwrap().get(session_id) {
+            sink.send(event);
+        }
+    }
+
+    pub fn publish(&self, context: &Context, event: ChatEvent) {
+        if let Some(sink) = self.sink(context) {
+            sink.send(event);
+        }
+    }
+}
+
+/// Wraps a graph task to publish when it starts, completes or fails. Keeps
+/// the id of the wrapped task, so edges and `GoTo`s are unchanged.
+pub struct StreamedTask {
+    inner: Arc<dyn Task>,
+    events: ChatEvents,
+}
+
+impl StreamedTask {
+    pub fn new(inner: Arc<dyn Task>, events: ChatEvents) -> Self {
+        Self { inner, events }
+    }
+}
+
+#[async_trait]
+impl Task for StreamedTask {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
+        let task_id = self.id().to_string();
+        let started = Instant::now();
+
+        self.events.publish(
+            &context,
+            ChatEvent::TaskStarted {
+                task_id: task_id.clone(),
+            },
+        );
+
+        let result = self.inner.run(context.clone()).await;
+
+        let event = match &result {
+            Ok(_) => ChatEvent::TaskCompleted {
+                task_id,
+                elapsed_ms: started.elapsed().as_millis(),
+            },
+            Err(e) => ChatEvent::TaskFailed {
+                task_id,
+                error: e.to_string(),
+            },
+        };
+
+        self.events.publish(&context, event);
+
+        result
+    }
+}
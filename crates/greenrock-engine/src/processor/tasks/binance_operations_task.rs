@@ -1,18 +1,170 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use graph_flow::GraphError::TaskExecutionFailed;
 use graph_flow::{Context, NextAction, Task, TaskResult};
-use tracing::info;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        money::{Price, Quantity},
+        orders::OrderRequest,
+    },
+    processor::{
+        approvals::{
+            Decision, PendingApproval, request_approval, resolve_approvals, take_decision,
+        },
+        llm::LlmConfig,
+        operations::{ACCOUNT_KEY, Operation, OperationParser, PENDING_KEY, confirmation},
+        prompts::library::PromptLibrary,
+        tasks::reply_generation_task::push_finding,
+    },
+    runner::core::{ExecutionMode, LiveRunner},
+};
 
-pub struct BinanceOperationsTask;
+/// Parses an operational request into an order plan and waits for the user
+/// to approve it. The session resumes here with a `Decision`, or with the
+/// next chat message read as a confirmation or refusal.
+pub struct BinanceOperationsTask {
+    runner: Arc<LiveRunner>,
+    prompts: PromptLibrary,
+    execution: ExecutionMode,
+    llm: LlmConfig,
+}
 
 impl BinanceOperationsTask {
-    pub fn new() -> Self {
-        Self
+    pub fn new(runner: Arc<LiveRunner>, prompts: PromptLibrary, execution: ExecutionMode) -> Self {
+        Self {
+            runner,
+            prompts,
+            execution,
+            llm: LlmConfig::for_task("operations"),
+        }
     }
-}
 
-impl Default for BinanceOperationsTask {
-    fn default() -> Self {
-        Self::new()
+    pub fn with_llm(mut self, llm: LlmConfig) -> Self {
+        self.llm = llm;
+        self
+    }
+
+    async fn propose(&self, context: &Context, user_input: &str) -> TaskResult {
+        let account: String = context.get_sync(ACCOUNT_KEY).unwrap_or_default();
+
+        let runner = self.runner.clone();
+        let quote = tokio::task::spawn_blocking(move || runner.pnl().quote_currency)
+            .await
+            .unwrap_or_else(|_| "USDT".to_string());
+
+        let operations = OperationParser::new(self.prompts.clone(), &quote)
+            .with_llm(self.llm.clone())
+            .parse(user_input, &account)
+            .await;
+
+        if operations.is_empty() {
+            push_finding(
+                context,
+                "The request could not be turned into an exchange operation. Name the asset, side and quantity, e.g. \"buy 0.01 BTC at market\" or \"cancel my BTC orders\".".to_string(),
+            )
+            .await;
+
+            return TaskResult::new(
+                Some("No operation to confirm".to_string()),
+                NextAction::ContinueAndExecute,
+            );
+        }
+
+        let plan = operations
+            .iter()
+            .enumerate()
+            .map(|(i, operation)| format!("{}. {}", i + 1, operation.describe()))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mode = match self.execution {
+            ExecutionMode::Paper => "paper",
+            ExecutionMode::Live => "live",
+        };
+
+        info!("Awaiting confirmation of {} operations", operations.len());
+
+        request_approval(
+            context,
+            PendingApproval::new(
+                self.id(),
+                format!("{} exchange operations in {mode} mode", operations.len()),
+                operations.iter().map(Operation::describe).collect(),
+            ),
+        )
+        .await;
+        context.set(PENDING_KEY, operations).await;
+
+        TaskResult::new(
+            Some(format!(
+                "I will run these operations in {mode} mode:\n{plan}\n\nReply \"confirm\" to execute them or \"cancel\" to drop them."
+            )),
+            NextAction::WaitForInput,
+        )
+    }
+
+    /// Executes one operation, returning a line for the reply.
+    fn execute(runner: &LiveRunner, execution: ExecutionMode, operation: &Operation) -> String {
+        match operation {
+            // Paper trading never rests orders on the venue, so the only
+            // orders there are not the runner's to cancel.
+            Operation::Cancel { .. } if execution == ExecutionMode::Paper => format!(
+                "Skipped {}: cancelling venue orders needs live trading.",
+                operation.describe()
+            ),
+            Operation::Cancel { symbol } => match runner.cancel_orders(symbol) {
+                Ok(count) => format!("Cancelled {count} open orders on {symbol}."),
+                Err(e) => format!("Failed to cancel orders on {symbol}: {e}"),
+            },
+            Operation::Order {
+                symbol,
+                side,
+                quantity,
+                price,
+            } => {
+                if runner.kill_switch().is_engaged() {
+                    return format!(
+                        "Skipped {}: the kill switch is engaged.",
+                        operation.describe()
+                    );
+                }
+
+                // The risk check measures every limit against this price.
+                let current_price = runner.market_current_price(symbol);
+                if current_price.is_nan() || current_price <= 0.0 {
+                    return format!(
+                        "Skipped {}: no current price for {symbol}.",
+                        operation.describe()
+                    );
+                }
+
+                let quantity = Quantity::new(*quantity);
+                let order = match price {
+                    Some(price) if execution == ExecutionMode::Paper => {
+                        return format!(
+                            "Skipped {}: limit orders at {price} need live trading.",
+                            operation.describe()
+                        );
+                    }
+                    Some(price) => OrderRequest::limit(symbol, *side, quantity, Price::new(*price)),
+                    None => OrderRequest::market(symbol, *side, quantity),
+                };
+
+                let reference_price = Price::from_f64(current_price);
+                let action_id = format!("chat-{}", Uuid::new_v4());
+
+                match runner.submit_order(&action_id, order, reference_price, execution) {
+                    Ok(order_id) => {
+                        format!("Executed {} (order {order_id}).", operation.describe())
+                    }
+                    Err(reason) => format!("Rejected {}: {reason}", operation.describe()),
+                }
+            }
+        }
     }
 }
 
@@ -22,15 +174,67 @@ impl Task for BinanceOperationsTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting Binance operations task");
 
-        // TODO: Implement Binance operations logic
-        // This task should handle Binance exchange operations and reporting
+        let user_input: String = context.get_sync("user_input").unwrap_or_default();
+        let pending: Vec<Operation> = context.get_sync(PENDING_KEY).unwrap_or_default();
+
+        if pending.is_empty() {
+            return Ok(self.propose(&context, &user_input).await);
+        }
+
+        let decision = take_decision(&context)
+            .await
+            .map(|decision| decision == Decision::Approve)
+            .or_else(|| confirmation(&user_input));
+
+        match decision {
+            Some(true) => {
+                let runner = self.runner.clone();
+                let execution = self.execution;
+                let operations = pending.clone();
+
+                let results = tokio::task::spawn_blocking(move || {
+                    operations
+                        .iter()
+                        .map(|operation| Self::execute(&runner, execution, operation))
+                        .collect::<Vec<_>>()
+                })
+                .await
+                .map_err(|e| TaskExecutionFailed(format!("Operations failed: {e}")))?;
+
+                info!("Executed {} operations", results.len());
+
+                push_finding(&context, results.join("\n")).await;
+            }
+            Some(false) => {
+                info!("Operations declined");
+                push_finding(
+                    &context,
+                    "The pending operations were dropped; nothing was executed.".to_string(),
+                )
+                .await;
+            }
+            None => {
+                warn!("Unclear confirmation: {}", user_input);
+
+                return Ok(TaskResult::new(
+                    Some(format!(
+                        "{} operations are waiting. Reply \"confirm\" to execute them or \"cancel\" to drop them.",
+                        pending.len()
+                    )),
+                    NextAction::WaitForInput,
+                ));
+            }
+        }
+
+        context.set(PENDING_KEY, Vec::<Operation>::new()).await;
+        resolve_approvals(&context, self.id()).await;
 
         Ok(TaskResult::new(
             Some("Binance operations completed".to_string()),
-            NextAction::End,
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -1,19 +1,109 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use graph_flow::GraphError::TaskExecutionFailed;
 use graph_flow::{Context, NextAction, Task, TaskResult};
+use rust_decimal::Decimal;
 use tracing::info;
 
-pub struct BinanceReportingTask;
+use crate::{
+    processor::{
+        operations::{ACCOUNT_KEY, is_operation_request},
+        tasks::{
+            binance_operations_task::BinanceOperationsTask, reply_generation_task::push_finding,
+        },
+    },
+    runner::core::LiveRunner,
+};
+
+/// Open orders and fills are listed for this pair when the context has no
+/// `symbol`, in addition to every held asset.
+const DEFAULT_SYMBOL: &str = "BTCUSDT";
+
+/// Recent fills listed per symbol.
+const RECENT_FILLS: usize = 5;
+
+/// Summarizes balances, open orders and recent fills of the exchange account.
+/// Requests for an operation continue to `BinanceOperationsTask`.
+pub struct BinanceReportingTask {
+    runner: Arc<LiveRunner>,
+}
 
 impl BinanceReportingTask {
-    pub fn new() -> Self {
-        Self
+    pub fn new(runner: Arc<LiveRunner>) -> Self {
+        Self { runner }
     }
 }
 
-impl Default for BinanceReportingTask {
-    fn default() -> Self {
-        Self::new()
+/// Plain-text account summary, one section per line group.
+fn account_summary(runner: &LiveRunner, symbol: &str) -> String {
+    let balances = runner.balances();
+    let quote = runner.pnl().quote_currency;
+
+    let mut lines = vec!["Balances (free / locked):".to_string()];
+    let mut symbols = vec![symbol.to_uppercase()];
+
+    for balance in balances
+        .iter()
+        .filter(|balance| balance.total() > Decimal::ZERO)
+    {
+        lines.push(format!(
+            "- {}: {} / {}",
+            balance.asset,
+            balance.free.normalize(),
+            balance.locked.normalize()
+        ));
+
+        let pair = format!("{}{quote}", balance.asset);
+        if balance.asset != quote && !symbols.contains(&pair) {
+            symbols.push(pair);
+        }
     }
+
+    if lines.len() == 1 {
+        lines.push("- none".to_string());
+    }
+
+    for symbol in &symbols {
+        let orders = runner.open_orders(symbol);
+
+        if orders.is_empty() {
+            lines.push(format!("Open orders on {symbol}: none"));
+        } else {
+            lines.push(format!("Open orders on {symbol}:"));
+            lines.extend(orders.iter().map(|order| {
+                format!(
+                    "- {} {:?} {} @ {} (filled {}, {})",
+                    order.order_type,
+                    order.side,
+                    order.quantity,
+                    order.price,
+                    order.executed_qty,
+                    order.status
+                )
+            }));
+        }
+
+        let mut fills = runner.trade_history(symbol);
+        fills.sort_by_key(|fill| fill.time);
+
+        if !fills.is_empty() {
+            lines.push(format!("Recent fills on {symbol}:"));
+            lines.extend(fills.iter().rev().take(RECENT_FILLS).map(|fill| {
+                format!(
+                    "- {} {:?} {} @ {} (fee {} {})",
+                    fill.time.format("%Y-%m-%d %H:%M"),
+                    fill.side,
+                    fill.qty,
+                    fill.price,
+                    fill.fee,
+                    fill.fee_asset
+                )
+            }));
+        }
+    }
+
+    lines.join("\n")
 }
 
 #[async_trait]
@@ -22,15 +112,34 @@ impl Task for BinanceReportingTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
-        info!("Starting Binance operations task");
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
+        info!("Starting Binance reporting task");
+
+        let symbol = context
+            .get_sync::<String>("symbol")
+            .unwrap_or_else(|| DEFAULT_SYMBOL.to_string());
+
+        let runner = self.runner.clone();
+        let summary = tokio::task::spawn_blocking(move || account_summary(&runner, &symbol))
+            .await
+            .map_err(|e| TaskExecutionFailed(format!("Failed to load account: {e}")))?;
+
+        context.set(ACCOUNT_KEY, summary.clone()).await;
+
+        let user_input: String = context.get_sync("user_input").unwrap_or_default();
+
+        if is_operation_request(&user_input) {
+            return Ok(TaskResult::new(
+                Some("Binance reporting completed".to_string()),
+                NextAction::GoTo(std::any::type_name::<BinanceOperationsTask>().to_string()),
+            ));
+        }
 
-        // TODO: Implement Binance operations logic
-        // This task should handle Binance exchange operations and reporting
+        push_finding(&context, format!("Exchange account:\n{summary}")).await;
 
         Ok(TaskResult::new(
-            Some("Binance operations completed".to_string()),
-            NextAction::End,
+            Some("Binance reporting completed".to_string()),
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -1,43 +1,93 @@
-use askama::Template;
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use graph_flow::GraphError::TaskExecutionFailed;
 use graph_flow::{Context, MessageRole, NextAction, Task, TaskResult};
-use rig::client::CompletionClient;
-use rig::completion::Chat;
 
 use rig::message::Message;
 use tracing::info;
 
-use anyhow::Result;
 // use rig::prelude::*;
 
-use crate::processor::prompts::main_system::EntryInteractionUserInputTemplate;
+use crate::processor::{
+    intent::IntentRouter,
+    llm::LlmConfig,
+    prompts::{
+        context::{RiskDisclaimerTemplate, SystemPersonaTemplate},
+        library::PromptLibrary,
+        tasks::EntryInteractionTemplate,
+    },
+    streaming::{ChatEvent, ChatEvents},
+    tasks::reply_generation_task::STREAMED_KEY,
+    tools::{ToolCallLog, ToolCallRecord, TradingTools},
+};
+use crate::runner::core::LiveRunner;
 
 pub const MAX_RETRIES: u32 = 3;
 
-pub fn get_llm_agent(
-    system_prompt: String,
-) -> Result<rig::agent::Agent<rig::providers::openrouter::CompletionModel>> {
-    let api_key = std::env::var("OPENROUTER_API_KEY")
-        .map_err(|_| anyhow::anyhow!("OPENROUTER_API_KEY not set"))?;
-
-    let client = rig::providers::openrouter::Client::new(&api_key);
-
-    let agent = client
-        .agent("google/gemini-2.0-flash-001")
-        .preamble(&system_prompt)
-        .build();
-
-    Ok(agent)
-}
+/// Tool-calling rounds the agent may take before it has to answer.
+pub const MAX_TOOL_TURNS: usize = 5;
 
+/// Routes the user query and drafts a first answer. The system prompt is
+/// rendered per run from the `market_context` and `portfolio_context` keys
+/// the chat handler puts in the context.
+///
+/// With a runner attached the agent can call the trading tools, and their
+/// calls are appended to `tool_calls` in the context.
 pub struct EntryInteractionTask {
-    system_prompt: String,
+    prompts: PromptLibrary,
+    router: IntentRouter,
+    runner: Option<Arc<LiveRunner>>,
+    events: ChatEvents,
+    llm: LlmConfig,
 }
 
 impl EntryInteractionTask {
-    pub fn new(system_prompt: String) -> Self {
-        Self { system_prompt }
+    pub fn new(prompts: PromptLibrary) -> Self {
+        Self {
+            prompts,
+            router: IntentRouter::from_env(),
+            runner: None,
+            events: ChatEvents::default(),
+            llm: LlmConfig::for_task("entry"),
+        }
+    }
+
+    pub fn with_tools(mut self, runner: Arc<LiveRunner>) -> Self {
+        self.runner = Some(runner);
+        self
+    }
+
+    pub fn with_llm(mut self, llm: LlmConfig) -> Self {
+        self.llm = llm;
+        self
+    }
+
+    /// Sends the answer tokens and tool calls of streamed sessions to
+    /// `events`.
+    pub fn with_events(mut self, events: ChatEvents) -> Self {
+        self.events = events;
+        self
+    }
+
+    async fn system_prompt(&self, context: &Context) -> anyhow::Result<String> {
+        let risk_disclaimer = self.prompts.render(&RiskDisclaimerTemplate).await?.text;
+
+        let persona = self
+            .prompts
+            .render(&SystemPersonaTemplate {
+                market_context: context.get_sync("market_context").unwrap_or_default(),
+                portfolio_context: context.get_sync("portfolio_context").unwrap_or_default(),
+                risk_disclaimer,
+            })
+            .await?;
+
+        Ok(persona.text)
+    }
+
+    pub fn with_router(mut self, router: IntentRouter) -> Self {
+        self.router = router;
+        self
     }
 }
 
@@ -66,35 +116,90 @@ impl Task for EntryInteractionTask {
 
         let messages = context.get_all_messages().await;
 
-        let agent = get_llm_agent(self.system_prompt.clone())
+        let routed = self.router.route(&user_input, &messages).await;
+
+        info!(
+            "Routed to {:?} ({:?}, confidence {:.2})",
+            routed.intent, routed.source, routed.confidence
+        );
+
+        context
+            .set("next_task", routed.intent.task_id().to_string())
+            .await;
+        context.set("intent", routed.clone()).await;
+
+        let system_prompt = self
+            .system_prompt(&context)
+            .await
+            .map_err(|e| TaskExecutionFailed(format!("Failed to render system prompt: {e}")))?;
+
+        let mut builder = self
+            .llm
+            .builder(&system_prompt)
             .map_err(|e| TaskExecutionFailed(format!("Failed to initialize LLM agent: {e}")))?;
 
-        let context_json = serde_json::to_string(&context).unwrap();
+        let tool_calls = ToolCallLog::streamed(self.events.sink(&context));
 
-        let prompt = EntryInteractionUserInputTemplate {
-            user_input,
-            history: messages.clone(),
-            context: context_json,
+        if let Some(runner) = &self.runner {
+            builder = builder.with_tools(&TradingTools::new(runner.clone(), tool_calls.clone()));
         }
-        .render()
-        .unwrap();
+
+        let agent = builder.build();
+
+        let prompt = self
+            .prompts
+            .render(&EntryInteractionTemplate {
+                user_input,
+                intent: routed.intent.as_str().to_string(),
+            })
+            .await
+            .map_err(|e| TaskExecutionFailed(format!("Failed to render prompt: {e}")))?
+            .text;
 
         // let c = context.get_rig_messages().await;
 
-        let answer = agent
-            .chat(
-                prompt.clone(),
-                messages
-                    .iter()
-                    .map(|m| match m.role {
-                        MessageRole::User => Message::user(m.content.clone()),
-                        MessageRole::Assistant => Message::assistant(m.content.clone()),
-                        MessageRole::System => Message::assistant(m.content.clone()),
+        let mut history: Vec<Message> = messages
+            .iter()
+            .map(|m| match m.role {
+                MessageRole::User => Message::user(m.content.clone()),
+                MessageRole::Assistant => Message::assistant(m.content.clone()),
+                MessageRole::System => Message::assistant(m.content.clone()),
+            })
+            .collect();
+
+        // Streamed sessions see the answer as it is written; the reply task
+        // then only has to send what it adds.
+        let sink = self.events.sink(&context);
+
+        let answer = match &sink {
+            Some(sink) => {
+                agent
+                    .stream(prompt.clone(), &mut history, MAX_TOOL_TURNS, |text| {
+                        sink.send(ChatEvent::Token {
+                            task_id: self.id().to_string(),
+                            text: text.to_string(),
+                        })
                     })
-                    .collect(),
-            )
-            .await
-            .map_err(|e| TaskExecutionFailed(format!("LLM prompt failed: {e}")))?;
+                    .await
+            }
+            None => {
+                agent
+                    .chat(prompt.clone(), &mut history, MAX_TOOL_TURNS)
+                    .await
+            }
+        };
+
+        // Calls made before a failure are kept for inspection.
+        let new_calls = tool_calls.take();
+
+        if !new_calls.is_empty() {
+            let mut recorded: Vec<ToolCallRecord> =
+                context.get_sync("tool_calls").unwrap_or_default();
+            recorded.extend(new_calls);
+            context.set("tool_calls", recorded).await;
+        }
+
+        let answer = answer.map_err(|e| TaskExecutionFailed(format!("LLM prompt failed: {e}")))?;
 
         info!("Answer generated: {}", answer);
 
@@ -105,7 +210,11 @@ impl Task for EntryInteractionTask {
             .await;
 
         context.set("answer", answer.clone()).await;
+        context.set(STREAMED_KEY, sink.is_some()).await;
 
-        Ok(TaskResult::new(Some(answer), NextAction::Continue))
+        Ok(TaskResult::new(
+            Some(answer),
+            NextAction::ContinueAndExecute,
+        ))
     }
 }
@@ -2,6 +2,15 @@ use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
 use tracing::info;
 
+use crate::{
+    portfolio::allocation::{HoldingsSnapshot, aggregate},
+    processor::{
+        portfolio::{ALLOCATION_KEY, HOLDINGS_KEY},
+        tasks::reply_generation_task::{ReplyGenerationTask, push_finding},
+    },
+};
+
+/// Aggregates the holdings into an allocation report with PnL and risk.
 pub struct PortfolioAggregationTask;
 
 impl PortfolioAggregationTask {
@@ -22,15 +31,27 @@ impl Task for PortfolioAggregationTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting portfolio aggregation task");
 
-        // TODO: Implement portfolio aggregation logic
-        // This task should aggregate portfolio data from multiple sources
+        let Some(snapshot) = context.get_sync::<HoldingsSnapshot>(HOLDINGS_KEY) else {
+            return Ok(TaskResult::new(
+                Some("No holdings to aggregate".to_string()),
+                NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+            ));
+        };
+
+        let report = aggregate(&snapshot);
+        let summary = report.summary();
+
+        info!("{}", summary);
+
+        push_finding(&context, format!("Current allocation:\n{summary}")).await;
+        context.set(ALLOCATION_KEY, report).await;
 
         Ok(TaskResult::new(
-            Some("Portfolio aggregation completed".to_string()),
-            NextAction::End,
+            Some(summary),
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -1,18 +1,26 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
-use tracing::info;
+use tracing::{error, info};
 
-pub struct PortfolioReportingTask;
+use crate::{
+    processor::{
+        portfolio::{HOLDINGS_KEY, PortfolioConfig},
+        tasks::reply_generation_task::{ReplyGenerationTask, push_finding},
+    },
+    runner::core::LiveRunner,
+};
 
-impl PortfolioReportingTask {
-    pub fn new() -> Self {
-        Self
-    }
+/// Pulls balances, prices and price history of the portfolio from the broker.
+pub struct PortfolioReportingTask {
+    runner: Arc<LiveRunner>,
+    config: PortfolioConfig,
 }
 
-impl Default for PortfolioReportingTask {
-    fn default() -> Self {
-        Self::new()
+impl PortfolioReportingTask {
+    pub fn new(runner: Arc<LiveRunner>, config: PortfolioConfig) -> Self {
+        Self { runner, config }
     }
 }
 
@@ -22,15 +30,34 @@ impl Task for PortfolioReportingTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting portfolio reporting task");
 
-        // TODO: Implement portfolio reporting logic
-        // This task should generate reports on portfolio performance and status
+        let snapshot = match self.config.snapshot(&self.runner).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                error!("Failed to load portfolio: {}", e);
+                push_finding(&context, format!("The portfolio could not be loaded: {e}")).await;
+
+                return Ok(TaskResult::new(
+                    Some("Portfolio reporting failed".to_string()),
+                    NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+                ));
+            }
+        };
+
+        info!(
+            "Loaded {} assets and {} {} cash",
+            snapshot.assets.len(),
+            snapshot.cash,
+            snapshot.quote_currency
+        );
+
+        context.set(HOLDINGS_KEY, snapshot).await;
 
         Ok(TaskResult::new(
             Some("Portfolio reporting completed".to_string()),
-            NextAction::Continue,
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -1,19 +1,68 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
 use tracing::info;
 
-pub struct PortfolioSelectionTask;
+use crate::{
+    models::money::{Price, Quantity},
+    portfolio::{
+        allocation::{AllocationMethod, AllocationReport, HoldingsSnapshot, select_allocation},
+        rebalancer::{RebalanceConfig, Rebalancer},
+    },
+    processor::{
+        portfolio::{ALLOCATION_KEY, CANDIDATES_KEY, HOLDINGS_KEY, PortfolioConfig, TARGET_KEY},
+        tasks::reply_generation_task::{ReplyGenerationTask, push_finding},
+    },
+};
+
+/// Computes a target allocation with every method, selects the configured
+/// one and lists the orders that would reach it. Nothing is placed.
+pub struct PortfolioSelectionTask {
+    config: PortfolioConfig,
+}
 
 impl PortfolioSelectionTask {
-    pub fn new() -> Self {
-        Self
+    pub fn new(config: PortfolioConfig) -> Self {
+        Self { config }
     }
 }
 
-impl Default for PortfolioSelectionTask {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Orders the rebalancer would send to move `snapshot` to `targets`.
+fn proposed_orders(snapshot: &HoldingsSnapshot, targets: &HashMap<String, f64>) -> Vec<String> {
+    let rebalancer = Rebalancer::new(RebalanceConfig {
+        quote_currency: snapshot.quote_currency.clone(),
+        ..Default::default()
+    });
+
+    let mut balances: HashMap<String, Quantity> = snapshot
+        .assets
+        .iter()
+        .map(|asset| (asset.asset.clone(), Quantity(asset.quantity)))
+        .collect();
+    balances.insert(snapshot.quote_currency.clone(), Quantity(snapshot.cash));
+
+    let prices = snapshot
+        .assets
+        .iter()
+        .map(|asset| (asset.symbol.clone(), Price(asset.price)))
+        .collect();
+
+    rebalancer
+        .plan_with(targets, &balances, &prices, &HashMap::new())
+        .legs
+        .iter()
+        .map(|leg| {
+            format!(
+                "- {:?} {} {} (~{} {})",
+                leg.side,
+                leg.quantity,
+                leg.symbol,
+                leg.notional.round_dp(2),
+                snapshot.quote_currency
+            )
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -22,15 +71,76 @@ impl Task for PortfolioSelectionTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting portfolio selection task");
 
-        // TODO: Implement portfolio selection logic
-        // This task should select optimal portfolio configurations
+        let (Some(snapshot), Some(report)) = (
+            context.get_sync::<HoldingsSnapshot>(HOLDINGS_KEY),
+            context.get_sync::<AllocationReport>(ALLOCATION_KEY),
+        ) else {
+            return Ok(TaskResult::new(
+                Some("No allocation to select from".to_string()),
+                NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+            ));
+        };
+
+        if snapshot.assets.is_empty() {
+            push_finding(
+                &context,
+                "There are no priced assets to allocate between.".to_string(),
+            )
+            .await;
+
+            return Ok(TaskResult::new(
+                Some("No assets to allocate".to_string()),
+                NextAction::ContinueAndExecute,
+            ));
+        }
+
+        let candidates: Vec<_> = AllocationMethod::ALL
+            .into_iter()
+            .map(|method| select_allocation(method, &snapshot, &report))
+            .collect();
+
+        let target = candidates
+            .iter()
+            .find(|candidate| candidate.method == self.config.method)
+            .cloned()
+            .unwrap_or_else(|| candidates[0].clone());
+
+        let orders = proposed_orders(&snapshot, &target.targets());
+
+        let mut finding = format!(
+            "Target allocation ({} on {} candles):\n{}\nOther methods:\n{}",
+            self.config.method,
+            self.config.interval,
+            target.summary(),
+            candidates
+                .iter()
+                .filter(|candidate| candidate.method != target.method)
+                .map(|candidate| format!("- {}", candidate.summary()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        if orders.is_empty() {
+            finding.push_str("\nThe portfolio is already within the drift threshold.");
+        } else {
+            finding.push_str(&format!(
+                "\nOrders to reach it, not placed:\n{}",
+                orders.join("\n")
+            ));
+        }
+
+        info!("{}", finding);
+
+        push_finding(&context, finding).await;
+        context.set(CANDIDATES_KEY, candidates).await;
+        context.set(TARGET_KEY, target).await;
 
         Ok(TaskResult::new(
             Some("Portfolio selection completed".to_string()),
-            NextAction::End,
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -2,6 +2,9 @@ use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
 use tracing::info;
 
+use crate::processor::regimen::{EVALUATIONS_KEY, RANKING_KEY, RegimenEvaluation};
+
+/// Ranks the regimen evaluations by score, best first.
 pub struct RegimenAggregationTask;
 
 impl RegimenAggregationTask {
@@ -22,15 +25,23 @@ impl Task for RegimenAggregationTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting regimen aggregation task");
 
-        // TODO: Implement regimen aggregation logic
-        // This task should aggregate data from multiple trading regimens
+        let mut ranking: Vec<RegimenEvaluation> =
+            context.get_sync(EVALUATIONS_KEY).unwrap_or_default();
+
+        ranking.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        for (rank, evaluation) in ranking.iter().enumerate() {
+            info!("#{} {}", rank + 1, evaluation.summary());
+        }
+
+        context.set(RANKING_KEY, ranking).await;
 
         Ok(TaskResult::new(
             Some("Regimen aggregation completed".to_string()),
-            NextAction::End,
+            NextAction::ContinueAndExecute,
         ))
     }
 }
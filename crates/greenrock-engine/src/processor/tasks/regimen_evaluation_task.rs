@@ -1,19 +1,92 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use graph_flow::GraphError::TaskExecutionFailed;
 use graph_flow::{Context, NextAction, Task, TaskResult};
+use rust_decimal::prelude::ToPrimitive;
 use tracing::info;
 
-pub struct RegimenEvaluationTask;
+use crate::{
+    analysis::regime::{MarketRegime, RegimeReport, regime_segments},
+    models::timeseries::Candle,
+    processor::{
+        regimen::{EVALUATIONS_KEY, REGIME_KEY, RegimenConfig, RegimenEvaluation},
+        tasks::reply_generation_task::ReplyGenerationTask,
+    },
+    runner::{
+        backtest::{BacktestConfig, simulate},
+        core::LiveRunner,
+    },
+};
+
+/// Backtests every registered regimen over the past windows that were in
+/// the current regime.
+pub struct RegimenEvaluationTask {
+    runner: Arc<LiveRunner>,
+    config: RegimenConfig,
+}
 
 impl RegimenEvaluationTask {
-    pub fn new() -> Self {
-        Self
+    pub fn new(runner: Arc<LiveRunner>, config: RegimenConfig) -> Self {
+        Self { runner, config }
     }
 }
 
-impl Default for RegimenEvaluationTask {
-    fn default() -> Self {
-        Self::new()
-    }
+fn evaluate(
+    runner: &LiveRunner,
+    config: &RegimenConfig,
+    symbol: &str,
+    candles: &[Candle],
+    regime: MarketRegime,
+) -> Vec<RegimenEvaluation> {
+    let windows: Vec<_> = regime_segments(candles, config.window, &config.thresholds)
+        .into_iter()
+        .filter(|(report, _)| report.regime == regime)
+        .map(|(_, range)| range)
+        .collect();
+
+    let backtest = BacktestConfig {
+        symbol: symbol.to_string(),
+        interval: config.interval.clone(),
+        limit: config.window as u16,
+        initial_cash: config.initial_cash,
+    };
+
+    let active = runner.strategy().active_name();
+
+    runner
+        .strategy()
+        .regimens()
+        .map(|(name, regimen)| {
+            let reports: Vec<_> = windows
+                .iter()
+                .map(|range| simulate(regimen.as_ref(), candles[range.clone()].to_vec(), &backtest))
+                .collect();
+
+            let count = reports.len().max(1) as f64;
+            let mean_return_percent = reports
+                .iter()
+                .map(|report| report.return_percent.to_f64().unwrap_or(0.0))
+                .sum::<f64>()
+                / count;
+            let mean_drawdown_percent = reports
+                .iter()
+                .map(|report| report.max_drawdown_percent.to_f64().unwrap_or(0.0))
+                .sum::<f64>()
+                / count;
+
+            RegimenEvaluation {
+                name: name.to_string(),
+                regime,
+                windows: reports.len(),
+                mean_return_percent,
+                mean_drawdown_percent,
+                fills: reports.iter().map(|report| report.fills).sum(),
+                score: mean_return_percent - mean_drawdown_percent / 2.0,
+                active: name == active,
+            }
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -22,15 +95,35 @@ impl Task for RegimenEvaluationTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting regimen evaluation task");
 
-        // TODO: Implement regimen evaluation logic
-        // This task should evaluate the performance and effectiveness of trading regimens
+        let Some(regime) = context.get_sync::<RegimeReport>(REGIME_KEY) else {
+            return Ok(TaskResult::new(
+                Some("No market regime to evaluate".to_string()),
+                NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+            ));
+        };
+
+        let symbol = self.config.symbol(&context);
+        let candles = self.config.candles(&self.runner, &symbol).await;
+
+        let runner = self.runner.clone();
+        let config = self.config.clone();
+
+        let evaluations = tokio::task::spawn_blocking(move || {
+            evaluate(&runner, &config, &symbol, &candles, regime.regime)
+        })
+        .await
+        .map_err(|e| TaskExecutionFailed(format!("Regimen evaluation failed: {e}")))?;
+
+        info!("Evaluated {} regimens", evaluations.len());
+
+        context.set(EVALUATIONS_KEY, evaluations).await;
 
         Ok(TaskResult::new(
             Some("Regimen evaluation completed".to_string()),
-            NextAction::End,
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -1,18 +1,27 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
 use tracing::info;
 
-pub struct RegimenReportingTask;
+use crate::{
+    analysis::regime::classify_regime,
+    processor::{
+        regimen::{REGIME_KEY, RegimenConfig},
+        tasks::reply_generation_task::{ReplyGenerationTask, push_finding},
+    },
+    runner::core::LiveRunner,
+};
 
-impl RegimenReportingTask {
-    pub fn new() -> Self {
-        Self
-    }
+/// Classifies the current market regime from the latest window of candles.
+pub struct RegimenReportingTask {
+    runner: Arc<LiveRunner>,
+    config: RegimenConfig,
 }
 
-impl Default for RegimenReportingTask {
-    fn default() -> Self {
-        Self::new()
+impl RegimenReportingTask {
+    pub fn new(runner: Arc<LiveRunner>, config: RegimenConfig) -> Self {
+        Self { runner, config }
     }
 }
 
@@ -22,15 +31,41 @@ impl Task for RegimenReportingTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting regimen reporting task");
 
-        // TODO: Implement regimen reporting logic
-        // This task should handle reporting on current trading regimens
+        let symbol = self.config.symbol(&context);
+        let candles = self.config.candles(&self.runner, &symbol).await;
+        let window = &candles[candles.len().saturating_sub(self.config.window)..];
+
+        let Some(report) = classify_regime(window, &self.config.thresholds) else {
+            let finding = format!(
+                "Not enough {} {} candles to classify the market regime.",
+                symbol, self.config.interval
+            );
+            push_finding(&context, finding.clone()).await;
+
+            return Ok(TaskResult::new(
+                Some(finding),
+                NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+            ));
+        };
+
+        let summary = format!(
+            "{} {} is in a {}.",
+            symbol,
+            self.config.interval,
+            report.summary()
+        );
+
+        info!("{}", summary);
+
+        push_finding(&context, summary.clone()).await;
+        context.set(REGIME_KEY, report).await;
 
         Ok(TaskResult::new(
-            Some("Regimen reporting completed".to_string()),
-            NextAction::End,
+            Some(summary),
+            NextAction::ContinueAndExecute,
         ))
     }
 }
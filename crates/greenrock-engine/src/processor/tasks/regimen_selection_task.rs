@@ -2,17 +2,26 @@ use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
 use tracing::info;
 
-pub struct RegimenSelectionTask;
+use crate::{
+    analysis::regime::RegimeReport,
+    processor::{
+        regimen::{
+            PROPOSAL_KEY, RANKING_KEY, REGIME_KEY, RegimenConfig, RegimenEvaluation,
+            RegimenProposal,
+        },
+        tasks::reply_generation_task::ReplyGenerationTask,
+    },
+};
 
-impl RegimenSelectionTask {
-    pub fn new() -> Self {
-        Self
-    }
+/// Proposes the best-ranked regimen when it beats the active one by at least
+/// `min_improvement` points.
+pub struct RegimenSelectionTask {
+    config: RegimenConfig,
 }
 
-impl Default for RegimenSelectionTask {
-    fn default() -> Self {
-        Self::new()
+impl RegimenSelectionTask {
+    pub fn new(config: RegimenConfig) -> Self {
+        Self { config }
     }
 }
 
@@ -22,15 +31,30 @@ impl Task for RegimenSelectionTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting regimen selection task");
 
-        // TODO: Implement regimen selection logic
-        // This task should select optimal trading regimens based on market conditions
+        let ranking: Vec<RegimenEvaluation> = context.get_sync(RANKING_KEY).unwrap_or_default();
+
+        let Some(regime) = context
+            .get_sync::<RegimeReport>(REGIME_KEY)
+            .filter(|_| !ranking.is_empty())
+        else {
+            return Ok(TaskResult::new(
+                Some("No regimen ranking to select from".to_string()),
+                NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+            ));
+        };
+
+        let proposal = RegimenProposal::select(regime, &ranking, self.config.min_improvement);
+
+        info!("{}", proposal.explanation);
+
+        context.set(PROPOSAL_KEY, proposal).await;
 
         Ok(TaskResult::new(
             Some("Regimen selection completed".to_string()),
-            NextAction::End,
+            NextAction::ContinueAndExecute,
         ))
     }
 }
@@ -1,18 +1,27 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use graph_flow::{Context, NextAction, Task, TaskResult};
-use tracing::info;
+use tracing::{error, info};
 
-pub struct RegimenSwitchingTask;
+use crate::{
+    processor::{
+        regimen::{PROPOSAL_KEY, RegimenConfig, RegimenProposal},
+        tasks::reply_generation_task::{ReplyGenerationTask, push_finding},
+    },
+    runner::core::LiveRunner,
+};
 
-impl RegimenSwitchingTask {
-    pub fn new() -> Self {
-        Self
-    }
+/// Applies the selected regimen to the runner when `auto_switch` is set,
+/// otherwise reports the switch as a proposal.
+pub struct RegimenSwitchingTask {
+    runner: Arc<LiveRunner>,
+    config: RegimenConfig,
 }
 
-impl Default for RegimenSwitchingTask {
-    fn default() -> Self {
-        Self::new()
+impl RegimenSwitchingTask {
+    pub fn new(runner: Arc<LiveRunner>, config: RegimenConfig) -> Self {
+        Self { runner, config }
     }
 }
 
@@ -22,15 +31,51 @@ impl Task for RegimenSwitchingTask {
         std::any::type_name::<Self>()
     }
 
-    async fn run(&self, _context: Context) -> graph_flow::Result<TaskResult> {
+    async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting regimen switching task");
 
-        // TODO: Implement regimen switching logic
-        // This task should handle switching between different trading regimens
+        let Some(mut proposal) = context.get_sync::<RegimenProposal>(PROPOSAL_KEY) else {
+            return Ok(TaskResult::new(
+                Some("No regimen proposal to apply".to_string()),
+                NextAction::GoTo(std::any::type_name::<ReplyGenerationTask>().to_string()),
+            ));
+        };
+
+        let finding = if !proposal.switch {
+            proposal.explanation.clone()
+        } else if !self.config.auto_switch {
+            format!(
+                "Proposed switching the strategy from {} to {}. {} Automatic switching is off, so the switch has not been applied.",
+                proposal.current, proposal.candidate, proposal.explanation
+            )
+        } else {
+            match self
+                .runner
+                .switch_regimen(&proposal.candidate, &proposal.explanation)
+            {
+                Ok(()) => {
+                    proposal.executed = true;
+                    format!(
+                        "Switched the strategy from {} to {}. {}",
+                        proposal.current, proposal.candidate, proposal.explanation
+                    )
+                }
+                Err(e) => {
+                    error!("Failed to switch regimen: {}", e);
+                    format!(
+                        "Could not switch the strategy from {} to {}: {}",
+                        proposal.current, proposal.candidate, e
+                    )
+                }
+            }
+        };
+
+        push_finding(&context, finding.clone()).await;
+        context.set(PROPOSAL_KEY, proposal).await;
 
         Ok(TaskResult::new(
-            Some("Regimen switching completed".to_string()),
-            NextAction::End,
+            Some(finding),
+            NextAction::ContinueAndExecute,
         ))
     }
 }
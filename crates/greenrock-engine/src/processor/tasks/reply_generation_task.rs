@@ -1,18 +1,114 @@
 use async_trait::async_trait;
+use graph_flow::GraphError::TaskExecutionFailed;
 use graph_flow::{Context, NextAction, Task, TaskResult};
-use tracing::info;
+use tracing::{error, info};
 
-pub struct ReplyGenerationTask;
+use crate::processor::{
+    llm::LlmConfig,
+    prompts::{
+        context::{RiskDisclaimerTemplate, SystemPersonaTemplate},
+        library::PromptLibrary,
+        tasks::ReplyGenerationTemplate,
+    },
+    streaming::{ChatEvent, ChatEvents},
+};
+
+/// Context key of the `Vec<String>` of findings branch tasks report.
+pub const FINDINGS_KEY: &str = "findings";
+
+/// Context key set once the turn has produced its reply.
+pub const COMPLETED_KEY: &str = "completed";
+
+/// Context key set when the entry answer was already streamed as tokens.
+pub const STREAMED_KEY: &str = "answer_streamed";
+
+/// Appends a finding for the final reply.
+pub async fn push_finding(context: &Context, finding: String) {
+    let mut findings: Vec<String> = context.get_sync(FINDINGS_KEY).unwrap_or_default();
+    findings.push(finding);
+    context.set(FINDINGS_KEY, findings).await;
+}
+
+/// Ends the flow with the reply. Without findings the entry answer is
+/// returned as is; otherwise the model rewrites it around the findings,
+/// falling back to the answer followed by the findings. Streamed sessions
+/// receive the rewritten reply as tokens while it is generated; the entry
+/// answer has been streamed by the entry task.
+pub struct ReplyGenerationTask {
+    prompts: PromptLibrary,
+    events: ChatEvents,
+    llm: LlmConfig,
+}
 
 impl ReplyGenerationTask {
-    pub fn new() -> Self {
-        Self
+    pub fn new(prompts: PromptLibrary) -> Self {
+        Self {
+            prompts,
+            events: ChatEvents::default(),
+            llm: LlmConfig::for_task("reply"),
+        }
+    }
+
+    pub fn with_llm(mut self, llm: LlmConfig) -> Self {
+        self.llm = llm;
+        self
+    }
+
+    pub fn with_events(mut self, events: ChatEvents) -> Self {
+        self.events = events;
+        self
     }
-}
 
-impl Default for ReplyGenerationTask {
-    fn default() -> Self {
-        Self::new()
+    fn send_token(&self, context: &Context, text: &str) {
+        self.events.publish(
+            context,
+            ChatEvent::Token {
+                task_id: self.id().to_string(),
+                text: text.to_string(),
+            },
+        );
+    }
+
+    async fn compose(
+        &self,
+        context: &Context,
+        user_input: String,
+        findings: &[String],
+    ) -> anyhow::Result<String> {
+        let risk_disclaimer = self.prompts.render(&RiskDisclaimerTemplate).await?.text;
+        let system_prompt = self
+            .prompts
+            .render(&SystemPersonaTemplate {
+                risk_disclaimer,
+                ..Default::default()
+            })
+            .await?
+            .text;
+
+        let prompt = self
+            .prompts
+            .render(&ReplyGenerationTemplate {
+                user_input,
+                findings: findings
+                    .iter()
+                    .map(|finding| format!("- {finding}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            })
+            .await?
+            .text;
+
+        let agent = self.llm.agent(&system_prompt)?;
+
+        if self.events.sink(context).is_none() {
+            return agent.prompt(prompt).await;
+        }
+
+        agent
+            .stream(prompt, &mut Vec::new(), 0, |text| {
+                self.send_token(context, text)
+            })
+            .await
     }
 }
 
@@ -25,13 +121,37 @@ impl Task for ReplyGenerationTask {
     async fn run(&self, context: Context) -> graph_flow::Result<TaskResult> {
         info!("Starting reply generation task");
 
-        let answer = context.get_sync::<String>("answer").unwrap();
+        let answer: String = context
+            .get_sync("answer")
+            .ok_or_else(|| TaskExecutionFailed("answer not found in context".into()))?;
+
+        let findings: Vec<String> = context.get_sync(FINDINGS_KEY).unwrap_or_default();
+
+        context.set(COMPLETED_KEY, true).await;
+
+        if findings.is_empty() {
+            if !context.get_sync::<bool>(STREAMED_KEY).unwrap_or(false) {
+                self.send_token(&context, &answer);
+            }
+            return Ok(TaskResult::new(Some(answer), NextAction::End));
+        }
+
+        let user_input: String = context.get_sync("user_input").unwrap_or_default();
+
+        let reply = match self.compose(&context, user_input, &findings).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                error!("Failed to compose reply, returning findings: {}", e);
+                format!("{answer}\n\n{}", findings.join("\n\n"))
+            }
+        };
+
+        info!("Reply: {}", reply);
 
-        info!("Answer: {}", answer);
+        context.set("answer", reply.clone()).await;
+        // Findings belong to this turn only.
+        context.set(FINDINGS_KEY, Vec::<String>::new()).await;
 
-        Ok(TaskResult::new(
-            Some("Reply generation completed".to_string()),
-            NextAction::End,
-        ))
+        Ok(TaskResult::new(Some(reply), NextAction::End))
     }
 }
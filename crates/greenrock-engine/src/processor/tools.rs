@@ -0,0 +1,574 @@
+//! Tools the chat agent can call for live data, backed by the runner.
+//!
+//! Every call is appended to a `ToolCallLog`, which the calling task stores
+//! under `tool_calls` in the session context, and sent as it happens to a
+//! streamed session.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use rig::{agent::AgentBuilder, completion::ToolDefinition, tool::Tool};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::info;
+
+use crate::{
+    models::{
+        account::Balance, analysis::TechnicalAnalysis, market::Ticker, orders::Order,
+        timeseries::Candle,
+    },
+    portfolio::ledger::{PnlReport, PositionReport},
+    processor::streaming::{ChatEvent, ChatSink},
+    runner::{
+        backtest::{BacktestConfig, BacktestReport},
+        core::LiveRunner,
+    },
+};
+
+/// Most candles a single tool call may fetch.
+const MAX_CANDLES: u16 = 1000;
+
+#[derive(Debug)]
+pub struct ToolError(String);
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub tool: String,
+    pub args: Value,
+    /// Tool output, absent when the call failed.
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Tool calls made during one agent run, shared by the tools of that run.
+#[derive(Debug, Clone, Default)]
+pub struct ToolCallLog {
+    records: Arc<Mutex<Vec<ToolCallRecord>>>,
+    sink: Option<ChatSink>,
+}
+
+impl ToolCallLog {
+    /// Log that also sends every call to `sink`.
+    pub fn streamed(sink: Option<ChatSink>) -> Self {
+        Self {
+            sink,
+            ..Self::default()
+        }
+    }
+
+    pub fn take(&self) -> Vec<ToolCallRecord> {
+        std::mem::take(&mut *self.records.lock().unwrap())
+    }
+
+    fn push(&self, record: ToolCallRecord) {
+        if let Some(sink) = &self.sink {
+            sink.send(ChatEvent::ToolCall {
+                tool: record.tool.clone(),
+                args: record.args.clone(),
+                error: record.error.clone(),
+            });
+        }
+
+        self.records.lock().unwrap().push(record);
+    }
+}
+
+/// Runner handle and call log shared by the trading tools.
+#[derive(Clone)]
+pub struct TradingTools {
+    runner: Arc<LiveRunner>,
+    log: ToolCallLog,
+}
+
+impl TradingTools {
+    pub fn new(runner: Arc<LiveRunner>, log: ToolCallLog) -> Self {
+        Self { runner, log }
+    }
+
+    /// Registers every trading tool on `builder`.
+    pub fn attach<M>(&self, builder: AgentBuilder<M>) -> AgentBuilder<M>
+    where
+        M: rig::completion::CompletionModel,
+    {
+        builder
+            .tool(GetPrice(self.clone()))
+            .tool(GetCandles(self.clone()))
+            .tool(ComputeIndicator(self.clone()))
+            .tool(GetBalance(self.clone()))
+            .tool(GetOpenOrders(self.clone()))
+            .tool(GetPortfolio(self.clone()))
+            .tool(RunBacktest(self.clone()))
+    }
+
+    /// Runs a blocking runner call off the async executor.
+    async fn blocking<T, F>(&self, f: F) -> Result<T, ToolError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&LiveRunner) -> T + Send + 'static,
+    {
+        let runner = self.runner.clone();
+
+        tokio::task::spawn_blocking(move || f(&runner))
+            .await
+            .map_err(|e| ToolError(format!("tool task failed: {e}")))
+    }
+
+    fn record<A: Serialize, O: Serialize>(
+        &self,
+        tool: &str,
+        args: &A,
+        result: &Result<O, ToolError>,
+    ) {
+        info!("Tool {} called", tool);
+
+        self.log.push(ToolCallRecord {
+            tool: tool.to_string(),
+            args: serde_json::to_value(args).unwrap_or(Value::Null),
+            output: result
+                .as_ref()
+                .ok()
+                .and_then(|output| serde_json::to_value(output).ok()),
+            error: result.as_ref().err().map(ToString::to_string),
+            at: Utc::now(),
+        });
+    }
+}
+
+fn definition(name: &str, description: &str, parameters: Value) -> ToolDefinition {
+    ToolDefinition {
+        name: name.to_string(),
+        description: description.to_string(),
+        parameters,
+    }
+}
+
+fn default_interval() -> String {
+    "1h".to_string()
+}
+
+fn default_candle_limit() -> u16 {
+    100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolArgs {
+    pub symbol: String,
+}
+
+pub struct GetPrice(TradingTools);
+
+impl Tool for GetPrice {
+    const NAME: &'static str = "get_price";
+
+    type Error = ToolError;
+    type Args = SymbolArgs;
+    type Output = Ticker;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        definition(
+            Self::NAME,
+            "Latest price and 24h statistics of a trading pair.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "symbol": { "type": "string", "description": "Pair symbol, e.g. BTCUSDT" }
+                },
+                "required": ["symbol"]
+            }),
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let symbol = args.symbol.to_uppercase();
+
+        let result = self
+            .0
+            .blocking(move |runner| runner.ticker(&symbol))
+            .await
+            .and_then(|ticker| {
+                ticker.ok_or_else(|| ToolError(format!("no ticker for {}", args.symbol)))
+            });
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandlesArgs {
+    pub symbol: String,
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    #[serde(default = "default_candle_limit")]
+    pub limit: u16,
+}
+
+impl CandlesArgs {
+    async fn fetch(&self, tools: &TradingTools) -> Result<Vec<Candle>, ToolError> {
+        let candles = tools
+            .runner
+            .candles(
+                &self.symbol.to_uppercase(),
+                &self.interval,
+                self.limit.clamp(1, MAX_CANDLES),
+                None,
+                None,
+            )
+            .await;
+
+        if candles.is_empty() {
+            return Err(ToolError(format!(
+                "no {} candles for {}",
+                self.interval, self.symbol
+            )));
+        }
+
+        Ok(candles)
+    }
+}
+
+fn candles_parameters() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "symbol": { "type": "string", "description": "Pair symbol, e.g. ETHUSDT" },
+            "interval": { "type": "string", "description": "Candle interval such as 1m, 15m, 1h, 4h or 1d. Defaults to 1h" },
+            "limit": { "type": "integer", "description": "Number of most recent candles, 1 to 1000. Defaults to 100" }
+        },
+        "required": ["symbol"]
+    })
+}
+
+pub struct GetCandles(TradingTools);
+
+impl Tool for GetCandles {
+    const NAME: &'static str = "get_candles";
+
+    type Error = ToolError;
+    type Args = CandlesArgs;
+    type Output = Vec<Candle>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        definition(
+            Self::NAME,
+            "OHLCV candles of a trading pair, oldest first.",
+            candles_parameters(),
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let result = args.fetch(&self.0).await;
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Indicator {
+    Rsi,
+    Ema,
+    Macd,
+    Atr,
+    Supertrend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorArgs {
+    pub indicator: Indicator,
+    /// Lookback period; the indicator's usual default when absent.
+    pub period: Option<usize>,
+    #[serde(flatten)]
+    pub candles: CandlesArgs,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndicatorValue {
+    pub symbol: String,
+    pub interval: String,
+    pub indicator: Indicator,
+    pub period: usize,
+    /// Values at the latest candle, e.g. `macd`, `signal` and `histogram`.
+    pub values: BTreeMap<String, f64>,
+    pub candles: usize,
+    pub as_of: DateTime<Utc>,
+}
+
+pub struct ComputeIndicator(TradingTools);
+
+impl Tool for ComputeIndicator {
+    const NAME: &'static str = "compute_indicator";
+
+    type Error = ToolError;
+    type Args = IndicatorArgs;
+    type Output = IndicatorValue;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        let mut parameters = candles_parameters();
+        parameters["properties"]["indicator"] = json!({
+            "type": "string",
+            "enum": ["rsi", "ema", "macd", "atr", "supertrend"],
+            "description": "Indicator to compute on the candle closes"
+        });
+        parameters["properties"]["period"] = json!({
+            "type": "integer",
+            "description": "Lookback period. Defaults to 14 for rsi and atr, 20 for ema, 10 for supertrend; macd is always 12/26/9"
+        });
+        parameters["required"] = json!(["symbol", "indicator"]);
+
+        definition(
+            Self::NAME,
+            "Technical indicator value at the latest candle of a trading pair.",
+            parameters,
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let result = args.candles.fetch(&self.0).await.map(|candles| {
+            let period = args.period.unwrap_or(match args.indicator {
+                Indicator::Rsi | Indicator::Atr => 14,
+                Indicator::Ema => 20,
+                Indicator::Macd => 26,
+                Indicator::Supertrend => 10,
+            });
+
+            let values: BTreeMap<String, f64> = match args.indicator {
+                Indicator::Rsi => [("rsi".to_string(), candles.rsi(period))].into(),
+                Indicator::Ema => [("ema".to_string(), candles.ema(period))].into(),
+                Indicator::Atr => [("atr".to_string(), candles.atr(period))].into(),
+                Indicator::Macd => {
+                    let macd = candles.macd(12, 26, 9);
+                    [
+                        ("macd".to_string(), macd.macd),
+                        ("signal".to_string(), macd.signal),
+                        ("histogram".to_string(), macd.histogram),
+                    ]
+                    .into()
+                }
+                Indicator::Supertrend => {
+                    let supertrend = candles.supertrend(period, 3.0);
+                    [
+                        ("value".to_string(), supertrend.value),
+                        ("trend".to_string(), supertrend.trend as f64),
+                        ("upper".to_string(), supertrend.upper),
+                        ("lower".to_string(), supertrend.lower),
+                    ]
+                    .into()
+                }
+            };
+
+            IndicatorValue {
+                symbol: args.candles.symbol.to_uppercase(),
+                interval: args.candles.interval.clone(),
+                indicator: args.indicator,
+                period,
+                values,
+                candles: candles.len(),
+                as_of: candles.last().map(|candle| candle.ts).unwrap_or_default(),
+            }
+        });
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoArgs {}
+
+fn no_parameters() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+pub struct GetBalance(TradingTools);
+
+impl Tool for GetBalance {
+    const NAME: &'static str = "get_balance";
+
+    type Error = ToolError;
+    type Args = NoArgs;
+    type Output = Vec<Balance>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        definition(
+            Self::NAME,
+            "Non-zero free and locked balances of the exchange account.",
+            no_parameters(),
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let result = self
+            .0
+            .blocking(|runner| {
+                runner
+                    .balances()
+                    .into_iter()
+                    .filter(|balance| {
+                        balance.free > Decimal::ZERO || balance.locked > Decimal::ZERO
+                    })
+                    .collect()
+            })
+            .await;
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
+
+pub struct GetOpenOrders(TradingTools);
+
+impl Tool for GetOpenOrders {
+    const NAME: &'static str = "get_open_orders";
+
+    type Error = ToolError;
+    type Args = SymbolArgs;
+    type Output = Vec<Order>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        definition(
+            Self::NAME,
+            "Orders resting on the exchange for a trading pair.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "symbol": { "type": "string", "description": "Pair symbol, e.g. BTCUSDT" }
+                },
+                "required": ["symbol"]
+            }),
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let symbol = args.symbol.to_uppercase();
+
+        let result = self
+            .0
+            .blocking(move |runner| runner.open_orders(&symbol))
+            .await;
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioSnapshot {
+    pub pnl: PnlReport,
+    pub positions: Vec<PositionReport>,
+}
+
+pub struct GetPortfolio(TradingTools);
+
+impl Tool for GetPortfolio {
+    const NAME: &'static str = "get_portfolio";
+
+    type Error = ToolError;
+    type Args = NoArgs;
+    type Output = PortfolioSnapshot;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        definition(
+            Self::NAME,
+            "Positions, cash, equity and PnL of the trading ledger.",
+            no_parameters(),
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let result = self
+            .0
+            .blocking(|runner| PortfolioSnapshot {
+                pnl: runner.pnl(),
+                positions: runner.positions(),
+            })
+            .await;
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
+
+fn default_backtest_limit() -> u16 {
+    500
+}
+
+fn default_initial_cash() -> Decimal {
+    Decimal::from(10_000)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestArgs {
+    pub symbol: String,
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    #[serde(default = "default_backtest_limit")]
+    pub limit: u16,
+    #[serde(default = "default_initial_cash")]
+    pub initial_cash: Decimal,
+}
+
+pub struct RunBacktest(TradingTools);
+
+impl Tool for RunBacktest {
+    const NAME: &'static str = "run_backtest";
+
+    type Error = ToolError;
+    type Args = BacktestArgs;
+    type Output = BacktestReport;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        definition(
+            Self::NAME,
+            "Backtests the engine's strategy on recent candles of a trading pair, without fees or risk limits.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "symbol": { "type": "string", "description": "Pair symbol, e.g. BTCUSDT" },
+                    "interval": { "type": "string", "description": "Candle interval. Defaults to 1h" },
+                    "limit": { "type": "integer", "description": "Number of most recent candles to replay, up to 1000. Defaults to 500" },
+                    "initial_cash": { "type": "number", "description": "Starting cash in the quote asset. Defaults to 10000" }
+                },
+                "required": ["symbol"]
+            }),
+        )
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let config = BacktestConfig {
+            symbol: args.symbol.to_uppercase(),
+            interval: args.interval.clone(),
+            limit: args.limit.clamp(1, MAX_CANDLES),
+            initial_cash: args.initial_cash,
+        };
+
+        let report = self.0.runner.backtest(&config).await;
+
+        let result = if report.candles == 0 {
+            Err(ToolError(format!(
+                "no {} candles for {}",
+                config.interval, config.symbol
+            )))
+        } else {
+            Ok(report)
+        };
+
+        self.0.record(Self::NAME, &args, &result);
+        result
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use polars::frame::DataFrame;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    models::{
+        money::Quantity,
+        orders::Fill,
+        timeseries::{Candle, CandleRing},
+    },
+    portfolio::ledger::{Ledger, PnlReport, split_symbol},
+    strategy::core::{Strategy, StrategyAction, StrategyContext},
+};
+
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub symbol: String,
+    pub interval: String,
+    /// Number of candles to replay, the most recent ones.
+    pub limit: u16,
+    /// Starting cash in the quote currency of `symbol`.
+    pub initial_cash: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub symbol: String,
+    pub interval: String,
+    pub candles: usize,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// Actions emitted by the strategy, each filled at the candle close.
+    pub fills: usize,
+    pub initial_cash: Decimal,
+    pub pnl: PnlReport,
+    /// Equity change over the run, in percent of `initial_cash`.
+    pub return_percent: Decimal,
+    /// Largest peak-to-trough equity drop, in percent of the peak.
+    pub max_drawdown_percent: Decimal,
+    /// Close-to-close change of `symbol` over the run, in percent.
+    pub buy_and_hold_percent: Decimal,
+}
+
+/// Replays `candles` through `strategy` the way the runner ticks it live,
+/// filling every emitted action at the candle close into a fresh ledger.
+///
+/// Risk limits, exchange filters and fees are not applied.
+pub fn simulate<S: Strategy + ?Sized>(
+    strategy: &S,
+    candles: Vec<Candle>,
+    config: &BacktestConfig,
+) -> BacktestReport {
+    let symbol = config.symbol.to_uppercase();
+    let (base, quote) = split_symbol(&symbol).unwrap_or((symbol.clone(), "USDT".to_string()));
+
+    let mut ledger = Ledger::new(&quote).with_cash(config.initial_cash);
+
+    let mut init_ctx = StrategyContext {
+        _data_scope: DataFrame::new(vec![]).unwrap(),
+        _trades: HashMap::new(),
+    };
+    let (mut ctx, mut state) = strategy.init(&mut init_ctx, &mut strategy.initial_state());
+
+    let mut data_scope = CandleRing::new(candles.len().max(1));
+    let mut fills = 0;
+    let mut peak = config.initial_cash;
+    let mut max_drawdown_percent = Decimal::ZERO;
+
+    for candle in &candles {
+        data_scope.upsert(candle.clone());
+
+        let response = strategy.tick(
+            &mut ctx,
+            candle.ts,
+            &mut state,
+            symbol.clone(),
+            data_scope.snapshot(),
+            candle.clone(),
+        );
+
+        if let StrategyAction::Emitted(action) = response {
+            let filled = ledger.apply_fill(&Fill {
+                trade_id: None,
+                symbol: symbol.clone(),
+                side: action.side,
+                price: candle.close,
+                qty: Quantity::from_f64(action.amount),
+                fee: Quantity::ZERO,
+                fee_asset: String::new(),
+                time: candle.ts,
+            });
+
+            if filled {
+                fills += 1;
+            }
+        }
+
+        ledger.mark(&base, candle.close);
+
+        let equity = ledger.pnl().equity;
+        peak = peak.max(equity);
+
+        if peak > Decimal::ZERO {
+            max_drawdown_percent =
+                max_drawdown_percent.max((peak - equity) / peak * Decimal::ONE_HUNDRED);
+        }
+    }
+
+    strategy.end(&mut ctx, &mut state);
+
+    let pnl = ledger.pnl();
+
+    let return_percent = if config.initial_cash > Decimal::ZERO {
+        (pnl.equity - config.initial_cash) / config.initial_cash * Decimal::ONE_HUNDRED
+    } else {
+        Decimal::ZERO
+    };
+
+    let buy_and_hold_percent = match (candles.first(), candles.last()) {
+        (Some(first), Some(last)) if first.close.is_positive() => {
+            (last.close.value() - first.close.value()) / first.close.value() * Decimal::ONE_HUNDRED
+        }
+        _ => Decimal::ZERO,
+    };
+
+    BacktestReport {
+        symbol,
+        interval: config.interval.clone(),
+        candles: candles.len(),
+        start: candles.first().map(|candle| candle.ts),
+        end: candles.last().map(|candle| candle.ts),
+        fills,
+        initial_cash: config.initial_cash,
+        pnl,
+        return_percent: return_percent.round_dp(2),
+        max_drawdown_percent: max_drawdown_percent.round_dp(2),
+        buy_and_hold_percent: buy_and_hold_percent.round_dp(2),
+    }
+}
@@ -1,18 +1,46 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use binance::model::{Order, OrderBook, TradeHistory};
 use chrono::{DateTime, Duration, Utc};
 use polars::frame::DataFrame;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
 // use ta::{DataItem, Next, indicators::MovingAverageConvergenceDivergence};
 use tokio::signal;
 
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 use crate::{
-    brokers::{binance::BinanceBroker, core::Broker},
-    models::timeseries::{Candle, CandleRing},
-    strategy::core::{Strategy, StrategyAction, StrategyContext},
+    brokers::{
+        core::{Broker, CancelAllError},
+        venue::VenueBroker,
+    },
+    models::{
+        account::{AccountEvent, Balance},
+        market::{OrderBook, Ticker},
+        money::{Price, Quantity},
+        orders::{Fill, Order, OrderAck, OrderRequest, Side},
+        symbols::SymbolInfo,
+        timeseries::{Candle, CandleRing, Trade, TradeFeed},
+    },
+    portfolio::{
+        ledger::{Ledger, PnlReport, PositionReport, split_symbol},
+        rebalancer::{LegExecution, RebalancePlan, Rebalancer},
+    },
+    runner::{
+        backtest::{BacktestConfig, BacktestReport, simulate},
+        events::{EventBus, RunnerEvent},
+        kill_switch::{FlattenResult, KillSwitch, KillSwitchReport},
+        risk::{RiskContext, RiskLimits, RiskManager},
+    },
+    strategy::{
+        core::{Strategy, StrategyAction, StrategyContext, StrategyTraitKind, TradingAction},
+        regimen::RegimenStrategy,
+    },
 };
 
 pub struct Runner<State, B, S>
@@ -23,11 +51,47 @@ where
 {
     broker: B,
     strategy: S,
+    events: EventBus,
+    ledger: Mutex<Ledger>,
+    risk: RiskManager,
+    kill_switch: Arc<KillSwitch>,
+    /// Live orders placed by this runner, by exchange order id, mapped to the
+    /// action that produced them.
+    live_orders: Mutex<HashMap<String, String>>,
+}
+
+/// The runner the service drives: the configured venue with the switchable
+/// regimen strategy.
+pub type LiveRunner = Runner<HashMap<String, f64>, VenueBroker, RegimenStrategy>;
+
+/// How risk-approved strategy actions are executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Fill at the last candle close or trade price into the ledger without
+    /// touching the broker.
+    Paper,
+    /// Send market orders through the broker.
+    Live,
+}
+
+impl ExecutionMode {
+    /// `Live` when `TRADING_MODE` is `live`, `Paper` otherwise.
+    pub fn from_env() -> Self {
+        match std::env::var("TRADING_MODE").as_deref() {
+            Ok("live") => ExecutionMode::Live,
+            _ => ExecutionMode::Paper,
+        }
+    }
 }
 
 pub struct RunConfig {
     pub symbol: String,
     pub interval: String,
+    pub execution: ExecutionMode,
+    /// Engage the kill switch when no candle arrives for this long.
+    pub stale_after: Option<std::time::Duration>,
+    /// Also feed every trade to `Strategy::on_trade`.
+    pub trade_feed: Option<TradeFeed>,
     // pub data_scope_len: usize,
     // pub start_time: Option<DateTime<Utc>>,
     // pub end_time: Option<DateTime<Utc>>,
@@ -40,21 +104,73 @@ where
     S: Strategy<State = State> + Send + Sync,
 {
     pub fn new(broker: B, strategy: S) -> Self {
-        Self { broker, strategy }
+        Self {
+            broker,
+            strategy,
+            events: EventBus::default(),
+            ledger: Mutex::new(Ledger::default()),
+            risk: RiskManager::default(),
+            kill_switch: Arc::new(KillSwitch::in_memory()),
+            live_orders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Shares a kill switch with other runners, typically one loaded from disk.
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    pub fn broker(&self) -> &B {
+        &self.broker
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    pub fn kill_switch(&self) -> &KillSwitch {
+        &self.kill_switch
+    }
+
+    pub fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk = RiskManager::new(limits);
+        self
+    }
+
+    pub fn risk(&self) -> &RiskManager {
+        &self.risk
+    }
+
+    /// Subscribe to the events published by the live loop.
+    pub fn events(&self) -> tokio::sync::broadcast::Receiver<RunnerEvent> {
+        self.events.subscribe()
     }
 
     pub fn open_orders(&self, symbol: &str) -> Vec<Order> {
         self.broker.open_orders(symbol)
     }
 
-    pub fn trade_history(&self, symbol: &str) -> Vec<TradeHistory> {
+    pub fn trade_history(&self, symbol: &str) -> Vec<Fill> {
         self.broker.trade_history(symbol)
     }
 
+    pub fn cancel_orders(&self, symbol: &str) -> anyhow::Result<usize> {
+        self.broker.cancel_orders(symbol)
+    }
+
     pub fn balance(&self) -> HashMap<String, f64> {
         self.broker.balance()
     }
 
+    pub fn balances(&self) -> Vec<Balance> {
+        self.broker.balances()
+    }
+
+    pub fn symbols(&self) -> Vec<SymbolInfo> {
+        self.broker.symbols()
+    }
+
     pub fn portfolio(&self) -> HashMap<String, f64> {
         self.strategy.portfolio()
     }
@@ -67,10 +183,212 @@ where
         self.broker.order_book_stream(symbol)
     }
 
+    pub fn trade_stream(
+        &self,
+        symbol: &str,
+        feed: TradeFeed,
+    ) -> tokio::sync::broadcast::Receiver<Trade> {
+        self.broker.trade_stream(symbol, feed)
+    }
+
+    pub fn account_stream(&self) -> tokio::sync::broadcast::Receiver<AccountEvent> {
+        self.broker.account_stream()
+    }
+
+    pub fn ticker(&self, symbol: &str) -> Option<Ticker> {
+        self.broker.ticker(symbol)
+    }
+
     pub fn market_current_price(&self, symbol: &str) -> f64 {
         self.broker.market_current_price(symbol)
     }
 
+    /// Records a paper, backtest or live execution in the portfolio ledger.
+    pub fn record_fill(&self, fill: &Fill) {
+        self.ledger.lock().unwrap().apply_fill(fill);
+    }
+
+    /// Ingests the broker trade history for `symbol` into the ledger.
+    /// Trades already seen are skipped.
+    pub fn sync_trade_history(&self, symbol: &str) {
+        let trades = self.broker.trade_history(symbol);
+
+        let mut ledger = self.ledger.lock().unwrap();
+        for fill in &trades {
+            ledger.apply_fill(fill);
+        }
+    }
+
+    /// Marks every ledger position with the current market price.
+    pub fn mark_to_market(&self) {
+        let (assets, quote) = {
+            let ledger = self.ledger.lock().unwrap();
+            (ledger.assets(), ledger.quote_currency().to_string())
+        };
+
+        let marks: Vec<(String, f64)> = assets
+            .into_iter()
+            .map(|asset| {
+                let price = self.broker.market_current_price(&format!("{asset}{quote}"));
+                (asset, price)
+            })
+            .filter(|(_, price)| *price > 0.0)
+            .collect();
+
+        let mut ledger = self.ledger.lock().unwrap();
+        for (asset, price) in marks {
+            ledger.mark(&asset, Price::from_f64(price));
+        }
+    }
+
+    /// Resets the ledger to the account balances: the quote currency as cash
+    /// and every other asset entered at its current price, so equity covers
+    /// the whole account rather than only fills since startup. Assets
+    /// without a price are left out.
+    ///
+    /// Performs blocking broker calls.
+    pub fn seed_ledger(&self) {
+        let quote = self.ledger.lock().unwrap().quote_currency().to_string();
+
+        let mut cash = Decimal::ZERO;
+        let mut holdings = Vec::new();
+
+        for balance in self.broker.balances() {
+            if balance.asset == quote {
+                cash = balance.total();
+                continue;
+            }
+
+            let price = self
+                .broker
+                .market_current_price(&format!("{}{quote}", balance.asset));
+            if price.is_nan() || price <= 0.0 {
+                warn!(
+                    "No {} price for {}, left out of the ledger",
+                    quote, balance.asset
+                );
+                continue;
+            }
+
+            holdings.push((
+                balance.asset.clone(),
+                Quantity::new(balance.total()),
+                Price::from_f64(price),
+            ));
+        }
+
+        info!(
+            "Ledger seeded with {} {} and {} holdings",
+            cash,
+            quote,
+            holdings.len()
+        );
+
+        self.ledger.lock().unwrap().seed(cash, holdings, Utc::now());
+    }
+
+    pub fn positions(&self) -> Vec<PositionReport> {
+        self.ledger.lock().unwrap().positions()
+    }
+
+    pub fn pnl(&self) -> PnlReport {
+        self.ledger.lock().unwrap().pnl()
+    }
+
+    /// Dry-run preview of the trades needed to reach the strategy portfolio.
+    ///
+    /// Paper execution plans from the ledger, where its rebalance fills land,
+    /// rather than the venue balances they never change.
+    pub fn rebalance_plan(
+        &self,
+        rebalancer: &Rebalancer,
+        execution: ExecutionMode,
+    ) -> RebalancePlan {
+        let targets = self.strategy.portfolio();
+
+        match execution {
+            ExecutionMode::Paper => {
+                let holdings = self.ledger.lock().unwrap().holdings();
+                rebalancer.plan_holdings(&self.broker, &targets, &holdings)
+            }
+            ExecutionMode::Live => rebalancer.plan(&self.broker, &targets),
+        }
+    }
+
+    /// Submits every leg of `plan` like a strategy order: through the
+    /// exchange filters and the risk manager, then filled on paper or sent
+    /// to the broker per `execution`.
+    pub fn execute_rebalance(
+        &self,
+        rebalancer: &Rebalancer,
+        plan: &RebalancePlan,
+        execution: ExecutionMode,
+    ) -> Vec<LegExecution> {
+        let supports_short = self.broker.supports_short();
+        let run_id = plan.created_at.timestamp_millis();
+
+        rebalancer.execute(plan, |leg, mut order| {
+            // Selling down a holding must not open a short on venues that
+            // allow one.
+            if leg.side == Side::Sell && supports_short {
+                order = order.reduce_only();
+            }
+
+            let action_id = format!("rebalance-{run_id}-{}", leg.symbol);
+            self.submit_order(&action_id, order, leg.price, execution)
+        })
+    }
+
+    /// Re-evaluates the rebalance plan on `rebalancer.config.check_every` and
+    /// acts on it whenever the schedule says it is due.
+    pub async fn run_rebalancer_with_cancel_signal(
+        &self,
+        rebalancer: &Rebalancer,
+        execution: ExecutionMode,
+        cancel: CancellationToken,
+    ) {
+        let mut ticker = tokio::time::interval(rebalancer.config.check_every);
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    break;
+                }
+                _ = ticker.tick() => {
+                    let plan = tokio::task::block_in_place(|| {
+                        self.rebalance_plan(rebalancer, execution)
+                    });
+
+                    if !rebalancer.is_due(&plan, Utc::now()) {
+                        continue;
+                    }
+
+                    if self.kill_switch.is_engaged() {
+                        warn!("Rebalance due but kill switch is engaged, skipping");
+                        continue;
+                    }
+
+                    if rebalancer.config.dry_run {
+                        info!("Rebalance due (dry run): {} legs", plan.legs.len());
+                        rebalancer.mark_run(plan.created_at);
+                        continue;
+                    }
+
+                    let executions =
+                        tokio::task::block_in_place(|| {
+                            self.execute_rebalance(rebalancer, &plan, execution)
+                        });
+
+                    info!(
+                        "Rebalance executed: {} of {} legs succeeded",
+                        executions.iter().filter(|e| e.error.is_none()).count(),
+                        executions.len()
+                    );
+                }
+            }
+        }
+    }
+
     pub async fn candles(
         &self,
         symbol: &str,
@@ -84,6 +402,17 @@ where
             .await
     }
 
+    /// Replays the latest `config.limit` candles through this runner's
+    /// strategy without touching the broker or the live ledger.
+    pub async fn backtest(&self, config: &BacktestConfig) -> BacktestReport {
+        let candles = self
+            .broker
+            .candles(&config.symbol, &config.interval, config.limit, None, None)
+            .await;
+
+        simulate(&self.strategy, candles, config)
+    }
+
     pub async fn candles_stream(
         &self,
         symbol: &str,
@@ -105,13 +434,12 @@ where
 
         let (mut ctx, mut state) = self.strategy.init(&mut init_ctx, &mut init_state);
 
-        let binance_broker = BinanceBroker::new();
-
-        let mut candle_rx = binance_broker.candle_stream(&config.symbol, &config.interval);
+        let mut candle_rx = self.broker.candle_stream(&config.symbol, &config.interval);
 
         // let mut data_scope = Vec::new();
 
-        let data_scope = binance_broker
+        let data_scope = self
+            .broker
             .candles(
                 &config.symbol,
                 &config.interval,
@@ -121,20 +449,123 @@ where
             )
             .await;
 
+        tokio::task::block_in_place(|| self.seed_ledger());
+
         let mut data_scope_ring = CandleRing::new(2000);
 
         for candle in data_scope {
             data_scope_ring.upsert(candle);
         }
 
+        if self.kill_switch.is_engaged() {
+            warn!(
+                "Kill switch engaged, {} runner paused until released",
+                config.symbol
+            );
+        }
+
+        // Live fills are booked from the account stream rather than the order
+        // acknowledgement, so partial and later limit fills are not missed.
+        let mut account_rx =
+            (config.execution == ExecutionMode::Live).then(|| self.broker.account_stream());
+
+        let mut trade_rx = config
+            .trade_feed
+            .map(|feed| self.broker.trade_stream(&config.symbol, feed));
+
+        let mut last_tick_at = Instant::now();
+        let mut staleness_check = tokio::time::interval(std::time::Duration::from_secs(5));
+
         loop {
             tokio::select! {
                 _ = cancel.cancelled() => {
                     break;
                 }
+                event = async {
+                    match account_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match event {
+                        Ok(event) => self.handle_account_event(event),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("account stream lagged by {} messages", n);
+                            self.events.publish(RunnerEvent::Error {
+                                message: format!("account stream lagged by {n} messages"),
+                            });
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                            error!("account stream closed, live fills are no longer tracked");
+                            self.events.publish(RunnerEvent::Error {
+                                message: "account stream closed".to_string(),
+                            });
+                            account_rx = None;
+                        }
+                    }
+                }
+                trade = async {
+                    match trade_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match trade {
+                        Ok(trade) => {
+                            if self.kill_switch.is_engaged() {
+                                self.broker.market_data_handled();
+                                continue;
+                            }
+
+                            if let StrategyAction::Emitted(action) =
+                                self.strategy.on_trade(&mut ctx, &mut state, &trade)
+                            {
+                                info!("Emitted action on trade: {:?}", action);
+                                self.events.publish(RunnerEvent::ActionEmitted {
+                                    action: (*action).clone(),
+                                });
+                                self.execute_action(&action, trade.price, config);
+                            }
+
+                            self.broker.market_data_handled();
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                            info!("trade stream lagged by {} messages", n);
+                            self.events.publish(RunnerEvent::Error {
+                                message: format!("trade stream lagged by {n} messages"),
+                            });
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                            info!("trade stream closed");
+                            self.events.publish(RunnerEvent::Error {
+                                message: "trade stream closed".to_string(),
+                            });
+                            trade_rx = None;
+                        }
+                    }
+                }
+                _ = staleness_check.tick(), if config.stale_after.is_some() => {
+                    let stale_after = config.stale_after.unwrap();
+
+                    if last_tick_at.elapsed() > stale_after && !self.kill_switch.is_engaged() {
+                        let reason = format!(
+                            "no {} candle for {:?}",
+                            config.symbol,
+                            last_tick_at.elapsed()
+                        );
+                        tokio::task::block_in_place(|| {
+                            self.trigger_kill_switch(
+                                &reason,
+                                self.kill_switch.flatten_on_trigger,
+                                config.execution,
+                            )
+                        });
+                    }
+                }
                 tick = candle_rx.recv() => {
                     match tick {
                         Ok(candle) => {
+                            last_tick_at = Instant::now();
                             // let di = DataItem::builder()
                             //     .high(candle.high)
                             //     .low(candle.low)
@@ -166,33 +597,66 @@ where
                             // data_scope.push(candle.clone());
                             data_scope_ring.upsert(candle.clone());
 
+                            if self.kill_switch.is_engaged() {
+                                self.broker.market_data_handled();
+                                continue;
+                            }
+
+                            let started = Instant::now();
+                            let at = DateTime::from_timestamp(candle.timestamp, 0).unwrap();
+
                             let response = self
                                 .strategy
                                 .tick(
                                     &mut ctx,
-                                    DateTime::from_timestamp(candle.timestamp, 0).unwrap(),
+                                    at,
                                     &mut state,
                                     config.symbol.to_string(),
                                     data_scope_ring.snapshot(),
-                                    candle,
+                                    candle.clone(),
                                 );
 
+                            self.events.publish(RunnerEvent::TickProcessed {
+                                symbol: config.symbol.clone(),
+                                interval: config.interval.clone(),
+                                candle: candle.clone(),
+                                elapsed_us: started.elapsed().as_micros(),
+                            });
+
+                            self.events.publish(RunnerEvent::IndicatorSnapshot {
+                                symbol: config.symbol.clone(),
+                                at,
+                                indicators: self.strategy.indicators(&state),
+                            });
+
                             match response {
                                 StrategyAction::Emitted(action) => {
                                     info!("Emitted action: {:?}", action);
+                                    self.events.publish(RunnerEvent::ActionEmitted {
+                                        action: (*action).clone(),
+                                    });
+                                    self.execute_action(&action, candle.close, config);
                                 }
                                 StrategyAction::Pass => {
                                     info!("Pass");
                                 }
                             }
 
+                            self.broker.market_data_handled();
+
                             // println!("atr: {atr:?}");
                         }
                         Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
                             info!("candle stream lagged by {} messages", n);
+                            self.events.publish(RunnerEvent::Error {
+                                message: format!("candle stream lagged by {n} messages"),
+                            });
                         }
                         Err(tokio::sync::broadcast::error::RecvError::Closed) => {
                             info!("candle stream closed");
+                            self.events.publish(RunnerEvent::Error {
+                                message: "candle stream closed".to_string(),
+                            });
                             break;
                         }
                     }
@@ -205,6 +669,415 @@ where
         ctx
     }
 
+    /// Runs `action` through the risk manager and, if approved, executes it
+    /// according to `config.execution`. Every outcome is published as an event.
+    fn execute_action(&self, action: &TradingAction, reference_price: Price, config: &RunConfig) {
+        let supports_short = self.broker.supports_short();
+
+        if action.kind == StrategyTraitKind::Short && !action.is_exit() && !supports_short {
+            warn!("Action {} opens a short the venue cannot hold", action.id);
+            self.events.publish(RunnerEvent::OrderRejected {
+                action_id: action.id.clone(),
+                symbol: action.symbol.to_uppercase(),
+                reason: "venue does not support short positions".to_string(),
+            });
+            return;
+        }
+
+        // Strategies size in f64; the order itself is decimal from here on.
+        let quantity = Quantity::from_f64(action.amount);
+        let mut order = OrderRequest::market(&action.symbol, action.side, quantity);
+
+        // Where selling can open a short, exits must not overshoot the
+        // position into the other side.
+        if action.is_exit() && supports_short {
+            order = order.reduce_only();
+        }
+
+        let _ = self.submit_order(&action.id, order, reference_price, config.execution);
+    }
+
+    /// Checks `order` against the exchange filters and the risk manager and,
+    /// if approved, fills it into the ledger at `reference_price` or sends it
+    /// to the broker. Every outcome is published as an event under
+    /// `action_id`; the paper or venue order id is returned, or the
+    /// rejection reason.
+    pub fn submit_order(
+        &self,
+        action_id: &str,
+        mut order: OrderRequest,
+        reference_price: Price,
+        execution: ExecutionMode,
+    ) -> Result<String, String> {
+        // Apply the exchange filters up front so paper fills match what the
+        // venue would accept.
+        if let Some(info) = tokio::task::block_in_place(|| self.broker.symbol_info(&order.symbol)) {
+            order = info.normalize(&order);
+
+            if let Err(violation) = info.validate(&order, reference_price) {
+                warn!(
+                    "Action {} violates exchange filters: {}",
+                    action_id, violation
+                );
+                self.events.publish(RunnerEvent::OrderRejected {
+                    action_id: action_id.to_string(),
+                    symbol: order.symbol,
+                    reason: violation.to_string(),
+                });
+                return Err(violation.to_string());
+            }
+        }
+
+        let base = split_symbol(&order.symbol).map(|(base, _)| base);
+
+        let (expected_price, open_orders) = match execution {
+            ExecutionMode::Paper => (reference_price.to_f64(), 0),
+            ExecutionMode::Live => tokio::task::block_in_place(|| {
+                (
+                    self.broker.market_current_price(&order.symbol),
+                    self.broker.open_orders(&order.symbol).len(),
+                )
+            }),
+        };
+
+        let (risk_ctx, position) = {
+            let mut ledger = self.ledger.lock().unwrap();
+
+            if let Some(base) = &base {
+                ledger.mark(base, reference_price);
+            }
+
+            let position = base
+                .as_ref()
+                .and_then(|base| ledger.position(base))
+                .map(|position| position.quantity)
+                .unwrap_or(Quantity::ZERO);
+
+            let risk_ctx = RiskContext {
+                reference_price: reference_price.to_f64(),
+                expected_price,
+                position: position.to_f64(),
+                gross_exposure: ledger.gross_exposure().to_f64().unwrap_or(0.0),
+                open_orders,
+                total_pnl: ledger.pnl().total_pnl.to_f64().unwrap_or(0.0),
+                at: Utc::now(),
+            };
+
+            (risk_ctx, position)
+        };
+
+        // The venue caps live reduce-only orders at the position; paper fills
+        // get the same treatment.
+        if order.reduce_only && execution == ExecutionMode::Paper {
+            let reducible = match order.side {
+                Side::Buy => (-position).max(Quantity::ZERO),
+                Side::Sell => position.max(Quantity::ZERO),
+            };
+            order.quantity = order.quantity.min(reducible);
+
+            if order.quantity.is_zero() {
+                warn!("Action {} has no position to reduce", action_id);
+                self.events.publish(RunnerEvent::OrderRejected {
+                    action_id: action_id.to_string(),
+                    symbol: order.symbol,
+                    reason: "no position to reduce".to_string(),
+                });
+                return Err("no position to reduce".to_string());
+            }
+        }
+
+        if let Err(rejection) = self.risk.check(&order, &risk_ctx) {
+            warn!("Risk rejected action {}: {}", action_id, rejection);
+            self.events.publish(RunnerEvent::OrderRejected {
+                action_id: action_id.to_string(),
+                symbol: order.symbol.clone(),
+                reason: rejection.to_string(),
+            });
+
+            if self.risk.limits().kill_switch_on_breach {
+                let reason = format!("risk limit breached: {rejection}");
+                tokio::task::block_in_place(|| {
+                    self.trigger_kill_switch(
+                        &reason,
+                        self.kill_switch.flatten_on_trigger,
+                        execution,
+                    )
+                });
+            }
+
+            return Err(rejection.to_string());
+        }
+
+        self.risk.record_order(risk_ctx.at);
+
+        match execution {
+            ExecutionMode::Paper => {
+                let order_id = format!("paper-{}", Uuid::new_v4());
+
+                self.events.publish(RunnerEvent::OrderSubmitted {
+                    action_id: action_id.to_string(),
+                    symbol: order.symbol.clone(),
+                    order_id: order_id.clone(),
+                    amount: order.quantity,
+                });
+
+                self.record_fill(&Fill {
+                    trade_id: None,
+                    symbol: order.symbol.clone(),
+                    side: order.side,
+                    price: reference_price,
+                    qty: order.quantity,
+                    fee: Quantity::ZERO,
+                    fee_asset: String::new(),
+                    time: risk_ctx.at,
+                });
+
+                self.events.publish(RunnerEvent::OrderFilled {
+                    action_id: action_id.to_string(),
+                    symbol: order.symbol,
+                    order_id: order_id.clone(),
+                    price: reference_price,
+                    amount: order.quantity,
+                });
+
+                Ok(order_id)
+            }
+            ExecutionMode::Live => {
+                match tokio::task::block_in_place(|| self.broker.place_order(&order)) {
+                    Ok(ack) => {
+                        // Fills arrive on the account stream, which is only
+                        // read once this returns, so the order is known by then.
+                        self.live_orders
+                            .lock()
+                            .unwrap()
+                            .insert(ack.order_id.clone(), action_id.to_string());
+
+                        self.events.publish(RunnerEvent::OrderSubmitted {
+                            action_id: action_id.to_string(),
+                            symbol: ack.symbol,
+                            order_id: ack.order_id.clone(),
+                            amount: order.quantity,
+                        });
+
+                        Ok(ack.order_id)
+                    }
+                    Err(e) => {
+                        error!("Failed to execute action {}: {}", action_id, e);
+                        self.events.publish(RunnerEvent::OrderRejected {
+                            action_id: action_id.to_string(),
+                            symbol: order.symbol,
+                            reason: e.to_string(),
+                        });
+
+                        Err(e.to_string())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Books fills from the account stream into the ledger and publishes
+    /// updates for orders this runner placed.
+    ///
+    /// Every fill on the account is booked, like `sync_trade_history`, so
+    /// rebalancer and manual trades show up in the ledger as well.
+    fn handle_account_event(&self, event: AccountEvent) {
+        let AccountEvent::Order(update) = event else {
+            return;
+        };
+
+        let action_id = {
+            let mut live_orders = self.live_orders.lock().unwrap();
+            if update.is_final() {
+                live_orders.remove(&update.order_id)
+            } else {
+                live_orders.get(&update.order_id).cloned()
+            }
+        };
+
+        if update.is_trade() {
+            self.record_fill(&Fill {
+                trade_id: update.trade_id,
+                symbol: update.symbol.clone(),
+                side: update.side,
+                price: update.last_filled_price,
+                qty: update.last_filled_qty,
+                fee: update.commission,
+                fee_asset: update.commission_asset.clone().unwrap_or_default(),
+                time: update.at,
+            });
+        }
+
+        let Some(action_id) = action_id else {
+            return;
+        };
+
+        if update.is_trade() {
+            self.events.publish(RunnerEvent::OrderFilled {
+                action_id: action_id.clone(),
+                symbol: update.symbol.clone(),
+                order_id: update.order_id.clone(),
+                price: update.last_filled_price,
+                amount: update.last_filled_qty,
+            });
+        }
+
+        self.events.publish(RunnerEvent::OrderUpdated {
+            action_id,
+            symbol: update.symbol,
+            order_id: update.order_id,
+            status: update.status,
+        });
+    }
+
+    /// Engages the kill switch and, in `Live` execution, cancels every open
+    /// order. When `flatten` is set, holdings or futures positions are
+    /// market-closed through `Broker::flatten_orders` in `Live`, or closed
+    /// in the ledger at the latest marks in `Paper`.
+    ///
+    /// Paper execution never touches the venue account, since the orders and
+    /// balances there are not the runner's.
+    ///
+    /// Performs blocking broker calls.
+    pub fn trigger_kill_switch(
+        &self,
+        reason: &str,
+        flatten: bool,
+        execution: ExecutionMode,
+    ) -> KillSwitchReport {
+        let persist_error = match self.kill_switch.engage(reason) {
+            Ok(false) => None,
+            result => {
+                self.events.publish(RunnerEvent::KillSwitchEngaged {
+                    reason: reason.to_string(),
+                });
+
+                result.err().map(|e| {
+                    self.events.publish(RunnerEvent::Error {
+                        message: format!("kill switch state not persisted: {e}"),
+                    });
+                    e.to_string()
+                })
+            }
+        };
+
+        let (cancelled_orders, cancel_error) = match execution {
+            ExecutionMode::Paper => (0, None),
+            ExecutionMode::Live => match self.broker.cancel_all_orders() {
+                Ok(count) => (count, None),
+                Err(e) => {
+                    error!("Kill switch failed to cancel open orders: {}", e);
+                    let cancelled = e
+                        .downcast_ref::<CancelAllError>()
+                        .map(|partial| partial.cancelled)
+                        .unwrap_or(0);
+                    (cancelled, Some(e.to_string()))
+                }
+            },
+        };
+
+        let flattened = match (flatten, execution) {
+            (false, _) => Vec::new(),
+            (true, ExecutionMode::Paper) => self.flatten_ledger(),
+            (true, ExecutionMode::Live) => self.flatten_positions(),
+        };
+
+        KillSwitchReport {
+            state: self.kill_switch.state(),
+            cancelled_orders,
+            cancel_error,
+            flattened,
+            persist_error,
+        }
+    }
+
+    /// Closes every ledger position with a paper fill at its latest mark.
+    fn flatten_ledger(&self) -> Vec<FlattenResult> {
+        self.mark_to_market();
+
+        let mut ledger = self.ledger.lock().unwrap();
+        let quote = ledger.quote_currency().to_string();
+        let at = Utc::now();
+
+        let mut assets = ledger.assets();
+        assets.sort();
+
+        assets
+            .into_iter()
+            .filter_map(|asset| {
+                let position = ledger.position(&asset)?;
+                if position.quantity.is_zero() {
+                    return None;
+                }
+
+                let side = if position.quantity.is_positive() {
+                    Side::Sell
+                } else {
+                    Side::Buy
+                };
+                let quantity = position.quantity.abs();
+                let price = position.last_price.unwrap_or(position.average_price);
+                let symbol = format!("{asset}{quote}");
+
+                ledger.apply_fill(&Fill {
+                    trade_id: None,
+                    symbol: symbol.clone(),
+                    side,
+                    price,
+                    qty: quantity,
+                    fee: Quantity::ZERO,
+                    fee_asset: String::new(),
+                    time: at,
+                });
+
+                Some(FlattenResult {
+                    symbol: symbol.clone(),
+                    quantity,
+                    ack: Some(OrderAck {
+                        order_id: format!("paper-{}", Uuid::new_v4()),
+                        symbol,
+                        side,
+                        status: "FILLED".to_string(),
+                        executed_qty: quantity,
+                        average_price: price,
+                    }),
+                    error: None,
+                })
+            })
+            .collect()
+    }
+
+    fn flatten_positions(&self) -> Vec<FlattenResult> {
+        let quote = self.ledger.lock().unwrap().quote_currency().to_string();
+
+        self.broker
+            .flatten_orders(&quote)
+            .into_iter()
+            .map(|order| {
+                let symbol = order.symbol.clone();
+                let quantity = order.quantity;
+
+                match self.broker.place_order(&order) {
+                    Ok(ack) => FlattenResult {
+                        symbol,
+                        quantity,
+                        ack: Some(ack),
+                        error: None,
+                    },
+                    Err(e) => {
+                        error!("Kill switch failed to flatten {}: {}", symbol, e);
+                        FlattenResult {
+                            symbol,
+                            quantity,
+                            ack: None,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
     pub async fn run_until_ctrl_c(&self, config: &RunConfig, state: State) -> StrategyContext {
         let cancel = CancellationToken::new();
         let cancel_clone = cancel.clone();
@@ -216,3 +1089,29 @@ where
         self.run_with_cancel_signal(state, config, cancel).await
     }
 }
+
+impl<B> Runner<HashMap<String, f64>, B, RegimenStrategy>
+where
+    B: Broker + Send + Sync,
+{
+    /// Activates the regimen `name` and publishes the switch. A no-op when it
+    /// is already active.
+    pub fn switch_regimen(&self, name: &str, reason: &str) -> anyhow::Result<()> {
+        let from = self.strategy.active_name();
+
+        if from == name {
+            return Ok(());
+        }
+
+        self.strategy.activate(name)?;
+
+        info!("Switched regimen {} -> {}: {}", from, name, reason);
+        self.events.publish(RunnerEvent::RegimenSwitched {
+            from,
+            to: name.to_string(),
+            reason: reason.to_string(),
+        });
+
+        Ok(())
+    }
+}
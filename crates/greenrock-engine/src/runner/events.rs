@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::{
+    models::{
+        money::{Price, Quantity},
+        timeseries::Candle,
+    },
+    strategy::core::TradingAction,
+};
+
+/// Typed events published by a `Runner` while it drives a strategy.
+///
+/// Serialized with a `type` tag so web clients can switch on the event kind.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunnerEvent {
+    TickProcessed {
+        symbol: String,
+        interval: String,
+        candle: Candle,
+        elapsed_us: u128,
+    },
+    IndicatorSnapshot {
+        symbol: String,
+        at: DateTime<Utc>,
+        indicators: HashMap<String, f64>,
+    },
+    ActionEmitted {
+        action: TradingAction,
+    },
+    OrderSubmitted {
+        action_id: String,
+        symbol: String,
+        order_id: String,
+        amount: Quantity,
+    },
+    OrderFilled {
+        action_id: String,
+        symbol: String,
+        order_id: String,
+        price: Price,
+        amount: Quantity,
+    },
+    /// Status change of a live order, from the account stream.
+    OrderUpdated {
+        action_id: String,
+        symbol: String,
+        order_id: String,
+        status: String,
+    },
+    OrderRejected {
+        action_id: String,
+        symbol: String,
+        reason: String,
+    },
+    KillSwitchEngaged {
+        reason: String,
+    },
+    /// The runner's strategy moved to another regimen.
+    RegimenSwitched {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl RunnerEvent {
+    /// Short name of the event, used as the SSE `event:` field.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RunnerEvent::TickProcessed { .. } => "tick_processed",
+            RunnerEvent::IndicatorSnapshot { .. } => "indicator_snapshot",
+            RunnerEvent::ActionEmitted { .. } => "action_emitted",
+            RunnerEvent::OrderSubmitted { .. } => "order_submitted",
+            RunnerEvent::OrderFilled { .. } => "order_filled",
+            RunnerEvent::OrderUpdated { .. } => "order_updated",
+            RunnerEvent::OrderRejected { .. } => "order_rejected",
+            RunnerEvent::KillSwitchEngaged { .. } => "kill_switch_engaged",
+            RunnerEvent::RegimenSwitched { .. } => "regimen_switched",
+            RunnerEvent::Error { .. } => "error",
+        }
+    }
+}
+
+/// Broadcast bus for `RunnerEvent`s. Publishing never fails: events are
+/// dropped when nobody is subscribed.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<RunnerEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _rx) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    pub fn publish(&self, event: RunnerEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RunnerEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::models::{money::Quantity, orders::OrderAck};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KillSwitchState {
+    pub engaged: bool,
+    pub reason: Option<String>,
+    pub engaged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlattenResult {
+    pub symbol: String,
+    pub quantity: Quantity,
+    pub ack: Option<OrderAck>,
+    pub error: Option<String>,
+}
+
+/// Outcome of engaging the kill switch through a runner.
+#[derive(Debug, Clone, Serialize)]
+pub struct KillSwitchReport {
+    pub state: KillSwitchState,
+    pub cancelled_orders: usize,
+    pub cancel_error: Option<String>,
+    pub flattened: Vec<FlattenResult>,
+    /// Set when the engaged state could not be written to disk, in which
+    /// case the halt only lasts until the next restart.
+    pub persist_error: Option<String>,
+}
+
+/// Global trading halt shared by every runner.
+///
+/// When created with `load`, the state is written to disk on every change so
+/// an engaged switch survives restarts and the engine does not resume trading
+/// until it is explicitly released.
+pub struct KillSwitch {
+    path: Option<PathBuf>,
+    state: RwLock<KillSwitchState>,
+    /// Market-close all positions when a limit breach or stale data engages
+    /// the switch automatically.
+    pub flatten_on_trigger: bool,
+}
+
+impl KillSwitch {
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            state: RwLock::new(KillSwitchState::default()),
+            flatten_on_trigger: false,
+        }
+    }
+
+    /// Loads the persisted state from `path`, starting released only when the
+    /// file does not exist yet. Any other failure starts engaged, since an
+    /// unreadable state file must not silently re-enable trading.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+
+        let halted = |reason: String| KillSwitchState {
+            engaged: true,
+            reason: Some(reason),
+            engaged_at: Some(Utc::now()),
+        };
+
+        let state = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                error!("Corrupt kill switch state at {}: {}", path.display(), e);
+                halted(format!("corrupt kill switch state: {e}"))
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => KillSwitchState::default(),
+            Err(e) => {
+                error!(
+                    "Failed to read kill switch state at {}: {}",
+                    path.display(),
+                    e
+                );
+                halted(format!("unreadable kill switch state: {e}"))
+            }
+        };
+
+        if state.engaged {
+            warn!(
+                "Kill switch is engaged ({}), trading stays halted until released",
+                state.reason.as_deref().unwrap_or("no reason")
+            );
+        }
+
+        Self {
+            path: Some(path),
+            state: RwLock::new(state),
+            flatten_on_trigger: false,
+        }
+    }
+
+    pub fn with_flatten_on_trigger(mut self, flatten: bool) -> Self {
+        self.flatten_on_trigger = flatten;
+        self
+    }
+
+    pub fn state(&self) -> KillSwitchState {
+        self.state.read().unwrap().clone()
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.state.read().unwrap().engaged
+    }
+
+    /// Engages the switch. Returns `false` when it was already engaged, in
+    /// which case the original reason is kept.
+    ///
+    /// The switch is engaged in memory even when writing the state fails;
+    /// the error is returned so the caller can report that the halt will not
+    /// survive a restart.
+    pub fn engage(&self, reason: &str) -> io::Result<bool> {
+        let mut state = self.state.write().unwrap();
+
+        if state.engaged {
+            return Ok(false);
+        }
+
+        *state = KillSwitchState {
+            engaged: true,
+            reason: Some(reason.to_string()),
+            engaged_at: Some(Utc::now()),
+        };
+
+        warn!("Kill switch engaged: {}", reason);
+        self.persist(&state)?;
+
+        Ok(true)
+    }
+
+    pub fn release(&self, reason: &str) -> io::Result<()> {
+        let mut state = self.state.write().unwrap();
+        *state = KillSwitchState::default();
+
+        info!("Kill switch released: {}", reason);
+        self.persist(&state)
+    }
+
+    /// Writes `state` to a temporary file next to the state file and renames
+    /// it into place, so a crash mid-write never leaves a truncated file.
+    fn persist(&self, state: &KillSwitchState) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let result = (|| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let content = serde_json::to_string_pretty(state)?;
+            let tmp = path.with_extension("json.tmp");
+            fs::write(&tmp, content)?;
+            fs::rename(&tmp, path)
+        })();
+
+        if let Err(e) = &result {
+            error!(
+                "Failed to persist kill switch state to {}: {}",
+                path.display(),
+                e
+            );
+        }
+
+        result
+    }
+}
+
+impl Default for KillSwitch {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("greenrock-kill-switch-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn starts_released_without_a_state_file() {
+        let switch = KillSwitch::load(temp_dir().join("kill_switch.json"));
+
+        assert!(!switch.is_engaged());
+    }
+
+    #[test]
+    fn starts_engaged_on_a_corrupt_state_file() {
+        let path = temp_dir().join("kill_switch.json");
+        fs::write(&path, "{not json").unwrap();
+
+        assert!(KillSwitch::load(&path).is_engaged());
+    }
+
+    #[test]
+    fn starts_engaged_when_the_state_cannot_be_read() {
+        // A directory where the file should be fails with something other
+        // than NotFound.
+        let path = temp_dir();
+
+        let switch = KillSwitch::load(&path);
+
+        assert!(switch.is_engaged());
+        assert!(switch.state().reason.unwrap().contains("unreadable"));
+    }
+
+    #[test]
+    fn persists_across_restarts() {
+        let path = temp_dir().join("kill_switch.json");
+
+        let switch = KillSwitch::load(&path);
+        assert!(switch.engage("daily loss").unwrap());
+        assert!(!switch.engage("again").unwrap());
+
+        let restarted = KillSwitch::load(&path);
+        assert!(restarted.is_engaged());
+        assert_eq!(restarted.state().reason.as_deref(), Some("daily loss"));
+
+        restarted.release("checked positions").unwrap();
+        assert!(!KillSwitch::load(&path).is_engaged());
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn reports_a_failed_write_but_stays_engaged() {
+        let path = temp_dir().join("kill_switch.json");
+        let switch = KillSwitch::load(&path);
+
+        // A directory in place of the state file makes the rename fail.
+        fs::create_dir(&path).unwrap();
+
+        assert!(switch.engage("daily loss").is_err());
+        assert!(switch.is_engaged());
+    }
+}
@@ -1 +1,5 @@
+pub mod backtest;
 pub mod core;
+pub mod events;
+pub mod kill_switch;
+pub mod risk;
@@ -0,0 +1,545 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fmt,
+    str::FromStr,
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::models::orders::{OrderRequest, OrderType, Side};
+
+/// Pre-trade limits applied to every order the runner sends.
+#[derive(Debug, Clone)]
+pub struct RiskLimits {
+    /// Capital allocated to the strategy, in the quote currency. Leverage is
+    /// measured against this plus the running PnL.
+    pub capital: f64,
+    /// Absolute position limit per symbol, in base asset units.
+    pub max_position_per_symbol: HashMap<String, f64>,
+    /// Position limit for symbols without an explicit entry.
+    pub default_max_position: Option<f64>,
+    pub max_order_notional: f64,
+    /// Loss since the start of the UTC day, in the quote currency.
+    pub max_daily_loss: f64,
+    pub max_open_orders: usize,
+    pub max_leverage: f64,
+    /// Maximum relative deviation of the order price from the last close.
+    pub price_collar: f64,
+    pub max_orders_per_minute: usize,
+    /// Engage the global kill switch on the first breach.
+    pub kill_switch_on_breach: bool,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        Self {
+            capital: 10_000.0,
+            max_position_per_symbol: HashMap::new(),
+            default_max_position: None,
+            max_order_notional: 1_000.0,
+            max_daily_loss: 500.0,
+            max_open_orders: 10,
+            max_leverage: 1.0,
+            price_collar: 0.05,
+            max_orders_per_minute: 10,
+            kill_switch_on_breach: false,
+        }
+    }
+}
+
+impl RiskLimits {
+    /// Defaults with the `RISK_*` variables applied: `RISK_CAPITAL`,
+    /// `RISK_MAX_ORDER_NOTIONAL`, `RISK_MAX_DAILY_LOSS`,
+    /// `RISK_MAX_OPEN_ORDERS`, `RISK_MAX_LEVERAGE`, `RISK_PRICE_COLLAR`,
+    /// `RISK_MAX_ORDERS_PER_MINUTE`, `RISK_MAX_POSITION`,
+    /// `RISK_KILL_SWITCH_ON_BREACH`, and `RISK_MAX_POSITIONS` as
+    /// `SYMBOL=limit` pairs separated by commas.
+    pub fn from_env() -> Self {
+        fn var<T: FromStr>(name: &str) -> Option<T> {
+            let value = env::var(name).ok()?;
+
+            match value.trim().parse() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    warn!("Invalid {} {}, keeping the default", name, value);
+                    None
+                }
+            }
+        }
+
+        let mut limits = Self::default();
+
+        if let Some(capital) = var("RISK_CAPITAL") {
+            limits.capital = capital;
+        }
+
+        if let Some(max_order_notional) = var("RISK_MAX_ORDER_NOTIONAL") {
+            limits.max_order_notional = max_order_notional;
+        }
+
+        if let Some(max_daily_loss) = var("RISK_MAX_DAILY_LOSS") {
+            limits.max_daily_loss = max_daily_loss;
+        }
+
+        if let Some(max_open_orders) = var("RISK_MAX_OPEN_ORDERS") {
+            limits.max_open_orders = max_open_orders;
+        }
+
+        if let Some(max_leverage) = var("RISK_MAX_LEVERAGE") {
+            limits.max_leverage = max_leverage;
+        }
+
+        if let Some(price_collar) = var("RISK_PRICE_COLLAR") {
+            limits.price_collar = price_collar;
+        }
+
+        if let Some(max_orders_per_minute) = var("RISK_MAX_ORDERS_PER_MINUTE") {
+            limits.max_orders_per_minute = max_orders_per_minute;
+        }
+
+        if let Some(max_position) = var("RISK_MAX_POSITION") {
+            limits.default_max_position = Some(max_position);
+        }
+
+        if let Some(kill_switch_on_breach) = var("RISK_KILL_SWITCH_ON_BREACH") {
+            limits.kill_switch_on_breach = kill_switch_on_breach;
+        }
+
+        if let Ok(pairs) = env::var("RISK_MAX_POSITIONS") {
+            for pair in pairs.split(',').filter(|pair| !pair.trim().is_empty()) {
+                let parsed = pair.split_once('=').and_then(|(symbol, limit)| {
+                    Some((symbol.trim().to_uppercase(), limit.trim().parse().ok()?))
+                });
+
+                match parsed {
+                    Some((symbol, limit)) => {
+                        limits.max_position_per_symbol.insert(symbol, limit);
+                    }
+                    None => warn!("Invalid RISK_MAX_POSITIONS entry {}, ignoring", pair),
+                }
+            }
+        }
+
+        limits
+    }
+}
+
+/// Snapshot of everything a check needs to know about the account.
+///
+/// Limits are checked in `f64`; order prices and quantities are converted
+/// explicitly when the check starts.
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    /// Last `Candle.close` for the order symbol.
+    pub reference_price: f64,
+    /// Price a market order is expected to fill at.
+    pub expected_price: f64,
+    /// Current signed position in the order symbol.
+    pub position: f64,
+    /// Gross exposure across all positions, in the quote currency.
+    pub gross_exposure: f64,
+    pub open_orders: usize,
+    /// Total PnL (realized + unrealized - fees) at the time of the check.
+    pub total_pnl: f64,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum RiskRejection {
+    /// The order or reference price is unknown, so no limit can be measured.
+    MissingPrice {
+        price: f64,
+        reference: f64,
+    },
+    MaxPosition {
+        position: f64,
+        limit: f64,
+    },
+    MaxOrderNotional {
+        notional: f64,
+        limit: f64,
+    },
+    MaxDailyLoss {
+        loss: f64,
+        limit: f64,
+    },
+    MaxOpenOrders {
+        open: usize,
+        limit: usize,
+    },
+    MaxLeverage {
+        leverage: f64,
+        limit: f64,
+    },
+    PriceCollar {
+        price: f64,
+        reference: f64,
+        limit: f64,
+    },
+    OrderRate {
+        orders: usize,
+        limit: usize,
+    },
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskRejection::MissingPrice { price, reference } => write!(
+                f,
+                "no usable price (order {price:.4}, reference {reference:.4})"
+            ),
+            RiskRejection::MaxPosition { position, limit } => {
+                write!(f, "position {position:.6} would exceed limit {limit:.6}")
+            }
+            RiskRejection::MaxOrderNotional { notional, limit } => {
+                write!(f, "order notional {notional:.2} exceeds limit {limit:.2}")
+            }
+            RiskRejection::MaxDailyLoss { loss, limit } => {
+                write!(f, "daily loss {loss:.2} exceeds limit {limit:.2}")
+            }
+            RiskRejection::MaxOpenOrders { open, limit } => {
+                write!(f, "{open} open orders, limit is {limit}")
+            }
+            RiskRejection::MaxLeverage { leverage, limit } => {
+                write!(f, "leverage {leverage:.2}x would exceed {limit:.2}x")
+            }
+            RiskRejection::PriceCollar {
+                price,
+                reference,
+                limit,
+            } => write!(
+                f,
+                "price {price:.4} is outside the {:.2}% collar around {reference:.4}",
+                limit * 100.0
+            ),
+            RiskRejection::OrderRate { orders, limit } => {
+                write!(f, "{orders} orders in the last minute, limit is {limit}")
+            }
+        }
+    }
+}
+
+struct RiskState {
+    recent_orders: VecDeque<DateTime<Utc>>,
+    day: Option<NaiveDate>,
+    day_start_pnl: f64,
+}
+
+pub struct RiskManager {
+    limits: RiskLimits,
+    state: Mutex<RiskState>,
+}
+
+impl RiskManager {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            state: Mutex::new(RiskState {
+                recent_orders: VecDeque::new(),
+                day: None,
+                day_start_pnl: 0.0,
+            }),
+        }
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Checks `order` against every limit, returning the first breach.
+    pub fn check(&self, order: &OrderRequest, ctx: &RiskContext) -> Result<(), RiskRejection> {
+        let limits = &self.limits;
+        let mut state = self.state.lock().unwrap();
+
+        // Reset the daily loss baseline on the first check of each UTC day.
+        let today = ctx.at.date_naive();
+        if state.day != Some(today) {
+            state.day = Some(today);
+            state.day_start_pnl = ctx.total_pnl;
+        }
+
+        let price = match order.order_type {
+            OrderType::Limit { price } => price.to_f64(),
+            // Stops fill at market once triggered, often far from the
+            // current price by design.
+            OrderType::Market | OrderType::StopMarket { .. } => ctx.expected_price,
+        };
+        let quantity = order.quantity.to_f64();
+
+        // A failed price lookup reads as 0, which would slip past the
+        // notional and collar limits.
+        if !(price > 0.0 && ctx.reference_price > 0.0) {
+            return Err(RiskRejection::MissingPrice {
+                price,
+                reference: ctx.reference_price,
+            });
+        }
+
+        let deviation = (price - ctx.reference_price).abs() / ctx.reference_price;
+        if deviation > limits.price_collar {
+            return Err(RiskRejection::PriceCollar {
+                price,
+                reference: ctx.reference_price,
+                limit: limits.price_collar,
+            });
+        }
+
+        let notional = quantity * price;
+        if notional > limits.max_order_notional {
+            return Err(RiskRejection::MaxOrderNotional {
+                notional,
+                limit: limits.max_order_notional,
+            });
+        }
+
+        let signed_qty = match order.side {
+            Side::Buy => quantity,
+            Side::Sell => -quantity,
+        };
+        let new_position = ctx.position + signed_qty;
+
+        // Orders that only shrink the position stay allowed past the
+        // position, loss and leverage limits so risk can always be reduced.
+        let increases_exposure = new_position.abs() > ctx.position.abs();
+
+        let position_limit = limits
+            .max_position_per_symbol
+            .get(&order.symbol.to_uppercase())
+            .copied()
+            .or(limits.default_max_position);
+
+        if let Some(limit) = position_limit
+            && increases_exposure
+            && new_position.abs() > limit
+        {
+            return Err(RiskRejection::MaxPosition {
+                position: new_position,
+                limit,
+            });
+        }
+
+        let loss = state.day_start_pnl - ctx.total_pnl;
+        if increases_exposure && loss > limits.max_daily_loss {
+            return Err(RiskRejection::MaxDailyLoss {
+                loss,
+                limit: limits.max_daily_loss,
+            });
+        }
+
+        if ctx.open_orders >= limits.max_open_orders {
+            return Err(RiskRejection::MaxOpenOrders {
+                open: ctx.open_orders,
+                limit: limits.max_open_orders,
+            });
+        }
+
+        let capital = limits.capital + ctx.total_pnl;
+        let exposure_change = (new_position.abs() - ctx.position.abs()) * price;
+        let leverage = if capital > 0.0 {
+            (ctx.gross_exposure + exposure_change).max(0.0) / capital
+        } else {
+            f64::INFINITY
+        };
+
+        if increases_exposure && leverage > limits.max_leverage {
+            return Err(RiskRejection::MaxLeverage {
+                leverage,
+                limit: limits.max_leverage,
+            });
+        }
+
+        let window_start = ctx.at - Duration::minutes(1);
+        while state
+            .recent_orders
+            .front()
+            .is_some_and(|at| *at < window_start)
+        {
+            state.recent_orders.pop_front();
+        }
+
+        if state.recent_orders.len() >= limits.max_orders_per_minute {
+            return Err(RiskRejection::OrderRate {
+                orders: state.recent_orders.len(),
+                limit: limits.max_orders_per_minute,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Records an accepted order for rate limiting.
+    pub fn record_order(&self, at: DateTime<Utc>) {
+        self.state.lock().unwrap().recent_orders.push_back(at);
+    }
+}
+
+impl Default for RiskManager {
+    fn default() -> Self {
+        Self::new(RiskLimits::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::money::{Price, Quantity};
+
+    fn ctx() -> RiskContext {
+        RiskContext {
+            reference_price: 100.0,
+            expected_price: 100.0,
+            position: 0.0,
+            gross_exposure: 0.0,
+            open_orders: 0,
+            total_pnl: 0.0,
+            at: Utc::now(),
+        }
+    }
+
+    fn order(side: Side, quantity: f64) -> OrderRequest {
+        OrderRequest::market("BTCUSDT", side, Quantity::from_f64(quantity))
+    }
+
+    #[test]
+    fn accepts_orders_within_limits() {
+        let risk = RiskManager::default();
+
+        assert!(risk.check(&order(Side::Buy, 1.0), &ctx()).is_ok());
+    }
+
+    #[test]
+    fn rejects_orders_without_a_price() {
+        let risk = RiskManager::default();
+
+        let no_quote = RiskContext {
+            expected_price: 0.0,
+            ..ctx()
+        };
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 1.0), &no_quote),
+            Err(RiskRejection::MissingPrice { .. })
+        ));
+
+        let no_reference = RiskContext {
+            reference_price: 0.0,
+            ..ctx()
+        };
+        assert!(matches!(
+            risk.check(&order(Side::Sell, 1.0), &no_reference),
+            Err(RiskRejection::MissingPrice { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_prices_outside_the_collar() {
+        let risk = RiskManager::default();
+        let limit = OrderRequest::limit(
+            "BTCUSDT",
+            Side::Buy,
+            Quantity::from_f64(1.0),
+            Price::from_f64(110.0),
+        );
+
+        assert!(matches!(
+            risk.check(&limit, &ctx()),
+            Err(RiskRejection::PriceCollar { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_large_orders() {
+        let risk = RiskManager::default();
+
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 20.0), &ctx()),
+            Err(RiskRejection::MaxOrderNotional { .. })
+        ));
+    }
+
+    #[test]
+    fn position_limit_only_blocks_increases() {
+        let risk = RiskManager::new(RiskLimits {
+            default_max_position: Some(1.0),
+            ..RiskLimits::default()
+        });
+        let long = RiskContext {
+            position: 1.0,
+            gross_exposure: 100.0,
+            ..ctx()
+        };
+
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 0.5), &long),
+            Err(RiskRejection::MaxPosition { .. })
+        ));
+        assert!(risk.check(&order(Side::Sell, 0.5), &long).is_ok());
+    }
+
+    #[test]
+    fn daily_loss_blocks_new_exposure() {
+        let risk = RiskManager::default();
+        let start = ctx();
+        risk.check(&order(Side::Buy, 1.0), &start).unwrap();
+
+        let losing = RiskContext {
+            position: 1.0,
+            gross_exposure: 100.0,
+            total_pnl: -600.0,
+            ..start
+        };
+
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 1.0), &losing),
+            Err(RiskRejection::MaxDailyLoss { .. })
+        ));
+        assert!(risk.check(&order(Side::Sell, 1.0), &losing).is_ok());
+    }
+
+    #[test]
+    fn rejects_too_many_open_orders() {
+        let risk = RiskManager::default();
+        let busy = RiskContext {
+            open_orders: 10,
+            ..ctx()
+        };
+
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 1.0), &busy),
+            Err(RiskRejection::MaxOpenOrders { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_leverage_above_the_limit() {
+        let risk = RiskManager::default();
+        let exposed = RiskContext {
+            gross_exposure: 9_950.0,
+            ..ctx()
+        };
+
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 1.0), &exposed),
+            Err(RiskRejection::MaxLeverage { .. })
+        ));
+    }
+
+    #[test]
+    fn rate_limits_orders_per_minute() {
+        let risk = RiskManager::default();
+        let ctx = ctx();
+
+        for _ in 0..10 {
+            risk.record_order(ctx.at);
+        }
+
+        assert!(matches!(
+            risk.check(&order(Side::Buy, 1.0), &ctx),
+            Err(RiskRejection::OrderRate { .. })
+        ));
+    }
+}
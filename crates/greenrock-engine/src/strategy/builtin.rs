@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use tracing::info;
+
+use crate::{
+    models::{analysis::TechnicalAnalysis, orders::Side, timeseries::Candle},
+    strategy::core::{Strategy, StrategyAction, StrategyContext, StrategyTraitKind, TradingAction},
+};
+
+/// State key set to 1 while the strategy holds its long position.
+const IN_POSITION: &str = "in_position";
+
+fn long_action(
+    id: &str,
+    at: DateTime<Utc>,
+    symbol: String,
+    side: Side,
+    amount: f64,
+) -> StrategyAction {
+    StrategyAction::Emitted(Box::new(TradingAction {
+        id: id.to_string(),
+        timestamp: at,
+        symbol,
+        side,
+        amount,
+        kind: StrategyTraitKind::Long,
+    }))
+}
+
+/// Long while the fast EMA is above the slow one. Suited to trending markets.
+#[derive(Clone)]
+pub struct EmaCrossStrategy {
+    pub fast_period: usize,
+    pub slow_period: usize,
+    pub amount: f64,
+}
+
+impl Default for EmaCrossStrategy {
+    fn default() -> Self {
+        Self {
+            fast_period: 12,
+            slow_period: 26,
+            amount: 0.01,
+        }
+    }
+}
+
+impl Strategy for EmaCrossStrategy {
+    type State = HashMap<String, f64>;
+
+    fn init(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+    ) -> (StrategyContext, Self::State) {
+        info!("init ema cross strategy");
+        (ctx.clone(), state.clone())
+    }
+
+    fn end(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+    ) -> (StrategyContext, Self::State) {
+        (ctx.clone(), state.clone())
+    }
+
+    fn tick(
+        &self,
+        _ctx: &mut StrategyContext,
+        at: DateTime<Utc>,
+        state: &mut Self::State,
+        symbol: String,
+        data_scope: Vec<Candle>,
+        _tick: Candle,
+    ) -> StrategyAction {
+        if data_scope.len() < self.slow_period {
+            return StrategyAction::Pass;
+        }
+
+        let fast = data_scope.ema(self.fast_period);
+        let slow = data_scope.ema(self.slow_period);
+        state.insert("ema_fast".to_string(), fast);
+        state.insert("ema_slow".to_string(), slow);
+
+        let in_position = state.get(IN_POSITION).is_some_and(|value| *value > 0.0);
+
+        if fast > slow && !in_position {
+            state.insert(IN_POSITION.to_string(), 1.0);
+            return long_action("ema_cross_buy", at, symbol, Side::Buy, self.amount);
+        }
+
+        if fast < slow && in_position {
+            state.insert(IN_POSITION.to_string(), 0.0);
+            return long_action("ema_cross_sell", at, symbol, Side::Sell, self.amount);
+        }
+
+        StrategyAction::Pass
+    }
+
+    fn initial_state(&self) -> Self::State {
+        Self::State::default()
+    }
+
+    fn portfolio(&self) -> HashMap<String, f64> {
+        HashMap::from([("BTCUSDT".to_string(), 1.0)])
+    }
+
+    fn indicators(&self, state: &Self::State) -> HashMap<String, f64> {
+        ["ema_fast", "ema_slow"]
+            .iter()
+            .filter_map(|key| state.get(*key).map(|value| (key.to_string(), *value)))
+            .collect()
+    }
+}
+
+/// Buys oversold RSI and sells once it recovers. Suited to ranging markets.
+#[derive(Clone)]
+pub struct RsiReversionStrategy {
+    pub period: usize,
+    pub oversold: f64,
+    pub exit: f64,
+    pub amount: f64,
+}
+
+impl Default for RsiReversionStrategy {
+    fn default() -> Self {
+        Self {
+            period: 14,
+            oversold: 30.0,
+            exit: 55.0,
+            amount: 0.01,
+        }
+    }
+}
+
+impl Strategy for RsiReversionStrategy {
+    type State = HashMap<String, f64>;
+
+    fn init(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+    ) -> (StrategyContext, Self::State) {
+        info!("init rsi reversion strategy");
+        (ctx.clone(), state.clone())
+    }
+
+    fn end(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+    ) -> (StrategyContext, Self::State) {
+        (ctx.clone(), state.clone())
+    }
+
+    fn tick(
+        &self,
+        _ctx: &mut StrategyContext,
+        at: DateTime<Utc>,
+        state: &mut Self::State,
+        symbol: String,
+        data_scope: Vec<Candle>,
+        _tick: Candle,
+    ) -> StrategyAction {
+        if data_scope.len() <= self.period {
+            return StrategyAction::Pass;
+        }
+
+        let rsi = data_scope.rsi(self.period);
+        state.insert("rsi".to_string(), rsi);
+
+        let in_position = state.get(IN_POSITION).is_some_and(|value| *value > 0.0);
+
+        if rsi < self.oversold && !in_position {
+            state.insert(IN_POSITION.to_string(), 1.0);
+            return long_action("rsi_reversion_buy", at, symbol, Side::Buy, self.amount);
+        }
+
+        if rsi > self.exit && in_position {
+            state.insert(IN_POSITION.to_string(), 0.0);
+            return long_action("rsi_reversion_sell", at, symbol, Side::Sell, self.amount);
+        }
+
+        StrategyAction::Pass
+    }
+
+    fn initial_state(&self) -> Self::State {
+        Self::State::default()
+    }
+
+    fn portfolio(&self) -> HashMap<String, f64> {
+        HashMap::from([("BTCUSDT".to_string(), 1.0)])
+    }
+
+    fn indicators(&self, state: &Self::State) -> HashMap<String, f64> {
+        state
+            .get("rsi")
+            .map(|rsi| HashMap::from([("rsi".to_string(), *rsi)]))
+            .unwrap_or_default()
+    }
+}
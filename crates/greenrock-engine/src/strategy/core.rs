@@ -3,15 +3,24 @@ use std::{collections::HashMap, time::Instant};
 use chrono::{DateTime, Utc};
 use polars::frame::DataFrame;
 use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::Serialize;
 // use ta::{DataItem, Next, indicators::MovingAverageConvergenceDivergence};
 use tracing::info;
 
-use crate::models::{analysis::TechnicalAnalysis, timeseries::Candle};
+use crate::models::{
+    analysis::TechnicalAnalysis,
+    orders::Side,
+    timeseries::{Candle, Trade},
+};
 // use rust_decimal::prelude::*;
 
-#[derive(Clone)]
+/// Direction of the position an action belongs to. Long actions buy to open
+/// and sell to close, short actions sell to open and buy to cover.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum StrategyTraitKind {
     Short,
+    #[default]
     Long,
 }
 
@@ -74,6 +83,22 @@ pub trait Strategy: Send + Sync {
     fn initial_state(&self) -> Self::State;
 
     fn portfolio(&self) -> HashMap<String, f64>;
+
+    /// Called for every trade when the runner is configured with a trade
+    /// feed, for strategies that react faster than the candle interval.
+    fn on_trade(
+        &self,
+        _ctx: &mut StrategyContext,
+        _state: &mut Self::State,
+        _trade: &Trade,
+    ) -> StrategyAction {
+        StrategyAction::Pass
+    }
+
+    /// Indicator values to publish after each tick, keyed by name.
+    fn indicators(&self, _state: &Self::State) -> HashMap<String, f64> {
+        HashMap::new()
+    }
 }
 
 #[derive(Clone)]
@@ -98,15 +123,28 @@ impl MinimalStrategy {
 //     Nothing,
 // }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct TradingAction {
     pub id: String,
     pub timestamp: DateTime<Utc>,
     pub symbol: String,
+    pub side: Side,
     pub amount: f64,
+    pub kind: StrategyTraitKind,
     // pub action: StrategyAction,
 }
 
+impl TradingAction {
+    /// Whether the action closes or reduces a position instead of opening
+    /// one: selling a long or covering a short.
+    pub fn is_exit(&self) -> bool {
+        matches!(
+            (self.kind, self.side),
+            (StrategyTraitKind::Long, Side::Sell) | (StrategyTraitKind::Short, Side::Buy)
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum StrategyAction {
     Emitted(Box<TradingAction>),
@@ -181,7 +219,9 @@ impl Strategy for MinimalStrategy {
                 id: "sell".to_string(),
                 timestamp,
                 symbol,
+                side: Side::Sell,
                 amount: 0.01,
+                kind: StrategyTraitKind::Long,
             }));
         }
 
@@ -237,4 +277,11 @@ impl Strategy for MinimalStrategy {
 
         portfolio
     }
+
+    fn indicators(&self, state: &Self::State) -> HashMap<String, f64> {
+        ["macd", "ema", "st"]
+            .iter()
+            .filter_map(|key| state.get(*key).map(|value| (key.to_string(), *value)))
+            .collect()
+    }
 }
@@ -1,2 +1,4 @@
+pub mod builtin;
 pub mod core;
+pub mod regimen;
 pub mod utils;
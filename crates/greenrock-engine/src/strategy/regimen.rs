@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, RwLock},
+};
+
+use chrono::{DateTime, Utc};
+use polars::frame::DataFrame;
+use tracing::{info, warn};
+
+use crate::{
+    models::timeseries::{Candle, Trade},
+    strategy::{
+        builtin::{EmaCrossStrategy, RsiReversionStrategy},
+        core::{MinimalStrategy, Strategy, StrategyAction, StrategyContext},
+    },
+};
+
+/// A registered strategy. All regimens share the `HashMap` state so that the
+/// runner can switch between them without changing its type.
+pub type Regimen = Arc<dyn Strategy<State = HashMap<String, f64>>>;
+
+/// State key holding the index of the regimen the state belongs to.
+const REGIMEN_KEY: &str = "regimen_index";
+
+/// Strategy that delegates to one of several named regimens and can be
+/// switched at runtime. The shared state is cleared on the first tick after
+/// a switch, so regimens never see each other's keys.
+pub struct RegimenStrategy {
+    regimens: Vec<(String, Regimen)>,
+    active: RwLock<usize>,
+}
+
+impl RegimenStrategy {
+    pub fn new(name: &str, regimen: Regimen) -> Self {
+        Self {
+            regimens: vec![(name.to_string(), regimen)],
+            active: RwLock::new(0),
+        }
+    }
+
+    pub fn with_regimen(mut self, name: &str, regimen: Regimen) -> Self {
+        self.regimens.push((name.to_string(), regimen));
+        self
+    }
+
+    /// `minimal`, `ema_cross` and `rsi_reversion`, starting with `minimal`.
+    pub fn builtin() -> Self {
+        Self::new(
+            "minimal",
+            Arc::new(MinimalStrategy::new(DataFrame::new(vec![]).unwrap())),
+        )
+        .with_regimen("ema_cross", Arc::new(EmaCrossStrategy::default()))
+        .with_regimen("rsi_reversion", Arc::new(RsiReversionStrategy::default()))
+    }
+
+    /// Built-in regimens with the one named by `REGIMEN` active.
+    pub fn from_env() -> Self {
+        let strategy = Self::builtin();
+
+        if let Ok(name) = env::var("REGIMEN")
+            && let Err(e) = strategy.activate(&name)
+        {
+            warn!("{}, keeping {}", e, strategy.active_name());
+        }
+
+        strategy
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.regimens.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn regimens(&self) -> impl Iterator<Item = (&str, &Regimen)> {
+        self.regimens
+            .iter()
+            .map(|(name, regimen)| (name.as_str(), regimen))
+    }
+
+    pub fn active_name(&self) -> String {
+        self.regimens[*self.active.read().unwrap()].0.clone()
+    }
+
+    /// Makes `name` the regimen that receives ticks and trades.
+    pub fn activate(&self, name: &str) -> anyhow::Result<()> {
+        let Some(index) = self.regimens.iter().position(|(n, _)| n == name) else {
+            anyhow::bail!(
+                "unknown regimen {name}, expected one of {}",
+                self.names().join(", ")
+            );
+        };
+
+        *self.active.write().unwrap() = index;
+        info!("Regimen {} active", name);
+
+        Ok(())
+    }
+
+    /// Active regimen, after resetting `state` if it belongs to another one.
+    fn active(&self, state: &mut HashMap<String, f64>) -> &Regimen {
+        let index = *self.active.read().unwrap();
+
+        if state.get(REGIMEN_KEY) != Some(&(index as f64)) {
+            state.clear();
+            state.insert(REGIMEN_KEY.to_string(), index as f64);
+        }
+
+        &self.regimens[index].1
+    }
+}
+
+impl Strategy for RegimenStrategy {
+    type State = HashMap<String, f64>;
+
+    fn init(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+    ) -> (StrategyContext, Self::State) {
+        self.active(state).init(ctx, state)
+    }
+
+    fn end(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+    ) -> (StrategyContext, Self::State) {
+        self.active(state).end(ctx, state)
+    }
+
+    fn tick(
+        &self,
+        ctx: &mut StrategyContext,
+        at: DateTime<Utc>,
+        state: &mut Self::State,
+        symbol: String,
+        data_scope: Vec<Candle>,
+        tick: Candle,
+    ) -> StrategyAction {
+        self.active(state)
+            .tick(ctx, at, state, symbol, data_scope, tick)
+    }
+
+    fn initial_state(&self) -> Self::State {
+        Self::State::default()
+    }
+
+    fn portfolio(&self) -> HashMap<String, f64> {
+        let index = *self.active.read().unwrap();
+        self.regimens[index].1.portfolio()
+    }
+
+    fn on_trade(
+        &self,
+        ctx: &mut StrategyContext,
+        state: &mut Self::State,
+        trade: &Trade,
+    ) -> StrategyAction {
+        self.active(state).on_trade(ctx, state, trade)
+    }
+
+    fn indicators(&self, state: &Self::State) -> HashMap<String, f64> {
+        let index = *self.active.read().unwrap();
+        self.regimens[index].1.indicators(state)
+    }
+}
@@ -31,6 +31,7 @@ import {
   fetchCandles,
   fetchOrderBook,
   // fetchPortfolio,
+  parseApiCandle,
 } from "./utils/core";
 
 export default function App() {
@@ -143,7 +144,7 @@ export default function App() {
 
       ws.onmessage = (event) => {
         try {
-          const apiCandle: ApiCandle = JSON.parse(event.data);
+          const apiCandle: ApiCandle = parseApiCandle(JSON.parse(event.data));
           // console.log("Received candle:", apiCandle);
 
           // Update candles state
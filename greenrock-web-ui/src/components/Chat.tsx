@@ -1,16 +1,20 @@
 import { useState, useCallback, useRef, useEffect } from "react";
-import { sendChatMessage } from "../utils/core";
-import type { ChatRequest, ChatResponse, PauseResponse } from "../types/core";
+import { streamChatMessage } from "../utils/core";
+import type { ChatRequest, ChatStreamEvent } from "../types/core";
 
 interface ChatMessage {
   id: string;
   role: "user" | "assistant";
   content: string;
   timestamp: Date;
-  status?: "completed" | "paused" | "error";
+  status?: "completed" | "paused" | "waiting_for_input" | "error";
   sessionId?: string;
 }
 
+// Task ids are Rust type paths; the last segment names the task
+const taskName = (taskId: string): string =>
+  (taskId.split("::").pop() ?? taskId).replace(/Task$/, "");
+
 interface ChatComponentProps {
   onClose: () => void;
 }
@@ -19,6 +23,7 @@ export function ChatComponent({ onClose }: ChatComponentProps) {
   const [messages, setMessages] = useState<ChatMessage[]>([]);
   const [inputValue, setInputValue] = useState("");
   const [isLoading, setIsLoading] = useState(false);
+  const [progress, setProgress] = useState<string | null>(null);
   const [currentSessionId, setCurrentSessionId] = useState<string | null>(null);
   const [isMobile, setIsMobile] = useState(false);
   const messagesEndRef = useRef<HTMLDivElement>(null);
@@ -56,56 +61,83 @@ export function ChatComponent({ onClose }: ChatComponentProps) {
     setInputValue("");
     setIsLoading(true);
 
+    // The reply is shown as it streams in and replaced by the final answer
+    const assistantId = (Date.now() + 1).toString();
+
+    const updateAssistant = (patch: Partial<ChatMessage>) => {
+      setMessages((prev) => {
+        const existing = prev.find((message) => message.id === assistantId);
+        if (!existing) {
+          return [
+            ...prev,
+            {
+              id: assistantId,
+              role: "assistant",
+              content: "",
+              timestamp: new Date(),
+              ...patch,
+            },
+          ];
+        }
+        return prev.map((message) =>
+          message.id === assistantId ? { ...message, ...patch } : message
+        );
+      });
+    };
+
     try {
       const request: ChatRequest = {
         query: inputValue,
         session_id: currentSessionId || undefined,
       };
 
-      const response = await sendChatMessage(request);
-
-      let assistantMessage: ChatMessage;
-
-      if ("answer" in response) {
-        // ChatResponse
-        const chatResponse = response as ChatResponse;
-        assistantMessage = {
-          id: (Date.now() + 1).toString(),
-          role: "assistant",
-          content: chatResponse.answer,
-          timestamp: new Date(),
-          status: chatResponse.status as "completed",
-          sessionId: chatResponse.session_id,
-        };
-        setCurrentSessionId(chatResponse.session_id);
-      } else {
-        // PauseResponse
-        const pauseResponse = response as PauseResponse;
-        assistantMessage = {
-          id: (Date.now() + 1).toString(),
-          role: "assistant",
-          content: `Task paused: ${pauseResponse.reason}\nNext task: ${pauseResponse.next_task}`,
-          timestamp: new Date(),
-          status: "paused",
-          sessionId: pauseResponse.session_id,
-        };
-        setCurrentSessionId(pauseResponse.session_id);
-      }
+      let streamed = "";
+      let streamedBy: string | null = null;
 
-      setMessages((prev) => [...prev, assistantMessage]);
+      await streamChatMessage(request, (event: ChatStreamEvent) => {
+        switch (event.type) {
+          case "task_started":
+            setProgress(`Running ${taskName(event.task_id)}...`);
+            break;
+          case "tool_call":
+            setProgress(`Called ${event.tool}`);
+            break;
+          case "token":
+            // The reply task rewrites the streamed entry answer when a
+            // branch added findings.
+            if (event.task_id !== streamedBy) {
+              streamedBy = event.task_id;
+              streamed = "";
+            }
+            streamed += event.text;
+            updateAssistant({ content: streamed });
+            break;
+          case "finished":
+            updateAssistant({
+              content: event.answer,
+              status: event.status as ChatMessage["status"],
+              sessionId: event.session_id,
+            });
+            setCurrentSessionId(event.session_id);
+            break;
+          case "error":
+            updateAssistant({
+              content: `Error: ${event.message}`,
+              status: "error",
+            });
+            break;
+        }
+      });
     } catch (error) {
-      const errorMessage: ChatMessage = {
-        id: (Date.now() + 1).toString(),
-        role: "assistant",
+      updateAssistant({
         content: `Error: ${
           error instanceof Error ? error.message : "Unknown error"
         }`,
-        timestamp: new Date(),
         status: "error",
-      };
-      setMessages((prev) => [...prev, errorMessage]);
+      });
     } finally {
       setIsLoading(false);
+      setProgress(null);
     }
   }, [inputValue, isLoading, currentSessionId]);
 
@@ -179,7 +211,8 @@ export function ChatComponent({ onClose }: ChatComponentProps) {
                     ? "bg-blue-600 text-white"
                     : message.status === "error"
                     ? "bg-rose-600 text-white"
-                    : message.status === "paused"
+                    : message.status === "paused" ||
+                      message.status === "waiting_for_input"
                     ? "bg-yellow-600 text-white"
                     : "bg-neutral-700 text-white"
                 }`}
@@ -190,7 +223,9 @@ export function ChatComponent({ onClose }: ChatComponentProps) {
                 <div className="text-xs opacity-75 mt-1">
                   {message.timestamp.toLocaleTimeString()}
                   {message.status && (
-                    <span className="ml-2 capitalize">({message.status})</span>
+                    <span className="ml-2 capitalize">
+                      ({message.status.replace(/_/g, " ")})
+                    </span>
                   )}
                 </div>
               </div>
@@ -212,7 +247,9 @@ export function ChatComponent({ onClose }: ChatComponentProps) {
                     style={{ animationDelay: "0.2s" }}
                   ></div>
                 </div>
-                <span className="text-sm">AI is thinking...</span>
+                <span className="text-sm">
+                  {progress ?? "AI is thinking..."}
+                </span>
               </div>
             </div>
           </div>
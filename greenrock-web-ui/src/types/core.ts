@@ -19,6 +19,17 @@ export type ApiCandle = {
   volume: number;
 };
 
+// Candle as sent by the engine; prices and volume are decimal strings
+export type ApiCandleResponse = {
+  close: string;
+  high: string;
+  low: string;
+  open: string;
+  timestamp: number;
+  ts: string;
+  volume: string;
+};
+
 export type Balance = {
   [symbol: string]: number;
 };
@@ -42,6 +53,16 @@ export type PauseResponse = {
   reason: string;
 };
 
+// Events of POST /chat/stream
+export type ChatStreamEvent =
+  | { type: "task_started"; task_id: string }
+  | { type: "task_completed"; task_id: string; elapsed_ms: number }
+  | { type: "task_failed"; task_id: string; error: string }
+  | { type: "token"; task_id: string; text: string }
+  | { type: "tool_call"; tool: string; args: unknown; error: string | null }
+  | { type: "finished"; session_id: string; status: string; answer: string }
+  | { type: "error"; message: string };
+
 // Portfolio types
 export type Portfolio = {
   [symbol: string]: number;
@@ -53,47 +74,46 @@ export type OrderBookEntry = {
   quantity: number;
 };
 
+// Decimals arrive as strings from the API
+export type OrderBookResponse = {
+  symbol: string;
+  last_update_id: number;
+  bids: { price: string; quantity: string }[];
+  asks: { price: string; quantity: string }[];
+  timestamp: number;
+};
+
 export type OrderBook = {
   symbol: string;
+  last_update_id: number;
   bids: OrderBookEntry[];
   asks: OrderBookEntry[];
   timestamp: number;
 };
 
-// Trading types - API response format
-export type BinanceTradeResponse = {
-  id: number;
+// Trading types - API response format (venue-neutral broker models)
+export type FillResponse = {
+  trade_id: number | null;
+  symbol: string;
+  side: "buy" | "sell";
   price: string;
   qty: string;
-  commission: string;
-  commissionAsset: string;
-  time: number;
-  isBuyer: boolean;
-  isMaker: boolean;
-  isBestMatch: boolean;
+  fee: string;
+  fee_asset: string;
+  time: string;
 };
 
-export type BinanceOrderResponse = {
+export type OrderResponse = {
+  order_id: string;
+  client_order_id: string;
   symbol: string;
-  orderId: number;
-  orderListId: number;
-  clientOrderId: string;
+  side: "buy" | "sell";
+  order_type: string;
   price: string;
-  origQty: string;
-  executedQty: string;
-  cummulativeQuoteQty: string;
+  quantity: string;
+  executed_qty: string;
   status: string;
-  timeInForce: string;
-  type: string;
-  side: string;
-  stopPrice: string;
-  icebergQty: string;
-  time: number;
-  updateTime: number;
-  isWorking: boolean;
-  workingTime: number;
-  origQuoteOrderQty: string;
-  selfTradePreventionMode: string;
+  time: string;
 };
 
 // Trading types - UI format
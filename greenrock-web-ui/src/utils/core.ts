@@ -1,16 +1,19 @@
 import type { Time } from "lightweight-charts";
 import type {
   ApiCandle,
+  ApiCandleResponse,
   Balance,
-  BinanceOrderResponse,
-  BinanceTradeResponse,
+  FillResponse,
+  OrderResponse,
   Candle,
   CandlesQuery,
   ChatRequest,
   ChatResponse,
+  ChatStreamEvent,
   Order,
   OrderBook,
   OrderBookQuery,
+  OrderBookResponse,
   PauseResponse,
   Portfolio,
   TimeRangePresets,
@@ -57,6 +60,16 @@ const createWebSocketUrl = (path: string): string => {
   return url.toString();
 };
 
+// Parse the decimal strings of an engine candle
+export const parseApiCandle = (candle: ApiCandleResponse): ApiCandle => ({
+  ...candle,
+  open: parseFloat(candle.open),
+  high: parseFloat(candle.high),
+  low: parseFloat(candle.low),
+  close: parseFloat(candle.close),
+  volume: parseFloat(candle.volume),
+});
+
 // Convert API candles to chart format
 export const convertApiCandlesToChart = (apiCandles: ApiCandle[]): Candle[] => {
   // console.log("Converting candles:", apiCandles.length, "samples:");
@@ -166,34 +179,30 @@ export const getTimeRangePresets = (): TimeRangePresets => {
   return presets;
 };
 
-// Transform Binance API responses to UI format
-export const transformBinanceTradeToUI = (
-  binanceTrade: BinanceTradeResponse,
-  symbol: string
-): Trade => {
+// Transform broker API responses to UI format
+export const transformFillToUI = (fill: FillResponse, index: number): Trade => {
   return {
-    id: binanceTrade.id.toString(),
-    symbol: symbol,
-    side: binanceTrade.isBuyer ? "buy" : "sell",
-    quantity: parseFloat(binanceTrade.qty),
-    price: parseFloat(binanceTrade.price),
-    timestamp: binanceTrade.time,
-    fee: parseFloat(binanceTrade.commission),
+    id: fill.trade_id?.toString() ?? `${fill.time}-${index}`,
+    symbol: fill.symbol,
+    side: fill.side,
+    quantity: parseFloat(fill.qty),
+    price: parseFloat(fill.price),
+    timestamp: new Date(fill.time).getTime(),
+    fee: parseFloat(fill.fee),
   };
 };
 
-export const transformBinanceOrderToUI = (
-  binanceOrder: BinanceOrderResponse
-): Order => {
+export const transformOrderToUI = (order: OrderResponse): Order => {
+  const price = parseFloat(order.price);
   return {
-    id: binanceOrder.orderId.toString(),
-    symbol: binanceOrder.symbol,
-    side: binanceOrder.side.toLowerCase() as "buy" | "sell",
-    type: binanceOrder.type.toLowerCase() as "market" | "limit",
-    quantity: parseFloat(binanceOrder.origQty),
-    price: binanceOrder.price ? parseFloat(binanceOrder.price) : undefined,
-    status: binanceOrder.status.toLowerCase(),
-    timestamp: binanceOrder.time,
+    id: order.order_id,
+    symbol: order.symbol,
+    side: order.side,
+    type: order.order_type.toLowerCase() as "market" | "limit",
+    quantity: parseFloat(order.quantity),
+    price: price > 0 ? price : undefined,
+    status: order.status.toLowerCase(),
+    timestamp: new Date(order.time).getTime(),
   };
 };
 
@@ -217,6 +226,53 @@ export const sendChatMessage = async (
   return response.json();
 };
 
+// Streams a chat turn, calling onEvent for every Server-Sent Event until the
+// "finished" or "error" event closes the stream
+export const streamChatMessage = async (
+  request: ChatRequest,
+  onEvent: (event: ChatStreamEvent) => void
+): Promise<void> => {
+  const url = createApiUrl("/chat/stream");
+  const response = await fetch(url, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify(request),
+  });
+  if (!response.ok || !response.body) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+
+  const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+  let buffer = "";
+
+  for (;;) {
+    const { value, done } = await reader.read();
+    if (done) break;
+
+    buffer += value;
+
+    let boundary = buffer.indexOf("\n\n");
+    while (boundary >= 0) {
+      const data = buffer
+        .slice(0, boundary)
+        .split("\n")
+        .filter((line) => line.startsWith("data:"))
+        .map((line) => line.slice(5).trimStart())
+        .join("\n");
+
+      buffer = buffer.slice(boundary + 2);
+      boundary = buffer.indexOf("\n\n");
+
+      // Keep-alive comments carry no data
+      if (data) {
+        onEvent(JSON.parse(data) as ChatStreamEvent);
+      }
+    }
+  }
+};
+
 // Strategy API
 export const fetchPortfolio = async (): Promise<Portfolio> => {
   const url = createApiUrl("/strategy/portfolio");
@@ -244,8 +300,8 @@ export const fetchOpenOrders = async (symbol: string): Promise<Order[]> => {
   if (!response.ok) {
     throw new Error(`HTTP error! status: ${response.status}`);
   }
-  const binanceOrders: BinanceOrderResponse[] = await response.json();
-  return binanceOrders.map(transformBinanceOrderToUI);
+  const orders: OrderResponse[] = await response.json();
+  return orders.map(transformOrderToUI);
 };
 
 export const fetchTradeHistory = async (symbol: string): Promise<Trade[]> => {
@@ -255,8 +311,8 @@ export const fetchTradeHistory = async (symbol: string): Promise<Trade[]> => {
   if (!response.ok) {
     throw new Error(`HTTP error! status: ${response.status}`);
   }
-  const binanceTrades: BinanceTradeResponse[] = await response.json();
-  return binanceTrades.map((trade) => transformBinanceTradeToUI(trade, symbol));
+  const fills: FillResponse[] = await response.json();
+  return fills.map(transformFillToUI);
 };
 
 export const fetchCandles = async (
@@ -279,7 +335,8 @@ export const fetchCandles = async (
   if (!response.ok) {
     throw new Error(`HTTP error! status: ${response.status}`);
   }
-  return response.json();
+  const data: { candles: ApiCandleResponse[] } = await response.json();
+  return { candles: data.candles.map(parseApiCandle) };
 };
 
 export const fetchOrderBook = async (
@@ -295,7 +352,12 @@ export const fetchOrderBook = async (
   if (!response.ok) {
     throw new Error(`HTTP error! status: ${response.status}`);
   }
-  return response.json();
+  const book: OrderBookResponse = await response.json();
+  const toEntry = (level: { price: string; quantity: string }) => ({
+    price: parseFloat(level.price),
+    quantity: parseFloat(level.quantity),
+  });
+  return { ...book, bids: book.bids.map(toEntry), asks: book.asks.map(toEntry) };
 };
 
 // WebSocket connection utilities